@@ -0,0 +1,49 @@
+//! Lay yoink's output out with a formatter of your own
+//!
+//! Run with `cargo run --example custom_formatter`.
+
+use std::fs;
+use std::path::Path;
+use yoink::{Config, FileProcessor, OutputFormatter};
+
+/// Wraps the files in a prompt asking for a review
+struct ReviewPrompt;
+
+impl OutputFormatter for ReviewPrompt {
+    fn tree_section(&self, tree: &str) -> String {
+        format!("Review the files below. The project looks like this:\n\n{}\n", tree)
+    }
+
+    fn file_chunk(&self, path: &Path, note: Option<&str>, content: &str) -> String {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        match note {
+            Some(note) => format!("--- {} ({}) ---\n{}\n\n", name, note, content),
+            None => format!("--- {} ---\n{}\n\n", name, content),
+        }
+    }
+
+    fn skipped_entry(&self, path: &Path, note: &str) -> String {
+        format!("(left out {}: {})\n\n", path.display(), note)
+    }
+
+    fn summary(&self, summary: &str) -> String {
+        format!("Point out bugs first, then style.\n\n[{}]\n", summary.lines().next().unwrap_or_default())
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    fs::create_dir(dir.path().join("src"))?;
+    fs::write(dir.path().join("src/lib.rs"), "pub fn add(a: i32, b: i32) -> i32 {\n    a - b\n}\n")?;
+    fs::write(dir.path().join("README.md"), "# adder\n")?;
+
+    let config = Config::builder().path(dir.path().to_string_lossy()).build();
+    let processor = FileProcessor::builder(config).formatter(ReviewPrompt).build();
+    let output = processor.assemble()?;
+
+    assert!(output.content.starts_with("Review the files below."));
+    assert!(output.content.contains("--- lib.rs ---\npub fn add"));
+    assert!(output.content.ends_with("[Text files processed: 2]\n"));
+    print!("{}", output.content);
+    Ok(())
+}
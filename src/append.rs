@@ -0,0 +1,130 @@
+//! Adding a run's output to yoink output already on the clipboard
+
+use crate::error::YoinkError;
+
+const HEADER: &str = "=== DIRECTORY STRUCTURE ===";
+const SUMMARY: &str = "\n=== SUMMARY ===\n";
+const DIVIDER: &str = "=== ADDITIONAL FILES (run ";
+
+/// What `--append` does when the clipboard holds something other than yoink output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AppendMode {
+    /// Keep it, verbatim, above the new output
+    Force,
+    /// Stop without touching the clipboard
+    #[default]
+    Strict,
+}
+
+/// The combined payload, and which run the new output became
+#[derive(Debug, PartialEq, Eq)]
+pub struct Appended {
+    pub text: String,
+    /// 1 when there was nothing to append to
+    pub run: usize,
+}
+
+/// Whether `text` starts the way yoink output does
+pub fn is_yoink_output(text: &str) -> bool {
+    text.starts_with(HEADER)
+}
+
+/// Put `output` after the `existing` clipboard content
+///
+/// Yoink output is joined under an "ADDITIONAL FILES" divider, with both
+/// summaries replaced by a single merged one at the end. Anything else is
+/// refused in strict mode, or kept as-is above the new output with `force`.
+pub fn append(existing: &str, output: &str, mode: AppendMode) -> Result<Appended, YoinkError> {
+    if existing.trim().is_empty() {
+        return Ok(Appended { text: output.to_string(), run: 1 });
+    }
+    if !is_yoink_output(existing) {
+        if mode == AppendMode::Strict {
+            return Err(YoinkError::NotYoinkOutput);
+        }
+        log::warn!("The clipboard doesn't hold yoink output; keeping it above the new output as-is");
+        return Ok(Appended { text: format!("{}\n\n{}", existing.trim_end(), output), run: 1 });
+    }
+
+    let (old_body, old_summary) = split_summary(existing);
+    let (new_body, new_summary) = split_summary(output);
+    let run = existing.matches(DIVIDER).count() + 2;
+    let mut text = format!("{}\n\n{}{}) ===\n\n{}", old_body.trim_end(), DIVIDER, run, new_body.trim_end());
+    text.push('\n');
+    text.push_str(&merge_summaries(old_summary, new_summary, run));
+    Ok(Appended { text, run })
+}
+
+// The output before its summary section, and the summary's lines
+fn split_summary(output: &str) -> (&str, &str) {
+    match output.rfind(SUMMARY) {
+        Some(at) => (&output[..at], &output[at + SUMMARY.len()..]),
+        None => (output, ""),
+    }
+}
+
+// Counts under the same label are added up; every other line is kept once,
+// in the order first seen
+fn merge_summaries(old: &str, new: &str, runs: usize) -> String {
+    let mut lines: Vec<(String, Option<u64>)> = Vec::new();
+    for line in old.lines().chain(new.lines()).filter(|line| !line.starts_with("Runs combined:")) {
+        let count = line.rsplit_once(": ").and_then(|(label, value)| Some((label, value.parse::<u64>().ok()?)));
+        match count {
+            Some((label, value)) if !line.starts_with(' ') => {
+                match lines.iter_mut().find(|(seen, total)| total.is_some() && seen == label) {
+                    Some((_, total)) => *total = total.map(|total| total + value),
+                    None => lines.push((label.to_string(), Some(value))),
+                }
+            }
+            _ if lines.iter().any(|(seen, total)| total.is_none() && seen == line) => {}
+            _ => lines.push((line.to_string(), None)),
+        }
+    }
+
+    let mut summary = String::from(SUMMARY);
+    summary.push_str(&format!("Runs combined: {}\n", runs));
+    for (line, total) in lines {
+        match total {
+            Some(total) => summary.push_str(&format!("{}: {}\n", line, total)),
+            None => summary.push_str(&format!("{}\n", line)),
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(file: &str, text_files: u64) -> String {
+        format!(
+            "{}\n📁 p/\n  📄 {}\n\n=== TEXT FILES ===\n\n=== {} ===\nbody\n\n{}Text files processed: {}\nBinary files skipped: 1\n",
+            HEADER, file, file, SUMMARY, text_files
+        )
+    }
+
+    #[test]
+    fn test_runs_are_joined_with_one_merged_summary() {
+        let second = append(&run("a.rs", 3), &run("b.rs", 2), AppendMode::Strict).unwrap();
+        assert_eq!(second.run, 2);
+        assert!(second.text.contains("=== a.rs ===\nbody\n\n=== ADDITIONAL FILES (run 2) ===\n\n=== DIRECTORY STRUCTURE ===\n"));
+        assert_eq!(second.text.matches("=== SUMMARY ===").count(), 1);
+        assert!(second.text.ends_with(
+            "=== SUMMARY ===\nRuns combined: 2\nText files processed: 5\nBinary files skipped: 2\n"
+        ));
+
+        let third = append(&second.text, &run("c.rs", 1), AppendMode::Strict).unwrap();
+        assert_eq!(third.run, 3);
+        assert!(third.text.contains("=== ADDITIONAL FILES (run 3) ==="));
+        assert!(third.text.ends_with("Runs combined: 3\nText files processed: 6\nBinary files skipped: 3\n"));
+    }
+
+    #[test]
+    fn test_other_clipboard_content_is_refused_or_kept() {
+        assert!(matches!(append("notes", &run("a.rs", 1), AppendMode::Strict), Err(YoinkError::NotYoinkOutput)));
+
+        let kept = append("notes\n", &run("a.rs", 1), AppendMode::Force).unwrap();
+        assert_eq!(kept.text, format!("notes\n\n{}", run("a.rs", 1)));
+        assert_eq!(append("", &run("a.rs", 1), AppendMode::Strict).unwrap().run, 1);
+    }
+}
@@ -0,0 +1,221 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+/// What we remember about a file between runs
+#[derive(Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    size: u64,
+    mtime_secs: u64,
+    mtime_nanos: u32,
+    is_text: bool,
+    content_hash: Option<u64>,
+}
+
+impl CacheEntry {
+    fn matches(&self, metadata: &Metadata) -> bool {
+        let (secs, nanos) = mtime(metadata);
+        self.size == metadata.len() && self.mtime_secs == secs && self.mtime_nanos == nanos
+    }
+}
+
+/// A file's classification as remembered from an earlier run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cached {
+    Binary,
+    /// A text file, with the hash of its content as it was read
+    Text { content_hash: u64 },
+}
+
+/// Sidecar cache of file classifications, keyed by canonical scan root
+///
+/// Entries are invalidated when a file's size or modification time changes,
+/// and a text file's entry only counts once its content is found to hash the
+/// same, which catches edits too quick for the modification time to show.
+/// Only files seen during the current run are written back, so entries for
+/// deleted files drop out naturally.
+pub struct FileCache {
+    location: PathBuf,
+    previous: HashMap<PathBuf, CacheEntry>,
+    current: Mutex<HashMap<PathBuf, CacheEntry>>,
+    hits: AtomicUsize,
+}
+
+impl FileCache {
+    /// Load the cache for `root`, starting empty if there is none or it is unreadable
+    pub fn open(root: &Path) -> Self {
+        let location = Self::location_for(root);
+        let previous = fs::read(&location)
+            .ok()
+            .and_then(|data| match serde_json::from_slice(&data) {
+                Ok(entries) => Some(entries),
+                Err(e) => {
+                    log::warn!("Ignoring corrupt cache {}: {}", location.display(), e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Self {
+            location,
+            previous,
+            current: Mutex::new(HashMap::new()),
+            hits: AtomicUsize::new(0),
+        }
+    }
+
+    /// Delete the cache for `root`
+    pub fn clear(root: &Path) -> io::Result<()> {
+        match fs::remove_file(Self::location_for(root)) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            other => other,
+        }
+    }
+
+    // Named by a stable hash of the canonical root, so the same root finds
+    // its cache again after yoink is rebuilt with another Rust release
+    fn location_for(root: &Path) -> PathBuf {
+        let canonical = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+
+        let mut path = dirs::cache_dir().unwrap_or_else(std::env::temp_dir);
+        path.push("yoink");
+        path.push(format!("{:016x}.json", content_hash(&canonical.to_string_lossy())));
+        path
+    }
+
+    /// Return the cached classification if the file's size and modification
+    /// time are unchanged; it's only a hit once [`FileCache::keep`] says so
+    pub fn lookup(&self, path: &Path, metadata: &Metadata) -> Option<Cached> {
+        let entry = self.previous.get(path).filter(|e| e.matches(metadata))?;
+        match (entry.is_text, entry.content_hash) {
+            (false, _) => Some(Cached::Binary),
+            (true, Some(content_hash)) => Some(Cached::Text { content_hash }),
+            (true, None) => None,
+        }
+    }
+
+    /// Carry a looked-up entry over to this run, counting it as a hit
+    pub fn keep(&self, path: &Path) {
+        if let Some(entry) = self.previous.get(path) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            self.current.lock().unwrap().insert(path.to_path_buf(), entry.clone());
+        }
+    }
+
+    /// Remember a file's classification, with its content for text files
+    pub fn record(&self, path: &Path, metadata: &Metadata, content: Option<&str>) {
+        let (mtime_secs, mtime_nanos) = mtime(metadata);
+        let entry = CacheEntry {
+            size: metadata.len(),
+            mtime_secs,
+            mtime_nanos,
+            is_text: content.is_some(),
+            content_hash: content.map(content_hash),
+        };
+        self.current.lock().unwrap().insert(path.to_path_buf(), entry);
+    }
+
+    /// Number of files whose classification came from the cache this run
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Write this run's entries back to disk
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(parent) = self.location.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let data = serde_json::to_vec(&*self.current.lock().unwrap())?;
+        fs::write(&self.location, data)
+    }
+}
+
+/// Stable 64-bit FNV-1a hash of file content
+///
+/// Unlike `DefaultHasher`, the result doesn't change between Rust releases,
+/// so it can be persisted and compared across runs.
+pub(crate) fn content_hash(content: &str) -> u64 {
+    content.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+fn mtime(metadata: &Metadata) -> (u64, u32) {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| (d.as_secs(), d.subsec_nanos()))
+        .unwrap_or((0, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_hits_until_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "alpha").unwrap();
+        let metadata = fs::metadata(&file).unwrap();
+
+        let mut cache = FileCache::open(dir.path());
+        cache.location = dir.path().join("cache.json");
+        assert_eq!(cache.lookup(&file, &metadata), None);
+        cache.record(&file, &metadata, Some("alpha"));
+        cache.save().unwrap();
+
+        let previous = serde_json::from_slice(&fs::read(&cache.location).unwrap()).unwrap();
+        let reloaded = FileCache { previous, ..FileCache::open(dir.path()) };
+        assert_eq!(reloaded.lookup(&file, &metadata), Some(Cached::Text { content_hash: content_hash("alpha") }));
+        assert_eq!(reloaded.hits(), 0);
+        reloaded.keep(&file);
+        assert_eq!(reloaded.hits(), 1);
+
+        fs::write(&file, "alpha beta").unwrap();
+        let changed = fs::metadata(&file).unwrap();
+        assert_eq!(reloaded.lookup(&file, &changed), None);
+    }
+
+    #[test]
+    fn test_text_changed_without_touching_mtime_is_read_again() {
+        use crate::config::Config;
+        use crate::text_processor::{TextProcessing, TextProcessor};
+        use std::sync::Arc;
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "alpha").unwrap();
+        let modified = fs::metadata(&file).unwrap().modified().unwrap();
+        let config = Config::builder().path(dir.path().to_string_lossy()).build();
+
+        let mut cache = FileCache::open(dir.path());
+        cache.location = dir.path().join("cache.json");
+        let cache = Arc::new(cache);
+        TextProcessor::new(&config).with_cache(cache.clone()).process_file(&file).unwrap();
+        cache.save().unwrap();
+
+        // Same size, same modification time, different content
+        fs::write(&file, "omega").unwrap();
+        fs::File::options().write(true).open(&file).unwrap().set_modified(modified).unwrap();
+
+        let previous = serde_json::from_slice(&fs::read(&cache.location).unwrap()).unwrap();
+        let reloaded = Arc::new(FileCache { previous, ..FileCache::open(dir.path()) });
+        let content = TextProcessor::new(&config).with_cache(reloaded.clone()).process_file(&file).unwrap();
+        assert_eq!(content.as_deref(), Some("omega"));
+        assert_eq!(reloaded.hits(), 0);
+    }
+
+    #[test]
+    fn test_cache_name_is_stable_across_builds() {
+        // Pinned, so a change of hash that would orphan every cache fails here
+        let location = FileCache::location_for(Path::new("/no/such/root"));
+        assert_eq!(location.file_name().unwrap(), "1f868ed6073730b2.json");
+    }
+}
@@ -0,0 +1,26 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared flag used to stop a run early, e.g. from a Ctrl-C handler
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the run to stop; work already in flight is allowed to finish
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Clear the flag so the token can be reused for another run
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,142 @@
+//! Splitting the output into pieces small enough to paste, for `--chunk-size`
+//!
+//! The output is split between the sections it was written in, so a file's
+//! section always lands whole in one chunk and the directory tree only in the
+//! first. A section too large for any chunk is cut down to fit, with a marker.
+
+use std::path::{Path, PathBuf};
+
+/// `content` in chunks of at most `size` characters, each labeled
+/// `[chunk i/n]`, or whole and unlabeled when it fits in one
+///
+/// `starts` holds the byte offset where each section was begun, in order.
+pub fn split(content: &str, starts: &[usize], size: usize) -> Vec<String> {
+    if content.chars().count() <= size {
+        return vec![content.to_string()];
+    }
+    let sections: Vec<&str> = match starts.first() {
+        Some(_) => starts
+            .iter()
+            .enumerate()
+            .map(|(index, &start)| &content[start..starts.get(index + 1).copied().unwrap_or(content.len())])
+            .collect(),
+        None => vec![content],
+    };
+
+    // Room for the label, as long as it could get with a chunk per section
+    let widest = sections.len().to_string().len();
+    let room = size.saturating_sub("[chunk /]\n".len() + 2 * widest).max(1);
+
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut current_chars = 0;
+    for section in sections {
+        let cut;
+        let section = if section.chars().count() > room {
+            cut = cut_to(section, room);
+            cut.as_str()
+        } else {
+            section
+        };
+        let chars = section.chars().count();
+        if current_chars + chars > room && !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+            current_chars = 0;
+        }
+        current.push_str(section);
+        current_chars += chars;
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    let count = chunks.len();
+    chunks.into_iter().enumerate().map(|(index, chunk)| label(index + 1, count) + &chunk).collect()
+}
+
+/// Where chunk `number` goes when the output was meant for `path`: `yoink.txt`
+/// becomes `yoink-1.txt`, `yoink-2.txt`, and so on
+pub fn numbered(path: &Path, number: usize) -> PathBuf {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(extension) => format!("{}-{}.{}", stem, number, extension.to_string_lossy()),
+        None => format!("{}-{}", stem, number),
+    };
+    path.with_file_name(name)
+}
+
+fn label(number: usize, count: usize) -> String {
+    format!("[chunk {}/{}]\n", number, count)
+}
+
+// The header and whole body lines of `section` that fit in `room` characters,
+// with a marker saying how many body lines were cut; when not one body line
+// fits whole, as much of the first as does, so a minified file still shows
+// something
+fn cut_to(section: &str, room: usize) -> String {
+    let mut lines = section.trim_end_matches('\n').lines();
+    let header = lines.next().unwrap_or_default();
+    let body: Vec<&str> = lines.collect();
+    let marker = |cut: usize| format!("... [cut {} of {} lines to fit --chunk-size]\n", cut, body.len());
+    let mut budget = room.saturating_sub(marker(body.len()).chars().count());
+    let mut kept = String::new();
+    let mut whole = 0;
+    for (index, line) in std::iter::once(header).chain(body.iter().copied()).enumerate() {
+        let chars = line.chars().count() + 1;
+        if chars > budget {
+            if whole == 0 && budget > 1 {
+                kept.extend(line.chars().take(budget - 1));
+                kept.push('\n');
+            }
+            break;
+        }
+        kept.push_str(line);
+        kept.push('\n');
+        budget -= chars;
+        if index > 0 {
+            whole += 1;
+        }
+    }
+    kept + &marker(body.len() - whole)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sections_stay_whole_and_oversized_ones_are_cut() {
+        let tree = "=== DIRECTORY STRUCTURE ===\nsrc/\n\n";
+        let a = "=== a.rs ===\naaaaaaaaaaaaaaaaaaaa\n\n";
+        let b = "=== b.rs ===\nbbbbbbbbbbbbbbbbbbbb\n\n";
+        let big = "=== big.rs ===\n".to_string() + &"x\n".repeat(100) + "\n";
+        let content = [tree, a, b, &big].concat();
+        let starts = [0, tree.len(), tree.len() + a.len(), tree.len() + a.len() + b.len()];
+
+        let chunks = split(&content, &starts, 90);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0], format!("[chunk 1/3]\n{}{}", tree, a));
+        assert_eq!(chunks[1], format!("[chunk 2/3]\n{}", b));
+        assert!(chunks[2].starts_with("[chunk 3/3]\n=== big.rs ===\nx\n"));
+        assert!(chunks[2].ends_with("... [cut 92 of 100 lines to fit --chunk-size]\n"));
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 90));
+
+        assert_eq!(split(&content, &starts, content.len()), vec![content.clone()]);
+        assert_eq!(numbered(Path::new("out/yoink.txt"), 2), Path::new("out/yoink-2.txt"));
+        assert_eq!(numbered(Path::new("context"), 1), Path::new("context-1"));
+    }
+
+    #[test]
+    fn test_a_single_long_line_is_cut_inside() {
+        let a = "=== a.rs ===\nfn a() {}\n\n";
+        let min = format!("=== app.min.js ===\n{}\n\n", "x".repeat(500));
+        let content = [a, &min].concat();
+
+        let chunks = split(&content, &[0, a.len()], 100);
+        assert_eq!(chunks.len(), 2);
+        let kept = chunks[1].strip_prefix("[chunk 2/2]\n=== app.min.js ===\n").unwrap();
+        assert!(kept.starts_with("xxxxxxxxxx"), "{}", kept);
+        assert!(kept.ends_with("x\n... [cut 1 of 1 lines to fit --chunk-size]\n"), "{}", kept);
+        assert_eq!(chunks[1].chars().count(), 100);
+    }
+}
@@ -1,48 +1,83 @@
-use clap::{Command, Arg};
-use std::fs;
-use std::path::PathBuf;
-use std::io::{Read, Write};
+use clap::{Command, Arg, ArgGroup};
 use colored::*;
-
-#[derive(Clone)]
-pub struct Config {
-    pub path: String,
-    pub max_size: u64,
-    pub verbose: bool,
-    pub max_depth: u32,
-    pub include_extensions: Option<Vec<String>>,
-    pub exclude_extensions: Option<Vec<String>>,
-    pub exclude_paths: Option<Vec<String>>,
-    pub pattern: Option<glob::Pattern>,
-    pub skip_hidden: bool,
-    pub sort: bool,
-    pub save_config: bool,
-    pub search_text: Option<String>,
-    pub case_sensitive: bool,
-}
+use crate::config::{Config, NestedRepoMode, OutputFormat, OversizeMode, SubmoduleMode};
+use crate::dedup::DedupMode;
+use crate::fit::FitStrategy;
+use crate::append::AppendMode;
+use crate::file_tree::TreeBudget;
+use crate::target::Target;
+use crate::formatter::FormatTemplate;
+use crate::header::HeaderTemplate;
+use crate::name_regex::NameRegex;
+use crate::modified::TimeBound;
+use crate::roots::parse_label;
+use crate::error::YoinkError;
+use crate::manifest::ManifestLocation;
+use std::fs;
+use std::io;
+use std::path::Path;
 
 impl Config {
-    pub fn from_matches(matches: &clap::ArgMatches) -> Self {
+    /// Start from the saved config, or the profile named by `--profile`, and
+    /// apply the flags over it; only a missing profile is an error, anything
+    /// else wrong with the saved config falls back to the defaults
+    pub fn from_matches(matches: &clap::ArgMatches) -> Result<Self, YoinkError> {
+        let profile = matches.get_one::<String>("profile").map(String::as_str);
         // Try to load config file first
         let mut config = if matches.get_flag("no-config") {
             Self::default()
         } else {
-            Self::load_from_file().unwrap_or_else(|_| Self::default())
+            match Self::load_saved(profile) {
+                Ok(Some((saved, drift))) => {
+                    if let Some(drift) = drift {
+                        eprintln!("{}: {}", "Warning".yellow(), drift);
+                    }
+                    saved
+                }
+                Ok(None) => Self::default(),
+                Err(YoinkError::Config { path, source }) if source.kind() == io::ErrorKind::PermissionDenied => {
+                    if first_notice(&path) {
+                        eprintln!(
+                            "{}: Can't read the saved configuration at {} ({}); using defaults. This is only shown once.",
+                            "Notice".blue(),
+                            path.display(),
+                            source
+                        );
+                    } else {
+                        log::debug!("Can't read the saved configuration at {}: {}", path.display(), source);
+                    }
+                    Self::default()
+                }
+                Err(e @ YoinkError::UnknownProfile { .. }) if !matches.get_flag("save-config") => return Err(e),
+                Err(YoinkError::UnknownProfile { .. }) => Self::default(),
+                Err(e) => {
+                    eprintln!("{}: {}. Using defaults.", "Warning".yellow(), e);
+                    Self::default()
+                }
+            }
         };
         
         // Override with command line arguments
         if matches.contains_id("path") {
-            config.path = matches.get_one::<String>("path").unwrap().clone();
+            let mut paths = matches.get_many::<String>("path").unwrap().cloned();
+            config.path = paths.next().unwrap();
+            config.extra_paths = paths.collect();
+            config.labels = matches.get_many::<(String, String)>("label").into_iter().flatten().cloned().collect();
         }
         
-        if matches.contains_id("max-size") {
+        // The flag's default shouldn't replace a saved limit
+        if matches.value_source("max-size") == Some(clap::parser::ValueSource::CommandLine) {
             config.max_size = matches.get_one::<String>("max-size")
                 .unwrap()
                 .parse::<u64>()
                 .unwrap_or(10) * 1024 * 1024;
         }
         
-        if matches.get_flag("verbose") {
+        if let Some(lines) = matches.get_one::<u64>("max-lines") {
+            config.max_lines = Some(*lines as usize);
+        }
+        
+        if matches.get_count("verbose") > 0 {
             config.verbose = true;
         }
         
@@ -67,24 +102,21 @@ impl Config {
                 .map(|p| p.split(',').map(|s| s.trim().to_string()).collect());
         }
         
+        if let Some(patterns) = matches.get_many::<Vec<glob::Pattern>>("exclude-glob") {
+            config.exclude_globs.extend(patterns.flatten().cloned());
+        }
+        
         if matches.contains_id("pattern") {
-            config.pattern = matches.get_one::<String>("pattern").map(|p| {
-                match glob::Pattern::new(p) {
-                    Ok(pattern) => pattern,
-                    Err(e) => {
-                        eprintln!("Warning: Invalid pattern '{}': {}. Treating as literal.", p, e);
-                        let escaped = p.chars().flat_map(|c| {
-                            match c {
-                                '*' | '?' | '[' | ']' | '{' | '}' | '(' | ')' => vec!['\\', c],
-                                _ => vec![c],
-                            }
-                        }).collect::<String>();
-                        glob::Pattern::new(&escaped).unwrap_or_else(|_| {
-                            glob::Pattern::new("NOMATCH").unwrap()
-                        })
-                    }
-                }
-            });
+            config.pattern = matches.get_one::<String>("pattern").map(|p| tolerant_glob(p));
+        }
+        
+        if let Some(patterns) = matches.get_many::<String>("exclude-pattern") {
+            let patterns = patterns.flat_map(|p| p.split(',')).map(str::trim).filter(|p| !p.is_empty());
+            config.exclude_patterns.extend(patterns.map(tolerant_glob));
+        }
+        
+        if let Some(regex) = matches.get_one::<NameRegex>("regex") {
+            config.regex = Some(regex.clone());
         }
         
         if matches.get_flag("no-hidden") {
@@ -97,187 +129,491 @@ impl Config {
         
         if matches.contains_id("search") {
             config.search_text = matches.get_one::<String>("search").map(|s| s.to_string());
+            config.search_regex = false;
+        }
+        
+        if let Some(pattern) = matches.get_one::<String>("search-regex") {
+            config.search_text = Some(pattern.clone());
+            config.search_regex = true;
         }
         
         if matches.get_flag("case-sensitive") {
             config.case_sensitive = true;
         }
         
-        config.save_config = matches.get_flag("save-config");
+        if matches.get_flag("word") {
+            config.whole_word = true;
+        }
         
-        // Save config if requested
-        if config.save_config {
-            if let Err(e) = config.save_to_file() {
-                eprintln!("{}: Failed to save config: {}", "Warning".yellow(), e);
-            } else {
-                println!("{}: Configuration saved", "Info".blue());
-            }
+        if let Some(names) = matches.get_many::<String>("symbol") {
+            config.symbols = names.cloned().collect();
         }
         
-        config
-    }
-    
-    fn default() -> Self {
-        Self {
-            path: ".".to_string(),
-            max_size: 10 * 1024 * 1024,
-            verbose: false,
-            max_depth: u32::MAX,
-            include_extensions: None,
-            exclude_extensions: None,
-            exclude_paths: None,
-            pattern: None,
-            skip_hidden: false,
-            sort: false,
-            save_config: false,
-            search_text: None,
-            case_sensitive: false,
+        if matches.get_flag("low-memory") {
+            config.low_memory = true;
         }
-    }
-    
-    fn get_config_path() -> PathBuf {
-        let mut path = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
-        path.push("yoink");
-        fs::create_dir_all(&path).ok();
-        path.push("config.json");
-        path
-    }
-    
-    fn save_to_file(&self) -> Result<(), String> {
-        let config_path = Self::get_config_path();
-        
-        // Create a serializable version of the config
-        let serializable_config = serde_json::json!({
-            "path": self.path,
-            "max_size": self.max_size / (1024 * 1024), // Convert back to MB
-            "verbose": self.verbose,
-            "max_depth": if self.max_depth == u32::MAX { null } else { self.max_depth },
-            "include_extensions": self.include_extensions,
-            "exclude_extensions": self.exclude_extensions,
-            "exclude_paths": self.exclude_paths,
-            "pattern": self.pattern.as_ref().map(|p| p.as_str()),
-            "skip_hidden": self.skip_hidden,
-            "sort": self.sort,
-            "search_text": self.search_text,
-            "case_sensitive": self.case_sensitive,
-        });
-        
-        let config_str = serde_json::to_string_pretty(&serializable_config)
-            .map_err(|e| format!("Failed to serialize config: {}", e))?;
-        
-        let mut file = fs::File::create(config_path)
-            .map_err(|e| format!("Failed to create config file: {}", e))?;
-        
-        file.write_all(config_str.as_bytes())
-            .map_err(|e| format!("Failed to write config file: {}", e))?;
-        
-        Ok(())
-    }
-    
-    fn load_from_file() -> Result<Self, String> {
-        let config_path = Self::get_config_path();
         
-        if !config_path.exists() {
-            return Err("Config file does not exist".to_string());
+        if matches.get_flag("watch") {
+            config.watch = true;
         }
         
-        let mut file = fs::File::open(config_path)
-            .map_err(|e| format!("Failed to open config file: {}", e))?;
+        if matches.get_flag("cache") {
+            config.cache = true;
+        }
         
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)
-            .map_err(|e| format!("Failed to read config file: {}", e))?;
+        if matches.get_flag("no-history") {
+            config.history = false;
+        }
         
-        let json: serde_json::Value = serde_json::from_str(&contents)
-            .map_err(|e| format!("Failed to parse config file: {}", e))?;
+        if matches.get_flag("no-cache") {
+            config.cache = false;
+        }
         
-        let mut config = Self::default();
+        if let Some(limit) = matches.get_one::<u64>("memory-limit") {
+            config.memory_limit = Some(*limit);
+        }
         
-        if let Some(path) = json.get("path").and_then(|v| v.as_str()) {
-            config.path = path.to_string();
+        if let Some(&limit) = matches.get_one::<u64>("max-total") {
+            config.max_total = (limit > 0).then_some(limit);
         }
         
-        if let Some(max_size) = json.get("max_size").and_then(|v| v.as_u64()) {
-            config.max_size = max_size * 1024 * 1024; // Convert from MB
+        if let Some(rev) = matches.get_one::<String>("diff-content") {
+            config.diff_content = Some(rev.clone());
         }
         
-        if let Some(verbose) = json.get("verbose").and_then(|v| v.as_bool()) {
-            config.verbose = verbose;
+        if matches.get_flag("edit") {
+            config.edit = true;
         }
         
-        if let Some(max_depth) = json.get("max_depth").and_then(|v| v.as_u64()) {
-            config.max_depth = max_depth as u32;
+        if matches.get_flag("keep-temp") {
+            config.keep_temp = true;
         }
         
-        if let Some(extensions) = json.get("include_extensions") {
-            if let Some(arr) = extensions.as_array() {
-                let exts: Vec<String> = arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect();
-                if !exts.is_empty() {
-                    config.include_extensions = Some(exts);
-                }
-            }
+        if matches.get_flag("pager") {
+            config.pager = true;
         }
         
-        if let Some(exclude) = json.get("exclude_extensions") {
-            if let Some(arr) = exclude.as_array() {
-                let exts: Vec<String> = arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect();
-                if !exts.is_empty() {
-                    config.exclude_extensions = Some(exts);
-                }
-            }
+        if matches.get_flag("pick") {
+            config.pick = true;
         }
         
-        if let Some(exclude_paths) = json.get("exclude_paths") {
-            if let Some(arr) = exclude_paths.as_array() {
-                let paths: Vec<String> = arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect();
-                if !paths.is_empty() {
-                    config.exclude_paths = Some(paths);
-                }
-            }
+        if matches.get_flag("pick-preview") {
+            config.pick_preview = true;
+        }
+        
+        if matches.get_flag("serve") {
+            config.serve = true;
+        }
+        
+        if let Some(bind) = matches.get_one::<std::net::IpAddr>("bind") {
+            config.bind = *bind;
+        }
+        
+        if let Some(seconds) = matches.get_one::<u64>("serve-timeout") {
+            config.serve_timeout = std::time::Duration::from_secs(*seconds);
+        }
+        
+        if matches.get_flag("copy") {
+            config.copy = true;
+        }
+        
+        if let Some(name) = matches.get_one::<String>("stdin-name") {
+            config.stdin_name = Some(name.clone());
+        }
+        
+        if let Some(path) = matches.get_one::<String>("manifest-save") {
+            config.manifest_save = Some(manifest_location(path));
+        }
+        
+        if let Some(path) = matches.get_one::<String>("manifest-diff") {
+            config.manifest_diff = Some(manifest_location(path));
         }
         
-        if let Some(pattern_str) = json.get("pattern").and_then(|v| v.as_str()) {
-            if let Ok(pattern) = glob::Pattern::new(pattern_str) {
-                config.pattern = Some(pattern);
+        if matches.get_flag("list") {
+            config.list = true;
+        }
+        
+        if matches.get_flag("json") {
+            config.list_json = true;
+        }
+        
+        if matches.get_flag("all") {
+            config.list_all = true;
+        }
+        
+        if matches.get_flag("dry-run") {
+            config.dry_run = true;
+        }
+        
+        if matches.get_flag("stats") {
+            config.stats = true;
+        }
+        
+        if let Some(indices) = matches.get_one::<Vec<usize>>("select") {
+            config.select = Some(indices.clone());
+        }
+        
+        if let Some(list) = matches.get_one::<String>("files-from") {
+            config.files_from = Some(std::path::PathBuf::from(list));
+        }
+        
+        if matches.get_flag("emit-files-list") {
+            config.emit_files_list = true;
+        }
+        
+        if matches.get_flag("preview") {
+            config.preview = true;
+        }
+        
+        if matches.get_flag("media-info") {
+            config.media_info = true;
+        }
+        
+        if matches.get_flag("git-meta") {
+            config.git_meta = true;
+        }
+        
+        if let Some(patterns) = matches.get_many::<glob::Pattern>("force-text") {
+            config.force_text.extend(patterns.cloned());
+        }
+        
+        if let Some(patterns) = matches.get_many::<glob::Pattern>("force-binary") {
+            config.force_binary.extend(patterns.cloned());
+        }
+        
+        if matches.get_flag("sniff-all") {
+            config.sniff_all = true;
+        }
+        
+        if matches.get_flag("dedup") {
+            config.dedup = Some(DedupMode::Exact);
+        }
+        
+        // Normalized matching finds every exact copy too, so it wins when both are given
+        if matches.get_flag("dedup-normalized") {
+            config.dedup = Some(DedupMode::Normalized);
+        }
+        
+        if let Some(target) = matches.get_one::<String>("explain") {
+            config.explain = Some(target.into());
+        }
+        
+        if let Some(files) = matches.get_one::<usize>("sample") {
+            config.sample = Some(*files);
+        }
+        
+        if let Some(seed) = matches.get_one::<u64>("seed") {
+            config.seed = Some(*seed);
+        }
+        
+        if matches.get_flag("group-by-dir") {
+            config.group_by_dir = true;
+        }
+        
+        if let Some(depth) = matches.get_one::<u64>("group-depth") {
+            config.group_depth = *depth as usize;
+        }
+        
+        if let Some(packages) = matches.get_many::<String>("package") {
+            config.packages.extend(packages.cloned());
+        }
+        
+        if matches.get_flag("list-packages") {
+            config.list_packages = true;
+        }
+        
+        if matches.get_flag("strict-filters") {
+            config.strict_filters = true;
+        }
+        
+        if matches.get_flag("skip-tests") {
+            config.skip_tests = true;
+        }
+        
+        if matches.get_flag("no-generated") {
+            config.skip_generated = true;
+        }
+        
+        if matches.get_flag("follow-links") {
+            config.follow_links = true;
+        }
+        
+        if matches.get_flag("git") || matches.get_flag("git-others") {
+            config.git_tracked = true;
+            config.git_others = matches.get_flag("git-others");
+        }
+        
+        if let Some(since) = matches.get_one::<TimeBound>("since") {
+            config.since = Some(since.clone());
+        }
+        
+        if let Some(until) = matches.get_one::<TimeBound>("until") {
+            config.until = Some(until.clone());
+        }
+        
+        if let Some(files) = matches.get_one::<u64>("max-files-per-dir") {
+            config.max_files_per_dir = Some(*files as usize);
+        }
+        
+        if let Some(bytes) = matches.get_one::<u64>("max-bytes-per-dir") {
+            config.max_bytes_per_dir = Some(*bytes);
+        }
+        
+        if matches.get_flag("excluded-overview") {
+            config.excluded_overview = true;
+        }
+        
+        if let Some(budget) = matches.get_one::<TreeBudget>("tree-budget") {
+            config.tree_budget = Some(*budget);
+        }
+        
+        if matches.get_flag("line-numbers") {
+            config.line_numbers = true;
+        }
+        
+        if matches.get_flag("detailed-summary") {
+            config.detailed_summary = true;
+        }
+        
+        if matches.get_flag("absolute-paths") {
+            config.absolute_paths = true;
+        }
+        
+        if matches.get_flag("strip-comments") {
+            config.strip_comments = true;
+        }
+        
+        if matches.get_flag("condense") {
+            config.condense = true;
+        }
+        
+        if let Some(header) = matches.get_one::<HeaderTemplate>("header-format") {
+            config.header_format = header.clone();
+        }
+        
+        if let Some(footer) = matches.get_one::<HeaderTemplate>("footer-format") {
+            config.footer_format = Some(footer.clone());
+        }
+        
+        if matches.get_flag("no-yoinkignore") {
+            config.yoinkignore = false;
+        }
+        
+        if let Some(ceiling) = matches.get_one::<u64>("depth-ceiling") {
+            config.depth_ceiling = *ceiling as usize;
+        }
+        
+        if let Some(seconds) = matches.get_one::<u64>("file-timeout") {
+            config.file_timeout = Some(std::time::Duration::from_secs(*seconds));
+        }
+        
+        // `-o -` is another way to say --stdout
+        match matches.get_one::<String>("output").map(String::as_str) {
+            Some("-") => config.stdout = true,
+            Some(file) => config.output = Some(std::path::PathBuf::from(file)),
+            None => {}
+        }
+        
+        if matches.get_flag("stdout") {
+            config.stdout = true;
+        }
+        
+        if let Some(mode) = matches.get_one::<String>("append") {
+            config.append = Some(match mode.as_str() {
+                "force" => AppendMode::Force,
+                _ => AppendMode::Strict,
+            });
+        }
+        
+        if let Some(dir) = matches.get_one::<String>("output-dir") {
+            config.output_dir = Some(std::path::PathBuf::from(dir));
+        }
+        
+        if matches.get_flag("force") {
+            config.force = true;
+        }
+        
+        if let Some(size) = matches.get_one::<u64>("chunk-size") {
+            config.chunk_size = Some(*size as usize);
+        }
+        
+        if let Some(number) = matches.get_one::<u64>("chunk") {
+            config.chunk = Some(*number as usize);
+        }
+        
+        if let Some(tokens) = matches.get_one::<u64>("max-tokens") {
+            config.max_tokens = Some(*tokens);
+        }
+        
+        if let Some(target) = matches.get_one::<Target>("target") {
+            config.target = Some(target.clone());
+        }
+        
+        if let Some(fit) = matches.get_one::<String>("fit") {
+            config.fit = match fit.as_str() {
+                "truncate" => FitStrategy::Truncate,
+                _ => FitStrategy::Drop,
+            };
+            // Fitting to a target is fitting to its window
+            if config.max_tokens.is_none() {
+                config.max_tokens = config.target.as_ref().map(|target| target.tokens);
             }
         }
         
-        if let Some(skip_hidden) = json.get("skip_hidden").and_then(|v| v.as_bool()) {
-            config.skip_hidden = skip_hidden;
+        if let Some(lines) = matches.get_one::<usize>("fit-min-lines") {
+            config.fit_min_lines = *lines;
+        }
+        
+        if let Some(mode) = matches.get_one::<String>("oversize") {
+            config.oversize = match mode.as_str() {
+                "summarize" => OversizeMode::Summarize,
+                _ => OversizeMode::Skip,
+            };
+        }
+        
+        if let Some((format, template)) = matches.get_one::<(OutputFormat, Option<FormatTemplate>)>("format") {
+            config.format = *format;
+            config.format_template = template.clone();
+        }
+        
+        if let Some(mode) = matches.get_one::<String>("submodules") {
+            config.submodules = match mode.as_str() {
+                "include" => SubmoduleMode::Include,
+                _ => SubmoduleMode::Skip,
+            };
         }
         
-        if let Some(sort) = json.get("sort").and_then(|v| v.as_bool()) {
-            config.sort = sort;
+        if let Some(mode) = matches.get_one::<String>("nested-repos") {
+            config.nested_repos = match mode.as_str() {
+                "include" => NestedRepoMode::Include,
+                "shallow" => NestedRepoMode::Shallow,
+                _ => NestedRepoMode::Skip,
+            };
         }
         
-        if let Some(search_text) = json.get("search_text").and_then(|v| v.as_str()) {
-            config.search_text = Some(search_text.to_string());
+        if matches.get_flag("strict") {
+            config.strict = true;
         }
         
-        if let Some(case_sensitive) = json.get("case_sensitive").and_then(|v| v.as_bool()) {
-            config.case_sensitive = case_sensitive;
+        // Only plain and Markdown output open with the header append.rs recognizes
+        if config.append.is_some() {
+            let format = match config.format {
+                _ if config.format_template.is_some() => Some("custom"),
+                OutputFormat::Xml => Some("xml"),
+                OutputFormat::Html => Some("html"),
+                OutputFormat::Plain | OutputFormat::Markdown => None,
+            };
+            if let Some(format) = format {
+                return Err(YoinkError::AppendFormat { format: format.to_string() });
+            }
+        }
+        
+        config.save_config = matches.get_flag("save-config");
+        
+        // Save config if requested
+        if config.save_config {
+            match config.save_to_file(profile) {
+                Ok(path) if profile.is_some() => {
+                    eprintln!("{}: Profile '{}' saved to {}", "Info".blue(), profile.unwrap_or_default(), path.display())
+                }
+                Ok(path) => eprintln!("{}: Configuration saved to {}", "Info".blue(), path.display()),
+                Err(e) => eprintln!("{}: {} - configuration not saved", "Warning".yellow(), e),
+            }
         }
         
         Ok(config)
     }
 }
 
+// Whether to mention that the saved config at `path` can't be read: only the
+// first time, since a locked-down config directory isn't fixed between runs.
+// Which path was mentioned is remembered in the cache directory.
+fn first_notice(path: &Path) -> bool {
+    let Some(mut marker) = dirs::cache_dir() else { return true };
+    marker.push("yoink");
+    marker.push("config-notice");
+    let shown = path.display().to_string();
+    if fs::read_to_string(&marker).is_ok_and(|noticed| noticed == shown) {
+        return false;
+    }
+    if let Err(e) = marker.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| fs::write(&marker, &shown)) {
+        log::debug!("Couldn't remember the config notice in {}: {}", marker.display(), e);
+    }
+    true
+}
+
+// A flag given without a path uses the default location for the scan root
+fn manifest_location(path: &str) -> ManifestLocation {
+    if path.is_empty() {
+        ManifestLocation::Default
+    } else {
+        ManifestLocation::Path(path.into())
+    }
+}
+
+// A file name glob, or, if it isn't valid, the same text matched literally
+fn tolerant_glob(p: &str) -> glob::Pattern {
+    match glob::Pattern::new(p) {
+        Ok(pattern) => pattern,
+        Err(e) => {
+            eprintln!("Warning: Invalid pattern '{}': {}. Treating as literal.", p, e);
+            let escaped = p.chars().flat_map(|c| {
+                match c {
+                    '*' | '?' | '[' | ']' | '{' | '}' | '(' | ')' => vec!['\\', c],
+                    _ => vec![c],
+                }
+            }).collect::<String>();
+            glob::Pattern::new(&escaped).unwrap_or_else(|_| {
+                glob::Pattern::new("NOMATCH").unwrap()
+            })
+        }
+    }
+}
+
+// A comma-separated list of globs, all of which have to be valid
+fn parse_globs(value: &str) -> Result<Vec<glob::Pattern>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .map(|pattern| glob::Pattern::new(pattern).map_err(|e| format!("'{}': {}", pattern, e)))
+        .collect()
+}
+
+// A built-in format, or a template read from `custom:<path>`, which lays out
+// the plain format's pieces
+fn parse_format(value: &str) -> Result<(OutputFormat, Option<FormatTemplate>), String> {
+    match value {
+        "plain" => Ok((OutputFormat::Plain, None)),
+        "markdown" => Ok((OutputFormat::Markdown, None)),
+        "xml" => Ok((OutputFormat::Xml, None)),
+        "html" => Ok((OutputFormat::Html, None)),
+        _ => match value.strip_prefix("custom:") {
+            Some(path) => FormatTemplate::load(std::path::Path::new(path)).map(|template| (OutputFormat::Plain, Some(template))),
+            None => Err("expected plain, markdown, xml, html, or custom:<template>".to_string()),
+        },
+    }
+}
+
 pub fn build_cli() -> Command {
     Command::new("yoink")
         .version("0.1.0")
         .about("Quickly grab text content into your clipboard")
         .arg(
             Arg::new("path")
-                .help("Directory or file to yoink")
+                .help("Directories, files, or a git repository URL to yoink, or - to read stdin")
                 .default_value(".")
+                .num_args(1..)
                 .index(1)
         )
+        .arg(
+            Arg::new("label")
+                .long("label")
+                .value_name("NAME=PATH")
+                .action(clap::ArgAction::Append)
+                .value_parser(parse_label)
+                .help("Show the files of PATH under [NAME] instead of its directory name (repeatable)")
+        )
         .arg(
             Arg::new("max-size")
                 .short('m')
@@ -286,12 +622,19 @@ pub fn build_cli() -> Command {
                 .default_value("10")
                 .help("Maximum file size in MB to consider")
         )
+        .arg(
+            Arg::new("max-lines")
+                .long("max-lines")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .help("Include only the first N lines of each file, marking how many more were cut (not with --search or --symbol)")
+        )
         .arg(
             Arg::new("verbose")
                 .short('v')
                 .long("verbose")
-                .action(clap::ArgAction::SetTrue)
-                .help("Show verbose output")
+                .action(clap::ArgAction::Count)
+                .help("Show verbose output (-vv for clipboard probing details)")
         )
         .arg(
             Arg::new("depth")
@@ -320,6 +663,14 @@ pub fn build_cli() -> Command {
                 .value_name("PATHS")
                 .help("Paths to exclude (comma-separated, exact names, not patterns)")
         )
+        .arg(
+            Arg::new("exclude-glob")
+                .long("exclude-glob")
+                .value_name("GLOBS")
+                .action(clap::ArgAction::Append)
+                .value_parser(parse_globs)
+                .help("Globs for paths relative to PATH to exclude, with anything under them (comma-separated, repeatable), e.g. \"**/__snapshots__/**,**/*_generated.rs\"")
+        )
         .arg(
             Arg::new("pattern")
                 .short('p')
@@ -327,6 +678,20 @@ pub fn build_cli() -> Command {
                 .value_name("PATTERN")
                 .help("Search pattern for filenames (supports glob patterns like *.txt, special chars like () need escaping with \\)")
         )
+        .arg(
+            Arg::new("exclude-pattern")
+                .long("exclude-pattern")
+                .value_name("GLOB")
+                .action(clap::ArgAction::Append)
+                .help("Leave out files whose names match GLOB, even if they match --pattern (comma-separated, repeatable), e.g. \"*_test.go,*.spec.ts\"")
+        )
+        .arg(
+            Arg::new("regex")
+                .long("regex")
+                .value_name("RE")
+                .value_parser(NameRegex::parse)
+                .help("Regular expression that filenames must match, on top of --pattern if both are given")
+        )
         .arg(
             Arg::new("no-hidden")
                 .short('H')
@@ -341,12 +706,616 @@ pub fn build_cli() -> Command {
                 .action(clap::ArgAction::SetTrue)
                 .help("Sort files by name before processing")
         )
+        .arg(
+            Arg::new("low-memory")
+                .long("low-memory")
+                .action(clap::ArgAction::SetTrue)
+                .help("Stream output through a temporary file instead of holding it in memory")
+        )
+        .arg(
+            Arg::new("watch")
+                .short('w')
+                .long("watch")
+                .action(clap::ArgAction::SetTrue)
+                .help("Keep running and re-yoink whenever files change")
+        )
+        .arg(
+            Arg::new("memory-limit")
+                .long("memory-limit")
+                .value_name("SIZE")
+                .value_parser(crate::utils::parse_size)
+                .help("Stop including file bodies once output reaches SIZE (e.g. 512M, 2G; plain numbers are MB)")
+        )
+        .arg(
+            Arg::new("max-total")
+                .long("max-total")
+                .value_name("SIZE")
+                .value_parser(crate::utils::parse_size)
+                .help("Leave out, and list, files once the output holds SIZE of file content; 0 for no limit [default: 50M]")
+        )
+        .arg(
+            Arg::new("force-text")
+                .long("force-text")
+                .value_name("GLOB")
+                .action(clap::ArgAction::Append)
+                .value_parser(|s: &str| glob::Pattern::new(s).map_err(|e| e.to_string()))
+                .help("Treat files matching GLOB as text without detecting their type (repeatable)")
+        )
+        .arg(
+            Arg::new("force-binary")
+                .long("force-binary")
+                .value_name("GLOB")
+                .action(clap::ArgAction::Append)
+                .value_parser(|s: &str| glob::Pattern::new(s).map_err(|e| e.to_string()))
+                .help("Treat files matching GLOB as binary, skipping them (repeatable)")
+        )
+        .arg(
+            Arg::new("sniff-all")
+                .long("sniff-all")
+                .action(clap::ArgAction::SetTrue)
+                .help("Check every file's content, including text misnamed .bin or .dat, rather than trusting known extensions")
+        )
+        .arg(
+            Arg::new("dedup")
+                .long("dedup")
+                .action(clap::ArgAction::SetTrue)
+                .help("Replace files whose content is identical to an earlier one with a one-line stub naming it")
+        )
+        .arg(
+            Arg::new("dedup-normalized")
+                .long("dedup-normalized")
+                .action(clap::ArgAction::SetTrue)
+                .help("Replace files that repeat an earlier one, ignoring line endings and trailing whitespace, with a one-line stub")
+        )
+        .arg(
+            Arg::new("oversize")
+                .long("oversize")
+                .value_name("MODE")
+                .value_parser(["skip", "summarize"])
+                .help("Skip files over --max-size or include a summary of each [default: skip]")
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .value_parser(parse_format)
+                .help("Lay files out under === path === headers (plain), as Markdown headings over fenced code blocks (markdown), as <document> elements (xml), as a browsable page (html), or with a template file (custom:<path>) [default: plain]")
+        )
+        .arg(
+            Arg::new("line-numbers")
+                .long("line-numbers")
+                .action(clap::ArgAction::SetTrue)
+                .help("Prefix each line of a file with its line number (search excerpts are already numbered)")
+        )
+        .arg(
+            Arg::new("detailed-summary")
+                .long("detailed-summary")
+                .action(clap::ArgAction::SetTrue)
+                .help("List each file's lines and characters in the summary, biggest first, with totals")
+        )
+        .arg(
+            Arg::new("absolute-paths")
+                .long("absolute-paths")
+                .action(clap::ArgAction::SetTrue)
+                .help("Show paths in headers as given on the command line rather than relative to PATH")
+        )
+        .arg(
+            Arg::new("strip-comments")
+                .long("strip-comments")
+                .action(clap::ArgAction::SetTrue)
+                .help("Remove comments from source files whose language is known by extension")
+        )
+        .arg(
+            Arg::new("condense")
+                .long("condense")
+                .action(clap::ArgAction::SetTrue)
+                .help("Collapse runs of blank lines into one and strip trailing whitespace from each line")
+        )
+        .arg(
+            Arg::new("header-format")
+                .long("header-format")
+                .value_name("TEMPLATE")
+                .value_parser(HeaderTemplate::parse)
+                .help("Line above each file in the plain format, with {path}, {name}, {size}, {lines}, and {ext} filled in [default: \"=== {path} ===\"]")
+        )
+        .arg(
+            Arg::new("footer-format")
+                .long("footer-format")
+                .value_name("TEMPLATE")
+                .value_parser(HeaderTemplate::parse)
+                .help("Line below each file in the plain format, with the same placeholders as --header-format")
+        )
+        .arg(
+            Arg::new("explain")
+                .long("explain")
+                .value_name("PATH")
+                .help("Show which check includes or skips PATH, or each file under it, instead of yoinking")
+        )
+        .arg(
+            Arg::new("sample")
+                .long("sample")
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize))
+                .help("Include N files picked at random, spread across top-level directories")
+        )
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .value_name("S")
+                .value_parser(clap::value_parser!(u64))
+                .requires("sample")
+                .help("Seed for --sample, to draw the same files again")
+        )
+        .arg(
+            Arg::new("group-by-dir")
+                .long("group-by-dir")
+                .action(clap::ArgAction::SetTrue)
+                .help("Write files grouped by top-level directory, each group under its own header")
+        )
+        .arg(
+            Arg::new("group-depth")
+                .long("group-depth")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .requires("group-by-dir")
+                .help("Directory levels that make up a group for --group-by-dir [default: 1]")
+        )
+        .arg(
+            Arg::new("package")
+                .long("package")
+                .value_name("NAME")
+                .action(clap::ArgAction::Append)
+                .help("Only yoink this Cargo or JS workspace member, plus the root manifests (repeatable)")
+        )
+        .arg(
+            Arg::new("list-packages")
+                .long("list-packages")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("package")
+                .help("Print the members of the workspace at PATH instead of yoinking")
+        )
+        .arg(
+            Arg::new("strict-filters")
+                .long("strict-filters")
+                .action(clap::ArgAction::SetTrue)
+                .help("Fail instead of warning when filters contradict each other (e.g. -e rs -x rs)")
+        )
+        .arg(
+            Arg::new("skip-tests")
+                .long("skip-tests")
+                .action(clap::ArgAction::SetTrue)
+                .help("Leave out test files (tests/, *_test.go, *.spec.ts, ...) and #[cfg(test)] modules in Rust files")
+        )
+        .arg(
+            Arg::new("no-generated")
+                .long("no-generated")
+                .action(clap::ArgAction::SetTrue)
+                .help("Leave out lockfiles, minified bundles, and source maps (Cargo.lock, package-lock.json, *.min.js, *.map, ...)")
+        )
+        .arg(
+            Arg::new("follow-links")
+                .long("follow-links")
+                .action(clap::ArgAction::SetTrue)
+                .help("Walk into symlinked directories, including each file once however many links reach it")
+        )
+        .arg(
+            Arg::new("git")
+                .long("git")
+                .action(clap::ArgAction::SetTrue)
+                .help("Only include files tracked by git, as git ls-files lists them, in the files and the tree")
+        )
+        .arg(
+            Arg::new("git-others")
+                .long("git-others")
+                .action(clap::ArgAction::SetTrue)
+                .help("Like --git, also including untracked files that aren't ignored")
+        )
+        .arg(
+            Arg::new("since")
+                .long("since")
+                .value_name("WHEN")
+                .value_parser(TimeBound::parse)
+                .help("Only include files modified since WHEN: a duration back from now (2h, 3d, 1w) or a date (2024-05-01)")
+        )
+        .arg(
+            Arg::new("until")
+                .long("until")
+                .value_name("WHEN")
+                .value_parser(TimeBound::parse)
+                .help("Only include files modified before WHEN, in the same forms as --since")
+        )
+        .arg(
+            Arg::new("max-files-per-dir")
+                .long("max-files-per-dir")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64))
+                .help("Take at most N files from any one directory, smallest first")
+        )
+        .arg(
+            Arg::new("max-bytes-per-dir")
+                .long("max-bytes-per-dir")
+                .value_name("SIZE")
+                .value_parser(crate::utils::parse_size)
+                .help("Take at most SIZE from any one directory, smallest files first (e.g. 512K; plain numbers are MB)")
+        )
+        .arg(
+            Arg::new("tree-budget")
+                .long("tree-budget")
+                .value_name("SIZE")
+                .value_parser(TreeBudget::parse)
+                .help("Cut the directory tree down to fit SIZE (e.g. 4K or \"300 lines\"), dropping files first, then depth")
+        )
+        .arg(
+            Arg::new("depth-ceiling")
+                .long("depth-ceiling")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .help("Never go more than N levels deep, whatever --depth says, warning where it cuts off [default: 64]")
+        )
+        .arg(
+            Arg::new("excluded-overview")
+                .long("excluded-overview")
+                .action(clap::ArgAction::SetTrue)
+                .help("List the names directly inside each excluded directory (e.g. node_modules), without their content")
+        )
+        .arg(
+            Arg::new("file-timeout")
+                .long("file-timeout")
+                .value_name("SECS")
+                .value_parser(clap::value_parser!(u64))
+                .help("Abandon a file that takes longer than SECS to read, 0 to wait forever [default: 30, for files on network mounts only]")
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("FILE")
+                .help("Write the output to FILE instead of the clipboard, creating its directory if needed (- for stdout)")
+        )
+        .arg(
+            Arg::new("stdout")
+                .long("stdout")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["output", "copy", "serve", "preview", "pager", "watch"])
+                .help("Write the output to stdout instead of the clipboard, keeping status messages off it")
+        )
+        .arg(
+            Arg::new("append")
+                .long("append")
+                .value_name("MODE")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("strict")
+                .value_parser(["strict", "force"])
+                .conflicts_with_all(["stdout", "serve"])
+                .help("Add to yoink output already on the clipboard; with =force, keep other clipboard text above it instead of stopping (plain and markdown output only)")
+        )
+        .arg(
+            Arg::new("output-dir")
+                .long("output-dir")
+                .value_name("DIR")
+                .help("Write each included file to its own .txt under DIR, with an index.json, instead of copying")
+        )
+        .arg(
+            Arg::new("force")
+                .long("force")
+                .action(clap::ArgAction::SetTrue)
+                .requires("destination")
+                .help("Overwrite the --output file, or write into --output-dir even if it isn't empty")
+        )
+        .group(ArgGroup::new("destination").args(["output", "output-dir"]).multiple(true))
+        .arg(
+            Arg::new("chunk-size")
+                .long("chunk-size")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64).range(100..))
+                .conflicts_with_all(["low-memory", "output-dir", "edit", "pager", "preview"])
+                .help("Split the output into chunks of at most N characters between files, copying the first (or writing numbered files with --output)")
+        )
+        .arg(
+            Arg::new("chunk")
+                .long("chunk")
+                .value_name("I")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .requires("chunk-size")
+                .conflicts_with("output")
+                .help("Copy chunk I of the split output instead of the first")
+        )
+        .arg(
+            Arg::new("max-tokens")
+                .long("max-tokens")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64))
+                .conflicts_with("low-memory")
+                .help("Keep the output under roughly N tokens (about 4 bytes each)")
+        )
+        .arg(
+            Arg::new("target")
+                .long("target")
+                .value_name("MODEL|SIZE")
+                .value_parser(Target::parse)
+                .help("Warn when the output looks too big for a model's context window (e.g. gpt-4o or 128k), naming the files to drop")
+        )
+        .group(ArgGroup::new("budget").args(["max-tokens", "target"]).multiple(true))
+        .arg(
+            Arg::new("fit")
+                .long("fit")
+                .value_name("STRATEGY")
+                .value_parser(["drop", "truncate"])
+                .num_args(0..=1)
+                .default_missing_value("drop")
+                .requires("budget")
+                .help("Drop whole files or truncate every file to fit --max-tokens, or the --target window [default: drop]")
+        )
+        .arg(
+            Arg::new("fit-min-lines")
+                .long("fit-min-lines")
+                .value_name("K")
+                .value_parser(clap::value_parser!(usize))
+                .requires("budget")
+                .help("Lines each file keeps before the rest of the budget is shared out [default: 10]")
+        )
+        .arg(
+            Arg::new("diff-content")
+                .long("diff-content")
+                .value_name("REV")
+                .num_args(0..=1)
+                .default_missing_value("HEAD")
+                .help("Copy diffs against REV for changed files instead of whole files [default: HEAD]")
+        )
+        .arg(
+            Arg::new("edit")
+                .long("edit")
+                .action(clap::ArgAction::SetTrue)
+                .help("Open the output in $VISUAL/$EDITOR and copy what you save")
+        )
+        .arg(
+            Arg::new("keep-temp")
+                .long("keep-temp")
+                .action(clap::ArgAction::SetTrue)
+                .requires("edit")
+                .help("Keep the temporary file used by --edit")
+        )
+        .arg(
+            Arg::new("pager")
+                .long("pager")
+                .action(clap::ArgAction::SetTrue)
+                .help("Preview the output in $PAGER and confirm before copying")
+        )
+        .arg(
+            Arg::new("pick")
+                .long("pick")
+                .action(clap::ArgAction::SetTrue)
+                .help("Choose files with fzf (or $YOINK_PICKER) after scanning")
+        )
+        .arg(
+            Arg::new("pick-preview")
+                .long("pick-preview")
+                .action(clap::ArgAction::SetTrue)
+                .requires("pick")
+                .help("Preview the highlighted file while picking")
+        )
+        .arg(
+            Arg::new("serve")
+                .long("serve")
+                .action(clap::ArgAction::SetTrue)
+                .help("Serve the output once over HTTP at a one-time URL instead of copying it")
+        )
+        .arg(
+            Arg::new("bind")
+                .long("bind")
+                .value_name("ADDR")
+                .value_parser(clap::value_parser!(std::net::IpAddr))
+                .requires("serve")
+                .help("Address for --serve to listen on [default: 127.0.0.1]")
+        )
+        .arg(
+            Arg::new("serve-timeout")
+                .long("serve-timeout")
+                .value_name("SECS")
+                .value_parser(clap::value_parser!(u64))
+                .requires("serve")
+                .help("Give up if nobody fetches the output within SECS [default: 300]")
+        )
+        .arg(
+            Arg::new("copy")
+                .long("copy")
+                .action(clap::ArgAction::SetTrue)
+                .help("Also copy to the clipboard when the output goes elsewhere")
+        )
+        .arg(
+            Arg::new("stdin-name")
+                .long("stdin-name")
+                .value_name("NAME")
+                .help("Name to show in the header when PATH is - [default: <stdin>]")
+        )
+        .arg(
+            Arg::new("manifest-save")
+                .long("manifest-save")
+                .value_name("PATH")
+                .num_args(0..=1)
+                .default_missing_value("")
+                .help("Record a content hash for each file, to diff against later [default: under the cache dir]")
+        )
+        .arg(
+            Arg::new("manifest-diff")
+                .long("manifest-diff")
+                .value_name("PATH")
+                .num_args(0..=1)
+                .default_missing_value("")
+                .help("Only include files that are new or changed since a saved manifest")
+        )
+        .arg(
+            Arg::new("list")
+                .long("list")
+                .action(clap::ArgAction::SetTrue)
+                .help("Print the numbered files that would be yoinked, for use with --select")
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .action(clap::ArgAction::SetTrue)
+                .requires("list")
+                .help("Print the listing as one JSON object per line")
+        )
+        .arg(
+            Arg::new("all")
+                .long("all")
+                .action(clap::ArgAction::SetTrue)
+                .requires("json")
+                .help("Also list files the filters reject, with the reason")
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["list", "select", "pick", "watch", "stdout", "output"])
+                .help("Print the paths that would be yoinked, one per line, without reading or copying them; exits 1 if there are none")
+        )
+        .arg(
+            Arg::new("stats")
+                .long("stats")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["dry-run", "list", "select", "pick", "watch", "stdout", "output"])
+                .help("Print the count and size of the files that would be yoinked by extension, largest first, instead of copying them")
+        )
+        .arg(
+            Arg::new("select")
+                .long("select")
+                .value_name("INDICES")
+                .value_parser(crate::selection::parse_indices)
+                .conflicts_with("list")
+                .help("Only include these files from the last --list (e.g. 1,4-7,12)")
+        )
+        .arg(
+            Arg::new("compare-with")
+                .long("compare-with")
+                .value_name("FLAGS")
+                .allow_hyphen_values(true)
+                .conflicts_with_all(["list", "select", "explain"])
+                .help("Print which files would change if FLAGS (e.g. \"-x md\") were added to this command, without reading any")
+        )
+        .arg(
+            Arg::new("files-from")
+                .long("files-from")
+                .value_name("PATH")
+                .conflicts_with("select")
+                .help("Include exactly the files listed in PATH (one per line, relative to the root), in that order")
+        )
+        .arg(
+            Arg::new("emit-files-list")
+                .long("emit-files-list")
+                .action(clap::ArgAction::SetTrue)
+                .requires("list")
+                .conflicts_with("json")
+                .help("Print the listing in the format --files-from reads")
+        )
+        .arg(
+            Arg::new("preview")
+                .long("preview")
+                .action(clap::ArgAction::SetTrue)
+                .help("Show a digest of the output (first lines of each file) and confirm before copying")
+        )
+        .arg(
+            Arg::new("media-info")
+                .long("media-info")
+                .action(clap::ArgAction::SetTrue)
+                .help("List skipped images with their dimensions and size in an ASSETS section")
+        )
+        .arg(
+            Arg::new("git-meta")
+                .long("git-meta")
+                .action(clap::ArgAction::SetTrue)
+                .help("Annotate each file header with its last commit")
+        )
+        .arg(
+            Arg::new("submodules")
+                .long("submodules")
+                .value_name("MODE")
+                .value_parser(["skip", "include"])
+                .help("Skip git submodules or walk into them [default: skip]")
+        )
+        .arg(
+            Arg::new("nested-repos")
+                .long("nested-repos")
+                .value_name("MODE")
+                .value_parser(["skip", "include", "shallow"])
+                .help("Skip separate git repositories below the root, walk into them, or take only their README and manifests [default: skip]")
+        )
+        .arg(
+            Arg::new("branch")
+                .long("branch")
+                .value_name("BRANCH")
+                .help("Branch or tag to clone when PATH is a git URL")
+        )
+        .arg(
+            Arg::new("keep-clone")
+                .long("keep-clone")
+                .action(clap::ArgAction::SetTrue)
+                .help("Keep the temporary checkout of a remote repository instead of deleting it")
+        )
+        .arg(
+            Arg::new("report")
+                .long("report")
+                .value_name("PATH")
+                .help("Write a JSON report of the run (per-file outcomes, timings, git info) to PATH")
+        )
+        .arg(
+            Arg::new("timings")
+                .long("timings")
+                .action(clap::ArgAction::SetTrue)
+                .help("Print how long each phase took to stderr")
+        )
+        .arg(
+            Arg::new("strict")
+                .long("strict")
+                .action(clap::ArgAction::SetTrue)
+                .help("Abort on the first file, directory tree, or --files-from entry that fails instead of skipping it")
+        )
+        .arg(
+            Arg::new("cache")
+                .long("cache")
+                .action(clap::ArgAction::SetTrue)
+                .help("Cache file classifications between runs to speed up repeated yoinks")
+        )
+        .arg(
+            Arg::new("no-cache")
+                .long("no-cache")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("cache")
+                .help("Disable the classification cache")
+        )
+        .arg(
+            Arg::new("no-yoinkignore")
+                .long("no-yoinkignore")
+                .action(clap::ArgAction::SetTrue)
+                .help("Don't leave out what .yoinkignore in PATH or the global ignore file in the config dir matches")
+        )
+        .arg(
+            Arg::new("clear-cache")
+                .long("clear-cache")
+                .action(clap::ArgAction::SetTrue)
+                .help("Delete the classification cache for this path before running")
+        )
+        .arg(
+            Arg::new("no-history")
+                .long("no-history")
+                .action(clap::ArgAction::SetTrue)
+                .help("Don't record this run in the invocation log")
+        )
         .arg(
             Arg::new("save-config")
                 .long("save-config")
                 .action(clap::ArgAction::SetTrue)
                 .help("Save current configuration as default")
         )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .value_name("NAME")
+                .conflicts_with("no-config")
+                .help("Start from the saved profile NAME instead of the default config; with --save-config, save to it")
+        )
         .arg(
             Arg::new("no-config")
                 .long("no-config")
@@ -360,11 +1329,63 @@ pub fn build_cli() -> Command {
                 .value_name("TEXT")
                 .help("Search for text content within files")
         )
+        .arg(
+            Arg::new("search-regex")
+                .long("search-regex")
+                .value_name("RE")
+                .conflicts_with("search")
+                .value_parser(|text: &str| NameRegex::parse(text).map(|_| text.to_string()))
+                .help("Search for lines matching a regular expression; matches only span lines with the (?s) flag")
+        )
         .arg(
             Arg::new("case-sensitive")
                 .short('c')
                 .long("case-sensitive")
                 .action(clap::ArgAction::SetTrue)
-                .help("Make text search case-sensitive")
+                .help("Make text search, literal or regex, case-sensitive")
+        )
+        .arg(
+            Arg::new("word")
+                .long("word")
+                .action(clap::ArgAction::SetTrue)
+                .help("Only match the search text as a whole word, with no letter, digit, or _ right before or after it")
+        )
+        .arg(
+            Arg::new("symbol")
+                .long("symbol")
+                .value_name("NAME")
+                .action(clap::ArgAction::Append)
+                .conflicts_with_all(["search", "search-regex", "diff-content"])
+                .help("Only copy the Rust, Python, or JavaScript/TypeScript function, type, or class called NAME (repeatable)")
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Manage the saved configuration")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("edit")
+                        .about("Open the saved config in $EDITOR, keeping it only if it still loads")
+                )
+        )
+        .subcommand(
+            Command::new("history")
+                .about("List the runs recorded from this directory, most recent first (set \"history\": true in the config to record them)")
+                .arg(
+                    Arg::new("all-dirs")
+                        .long("all-dirs")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("List runs from every directory")
+                )
+        )
+        .subcommand(
+            Command::new("rerun")
+                .about("Run the Nth most recent invocation from this directory again")
+                .arg(
+                    Arg::new("n")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("1")
+                        .help("Which invocation, counting back from the latest")
+                )
         )
 }
\ No newline at end of file
@@ -1,35 +1,37 @@
-use std::process::{Command, Stdio};
-use std::io::Write;
-use std::thread;
+use std::fs::File;
+use std::process::{ChildStdin, Command, Stdio};
+use std::io::{self, Seek, Write};
 use std::time::Duration;
 use indicatif::{ProgressBar, ProgressStyle};
-use colored::*;
-use super::ClipboardInterface;
+use super::{ClipboardInterface, ClipboardReceipt};
+use crate::error::YoinkError;
+use crate::progress;
 
-pub struct ClipboardManager {
-    verbose: bool,
-}
+#[derive(Default)]
+pub struct ClipboardManager;
 
 impl ClipboardManager {
-    pub fn new(verbose: bool) -> Self {
-        Self { verbose }
+    pub fn new() -> Self {
+        Self
     }
 
-    fn try_copy_to_clipboard(&self, text: &str) -> Result<(), String> {
+    /// Try each known clipboard utility in turn, using `feed` to write the
+    /// content to its stdin, and return the name of the one that worked
+    fn try_copy_to_clipboard(&self, feed: &mut dyn FnMut(&mut ChildStdin) -> io::Result<()>) -> Result<String, YoinkError> {
         // Check for macOS
         let macos_methods = [
             (vec!["pbcopy"], "pbcopy (macOS)"),
         ];
-        if self.try_methods(&macos_methods, text)? {
-            return Ok(());
+        if let Some(name) = self.try_methods(&macos_methods, feed)? {
+            return Ok(name);
         }
 
         // Check for Android/Termux
         let termux_methods = [
             (vec!["termux-clipboard-set"], "termux-clipboard-set (Android/Termux)"),
         ];
-        if self.try_methods(&termux_methods, text)? {
-            return Ok(());
+        if let Some(name) = self.try_methods(&termux_methods, feed)? {
+            return Ok(name);
         }
 
         // Check for Linux/X11
@@ -38,16 +40,21 @@ impl ClipboardManager {
             (vec!["xsel", "-b"], "xsel (Linux/X11)"),
             (vec!["wl-copy"], "wl-copy (Wayland)"),
         ];
-        if self.try_methods(&linux_methods, text)? {
-            return Ok(());
+        if let Some(name) = self.try_methods(&linux_methods, feed)? {
+            return Ok(name);
         }
 
-        Err("No clipboard utility found. Please make sure you have one of the following installed: xclip, xsel (Linux/X11), wl-copy (Wayland), pbcopy (macOS), or termux-clipboard-set (Android/Termux)".to_string())
+        Err(YoinkError::NoClipboard)
     }
 
-    fn try_methods(&self, methods: &[(Vec<&str>, &str)], text: &str) -> Result<bool, String> {
+    fn try_methods(
+        &self,
+        methods: &[(Vec<&str>, &str)],
+        feed: &mut dyn FnMut(&mut ChildStdin) -> io::Result<()>,
+    ) -> Result<Option<String>, YoinkError> {
         for (cmd_args, name) in methods {
             if let Some(cmd) = cmd_args.first() {
+                log::trace!("Trying clipboard command {}", name);
                 match Command::new(cmd)
                     .args(&cmd_args[1..])
                     .stdin(Stdio::piped())
@@ -56,50 +63,68 @@ impl ClipboardManager {
                     .spawn() {
                     Ok(mut child) => {
                         if let Some(mut stdin) = child.stdin.take() {
-                            match stdin.write_all(text.as_bytes()) {
+                            match feed(&mut stdin) {
                                 Ok(_) => {
                                     drop(stdin);
                                     match child.wait() {
                                         Ok(exit) => {
                                             if exit.success() {
-                                                if self.verbose {
-                                                    println!("Text copied using {}", name);
-                                                }
-                                                return Ok(true);
+                                                log::debug!("Text copied using {}", name);
+                                                return Ok(Some(name.to_string()));
                                             }
                                         }
                                         Err(e) => {
-                                            if self.verbose {
-                                                println!("Error waiting for clipboard process to finish: {}", e);
-                                            }
+                                            log::trace!("Error waiting for clipboard process to finish: {}", e);
                                         }
                                     }
                                 }
                                 Err(e) => {
-                                    if self.verbose {
-                                        println!("Error writing to clipboard: {}", e);
-                                    }
+                                    log::trace!("Error writing to clipboard: {}", e);
                                 }
                             }
                         }
                     }
                     Err(e) => {
-                        if self.verbose {
-                            println!("Command '{}' not available: {}", cmd, e);
-                        }
+                        log::trace!("Command '{}' not available: {}", cmd, e);
                     }
                 }
             }
         }
         
-        Ok(false)
+        Ok(None)
     }
-}
 
-impl ClipboardInterface for ClipboardManager {
-    fn copy_to_clipboard(&self, text: &str) -> Result<(), String> {
+    /// Read the clipboard with the first known paste utility that works
+    fn try_paste(&self) -> Result<String, YoinkError> {
+        let methods: [&[&str]; 5] = [
+            &["pbpaste"],
+            &["termux-clipboard-get"],
+            &["xclip", "-selection", "clipboard", "-o"],
+            &["xsel", "-b", "-o"],
+            &["wl-paste", "--no-newline"],
+        ];
+        // Some utilities fail rather than print nothing when the clipboard is empty
+        let mut found = false;
+        for args in methods {
+            log::trace!("Trying paste command {}", args[0]);
+            match Command::new(args[0]).args(&args[1..]).stdin(Stdio::null()).stderr(Stdio::null()).output() {
+                Ok(output) if output.status.success() => {
+                    log::debug!("Clipboard read using {}", args[0]);
+                    return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+                }
+                Ok(output) => {
+                    log::trace!("Command '{}' failed: {}", args[0], output.status);
+                    found = true;
+                }
+                Err(e) => log::trace!("Command '{}' not available: {}", args[0], e),
+            }
+        }
+        if found { Ok(String::new()) } else { Err(YoinkError::NoClipboard) }
+    }
+
+    fn with_spinner<T>(&self, copy: impl FnOnce() -> T) -> T {
         // Show a progress spinner for clipboard operations
-        let pb = ProgressBar::new_spinner();
+        let pb = progress::add(ProgressBar::new_spinner());
         pb.set_style(
             ProgressStyle::default_spinner()
                 .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏")
@@ -109,11 +134,33 @@ impl ClipboardInterface for ClipboardManager {
         pb.set_message("Copying to clipboard...");
         pb.enable_steady_tick(Duration::from_millis(80));
 
-        let result = self.try_copy_to_clipboard(text);
+        let result = copy();
         
         // Finish the progress bar
-        pb.finish_and_clear();
+        progress::finish(&pb);
         
         result
     }
-} 
\ No newline at end of file
+}
+
+impl ClipboardInterface for ClipboardManager {
+    fn copy_to_clipboard(&self, text: &str) -> Result<ClipboardReceipt, YoinkError> {
+        let method = self.with_spinner(|| self.try_copy_to_clipboard(&mut |stdin| stdin.write_all(text.as_bytes())))?;
+        Ok(ClipboardReceipt { method, bytes: text.len() as u64 })
+    }
+
+    fn paste_from_clipboard(&self) -> Result<String, YoinkError> {
+        self.try_paste()
+    }
+
+    fn copy_from_file(&self, file: &mut File) -> Result<ClipboardReceipt, YoinkError> {
+        let bytes = file.metadata().map_err(|source| YoinkError::Output { source })?.len();
+        
+        // Stream straight from the file so the content is never held in memory
+        let method = self.with_spinner(|| self.try_copy_to_clipboard(&mut |stdin| {
+            file.rewind()?;
+            io::copy(file, stdin).map(|_| ())
+        }))?;
+        Ok(ClipboardReceipt { method, bytes })
+    }
+}
@@ -1,35 +1,35 @@
-use std::cell::RefCell;
-use super::ClipboardInterface;
+use std::sync::Mutex;
+use super::{ClipboardInterface, ClipboardReceipt};
+use crate::error::YoinkError;
 
 /// Mock implementation of ClipboardInterface for testing
+#[derive(Default)]
 pub struct MockClipboardManager {
-    verbose: bool,
-    copied_text: RefCell<Option<String>>,
+    copied_text: Mutex<Option<String>>,
 }
 
 impl MockClipboardManager {
-    pub fn new(verbose: bool) -> Self {
-        Self {
-            verbose,
-            copied_text: RefCell::new(None),
-        }
+    pub fn new() -> Self {
+        Self::default()
     }
 
     /// Get the text that was "copied" to the clipboard
     pub fn get_copied_text(&self) -> Option<String> {
-        self.copied_text.borrow().clone()
+        self.copied_text.lock().unwrap().clone()
     }
 }
 
 impl ClipboardInterface for MockClipboardManager {
-    fn copy_to_clipboard(&self, text: &str) -> Result<(), String> {
+    fn copy_to_clipboard(&self, text: &str) -> Result<ClipboardReceipt, YoinkError> {
         // Store the text instead of actually copying to clipboard
-        *self.copied_text.borrow_mut() = Some(text.to_string());
+        *self.copied_text.lock().unwrap() = Some(text.to_string());
         
-        if self.verbose {
-            println!("Mock clipboard: text copied (length: {})", text.len());
-        }
+        log::debug!("Mock clipboard: text copied (length: {})", text.len());
         
-        Ok(())
+        Ok(ClipboardReceipt { method: "mock".to_string(), bytes: text.len() as u64 })
     }
-} 
\ No newline at end of file
+
+    fn paste_from_clipboard(&self) -> Result<String, YoinkError> {
+        Ok(self.get_copied_text().unwrap_or_default())
+    }
+} 
@@ -7,8 +7,37 @@ pub use manager::ClipboardManager;
 #[cfg(test)]
 pub use mock::MockClipboardManager;
 
+use crate::error::YoinkError;
+use serde::Serialize;
+use std::any::Any;
+use std::fs::File;
+use std::io::{Read, Seek};
+
+/// Confirmation of a successful copy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClipboardReceipt {
+    /// Utility that received the content, e.g. "xclip (Linux/X11)"
+    pub method: String,
+    pub bytes: u64,
+}
+
 /// Trait defining the clipboard operations interface
-pub trait ClipboardInterface {
+pub trait ClipboardInterface: Any + Send + Sync {
     /// Copy text to the system clipboard
-    fn copy_to_clipboard(&self, text: &str) -> Result<(), String>;
+    fn copy_to_clipboard(&self, text: &str) -> Result<ClipboardReceipt, YoinkError>;
+
+    /// Read the text currently on the system clipboard
+    fn paste_from_clipboard(&self) -> Result<String, YoinkError>;
+
+    /// Copy the contents of a file to the system clipboard
+    ///
+    /// The default implementation reads the whole file into memory;
+    /// implementations that can stream should override it.
+    fn copy_from_file(&self, file: &mut File) -> Result<ClipboardReceipt, YoinkError> {
+        let mut text = String::new();
+        file.rewind()
+            .and_then(|_| file.read_to_string(&mut text))
+            .map_err(|source| YoinkError::Output { source })?;
+        self.copy_to_clipboard(&text)
+    }
 } 
\ No newline at end of file
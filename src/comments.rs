@@ -0,0 +1,271 @@
+//! Finding comments, for `--strip-comments` and highlighting in HTML output
+//!
+//! Each family of languages gets a small state machine that knows its comment
+//! markers and string quotes, which is enough to leave a marker inside a
+//! string literal alone without parsing the language. When stripping, lines
+//! left empty by a removed comment are dropped; other lines keep everything
+//! but the comment.
+
+use std::path::Path;
+
+/// The comment and string syntax shared by a family of languages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    /// `//` and `/* */`, with `"`, `'`, and backtick strings
+    CLike,
+    /// Like `CLike`, but `'` only starts a character literal that closes
+    /// right after, so lifetimes pass through, and raw strings are understood
+    Rust,
+    /// `#` at the start of a word, with `"` and `'` strings
+    Hash,
+    /// `<!-- -->`
+    Markup,
+}
+
+impl Syntax {
+    /// The syntax of `path`, by extension, if its comments are known
+    pub fn of(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Some(match extension.as_str() {
+            "rs" => Self::Rust,
+            "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "hh" | "java" | "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "go" | "swift"
+            | "kt" | "kts" | "scala" | "cs" | "dart" => Self::CLike,
+            "py" | "sh" | "bash" | "zsh" | "fish" | "toml" | "yaml" | "yml" | "rb" | "pl" | "r" => Self::Hash,
+            "html" | "htm" | "xhtml" | "xml" | "svg" => Self::Markup,
+            _ => return None,
+        })
+    }
+
+    // The comment starting at `at`, as the length of its opening marker and
+    // its closing marker, or no closing marker for one that runs to the end
+    // of the line
+    fn comment_at(self, source: &str, at: usize) -> Option<(usize, Option<&'static str>)> {
+        let rest = &source[at..];
+        match self {
+            Self::CLike | Self::Rust if rest.starts_with("//") => Some((2, None)),
+            Self::CLike | Self::Rust if rest.starts_with("/*") => Some((2, Some("*/"))),
+            Self::Hash if rest.starts_with('#') && !(at == 0 && rest.starts_with("#!")) => {
+                source[..at].chars().next_back().is_none_or(char::is_whitespace).then_some((1, None))
+            }
+            Self::Markup if rest.starts_with("<!--") => Some((4, Some("-->"))),
+            _ => None,
+        }
+    }
+
+    // Where the string literal starting at `at` ends, if one starts there
+    fn string_end(self, source: &str, at: usize) -> Option<usize> {
+        let rest = &source[at..];
+        let quote = rest.chars().next()?;
+        let previous = source[..at].chars().next_back();
+        match (self, quote) {
+            (Self::CLike, '"' | '\'' | '`') | (Self::Rust, '"') => Some(quoted_end(source, at, quote)),
+            (Self::Rust, '\'') => char_literal_end(source, at),
+            (Self::Rust, 'r' | 'b') if !previous.is_some_and(is_identifier) => raw_string_end(source, at),
+            (Self::Hash, '"' | '\'') if !previous.is_some_and(char::is_alphanumeric) => Some(quoted_end(source, at, quote)),
+            _ => None,
+        }
+    }
+}
+
+/// What a stretch of source is, as far as comments go
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    Code,
+    Comment,
+    /// A string or character literal, quotes included
+    String,
+}
+
+/// `source` as consecutive stretches of code, comments, and string literals
+///
+/// A comment that runs to the end of its line stops before the line ending.
+pub fn tokens(syntax: Syntax, source: &str) -> Vec<(Token, &str)> {
+    let mut tokens = Vec::new();
+    let (mut code, mut at) = (0, 0);
+    while let Some(c) = source[at..].chars().next() {
+        let found = match syntax.comment_at(source, at) {
+            Some((open, close)) => Some((Token::Comment, comment_end(source, at + open, close))),
+            None => syntax.string_end(source, at).map(|end| (Token::String, end)),
+        };
+        let Some((token, end)) = found else {
+            at += c.len_utf8();
+            continue;
+        };
+        if code < at {
+            tokens.push((Token::Code, &source[code..at]));
+        }
+        tokens.push((token, &source[at..end]));
+        (code, at) = (end, end);
+    }
+    if code < source.len() {
+        tokens.push((Token::Code, &source[code..]));
+    }
+    tokens
+}
+
+/// `source` without its comments
+pub fn strip(syntax: Syntax, source: &str) -> String {
+    let mut out = Output { text: String::with_capacity(source.len()), line_start: 0, cut: false };
+    for (token, text) in tokens(syntax, source) {
+        match token {
+            Token::Comment => out.cut = true,
+            Token::String => out.push_str(text),
+            Token::Code => {
+                for line in text.split_inclusive('\n') {
+                    match line.strip_suffix('\n') {
+                        Some(line) => {
+                            out.text.push_str(line);
+                            out.end_line();
+                        }
+                        None => out.text.push_str(line),
+                    }
+                }
+            }
+        }
+    }
+    out.finish()
+}
+
+// Just past the comment whose body starts at `body`, leaving the line ending,
+// carriage return included, to a comment without a closing marker
+fn comment_end(source: &str, body: usize, close: Option<&str>) -> usize {
+    match close {
+        Some(close) => source[body..].find(close).map_or(source.len(), |end| body + end + close.len()),
+        None => source[body..].find('\n').map_or(source.len(), |end| {
+            let end = body + end;
+            if source[..end].ends_with('\r') { end - 1 } else { end }
+        }),
+    }
+}
+
+// The stripped text, tracking whether a comment was cut from the current line
+struct Output {
+    text: String,
+    line_start: usize,
+    cut: bool,
+}
+
+impl Output {
+    fn push_str(&mut self, text: &str) {
+        self.text.push_str(text);
+        if let Some(newline) = text.rfind('\n') {
+            self.line_start = self.text.len() - text.len() + newline + 1;
+            self.cut = false;
+        }
+    }
+
+    fn end_line(&mut self) {
+        if self.tidy_line() {
+            self.text.push('\n');
+        }
+        self.line_start = self.text.len();
+        self.cut = false;
+    }
+
+    fn finish(mut self) -> String {
+        self.tidy_line();
+        self.text
+    }
+
+    // Drop the current line if cutting a comment left it blank, or the
+    // whitespace the comment followed; returns whether the line is kept
+    fn tidy_line(&mut self) -> bool {
+        if !self.cut {
+            return true;
+        }
+        let line = &self.text[self.line_start..];
+        if line.trim().is_empty() {
+            self.text.truncate(self.line_start);
+            return false;
+        }
+        let carriage_return = line.ends_with('\r');
+        let kept = self.line_start + line.trim_end().len();
+        self.text.truncate(kept);
+        if carriage_return {
+            self.text.push('\r');
+        }
+        true
+    }
+}
+
+fn is_identifier(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// Just past the `quote` closing the string opened at `start`, skipping escaped
+// quotes, or the end of `source` for a string never closed
+fn quoted_end(source: &str, start: usize, quote: char) -> usize {
+    let mut escaped = false;
+    for (offset, c) in source[start + 1..].char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == quote => return start + 1 + offset + c.len_utf8(),
+            _ => {}
+        }
+    }
+    source.len()
+}
+
+// A Rust character literal such as 'a' or '\u{1F600}', as opposed to a lifetime
+fn char_literal_end(source: &str, start: usize) -> Option<usize> {
+    let body = &source[start + 1..];
+    let mut chars = body.chars();
+    let first = chars.next()?;
+    let length = if first == '\\' {
+        body.char_indices().skip(2).take(10).find(|&(_, c)| c == '\'').map(|(offset, _)| offset)?
+    } else {
+        (chars.next()? == '\'').then_some(first.len_utf8())?
+    };
+    Some(start + 1 + length + 1)
+}
+
+// A Rust raw string such as r"..." or br#"..."#
+fn raw_string_end(source: &str, start: usize) -> Option<usize> {
+    let rest = &source[start..];
+    let rest = rest.strip_prefix('b').unwrap_or(rest).strip_prefix('r')?;
+    let hashes = rest.len() - rest.trim_start_matches('#').len();
+    let body = rest[hashes..].strip_prefix('"')?;
+    let close = format!("\"{}", "#".repeat(hashes));
+    let opened = source.len() - body.len();
+    Some(body.find(&close).map_or(source.len(), |end| opened + end + close.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_c_like_comments_go_and_strings_stay() {
+        let source = "/* License\n * MIT\n */\nconst url = \"http://example.com\"; // home\nlet s = '/* not */';\n\n/// Doc\nfn f() {} /* inline */ g();\n";
+        assert_eq!(
+            strip(Syntax::CLike, source),
+            "const url = \"http://example.com\";\nlet s = '/* not */';\n\nfn f() {}  g();\n"
+        );
+    }
+
+    #[test]
+    fn test_rust_lifetimes_chars_and_raw_strings() {
+        let source = "fn f<'a>(s: &'a str) -> char { // first\n    let q = '\"'; let r = r#\"a \"// b\"#; // end\r\n    '\\''\n}";
+        assert_eq!(
+            strip(Syntax::Rust, source),
+            "fn f<'a>(s: &'a str) -> char {\n    let q = '\"'; let r = r#\"a \"// b\"#;\r\n    '\\''\n}"
+        );
+    }
+
+    #[test]
+    fn test_hash_and_markup_comments() {
+        let python = "#!/usr/bin/env python\n# setup\nx = \"#1\"  # count\nurl: http://host/#anchor\nname = 'it''s'\n";
+        assert_eq!(strip(Syntax::Hash, python), "#!/usr/bin/env python\nx = \"#1\"\nurl: http://host/#anchor\nname = 'it''s'\n");
+        assert_eq!(strip(Syntax::Markup, "<!-- head -->\n<p>a<!-- b --></p>\n"), "<p>a</p>\n");
+        assert_eq!(Syntax::of(Path::new("notes.txt")), None);
+    }
+
+    #[test]
+    fn test_tokens_cover_the_source() {
+        assert_eq!(
+            tokens(Syntax::CLike, "a(\"//\"); // b\nc"),
+            [(Token::Code, "a("), (Token::String, "\"//\""), (Token::Code, "); "), (Token::Comment, "// b"), (Token::Code, "\nc")]
+        );
+    }
+}
@@ -0,0 +1,119 @@
+//! Which files two sets of flags would yoink differently, for `--compare-with`
+
+use crate::utils;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// A file only one side of a comparison includes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComparedFile {
+    /// Relative to the scan root
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+/// The difference between the files the current flags and the other flags include
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Comparison {
+    pub only_current: Vec<ComparedFile>,
+    pub only_other: Vec<ComparedFile>,
+    /// Files both include
+    pub shared: usize,
+}
+
+impl Comparison {
+    /// Compare two listings, sizing the files only one of them has with `size`
+    pub fn new(current: &[PathBuf], other: &[PathBuf], size: impl Fn(&Path) -> u64) -> Self {
+        let current: BTreeSet<&PathBuf> = current.iter().collect();
+        let other: BTreeSet<&PathBuf> = other.iter().collect();
+        let sized = |paths: std::collections::btree_set::Difference<'_, &PathBuf>| {
+            paths.map(|path| ComparedFile { path: path.to_path_buf(), size: size(path) }).collect()
+        };
+        Self {
+            only_current: sized(current.difference(&other)),
+            only_other: sized(other.difference(&current)),
+            shared: current.intersection(&other).count(),
+        }
+    }
+
+    /// Compare two listings of files under `root`, sizing them from disk
+    pub fn of_listings(root: &Path, current: &[PathBuf], other: &[PathBuf]) -> Self {
+        let base = utils::relative_base(root);
+        Self::new(current, other, |path| base.join(path).metadata().map_or(0, |m| m.len()))
+    }
+}
+
+/// Combined size of `files`
+pub fn total_size(files: &[ComparedFile]) -> u64 {
+    files.iter().map(|file| file.size).sum()
+}
+
+/// The command line `args` with its `--compare-with` option replaced by the
+/// flags it names, so they're parsed after, and can override, the rest
+pub fn other_args(args: impl IntoIterator<Item = OsString>, flags: &str) -> Result<Vec<OsString>, String> {
+    let mut other = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--compare-with" {
+            args.next();
+        } else if !arg.to_string_lossy().starts_with("--compare-with=") {
+            other.push(arg);
+        }
+    }
+    other.extend(split_flags(flags)?.into_iter().map(OsString::from));
+    Ok(other)
+}
+
+/// Split a flag string on whitespace, keeping quoted parts together
+pub fn split_flags(flags: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    for c in flags.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if let Some(q) = quote {
+        return Err(format!("unmatched {} in '{}'", q, flags));
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_flags_keeps_quoted_words() {
+        assert_eq!(split_flags(r#"-x md --pattern "src/**/*.rs"  -e ''"#).unwrap(), ["-x", "md", "--pattern", "src/**/*.rs", "-e", ""]);
+        assert!(split_flags("--pattern 'src").is_err());
+    }
+
+    #[test]
+    fn test_other_args_replace_the_compare_option() {
+        let args = ["yoink", "src", "--compare-with", "-x md", "-e", "rs,md", "--compare-with=-s"].map(OsString::from);
+        assert_eq!(other_args(args, "-x md").unwrap(), ["yoink", "src", "-e", "rs,md", "-x", "md"].map(OsString::from));
+    }
+
+    #[test]
+    fn test_comparison_splits_files_by_side() {
+        let paths = |names: &[&str]| names.iter().map(PathBuf::from).collect::<Vec<_>>();
+        let comparison = Comparison::new(&paths(&["a.rs", "b.md", "c.md"]), &paths(&["a.rs", "d.txt"]), |path| path.as_os_str().len() as u64);
+
+        assert_eq!(comparison.only_current.iter().map(|f| f.path.to_str().unwrap()).collect::<Vec<_>>(), ["b.md", "c.md"]);
+        assert_eq!(comparison.only_other, [ComparedFile { path: PathBuf::from("d.txt"), size: 5 }]);
+        assert_eq!(comparison.shared, 1);
+        assert_eq!(total_size(&comparison.only_current), 8);
+    }
+}
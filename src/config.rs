@@ -0,0 +1,2104 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::io::{Read, Write};
+use std::time::Duration;
+use crate::error::YoinkError;
+use serde::{Deserialize, Serialize};
+use crate::manifest::ManifestLocation;
+use crate::dedup::DedupMode;
+use crate::fit::FitStrategy;
+use crate::append::AppendMode;
+use crate::file_tree::TreeBudget;
+use crate::formatter::FormatTemplate;
+use crate::header::HeaderTemplate;
+use crate::name_regex::NameRegex;
+use crate::modified::TimeBound;
+use crate::target::Target;
+
+/// What to do with git submodules found under the root
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubmoduleMode {
+    /// Leave them out, showing each as a single collapsed line in the tree
+    #[default]
+    Skip,
+    /// Walk into them like any other directory
+    Include,
+}
+
+/// What to do with separate git repositories nested under the root
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NestedRepoMode {
+    /// Leave them out, showing each as a single collapsed line in the tree
+    #[default]
+    Skip,
+    /// Walk into them like any other directory
+    Include,
+    /// Include only each repository's top-level README and manifests
+    Shallow,
+}
+
+/// What to do with files over the size limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OversizeMode {
+    /// Leave them out, counting them in the summary
+    #[default]
+    Skip,
+    /// Include a stub with their size, line count, and first and last lines
+    Summarize,
+}
+
+/// How each file is laid out in the output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// `=== path ===` headers over the raw content
+    #[default]
+    Plain,
+    /// `## path` headings over fenced code blocks tagged with the language
+    Markdown,
+    /// `<document path="...">` elements inside a single `<documents>` root
+    Xml,
+    /// A self-contained page with a collapsible `<details>` element per file
+    Html,
+}
+
+/// Overrides for files under one directory of the scan root
+///
+/// When several rules match a file, the one with the longest `path` wins.
+/// A rule that sets either extension list replaces both global lists.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathRule {
+    /// Directory prefix relative to the scan root, e.g. `docs/adr`
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_extensions: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude_extensions: Option<Vec<String>>,
+    /// Size limit in bytes, stored as MB in the config file
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "size::option")]
+    pub max_size: Option<u64>,
+    /// Leave out everything under `path`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip: bool,
+}
+
+impl PathRule {
+    fn prefix(&self) -> &Path {
+        let trimmed = self.path.trim_start_matches("./").trim_end_matches('/');
+        Path::new(trimmed)
+    }
+
+    /// Whether this rule's extension lists replace the global ones
+    pub fn filters_extensions(&self) -> bool {
+        self.include_extensions.is_some() || self.exclude_extensions.is_some()
+    }
+}
+
+// Sizes in the config file are a number of MB, or a string with a unit
+// such as "512K" when they aren't whole megabytes
+mod size {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    const MB: u64 = 1024 * 1024;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Size {
+        Megabytes(u64),
+        Text(String),
+    }
+
+    impl Size {
+        fn bytes(self) -> Result<u64, String> {
+            match self {
+                Size::Megabytes(mb) => mb.checked_mul(MB).ok_or_else(|| format!("{} MB is too large", mb)),
+                Size::Text(text) => crate::utils::parse_size(&text),
+            }
+        }
+    }
+
+    pub fn serialize<S: Serializer>(bytes: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        match bytes {
+            bytes if bytes % MB == 0 => serializer.serialize_u64(bytes / MB),
+            bytes if bytes % 1024 == 0 => serializer.serialize_str(&format!("{}K", bytes / 1024)),
+            bytes => serializer.serialize_str(&format!("{}B", bytes)),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        Size::deserialize(deserializer)?.bytes().map_err(D::Error::custom)
+    }
+
+    pub mod option {
+        use super::Size;
+        use serde::de::Error;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(bytes: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error> {
+            match bytes {
+                Some(bytes) => super::serialize(bytes, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+            Option::<Size>::deserialize(deserializer)?.map(Size::bytes).transpose().map_err(D::Error::custom)
+        }
+    }
+}
+
+// Globs are written as their pattern strings, and an invalid one fails the load
+mod globs {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    fn parse<E: Error>(pattern: &str) -> Result<glob::Pattern, E> {
+        glob::Pattern::new(pattern).map_err(|e| E::custom(format!("invalid glob '{}': {}", pattern, e)))
+    }
+
+    pub fn serialize<S: Serializer>(patterns: &[glob::Pattern], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(patterns.iter().map(glob::Pattern::as_str))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<glob::Pattern>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?.iter().map(|p| parse(p)).collect()
+    }
+
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(pattern: &Option<glob::Pattern>, serializer: S) -> Result<S::Ok, S::Error> {
+            match pattern {
+                Some(pattern) => serializer.serialize_str(pattern.as_str()),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<glob::Pattern>, D::Error> {
+            Option::<String>::deserialize(deserializer)?.map(|p| super::parse(&p)).transpose()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub path: String,
+    /// Further roots yoinked alongside `path`, which alone decides where
+    /// things like the cache, diffs, and git metadata come from
+    pub extra_paths: Vec<String>,
+    /// Names to show roots under in place of their directory names, each
+    /// paired with the path given for its root, in the order given
+    pub labels: Vec<(String, String)>,
+    pub max_size: u64,
+    /// Lines of each file to include before cutting it off with a marker
+    pub max_lines: Option<usize>,
+    pub verbose: bool,
+    pub max_depth: u32,
+    pub include_extensions: Option<Vec<String>>,
+    pub exclude_extensions: Option<Vec<String>>,
+    pub exclude_paths: Option<Vec<String>>,
+    /// Globs for paths relative to the root to leave out, along with anything under them
+    pub exclude_globs: Vec<glob::Pattern>,
+    pub pattern: Option<glob::Pattern>,
+    /// File name globs to leave out, even when they match `pattern`
+    pub exclude_patterns: Vec<glob::Pattern>,
+    /// Regular expression that file names must also match
+    pub regex: Option<NameRegex>,
+    pub skip_hidden: bool,
+    pub sort: bool,
+    pub save_config: bool,
+    pub search_text: Option<String>,
+    /// Whether `search_text` is a regular expression rather than literal text
+    pub search_regex: bool,
+    pub case_sensitive: bool,
+    /// Only count a search match with no word character right before or after it
+    pub whole_word: bool,
+    pub low_memory: bool,
+    pub watch: bool,
+    pub cache: bool,
+    pub strict: bool,
+    pub memory_limit: Option<u64>,
+    /// Most bytes of file content in the output; files past it are left out
+    /// and listed, `None` for no limit
+    pub max_total: Option<u64>,
+    pub submodules: SubmoduleMode,
+    pub diff_content: Option<String>,
+    pub git_meta: bool,
+    pub edit: bool,
+    pub keep_temp: bool,
+    pub pager: bool,
+    pub pick: bool,
+    pub pick_preview: bool,
+    pub serve: bool,
+    pub bind: IpAddr,
+    pub serve_timeout: Duration,
+    /// Copy to the clipboard even when the output also goes somewhere else
+    pub copy: bool,
+    /// Header name for content read from stdin
+    pub stdin_name: Option<String>,
+    /// Record content hashes of this run's files
+    pub manifest_save: Option<ManifestLocation>,
+    /// Only include files that changed since this manifest
+    pub manifest_diff: Option<ManifestLocation>,
+    /// Print numbered candidates instead of yoinking them
+    pub list: bool,
+    /// Print the listing as JSON lines
+    pub list_json: bool,
+    /// Also list files the filters reject
+    pub list_all: bool,
+    /// Print the files that would be yoinked and the counts, without reading them
+    pub dry_run: bool,
+    /// Print file counts and sizes by extension instead of yoinking
+    pub stats: bool,
+    /// 1-based indices into the last listing to include
+    pub select: Option<Vec<usize>>,
+    /// Include exactly the files named in this list, in its order
+    pub files_from: Option<PathBuf>,
+    /// Print the listing as a list `files_from` can read back
+    pub emit_files_list: bool,
+    pub preview: bool,
+    /// List images with their dimensions instead of only counting them
+    pub media_info: bool,
+    /// Per-directory overrides from the config file
+    pub rules: Vec<PathRule>,
+    /// Estimated token budget for the whole output
+    pub max_tokens: Option<u64>,
+    pub fit: FitStrategy,
+    /// Lines every file keeps before the rest of the budget is shared out
+    pub fit_min_lines: usize,
+    /// Context window to warn about overflowing, and to fit with `--fit`
+    pub target: Option<Target>,
+    /// Collapse files whose content repeats an earlier file
+    pub dedup: Option<DedupMode>,
+    pub oversize: OversizeMode,
+    pub format: OutputFormat,
+    /// Template laying the output out instead of `format`, from `--format custom:<path>`
+    pub format_template: Option<FormatTemplate>,
+    /// Number of candidate files to pick at random
+    pub sample: Option<usize>,
+    /// Seed for sampling, random when unset
+    pub seed: Option<u64>,
+    /// Print why this file, or each file under it, would be included or skipped
+    pub explain: Option<PathBuf>,
+    /// Globs for files that are always treated as text, skipping detection
+    pub force_text: Vec<glob::Pattern>,
+    /// Globs for files that are always treated as binary; these win over `force_text`
+    pub force_binary: Vec<glob::Pattern>,
+    /// Sniff every file's content, letting it overrule what the extension suggests
+    pub sniff_all: bool,
+    /// Write files grouped by directory, each group under its own header
+    pub group_by_dir: bool,
+    /// How many directory levels make up a group
+    pub group_depth: usize,
+    /// Workspace members to restrict the yoink to, by package name or path
+    pub packages: Vec<String>,
+    /// Print the workspace's members instead of yoinking
+    pub list_packages: bool,
+    pub nested_repos: NestedRepoMode,
+    /// Write the output to this file instead of the clipboard
+    pub output: Option<PathBuf>,
+    /// Write the output to stdout instead of the clipboard
+    pub stdout: bool,
+    /// Add the output to yoink output already on the clipboard
+    pub append: Option<AppendMode>,
+    /// Also write each included file's chunk to its own file under this directory
+    pub output_dir: Option<PathBuf>,
+    /// Overwrite `output`, or write into `output_dir` when it already has files in it
+    pub force: bool,
+    /// Most characters in each piece the output is split into
+    pub chunk_size: Option<usize>,
+    /// 1-based chunk to deliver when the output is split
+    pub chunk: Option<usize>,
+    /// Most files any one directory may contribute
+    pub max_files_per_dir: Option<usize>,
+    /// Most bytes any one directory may contribute
+    pub max_bytes_per_dir: Option<u64>,
+    /// Leave out test files, and test modules in Rust files
+    pub skip_tests: bool,
+    /// Test patterns on top of the built-in ones, in the same form
+    pub test_patterns: Vec<String>,
+    /// Leave out lockfiles, minified bundles, and source maps
+    pub skip_generated: bool,
+    /// Generated-file name globs on top of the built-in ones
+    pub generated_patterns: Vec<String>,
+    /// Walk into symlinked directories rather than past them
+    pub follow_links: bool,
+    /// Only scan files git tracks, as `git ls-files` lists them
+    pub git_tracked: bool,
+    /// With `git_tracked`, also take untracked files that aren't ignored
+    pub git_others: bool,
+    /// Leave out files last modified before this
+    pub since: Option<TimeBound>,
+    /// Leave out files last modified after this
+    pub until: Option<TimeBound>,
+    /// Refuse to run when the filters contradict each other, rather than warn
+    pub strict_filters: bool,
+    /// How long reading any one file may take before it's abandoned, zero to
+    /// wait forever; `None` times only reads from network mounts, after
+    /// [`crate::deadline::NETWORK_TIMEOUT`]
+    pub file_timeout: Option<Duration>,
+    /// List what's directly inside each excluded directory, without its content
+    pub excluded_overview: bool,
+    /// Most the directory tree may take up before it loses detail
+    pub tree_budget: Option<TreeBudget>,
+    /// Deepest the scan and the tree go, as a guard against runaway nesting
+    pub depth_ceiling: usize,
+    /// Prefix each line of a file's content with its line number
+    pub line_numbers: bool,
+    /// List each file's lines and characters in the summary
+    pub detailed_summary: bool,
+    /// Show paths in headers as walked rather than relative to the scanned root
+    pub absolute_paths: bool,
+    /// Remove comments from files whose language is known by extension
+    pub strip_comments: bool,
+    /// Collapse runs of blank lines and strip trailing whitespace from each file
+    pub condense: bool,
+    /// The line above each file in the plain format
+    pub header_format: HeaderTemplate,
+    /// A line below each file in the plain format, or nothing but a blank line
+    pub footer_format: Option<HeaderTemplate>,
+    /// Leave out what `.yoinkignore` and the global ignore file match
+    pub yoinkignore: bool,
+    /// Only include the functions, types, and classes with these names
+    pub symbols: Vec<String>,
+    /// Record each run in the invocation log, for `yoink history` and `yoink rerun`
+    pub history: bool,
+    /// Keep sensitive values such as search terms in the invocation log
+    pub history_full: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            path: ".".to_string(),
+            extra_paths: Vec::new(),
+            labels: Vec::new(),
+            max_size: 10 * 1024 * 1024,
+            max_lines: None,
+            verbose: false,
+            max_depth: u32::MAX,
+            include_extensions: None,
+            exclude_extensions: None,
+            exclude_paths: None,
+            exclude_globs: Vec::new(),
+            pattern: None,
+            exclude_patterns: Vec::new(),
+            regex: None,
+            skip_hidden: false,
+            sort: false,
+            save_config: false,
+            search_text: None,
+            search_regex: false,
+            case_sensitive: false,
+            whole_word: false,
+            low_memory: false,
+            watch: false,
+            cache: false,
+            strict: false,
+            memory_limit: None,
+            max_total: Some(50 * 1024 * 1024),
+            submodules: SubmoduleMode::Skip,
+            diff_content: None,
+            git_meta: false,
+            edit: false,
+            keep_temp: false,
+            pager: false,
+            pick: false,
+            pick_preview: false,
+            serve: false,
+            bind: IpAddr::from([127, 0, 0, 1]),
+            serve_timeout: Duration::from_secs(300),
+            copy: false,
+            stdin_name: None,
+            manifest_save: None,
+            manifest_diff: None,
+            list: false,
+            list_json: false,
+            list_all: false,
+            dry_run: false,
+            stats: false,
+            select: None,
+            files_from: None,
+            emit_files_list: false,
+            preview: false,
+            media_info: false,
+            rules: Vec::new(),
+            max_tokens: None,
+            fit: FitStrategy::Drop,
+            fit_min_lines: 10,
+            target: None,
+            dedup: None,
+            oversize: OversizeMode::Skip,
+            format: OutputFormat::Plain,
+            format_template: None,
+            sample: None,
+            seed: None,
+            explain: None,
+            force_text: Vec::new(),
+            force_binary: Vec::new(),
+            sniff_all: false,
+            group_by_dir: false,
+            group_depth: 1,
+            packages: Vec::new(),
+            list_packages: false,
+            nested_repos: NestedRepoMode::Skip,
+            output: None,
+            stdout: false,
+            append: None,
+            output_dir: None,
+            force: false,
+            chunk_size: None,
+            chunk: None,
+            max_files_per_dir: None,
+            max_bytes_per_dir: None,
+            skip_tests: false,
+            test_patterns: Vec::new(),
+            skip_generated: false,
+            generated_patterns: Vec::new(),
+            follow_links: false,
+            git_tracked: false,
+            git_others: false,
+            since: None,
+            until: None,
+            strict_filters: false,
+            file_timeout: None,
+            excluded_overview: false,
+            tree_budget: None,
+            depth_ceiling: crate::depth::DEFAULT_CEILING,
+            line_numbers: false,
+            detailed_summary: false,
+            absolute_paths: false,
+            strip_comments: false,
+            condense: false,
+            header_format: HeaderTemplate::default(),
+            footer_format: None,
+            yoinkignore: true,
+            symbols: Vec::new(),
+            history: false,
+            history_full: false,
+        }
+    }
+}
+
+impl Config {
+    /// Start building a configuration without going through the command line
+    ///
+    /// ```
+    /// let config = yoink::Config::builder()
+    ///     .path("src")
+    ///     .include_extensions(["rs", "toml"])
+    ///     .skip_hidden(true)
+    ///     .build();
+    ///
+    /// assert_eq!(config.path, "src");
+    /// assert!(config.skip_hidden);
+    /// ```
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
+    /// The most specific rule covering `relative`, a path under the scan root
+    pub fn rule_for(&self, relative: &Path) -> Option<&PathRule> {
+        self.rules
+            .iter()
+            .filter(|rule| relative.starts_with(rule.prefix()))
+            .max_by_key(|rule| rule.prefix().components().count())
+    }
+
+    /// Size limit for a file, taking rules into account
+    pub fn max_size_for(&self, relative: &Path) -> u64 {
+        self.rule_for(relative).and_then(|rule| rule.max_size).unwrap_or(self.max_size)
+    }
+
+    /// Whether an override decides that `relative` is text, and the glob that did
+    pub fn forced_kind(&self, relative: &Path) -> Option<(bool, &glob::Pattern)> {
+        let binary = self.force_binary.iter().find(|p| p.matches_path(relative));
+        let text = || self.force_text.iter().find(|p| p.matches_path(relative));
+        binary.map(|pattern| (false, pattern)).or_else(|| text().map(|pattern| (true, pattern)))
+    }
+
+    /// The `--exclude-pattern` glob matching the file name `name`
+    pub fn excluding_pattern(&self, name: &str) -> Option<&glob::Pattern> {
+        self.exclude_patterns.iter().find(|pattern| pattern.matches(name))
+    }
+
+    /// The `--exclude-glob` pattern matching `relative` or a directory above it
+    ///
+    /// `*` stops at a `/`, and a directory also matches as `dir/`, so
+    /// `**/__snapshots__/**` takes out the directory as well as its files.
+    pub fn excluding_glob(&self, relative: &Path, is_dir: bool) -> Option<&glob::Pattern> {
+        const MATCH: glob::MatchOptions = glob::MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        if self.exclude_globs.is_empty() {
+            return None;
+        }
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        let mut candidates: Vec<&str> = relative
+            .match_indices('/')
+            .flat_map(|(end, _)| [&relative[..end], &relative[..=end]])
+            .collect();
+        let dir = format!("{}/", relative);
+        candidates.push(&relative);
+        if is_dir {
+            candidates.push(&dir);
+        }
+        self.exclude_globs.iter().find(|pattern| candidates.iter().any(|path| pattern.matches_with(path, MATCH)))
+    }
+
+    /// Filters that can't all hold, so that no file could be yoinked
+    pub fn filter_conflicts(&self) -> Vec<FilterConflict> {
+        let mut conflicts = Vec::new();
+        let included = self.include_extensions.as_deref().unwrap_or_default();
+        let excluded = self.exclude_extensions.as_deref().unwrap_or_default();
+
+        let both: Vec<String> = included.iter().filter(|ext| excluded.contains(ext)).cloned().collect();
+        if !both.is_empty() {
+            conflicts.push(FilterConflict::IncludedAndExcluded { extensions: both });
+        }
+
+        if let Some(pattern) = &self.pattern {
+            if let Some(extension) = pattern_extension(pattern.as_str()) {
+                let pattern = pattern.as_str().to_string();
+                if excluded.contains(&extension) {
+                    conflicts.push(FilterConflict::PatternExcluded { pattern, extension });
+                } else if self.include_extensions.is_some() && !included.contains(&extension) {
+                    conflicts.push(FilterConflict::PatternNotIncluded { pattern, extension });
+                }
+            }
+        }
+
+        // Depth 0 is the root itself, which only yields a file when the root is one
+        if self.max_depth == 0 && Path::new(&self.path).is_dir() {
+            conflicts.push(FilterConflict::ZeroDepth);
+        }
+        conflicts
+    }
+
+    /// Reject rules that share a prefix, since neither could win
+    pub fn validate_rules(rules: &[PathRule]) -> Result<(), String> {
+        let mut seen = std::collections::HashSet::new();
+        for rule in rules {
+            if !seen.insert(rule.prefix()) {
+                return Err(format!("more than one rule for '{}'", rule.prefix().display()));
+            }
+        }
+        Ok(())
+    }
+
+    /// `path` and then each of `extra_paths`
+    pub fn roots(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.path.as_str()).chain(self.extra_paths.iter().map(String::as_str))
+    }
+
+    /// Directory that scanned paths are shown and matched relative to: that
+    /// of the single root, or the deepest one all the roots share, `.` if none
+    pub fn base(&self) -> PathBuf {
+        let mut base = crate::utils::relative_base(Path::new(&self.path)).to_path_buf();
+        for root in &self.extra_paths {
+            let other = crate::utils::relative_base(Path::new(root));
+            base = base.components().zip(other.components()).take_while(|(a, b)| a == b).map(|(a, _)| a).collect();
+        }
+        if base.as_os_str().is_empty() {
+            base.push(".");
+        }
+        base
+    }
+
+    /// Whether the path is `-`, meaning a single document is read from stdin
+    pub fn reads_stdin(&self) -> bool {
+        self.path == "-"
+    }
+
+    /// Where the saved config lives
+    pub fn get_config_path() -> PathBuf {
+        let mut path = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
+        path.push("yoink");
+        path.push("config.json");
+        path
+    }
+
+    /// Save the settings worth keeping as the saved config, or as its
+    /// `profile`, returning where they were written
+    pub fn save_to_file(&self, profile: Option<&str>) -> Result<PathBuf, YoinkError> {
+        let config_path = Self::get_config_path();
+        self.save_to(&config_path, profile)?;
+        Ok(config_path)
+    }
+
+    /// Save the settings worth keeping to `config_path`, creating its
+    /// directory if needed
+    ///
+    /// With a `profile`, the settings go under that name in the file's
+    /// `profiles` and the rest of the file is kept; without one they replace
+    /// the top-level settings, keeping the profiles. The file is written
+    /// aside and renamed over the old one, so a save that fails leaves any
+    /// earlier config as it was.
+    pub fn save_to(&self, config_path: &Path, profile: Option<&str>) -> Result<(), YoinkError> {
+        let dir = config_path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        fs::create_dir_all(dir)
+            .map_err(|source| YoinkError::Config { path: dir.to_path_buf(), source })?;
+        
+        let mut saved = ConfigFile::from(self);
+        saved.hostname = crate::utils::hostname();
+        saved.version = Some(env!("CARGO_PKG_VERSION").to_string());
+        let format_error = |source| YoinkError::ConfigFormat { path: config_path.to_path_buf(), source };
+        
+        // Whatever this save doesn't replace is carried over from the file
+        let mut existing = match config_path.exists() {
+            true => Self::read_json(config_path).ok(),
+            false => None,
+        };
+        let mut profiles: BTreeMap<String, Section> = match existing.as_mut().and_then(|json| json.as_object_mut()).and_then(|json| json.remove("profiles")) {
+            Some(serde_json::Value::Object(profiles)) => profiles.into_iter().map(|(name, json)| (name, Section::kept(json))).collect(),
+            _ => BTreeMap::new(),
+        };
+        let settings = match profile {
+            Some(name) => {
+                profiles.insert(name.to_string(), Section::Settings(Box::new(saved)));
+                Section::kept(existing.filter(serde_json::Value::is_object).unwrap_or_else(|| serde_json::json!({})))
+            }
+            None => Section::Settings(Box::new(saved)),
+        };
+        let config_str = serde_json::to_string_pretty(&SavedFile { settings, profiles }).map_err(format_error)?;
+        
+        let error = |source| YoinkError::Config { path: config_path.to_path_buf(), source };
+        let mut file = tempfile::NamedTempFile::new_in(dir).map_err(error)?;
+        file.write_all(config_str.as_bytes()).and_then(|_| file.as_file().sync_all()).map_err(error)?;
+        file.persist(config_path).map_err(|e| error(e.error))?;
+        
+        Ok(())
+    }
+
+    /// Load the saved config, returning `Ok(None)` if none has been saved
+    pub fn load_from_file() -> Result<Option<Self>, YoinkError> {
+        Ok(Self::load_saved(None)?.map(|(config, _)| config))
+    }
+
+    /// Load the saved config, or the named `profile` in it, along with a
+    /// warning if it was saved on another machine and names paths that don't
+    /// exist on this one
+    ///
+    /// The top-level settings are the default profile, so a file saved before
+    /// profiles existed loads as it always did.
+    pub fn load_saved(profile: Option<&str>) -> Result<Option<(Self, Option<Drift>)>, YoinkError> {
+        let config_path = Self::get_config_path();
+
+        // A directory that can't be searched is as unreadable as the file
+        match config_path.try_exists() {
+            Ok(true) => {}
+            Ok(false) if profile.is_none() => return Ok(None),
+            Ok(false) => return Err(Self::unknown_profile(&config_path, &serde_json::Value::Null, profile.unwrap_or_default())),
+            Err(source) => return Err(YoinkError::Config { path: config_path, source }),
+        }
+        Self::load_profile(&config_path, profile).map(Some)
+    }
+
+    // `load_saved` from the file at `config_path`
+    fn load_profile(config_path: &Path, profile: Option<&str>) -> Result<(Self, Option<Drift>), YoinkError> {
+        let json = Self::read_json(config_path)?;
+        let json = match profile {
+            Some(name) => json
+                .get("profiles")
+                .and_then(|profiles| profiles.get(name))
+                .ok_or_else(|| Self::unknown_profile(config_path, &json, name))?,
+            None => &json,
+        };
+        let drift = Drift::detect(json, crate::utils::hostname().as_deref());
+        Ok((Self::from_json(config_path, json)?, drift))
+    }
+
+    // A profile missing from `json`, listing the ones that are there
+    fn unknown_profile(config_path: &Path, json: &serde_json::Value, name: &str) -> YoinkError {
+        let available = json
+            .get("profiles")
+            .and_then(serde_json::Value::as_object)
+            .map(|profiles| profiles.keys().cloned().collect())
+            .unwrap_or_default();
+        YoinkError::UnknownProfile { path: config_path.to_path_buf(), name: name.to_string(), available }
+    }
+
+    /// Check that a config file, and every profile in it, would load, without
+    /// using it
+    pub fn validate_file(config_path: &Path) -> Result<(), YoinkError> {
+        let json = Self::read_json(config_path)?;
+        Self::from_json(config_path, &json)?;
+        let profiles = json.get("profiles").and_then(serde_json::Value::as_object);
+        for profile in profiles.into_iter().flat_map(|profiles| profiles.values()) {
+            Self::from_json(config_path, profile)?;
+        }
+        Ok(())
+    }
+
+    /// Open the saved config in the user's editor, keeping the edit only if it loads
+    ///
+    /// The edit is made on a copy next to the config, so a broken file never
+    /// replaces a working one. A rejected copy is left in place and picked up
+    /// again by the next edit. Returns the config's path.
+    pub fn edit_saved() -> Result<PathBuf, YoinkError> {
+        let config_path = Self::get_config_path();
+        if !config_path.exists() {
+            Self::default().save_to_file(None)?;
+        }
+
+        let draft = config_path.with_extension("json.edit");
+        if draft.exists() {
+            log::info!("Resuming the rejected edit in {}", draft.display());
+        } else {
+            fs::copy(&config_path, &draft)
+                .map_err(|source| YoinkError::Config { path: draft.clone(), source })?;
+        }
+
+        crate::editor::edit_file(&draft)?;
+        Self::validate_file(&draft)?;
+        fs::rename(&draft, &config_path)
+            .map_err(|source| YoinkError::Config { path: config_path.clone(), source })?;
+        Ok(config_path)
+    }
+
+    /// The settings as the saved config would hold them, for the invocation
+    /// log, without the search term unless `history_full` is set
+    pub(crate) fn to_history_settings(&self) -> serde_json::Value {
+        let mut saved = ConfigFile::from(self);
+        if !self.history_full {
+            saved.search_text = None;
+        }
+        serde_json::to_value(&saved).expect("config settings always serialize")
+    }
+
+    /// Settings from the invocation log at `log`, checked and filled in the
+    /// way a saved config is
+    pub(crate) fn from_history_settings(log: &Path, settings: &serde_json::Value) -> Result<Self, YoinkError> {
+        Self::from_json(log, settings)
+    }
+
+    fn read_json(config_path: &Path) -> Result<serde_json::Value, YoinkError> {
+        let mut file = fs::File::open(config_path)
+            .map_err(|source| YoinkError::Config { path: config_path.to_path_buf(), source })?;
+
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)
+            .map_err(|source| YoinkError::Config { path: config_path.to_path_buf(), source })?;
+
+        serde_json::from_str(&contents)
+            .map_err(|source| YoinkError::ConfigFormat { path: config_path.to_path_buf(), source })
+    }
+
+    fn from_json(config_path: &Path, json: &serde_json::Value) -> Result<Self, YoinkError> {
+        let saved = ConfigFile::deserialize(json)
+            .map_err(|source| YoinkError::ConfigFormat { path: config_path.to_path_buf(), source })?;
+        Self::validate_rules(&saved.rules)
+            .map_err(|message| YoinkError::ConfigRule { path: config_path.to_path_buf(), message })?;
+        Ok(saved.into_config())
+    }
+}
+
+/// The config file: the default profile's settings at the top level, then
+/// any named profiles
+#[derive(Serialize)]
+struct SavedFile {
+    #[serde(flatten)]
+    settings: Section,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    profiles: BTreeMap<String, Section>,
+}
+
+/// One profile's settings in the config file
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Section {
+    Settings(Box<ConfigFile>),
+    /// Settings that no longer load, written back as they were found
+    Kept(serde_json::Value),
+}
+
+impl Section {
+    // Settings carried over from the file, keeping the key order of a fresh
+    // save when they still load
+    fn kept(json: serde_json::Value) -> Self {
+        match ConfigFile::deserialize(&json) {
+            Ok(settings) => Self::Settings(Box::new(settings)),
+            Err(_) => Self::Kept(json),
+        }
+    }
+}
+
+/// The saved form of a `Config`, with a key for every setting worth keeping
+/// between runs
+///
+/// Missing keys take the defaults, so older files still load.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+struct ConfigFile {
+    path: String,
+    extra_paths: Vec<String>,
+    labels: Vec<(String, String)>,
+    #[serde(with = "size")]
+    max_size: u64,
+    max_lines: Option<usize>,
+    verbose: bool,
+    /// Unlimited when null
+    max_depth: Option<u32>,
+    include_extensions: Option<Vec<String>>,
+    exclude_extensions: Option<Vec<String>>,
+    exclude_paths: Option<Vec<String>>,
+    #[serde(with = "globs")]
+    exclude_globs: Vec<glob::Pattern>,
+    #[serde(with = "globs::option")]
+    pattern: Option<glob::Pattern>,
+    #[serde(with = "globs")]
+    exclude_patterns: Vec<glob::Pattern>,
+    regex: Option<NameRegex>,
+    skip_hidden: bool,
+    sort: bool,
+    search_text: Option<String>,
+    search_regex: bool,
+    case_sensitive: bool,
+    whole_word: bool,
+    low_memory: bool,
+    cache: bool,
+    strict: bool,
+    #[serde(with = "size::option")]
+    memory_limit: Option<u64>,
+    #[serde(with = "size::option")]
+    max_total: Option<u64>,
+    submodules: SubmoduleMode,
+    git_meta: bool,
+    keep_temp: bool,
+    pick_preview: bool,
+    bind: IpAddr,
+    /// In seconds
+    serve_timeout: u64,
+    copy: bool,
+    media_info: bool,
+    rules: Vec<PathRule>,
+    max_tokens: Option<u64>,
+    fit: FitStrategy,
+    fit_min_lines: usize,
+    target: Option<Target>,
+    dedup: Option<DedupMode>,
+    oversize: OversizeMode,
+    format: OutputFormat,
+    format_template: Option<FormatTemplate>,
+    #[serde(with = "globs")]
+    force_text: Vec<glob::Pattern>,
+    #[serde(with = "globs")]
+    force_binary: Vec<glob::Pattern>,
+    sniff_all: bool,
+    group_by_dir: bool,
+    group_depth: usize,
+    packages: Vec<String>,
+    nested_repos: NestedRepoMode,
+    max_files_per_dir: Option<usize>,
+    #[serde(with = "size::option")]
+    max_bytes_per_dir: Option<u64>,
+    skip_tests: bool,
+    test_patterns: Vec<String>,
+    skip_generated: bool,
+    generated_patterns: Vec<String>,
+    follow_links: bool,
+    git_tracked: bool,
+    git_others: bool,
+    since: Option<TimeBound>,
+    until: Option<TimeBound>,
+    strict_filters: bool,
+    /// In seconds
+    file_timeout: Option<u64>,
+    excluded_overview: bool,
+    tree_budget: Option<TreeBudget>,
+    depth_ceiling: usize,
+    line_numbers: bool,
+    detailed_summary: bool,
+    absolute_paths: bool,
+    strip_comments: bool,
+    condense: bool,
+    chunk_size: Option<usize>,
+    header_format: HeaderTemplate,
+    footer_format: Option<HeaderTemplate>,
+    yoinkignore: bool,
+    history: bool,
+    history_full: bool,
+    /// Where and by which version the file was written, for spotting drift
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hostname: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+}
+
+impl Default for ConfigFile {
+    fn default() -> Self {
+        Self::from(&Config::default())
+    }
+}
+
+impl From<&Config> for ConfigFile {
+    fn from(config: &Config) -> Self {
+        // Listed without `..` so that a new field doesn't build until it's
+        // either saved or marked as applying to a single run
+        let Config {
+            path,
+            extra_paths,
+            labels,
+            max_size,
+            max_lines,
+            verbose,
+            max_depth,
+            include_extensions,
+            exclude_extensions,
+            exclude_paths,
+            exclude_globs,
+            pattern,
+            exclude_patterns,
+            regex,
+            skip_hidden,
+            sort,
+            search_text,
+            search_regex,
+            case_sensitive,
+            whole_word,
+            low_memory,
+            cache,
+            strict,
+            memory_limit,
+            max_total,
+            submodules,
+            git_meta,
+            keep_temp,
+            pick_preview,
+            bind,
+            serve_timeout,
+            copy,
+            media_info,
+            rules,
+            max_tokens,
+            fit,
+            fit_min_lines,
+            target,
+            dedup,
+            oversize,
+            format,
+            format_template,
+            force_text,
+            force_binary,
+            sniff_all,
+            group_by_dir,
+            group_depth,
+            packages,
+            nested_repos,
+            max_files_per_dir,
+            max_bytes_per_dir,
+            skip_tests,
+            test_patterns,
+            skip_generated,
+            generated_patterns,
+            follow_links,
+            git_tracked,
+            git_others,
+            since,
+            until,
+            strict_filters,
+            file_timeout,
+            excluded_overview,
+            tree_budget,
+            depth_ceiling,
+            line_numbers,
+            detailed_summary,
+            absolute_paths,
+            strip_comments,
+            condense,
+            chunk_size,
+            header_format,
+            footer_format,
+            yoinkignore,
+            history,
+            history_full,
+            // Actions and inputs for one run, which would be surprising to replay
+            save_config: _,
+            watch: _,
+            diff_content: _,
+            edit: _,
+            pager: _,
+            pick: _,
+            serve: _,
+            stdin_name: _,
+            manifest_save: _,
+            manifest_diff: _,
+            list: _,
+            list_json: _,
+            list_all: _,
+            dry_run: _,
+            stats: _,
+            select: _,
+            files_from: _,
+            emit_files_list: _,
+            preview: _,
+            sample: _,
+            seed: _,
+            explain: _,
+            list_packages: _,
+            output: _,
+            stdout: _,
+            append: _,
+            output_dir: _,
+            force: _,
+            chunk: _,
+            symbols: _,
+        } = config.clone();
+
+        Self {
+            path,
+            extra_paths,
+            labels,
+            max_size,
+            max_lines,
+            verbose,
+            max_depth: (max_depth != u32::MAX).then_some(max_depth),
+            include_extensions,
+            exclude_extensions,
+            exclude_paths,
+            exclude_globs,
+            pattern,
+            exclude_patterns,
+            regex,
+            skip_hidden,
+            sort,
+            search_text,
+            search_regex,
+            case_sensitive,
+            whole_word,
+            low_memory,
+            cache,
+            strict,
+            memory_limit,
+            max_total,
+            submodules,
+            git_meta,
+            keep_temp,
+            pick_preview,
+            bind,
+            serve_timeout: serve_timeout.as_secs(),
+            copy,
+            media_info,
+            rules,
+            max_tokens,
+            fit,
+            fit_min_lines,
+            target,
+            dedup,
+            oversize,
+            format,
+            format_template,
+            force_text,
+            force_binary,
+            sniff_all,
+            group_by_dir,
+            group_depth,
+            packages,
+            nested_repos,
+            max_files_per_dir,
+            max_bytes_per_dir,
+            skip_tests,
+            test_patterns,
+            skip_generated,
+            generated_patterns,
+            follow_links,
+            git_tracked,
+            git_others,
+            since,
+            until,
+            strict_filters,
+            file_timeout: file_timeout.map(|timeout| timeout.as_secs()),
+            excluded_overview,
+            tree_budget,
+            depth_ceiling,
+            line_numbers,
+            detailed_summary,
+            absolute_paths,
+            strip_comments,
+            condense,
+            chunk_size,
+            header_format,
+            footer_format,
+            yoinkignore,
+            history,
+            history_full,
+            hostname: None,
+            version: None,
+        }
+    }
+}
+
+impl ConfigFile {
+    fn into_config(self) -> Config {
+        // An empty list would filter out everything, which is never what was meant
+        let non_empty = |list: Option<Vec<String>>| list.filter(|list| !list.is_empty());
+        Config {
+            path: self.path,
+            extra_paths: self.extra_paths,
+            labels: self.labels,
+            max_size: self.max_size,
+            max_lines: self.max_lines,
+            verbose: self.verbose,
+            max_depth: self.max_depth.unwrap_or(u32::MAX),
+            include_extensions: non_empty(self.include_extensions),
+            exclude_extensions: non_empty(self.exclude_extensions),
+            exclude_paths: non_empty(self.exclude_paths),
+            exclude_globs: self.exclude_globs,
+            pattern: self.pattern,
+            exclude_patterns: self.exclude_patterns,
+            regex: self.regex,
+            skip_hidden: self.skip_hidden,
+            sort: self.sort,
+            search_text: self.search_text,
+            search_regex: self.search_regex,
+            case_sensitive: self.case_sensitive,
+            whole_word: self.whole_word,
+            low_memory: self.low_memory,
+            cache: self.cache,
+            strict: self.strict,
+            memory_limit: self.memory_limit,
+            max_total: self.max_total,
+            submodules: self.submodules,
+            git_meta: self.git_meta,
+            keep_temp: self.keep_temp,
+            pick_preview: self.pick_preview,
+            bind: self.bind,
+            serve_timeout: Duration::from_secs(self.serve_timeout),
+            copy: self.copy,
+            media_info: self.media_info,
+            rules: self.rules,
+            max_tokens: self.max_tokens,
+            fit: self.fit,
+            fit_min_lines: self.fit_min_lines,
+            target: self.target.clone(),
+            dedup: self.dedup,
+            oversize: self.oversize,
+            format: self.format,
+            format_template: self.format_template,
+            force_text: self.force_text,
+            force_binary: self.force_binary,
+            sniff_all: self.sniff_all,
+            group_by_dir: self.group_by_dir,
+            group_depth: self.group_depth,
+            packages: self.packages,
+            nested_repos: self.nested_repos,
+            max_files_per_dir: self.max_files_per_dir,
+            max_bytes_per_dir: self.max_bytes_per_dir,
+            skip_tests: self.skip_tests,
+            test_patterns: self.test_patterns,
+            skip_generated: self.skip_generated,
+            generated_patterns: self.generated_patterns,
+            follow_links: self.follow_links,
+            git_tracked: self.git_tracked,
+            git_others: self.git_others,
+            since: self.since,
+            until: self.until,
+            strict_filters: self.strict_filters,
+            file_timeout: self.file_timeout.map(Duration::from_secs),
+            excluded_overview: self.excluded_overview,
+            tree_budget: self.tree_budget,
+            depth_ceiling: self.depth_ceiling,
+            line_numbers: self.line_numbers,
+            detailed_summary: self.detailed_summary,
+            absolute_paths: self.absolute_paths,
+            strip_comments: self.strip_comments,
+            condense: self.condense,
+            chunk_size: self.chunk_size,
+            header_format: self.header_format,
+            footer_format: self.footer_format,
+            yoinkignore: self.yoinkignore,
+            history: self.history,
+            history_full: self.history_full,
+            ..Config::default()
+        }
+    }
+}
+
+/// Two filters that between them rule out every file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterConflict {
+    /// Extensions in both the include and the exclude list
+    IncludedAndExcluded { extensions: Vec<String> },
+    /// `--pattern` only matches an extension that is excluded
+    PatternExcluded { pattern: String, extension: String },
+    /// `--pattern` only matches an extension missing from the include list
+    PatternNotIncluded { pattern: String, extension: String },
+    /// `--depth 0` on a directory
+    ZeroDepth,
+}
+
+impl std::fmt::Display for FilterConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FilterConflict::IncludedAndExcluded { extensions } => {
+                write!(f, "'{}' is both included (--extensions) and excluded (--exclude)", extensions.join(","))
+            }
+            FilterConflict::PatternExcluded { pattern, extension } => {
+                write!(f, "--pattern '{}' only matches .{} files, which --exclude leaves out", pattern, extension)
+            }
+            FilterConflict::PatternNotIncluded { pattern, extension } => {
+                write!(f, "--pattern '{}' only matches .{} files, which aren't in --extensions", pattern, extension)
+            }
+            FilterConflict::ZeroDepth => write!(f, "--depth 0 stops at the root directory, so no files are found"),
+        }
+    }
+}
+
+// The extension every match of `pattern` must have, if it names a literal one
+fn pattern_extension(pattern: &str) -> Option<String> {
+    let (_, extension) = pattern.rsplit_once('.')?;
+    let literal = !extension.is_empty() && extension.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    literal.then(|| extension.to_lowercase())
+}
+
+/// A saved config from another machine that names a path missing on this one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drift {
+    pub saved_on: String,
+    pub missing: PathBuf,
+}
+
+impl Drift {
+    /// Compare a saved config against the current hostname
+    ///
+    /// Only absolute paths are checked, since relative ones resolve against
+    /// wherever yoink is run.
+    fn detect(json: &serde_json::Value, hostname: Option<&str>) -> Option<Self> {
+        let saved_on = json.get("hostname")?.as_str()?;
+        if hostname? == saved_on {
+            return None;
+        }
+
+        let listed = |key: &str| json.get(key).and_then(|v| v.as_array()).cloned().unwrap_or_default();
+        let rule_paths = listed("rules").into_iter().filter_map(|rule| rule.get("path").cloned());
+        let missing = json.get("path").cloned().into_iter()
+            .chain(listed("exclude_paths"))
+            .chain(rule_paths)
+            .filter_map(|v| v.as_str().map(PathBuf::from))
+            .find(|path| path.is_absolute() && !path.exists())?;
+
+        Some(Self { saved_on: saved_on.to_string(), missing })
+    }
+}
+
+impl std::fmt::Display for Drift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the saved config is from {} and {} doesn't exist here; run with --no-config to ignore it",
+            self.saved_on,
+            self.missing.display()
+        )
+    }
+}
+
+/// Builder for `Config`, starting from the same defaults the CLI uses
+#[derive(Default)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl ConfigBuilder {
+    /// Directory or file to yoink
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.config.path = path.into();
+        self
+    }
+
+    /// Another directory or file to yoink along with `path`
+    pub fn extra_path(mut self, path: impl Into<String>) -> Self {
+        self.config.extra_paths.push(path.into());
+        self
+    }
+
+    /// Show the root given as `path` under `label` rather than its directory name
+    pub fn label(mut self, label: impl Into<String>, path: impl Into<String>) -> Self {
+        self.config.labels.push((label.into(), path.into()));
+        self
+    }
+
+    /// Maximum file size in bytes
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.config.max_size = bytes;
+        self
+    }
+
+    /// Include only the first `lines` lines of each file
+    pub fn max_lines(mut self, lines: usize) -> Self {
+        self.config.max_lines = Some(lines);
+        self
+    }
+
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.config.verbose = verbose;
+        self
+    }
+
+    /// Maximum directory depth to traverse
+    pub fn max_depth(mut self, depth: u32) -> Self {
+        self.config.max_depth = depth;
+        self
+    }
+
+    /// Only include files with these extensions (case-insensitive)
+    pub fn include_extensions<I, S>(mut self, exts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.config.include_extensions = Some(exts.into_iter().map(|e| e.as_ref().to_lowercase()).collect());
+        self
+    }
+
+    /// Skip files with these extensions (case-insensitive)
+    pub fn exclude_extensions<I, S>(mut self, exts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.config.exclude_extensions = Some(exts.into_iter().map(|e| e.as_ref().to_lowercase()).collect());
+        self
+    }
+
+    /// Skip any path containing one of these components
+    pub fn exclude_paths<I, S>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.exclude_paths = Some(paths.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Leave out paths matching `pattern`, relative to the root, and anything under them
+    pub fn exclude_glob(mut self, pattern: glob::Pattern) -> Self {
+        self.config.exclude_globs.push(pattern);
+        self
+    }
+
+    /// Glob pattern that file names must match
+    pub fn pattern(mut self, pattern: glob::Pattern) -> Self {
+        self.config.pattern = Some(pattern);
+        self
+    }
+
+    /// Leave out files whose names match `pattern`, whatever else they match
+    pub fn exclude_pattern(mut self, pattern: glob::Pattern) -> Self {
+        self.config.exclude_patterns.push(pattern);
+        self
+    }
+
+    /// Regular expression that file names must match, on top of any pattern
+    pub fn regex(mut self, regex: NameRegex) -> Self {
+        self.config.regex = Some(regex);
+        self
+    }
+
+    pub fn skip_hidden(mut self, skip: bool) -> Self {
+        self.config.skip_hidden = skip;
+        self
+    }
+
+    pub fn sort(mut self, sort: bool) -> Self {
+        self.config.sort = sort;
+        self
+    }
+
+    /// Only include files containing this text, with context around matches
+    pub fn search_text(mut self, text: impl Into<String>) -> Self {
+        self.config.search_text = Some(text.into());
+        self
+    }
+
+    /// Only include files with a line matching this regular expression
+    pub fn search_regex(mut self, pattern: impl Into<String>) -> Self {
+        self.config.search_text = Some(pattern.into());
+        self.config.search_regex = true;
+        self
+    }
+
+    pub fn case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.config.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Only match the search as a whole word
+    pub fn whole_word(mut self, whole_word: bool) -> Self {
+        self.config.whole_word = whole_word;
+        self
+    }
+
+    /// Spill formatted output to a temporary file instead of holding it in memory
+    pub fn low_memory(mut self, low_memory: bool) -> Self {
+        self.config.low_memory = low_memory;
+        self
+    }
+
+    /// Keep running and re-yoink when files change
+    pub fn watch(mut self, watch: bool) -> Self {
+        self.config.watch = watch;
+        self
+    }
+
+    /// Remember file classifications between runs to skip re-sniffing
+    pub fn cache(mut self, cache: bool) -> Self {
+        self.config.cache = cache;
+        self
+    }
+
+    /// Abort on the first file that fails instead of recording it and moving on
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.config.strict = strict;
+        self
+    }
+
+    /// Stop including file bodies once formatted content reaches this many bytes
+    /// Leave out files once the output holds `bytes` of file content, or
+    /// never with `None`
+    pub fn max_total(mut self, bytes: Option<u64>) -> Self {
+        self.config.max_total = bytes;
+        self
+    }
+
+    pub fn memory_limit(mut self, bytes: u64) -> Self {
+        self.config.memory_limit = Some(bytes);
+        self
+    }
+
+    /// Whether to skip or walk into git submodules
+    pub fn submodules(mut self, mode: SubmoduleMode) -> Self {
+        self.config.submodules = mode;
+        self
+    }
+
+    /// Include only patches against `rev` for changed files, and new files in full
+    pub fn diff_content(mut self, rev: impl Into<String>) -> Self {
+        self.config.diff_content = Some(rev.into());
+        self
+    }
+
+    /// Annotate file headers with their last commit
+    pub fn git_meta(mut self, git_meta: bool) -> Self {
+        self.config.git_meta = git_meta;
+        self
+    }
+
+    /// Open the output in the user's editor before copying it
+    pub fn edit(mut self, edit: bool) -> Self {
+        self.config.edit = edit;
+        self
+    }
+
+    /// Leave the edited temp file behind instead of deleting it
+    pub fn keep_temp(mut self, keep_temp: bool) -> Self {
+        self.config.keep_temp = keep_temp;
+        self
+    }
+
+    /// Preview the output in the user's pager and ask before copying it
+    pub fn pager(mut self, pager: bool) -> Self {
+        self.config.pager = pager;
+        self
+    }
+
+    /// Choose which scanned files to include with an interactive picker
+    pub fn pick(mut self, pick: bool) -> Self {
+        self.config.pick = pick;
+        self
+    }
+
+    /// Show the highlighted file in the picker's preview window
+    pub fn pick_preview(mut self, pick_preview: bool) -> Self {
+        self.config.pick_preview = pick_preview;
+        self
+    }
+
+    /// Serve the output once over HTTP instead of copying it
+    pub fn serve(mut self, serve: bool) -> Self {
+        self.config.serve = serve;
+        self
+    }
+
+    /// Address the HTTP server listens on
+    pub fn bind(mut self, bind: IpAddr) -> Self {
+        self.config.bind = bind;
+        self
+    }
+
+    /// How long to wait for the served output to be fetched
+    pub fn serve_timeout(mut self, timeout: Duration) -> Self {
+        self.config.serve_timeout = timeout;
+        self
+    }
+
+    /// Also copy to the clipboard when serving
+    pub fn copy(mut self, copy: bool) -> Self {
+        self.config.copy = copy;
+        self
+    }
+
+    /// Name shown in the header when reading from stdin
+    pub fn stdin_name(mut self, name: impl Into<String>) -> Self {
+        self.config.stdin_name = Some(name.into());
+        self
+    }
+
+    /// Save a manifest of content hashes after the run
+    pub fn manifest_save(mut self, location: ManifestLocation) -> Self {
+        self.config.manifest_save = Some(location);
+        self
+    }
+
+    /// Only include files that are new or changed since a saved manifest
+    pub fn manifest_diff(mut self, location: ManifestLocation) -> Self {
+        self.config.manifest_diff = Some(location);
+        self
+    }
+
+    /// Print numbered candidates instead of yoinking them
+    pub fn list(mut self, list: bool) -> Self {
+        self.config.list = list;
+        self
+    }
+
+    /// Only include these 1-based indices from the last listing
+    pub fn select(mut self, indices: impl IntoIterator<Item = usize>) -> Self {
+        self.config.select = Some(indices.into_iter().collect());
+        self
+    }
+
+    /// Include exactly the files listed in `list`, bypassing the filters
+    pub fn files_from(mut self, list: impl Into<PathBuf>) -> Self {
+        self.config.files_from = Some(list.into());
+        self
+    }
+
+    /// Print the listing in the format `files_from` reads
+    pub fn emit_files_list(mut self, emit: bool) -> Self {
+        self.config.emit_files_list = emit;
+        self
+    }
+
+    /// Show a digest of the output and ask before copying it
+    pub fn preview(mut self, preview: bool) -> Self {
+        self.config.preview = preview;
+        self
+    }
+
+    /// Add an assets table with each image's dimensions and size
+    pub fn media_info(mut self, media_info: bool) -> Self {
+        self.config.media_info = media_info;
+        self
+    }
+
+    /// Add a per-directory override
+    pub fn rule(mut self, rule: PathRule) -> Self {
+        self.config.rules.push(rule);
+        self
+    }
+
+    /// Keep the output under roughly `tokens` tokens
+    pub fn max_tokens(mut self, tokens: u64) -> Self {
+        self.config.max_tokens = Some(tokens);
+        self
+    }
+
+    pub fn fit(mut self, fit: FitStrategy) -> Self {
+        self.config.fit = fit;
+        self
+    }
+
+    pub fn fit_min_lines(mut self, lines: usize) -> Self {
+        self.config.fit_min_lines = lines;
+        self
+    }
+
+    /// Warn when the output looks too big for `target`'s context window
+    pub fn target(mut self, target: Target) -> Self {
+        self.config.target = Some(target);
+        self
+    }
+
+    pub fn dedup(mut self, mode: DedupMode) -> Self {
+        self.config.dedup = Some(mode);
+        self
+    }
+
+    pub fn oversize(mut self, mode: OversizeMode) -> Self {
+        self.config.oversize = mode;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> Self {
+        self.config.format = format;
+        self
+    }
+
+    /// Pick `files` of the candidates at random, reproducibly for a given `seed`
+    pub fn sample(mut self, files: usize, seed: u64) -> Self {
+        self.config.sample = Some(files);
+        self.config.seed = Some(seed);
+        self
+    }
+
+    /// Always treat files matching `pattern` as text
+    pub fn force_text(mut self, pattern: glob::Pattern) -> Self {
+        self.config.force_text.push(pattern);
+        self
+    }
+
+    /// Always treat files matching `pattern` as binary
+    pub fn force_binary(mut self, pattern: glob::Pattern) -> Self {
+        self.config.force_binary.push(pattern);
+        self
+    }
+
+    /// Decide every file's type from its content, not just files without a
+    /// known extension
+    pub fn sniff_all(mut self, sniff: bool) -> Self {
+        self.config.sniff_all = sniff;
+        self
+    }
+
+    /// Group files by their first `depth` directories, each under a header
+    pub fn group_by_dir(mut self, depth: usize) -> Self {
+        self.config.group_by_dir = true;
+        self.config.group_depth = depth;
+        self
+    }
+
+    /// Only yoink the workspace member `name`, plus the root manifests
+    pub fn package(mut self, name: impl Into<String>) -> Self {
+        self.config.packages.push(name.into());
+        self
+    }
+
+    /// Whether to skip, walk into, or only skim nested git repositories
+    pub fn nested_repos(mut self, mode: NestedRepoMode) -> Self {
+        self.config.nested_repos = mode;
+        self
+    }
+
+    /// Write the output to `file` instead of the clipboard, replacing it only if `force`
+    pub fn output(mut self, file: impl Into<PathBuf>, force: bool) -> Self {
+        self.config.output = Some(file.into());
+        self.config.force = force;
+        self
+    }
+
+    /// Write the output to stdout instead of the clipboard
+    pub fn stdout(mut self, stdout: bool) -> Self {
+        self.config.stdout = stdout;
+        self
+    }
+
+    /// Add the output to yoink output already on the clipboard
+    pub fn append(mut self, mode: AppendMode) -> Self {
+        self.config.append = Some(mode);
+        self
+    }
+
+    /// Write each included file's chunk to its own file under `dir`
+    pub fn output_dir(mut self, dir: impl Into<PathBuf>, force: bool) -> Self {
+        self.config.output_dir = Some(dir.into());
+        self.config.force = force;
+        self
+    }
+
+    /// Split the output into chunks of at most `size` characters
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.config.chunk_size = Some(size);
+        self
+    }
+
+    /// Deliver chunk `number`, counting from 1, of the split output
+    pub fn chunk(mut self, number: usize) -> Self {
+        self.config.chunk = Some(number);
+        self
+    }
+
+    /// Cap the files and bytes taken from any one directory
+    pub fn dir_limits(mut self, files: Option<usize>, bytes: Option<u64>) -> Self {
+        self.config.max_files_per_dir = files;
+        self.config.max_bytes_per_dir = bytes;
+        self
+    }
+
+    /// Leave out test files and Rust test modules, also treating `extra`
+    /// patterns as tests
+    pub fn skip_tests(mut self, extra: &[&str]) -> Self {
+        self.config.skip_tests = true;
+        self.config.test_patterns.extend(extra.iter().map(|p| p.to_string()));
+        self
+    }
+
+    /// Leave out lockfiles and build output, also treating names matching
+    /// `extra` globs as generated
+    pub fn skip_generated(mut self, extra: &[&str]) -> Self {
+        self.config.skip_generated = true;
+        self.config.generated_patterns.extend(extra.iter().map(|p| p.to_string()));
+        self
+    }
+
+    /// Walk into symlinked directories, keeping a file reached through
+    /// several links once
+    pub fn follow_links(mut self, follow: bool) -> Self {
+        self.config.follow_links = follow;
+        self
+    }
+
+    /// Only scan files git tracks, along with untracked ones that aren't
+    /// ignored when `others` is set
+    pub fn git_tracked(mut self, others: bool) -> Self {
+        self.config.git_tracked = true;
+        self.config.git_others = others;
+        self
+    }
+
+    /// Leave out files last modified before `since` or after `until`
+    pub fn modified(mut self, since: Option<TimeBound>, until: Option<TimeBound>) -> Self {
+        self.config.since = since;
+        self.config.until = until;
+        self
+    }
+
+    /// Abandon a file whose read takes longer than `timeout`, never with zero,
+    /// or only on network mounts with `None`
+    pub fn file_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.config.file_timeout = timeout;
+        self
+    }
+
+    /// Add a one-line listing of each excluded directory's children
+    pub fn excluded_overview(mut self, overview: bool) -> Self {
+        self.config.excluded_overview = overview;
+        self
+    }
+
+    /// Cut the directory tree down until it fits `budget`
+    pub fn tree_budget(mut self, budget: TreeBudget) -> Self {
+        self.config.tree_budget = Some(budget);
+        self
+    }
+
+    /// Never walk more than `ceiling` levels below the root
+    pub fn depth_ceiling(mut self, ceiling: usize) -> Self {
+        self.config.depth_ceiling = ceiling;
+        self
+    }
+
+    /// Number each line of a file's content
+    pub fn line_numbers(mut self, numbered: bool) -> Self {
+        self.config.line_numbers = numbered;
+        self
+    }
+
+    /// List each file's lines and characters in the summary
+    pub fn detailed_summary(mut self, detailed: bool) -> Self {
+        self.config.detailed_summary = detailed;
+        self
+    }
+
+    /// Show paths in headers as walked rather than relative to the scanned root
+    pub fn absolute_paths(mut self, absolute: bool) -> Self {
+        self.config.absolute_paths = absolute;
+        self
+    }
+
+    /// Remove comments from files whose language is known by extension
+    pub fn strip_comments(mut self, strip: bool) -> Self {
+        self.config.strip_comments = strip;
+        self
+    }
+
+    /// Collapse runs of blank lines and strip trailing whitespace from each file
+    pub fn condense(mut self, condense: bool) -> Self {
+        self.config.condense = condense;
+        self
+    }
+
+    /// Write `header` above each file in the plain format, and `footer`
+    /// below it when given
+    pub fn header_format(mut self, header: HeaderTemplate, footer: Option<HeaderTemplate>) -> Self {
+        self.config.header_format = header;
+        self.config.footer_format = footer;
+        self
+    }
+
+    /// Honor `.yoinkignore` in the root and the global ignore file
+    pub fn yoinkignore(mut self, honor: bool) -> Self {
+        self.config.yoinkignore = honor;
+        self
+    }
+
+    /// Only include the item called `name`; repeat for more
+    pub fn symbol(mut self, name: impl Into<String>) -> Self {
+        self.config.symbols.push(name.into());
+        self
+    }
+
+    /// Record the run in the invocation log, with sensitive values only if `full`
+    pub fn history(mut self, record: bool, full: bool) -> Self {
+        self.config.history = record;
+        self.config.history_full = full;
+        self
+    }
+
+    pub fn build(self) -> Config {
+        self.config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_drift_needs_another_host_and_a_missing_absolute_path() {
+        let saved = json!({ "hostname": "laptop", "path": ".", "rules": [{ "path": "/gone/vendor", "skip": true }] });
+
+        assert_eq!(Drift::detect(&saved, Some("laptop")), None);
+        assert_eq!(Drift::detect(&saved, None), None);
+        assert_eq!(
+            Drift::detect(&saved, Some("server")),
+            Some(Drift { saved_on: "laptop".to_string(), missing: PathBuf::from("/gone/vendor") })
+        );
+
+        let relative = json!({ "hostname": "laptop", "path": "src", "exclude_paths": ["target"] });
+        assert_eq!(Drift::detect(&relative, Some("server")), None);
+    }
+
+    fn conflicts(builder: ConfigBuilder) -> Vec<FilterConflict> {
+        builder.build().filter_conflicts()
+    }
+
+    #[test]
+    fn test_extension_in_both_lists_conflicts() {
+        let found = conflicts(Config::builder().include_extensions(["rs", "md"]).exclude_extensions(["RS"]));
+        assert_eq!(found, [FilterConflict::IncludedAndExcluded { extensions: vec!["rs".to_string()] }]);
+        assert_eq!(found[0].to_string(), "'rs' is both included (--extensions) and excluded (--exclude)");
+    }
+
+    #[test]
+    fn test_pattern_extension_conflicts_with_extension_lists() {
+        let pattern = || glob::Pattern::new("*.MD").unwrap();
+        assert_eq!(
+            conflicts(Config::builder().pattern(pattern()).exclude_extensions(["md"])),
+            [FilterConflict::PatternExcluded { pattern: "*.MD".to_string(), extension: "md".to_string() }]
+        );
+        assert_eq!(
+            conflicts(Config::builder().pattern(pattern()).include_extensions(["rs"])),
+            [FilterConflict::PatternNotIncluded { pattern: "*.MD".to_string(), extension: "md".to_string() }]
+        );
+        assert!(conflicts(Config::builder().pattern(pattern()).include_extensions(["md"])).is_empty());
+        assert!(conflicts(Config::builder().pattern(glob::Pattern::new("*.[mr]d").unwrap()).exclude_extensions(["md"])).is_empty());
+    }
+
+    #[test]
+    fn test_zero_depth_conflicts_only_for_a_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        fs::write(&file, "notes").unwrap();
+
+        let at = |path: &Path| conflicts(Config::builder().path(path.to_string_lossy()).max_depth(0));
+        assert_eq!(at(dir.path()), [FilterConflict::ZeroDepth]);
+        assert!(at(&file).is_empty());
+    }
+
+    #[test]
+    fn test_every_saved_setting_round_trips() {
+        // Built without `..` so a new field has to be given a value here too
+        let config = Config {
+            path: "/work/project".to_string(),
+            extra_paths: vec!["/work/docs".to_string()],
+            labels: vec![("guide".to_string(), "/work/docs".to_string())],
+            max_size: 512 * 1024,
+            max_lines: Some(2_000),
+            verbose: true,
+            max_depth: 4,
+            include_extensions: Some(vec!["rs".to_string()]),
+            exclude_extensions: Some(vec!["lock".to_string()]),
+            exclude_paths: Some(vec!["target".to_string()]),
+            exclude_globs: vec![glob::Pattern::new("**/__snapshots__/**").unwrap()],
+            pattern: Some(glob::Pattern::new("*.rs").unwrap()),
+            exclude_patterns: vec![glob::Pattern::new("*_test.rs").unwrap()],
+            regex: Some(NameRegex::parse(r"^(handler|service)_").unwrap()),
+            skip_hidden: true,
+            sort: true,
+            search_text: Some("TODO".to_string()),
+            search_regex: false,
+            case_sensitive: true,
+            whole_word: true,
+            low_memory: true,
+            cache: true,
+            strict: true,
+            memory_limit: Some(3 * 1024 * 1024 * 1024),
+            max_total: Some(200 * 1024 * 1024),
+            submodules: SubmoduleMode::Include,
+            git_meta: true,
+            keep_temp: true,
+            pick_preview: true,
+            bind: IpAddr::from([0, 0, 0, 0]),
+            serve_timeout: Duration::from_secs(30),
+            copy: true,
+            media_info: true,
+            rules: vec![PathRule { path: "vendor".to_string(), skip: true, ..PathRule::default() }],
+            max_tokens: Some(100_000),
+            fit: FitStrategy::Truncate,
+            fit_min_lines: 3,
+            target: Some(Target::parse("gpt-4o").unwrap()),
+            dedup: Some(DedupMode::Normalized),
+            oversize: OversizeMode::Summarize,
+            format: OutputFormat::Markdown,
+            format_template: None,
+            force_text: vec![glob::Pattern::new("*.inc").unwrap()],
+            force_binary: vec![glob::Pattern::new("*.dat").unwrap()],
+            sniff_all: true,
+            group_by_dir: true,
+            group_depth: 2,
+            packages: vec!["acme-parser".to_string()],
+            nested_repos: NestedRepoMode::Shallow,
+            max_files_per_dir: Some(10),
+            max_bytes_per_dir: Some(100),
+            skip_tests: true,
+            test_patterns: vec!["fixtures/".to_string()],
+            skip_generated: true,
+            generated_patterns: vec!["*.pb.go".to_string()],
+            follow_links: true,
+            git_tracked: true,
+            git_others: true,
+            since: Some(TimeBound::parse("3d").unwrap()),
+            until: Some(TimeBound::parse("2024-05-01").unwrap()),
+            strict_filters: true,
+            file_timeout: Some(Duration::from_secs(5)),
+            excluded_overview: true,
+            tree_budget: Some(TreeBudget::Lines(300)),
+            depth_ceiling: 32,
+            line_numbers: true,
+            detailed_summary: true,
+            absolute_paths: true,
+            strip_comments: true,
+            condense: true,
+            chunk_size: Some(100_000),
+            header_format: HeaderTemplate::parse("// FILE: {path}").unwrap(),
+            footer_format: Some(HeaderTemplate::parse("// END: {name}").unwrap()),
+            yoinkignore: false,
+            history: true,
+            history_full: true,
+            // Settings for a single run aren't saved, so these stay at their defaults
+            save_config: false,
+            watch: false,
+            diff_content: None,
+            edit: false,
+            pager: false,
+            pick: false,
+            serve: false,
+            stdin_name: None,
+            manifest_save: None,
+            manifest_diff: None,
+            list: false,
+            list_json: false,
+            list_all: false,
+            dry_run: false,
+            stats: false,
+            select: None,
+            files_from: None,
+            emit_files_list: false,
+            preview: false,
+            sample: None,
+            seed: None,
+            explain: None,
+            list_packages: false,
+            output: None,
+            stdout: false,
+            append: None,
+            output_dir: None,
+            force: false,
+            chunk: None,
+            symbols: Vec::new(),
+        };
+
+        let json = serde_json::to_value(ConfigFile::from(&config)).unwrap();
+        assert_eq!(json["max_size"], "512K");
+        assert_eq!(json["memory_limit"], 3072);
+        assert_eq!(json["max_bytes_per_dir"], "100B");
+        assert_eq!(Config::from_json(Path::new("config.json"), &json).unwrap(), config);
+    }
+
+    #[test]
+    fn test_older_files_still_load_and_bad_globs_are_rejected() {
+        let path = Path::new("config.json");
+        let old = json!({ "path": ".", "max_size": 5, "max_depth": null, "include_extensions": [], "pattern": "*.md", "sort": true });
+        let config = Config::from_json(path, &old).unwrap();
+
+        assert_eq!(config.max_size, 5 * 1024 * 1024);
+        assert_eq!(config.max_depth, u32::MAX);
+        assert_eq!(config.include_extensions, None);
+        assert_eq!(config.pattern.as_ref().map(glob::Pattern::as_str), Some("*.md"));
+        assert!(config.sort);
+        assert_eq!(config.fit_min_lines, Config::default().fit_min_lines);
+
+        let bad = json!({ "force_text": ["[unclosed"] });
+        assert!(matches!(Config::from_json(path, &bad), Err(YoinkError::ConfigFormat { .. })));
+    }
+
+    #[test]
+    fn test_profiles_are_saved_beside_the_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.json");
+        // A file from before profiles existed
+        fs::write(&config_path, r#"{ "path": "src", "sort": true }"#).unwrap();
+
+        Config::builder().path("docs").include_extensions(["md"]).build().save_to(&config_path, Some("docs")).unwrap();
+        Config::builder().path("backend").build().save_to(&config_path, Some("backend")).unwrap();
+        let (default, _) = Config::load_profile(&config_path, None).unwrap();
+        assert_eq!((default.path.as_str(), default.sort), ("src", true));
+        let (docs, _) = Config::load_profile(&config_path, Some("docs")).unwrap();
+        assert_eq!((docs.path.as_str(), docs.include_extensions), ("docs", Some(vec!["md".to_string()])));
+
+        // Saving the default keeps the profiles
+        Config::builder().path("app").build().save_to(&config_path, None).unwrap();
+        assert_eq!(Config::load_profile(&config_path, None).unwrap().0.path, "app");
+        assert_eq!(Config::load_profile(&config_path, Some("backend")).unwrap().0.path, "backend");
+        Config::validate_file(&config_path).unwrap();
+
+        let missing = Config::load_profile(&config_path, Some("frontend")).unwrap_err();
+        assert!(matches!(&missing, YoinkError::UnknownProfile { available, .. } if available == &["backend", "docs"]));
+        assert!(missing.to_string().ends_with("available profiles: backend, docs"), "{}", missing);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_saving_into_a_read_only_directory_fails_and_keeps_the_old_file() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("yoink/config.json");
+        Config::builder().path("old").build().save_to(&config_path, None).unwrap();
+
+        let locked = config_path.parent().unwrap();
+        fs::set_permissions(locked, fs::Permissions::from_mode(0o555)).unwrap();
+        let unlock = || fs::set_permissions(locked, fs::Permissions::from_mode(0o755)).unwrap();
+        // Permissions don't stop root, so there's no failure to see
+        if tempfile::NamedTempFile::new_in(locked).is_ok() {
+            return unlock();
+        }
+
+        let replaced = Config::builder().path("new").build().save_to(&config_path, None);
+        assert!(matches!(replaced, Err(YoinkError::Config { path, .. }) if path == config_path));
+        let nested = Config::default().save_to(&locked.join("sub/config.json"), None);
+        assert!(matches!(nested, Err(YoinkError::Config { path, .. }) if path == locked.join("sub")));
+        unlock();
+
+        let kept = Config::from_json(&config_path, &Config::read_json(&config_path).unwrap()).unwrap();
+        assert_eq!(kept.path, "old");
+    }
+}
@@ -0,0 +1,138 @@
+//! Giving up on work that doesn't finish in time
+
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// How long a read from a network mount may take when `--file-timeout`
+/// isn't given; reads from local disks aren't timed at all then
+pub const NETWORK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Filesystem types whose reads can hang when the server goes away
+const NETWORK_FILESYSTEMS: &[&str] = &[
+    "nfs", "nfs4", "cifs", "smb3", "smbfs", "ncpfs", "afs", "9p", "ceph", "glusterfs", "lustre", "davfs",
+    "fuse.sshfs", "fuse.rclone", "fuse.s3fs", "fuse.gcsfuse",
+];
+
+/// The mount points of the system, to tell which files live on network mounts
+#[derive(Debug, Default)]
+pub struct NetworkMounts {
+    /// Each mount point and whether it's a network filesystem, deepest first
+    mounts: Vec<(PathBuf, bool)>,
+}
+
+impl NetworkMounts {
+    /// The mounts in `/proc/self/mountinfo`; none where there's no such file,
+    /// so nothing counts as a network mount there
+    pub fn load() -> Self {
+        std::fs::read_to_string("/proc/self/mountinfo").map(|info| Self::parse(&info)).unwrap_or_default()
+    }
+
+    fn parse(mountinfo: &str) -> Self {
+        // Fields are `id parent dev root mount-point options [optional...] - type source ...`
+        let mut mounts: Vec<(PathBuf, bool)> = mountinfo
+            .lines()
+            .filter_map(|line| {
+                let (fields, rest) = line.split_once(" - ")?;
+                let point = fields.split(' ').nth(4)?;
+                let fstype = rest.split(' ').next()?;
+                Some((PathBuf::from(unescape(point)), NETWORK_FILESYSTEMS.contains(&fstype)))
+            })
+            .collect();
+        mounts.sort_by_key(|(point, _)| std::cmp::Reverse(point.components().count()));
+        Self { mounts }
+    }
+
+    /// Whether `path` is on a network mount, going by where it sits rather
+    /// than where any symlinks in it lead
+    pub fn holds(&self, path: &Path) -> bool {
+        let Ok(path) = std::path::absolute(path) else { return false };
+        self.mounts.iter().find(|(point, _)| path.starts_with(point)).is_some_and(|(_, network)| *network)
+    }
+}
+
+// Mount points have spaces and the like written as octal escapes, `\040`
+fn unescape(point: &str) -> String {
+    let mut bytes = Vec::with_capacity(point.len());
+    let mut rest = point.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = tail.get(..3).and_then(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 8).ok());
+        match escaped {
+            Some(value) if byte == b'\\' => {
+                bytes.push(value);
+                rest = &tail[3..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Run `work` on a helper thread, waiting at most `timeout` for its result
+///
+/// Returns `None` once the deadline passes. A read blocked in the kernel
+/// can't be interrupted, so the helper is left behind rather than killed:
+/// it finishes on its own if the read ever returns, and otherwise lives
+/// until the process exits. Each abandoned file can therefore cost one
+/// parked thread and whatever it had read so far. A panic in `work`, or
+/// failing to start the thread, panics on the calling thread.
+pub fn run<T: Send + 'static>(timeout: Duration, work: impl FnOnce() -> T + Send + 'static) -> Option<T> {
+    let (tx, rx) = mpsc::sync_channel(1);
+    thread::Builder::new()
+        .name("yoink-read".to_string())
+        .spawn(move || {
+            // The receiver is gone if the deadline already passed
+            let _ = tx.send(panic::catch_unwind(AssertUnwindSafe(work)));
+        })
+        .expect("failed to start a reader thread");
+
+    match rx.recv_timeout(timeout) {
+        Ok(Ok(result)) => Some(result),
+        Ok(Err(payload)) => panic::resume_unwind(payload),
+        Err(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_returns_result_in_time() {
+        assert_eq!(run(Duration::from_secs(10), || 42), Some(42));
+    }
+
+    #[test]
+    fn test_gives_up_on_slow_work() {
+        let started = std::time::Instant::now();
+        assert_eq!(run(Duration::from_millis(50), || thread::sleep(Duration::from_secs(5))), None);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_panic_reaches_the_caller() {
+        let result = panic::catch_unwind(|| run(Duration::from_secs(10), || -> u32 { panic!("boom") }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_network_mounts_are_told_from_local_ones() {
+        let mounts = NetworkMounts::parse(
+            "22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw\n\
+             40 22 0:45 / /mnt/share rw,relatime shared:20 - nfs4 server:/export rw\n\
+             41 40 0:46 / /mnt/share/scratch rw - tmpfs tmpfs rw\n\
+             42 22 0:47 / /media/my\\040drive rw - cifs //host/drive rw\n",
+        );
+
+        assert!(mounts.holds(Path::new("/mnt/share/src/main.rs")));
+        assert!(!mounts.holds(Path::new("/mnt/share/scratch/main.rs")));
+        assert!(!mounts.holds(Path::new("/home/me/main.rs")));
+        assert!(mounts.holds(Path::new("/media/my drive/notes.txt")));
+        assert!(!NetworkMounts::default().holds(Path::new("/mnt/share/src/main.rs")));
+    }
+}
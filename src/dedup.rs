@@ -0,0 +1,166 @@
+//! Collapsing files whose content duplicates an earlier file
+
+use crate::cache::content_hash;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What counts as the same content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DedupMode {
+    /// Byte-for-byte identical
+    Exact,
+    /// Identical once line endings and trailing whitespace are normalized
+    Normalized,
+}
+
+/// Hashes of one file's content, taken while it's already in memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fingerprint {
+    exact: u64,
+    /// With CRLF line endings turned into LF
+    eol: u64,
+    /// With line endings normalized and trailing whitespace stripped from each line
+    normalized: u64,
+    crlf: bool,
+    /// Bytes of content, all of which a stub leaves out
+    pub len: u64,
+}
+
+impl Fingerprint {
+    pub fn of(content: &str) -> Self {
+        let crlf = content.contains("\r\n");
+        let eol = if crlf { content_hash(&content.replace("\r\n", "\n")) } else { content_hash(content) };
+        Self { exact: content_hash(content), eol, normalized: content_hash(&normalize(content)), crlf, len: content.len() as u64 }
+    }
+
+    fn key(&self, mode: DedupMode) -> u64 {
+        match mode {
+            DedupMode::Exact => self.exact,
+            DedupMode::Normalized => self.normalized,
+        }
+    }
+
+    /// What had to be normalized for `self` to equal `other`, if anything
+    fn normalization(&self, other: &Fingerprint) -> Option<&'static str> {
+        if self.exact == other.exact {
+            None
+        } else if self.eol == other.eol {
+            Some("line endings")
+        } else if self.crlf != other.crlf {
+            Some("line endings and trailing whitespace")
+        } else {
+            Some("trailing whitespace")
+        }
+    }
+}
+
+// Line endings made LF and trailing whitespace stripped from each line
+fn normalize(content: &str) -> String {
+    content.lines().flat_map(|line| [line.trim_end(), "\n"]).collect()
+}
+
+/// First file seen with each content, in output order, and how it's shown
+#[derive(Debug)]
+pub struct Deduper {
+    mode: DedupMode,
+    seen: HashMap<u64, (PathBuf, PathBuf, Fingerprint)>,
+}
+
+impl Deduper {
+    pub fn new(mode: DedupMode) -> Self {
+        Self { mode, seen: HashMap::new() }
+    }
+
+    /// Why `path`, shown as `shown`, gets a stub instead of its content when
+    /// that content was already seen, such as "identical to src/a.rs"
+    ///
+    /// A matching hash is only taken at its word once both files have been
+    /// read again and compared.
+    pub fn check(&mut self, path: &Path, shown: &Path, fingerprint: Fingerprint) -> Option<String> {
+        let key = fingerprint.key(self.mode);
+        let Some((first_path, first, original)) = self.seen.get(&key) else {
+            self.seen.insert(key, (path.to_path_buf(), shown.to_path_buf(), fingerprint));
+            return None;
+        };
+        if !self.same_content(first_path, path) {
+            log::debug!("{} only hashes like {}, keeping both", path.display(), first_path.display());
+            return None;
+        }
+
+        let note = match fingerprint.normalization(original) {
+            None => format!("identical to {}", first.display()),
+            Some(normalized) => format!("identical to {} after normalizing {}", first.display(), normalized),
+        };
+        Some(note)
+    }
+
+    // Whether the two files hold the same content as far as the mode goes
+    fn same_content(&self, first: &Path, path: &Path) -> bool {
+        let (Ok(first), Ok(content)) = (fs::read(first), fs::read(path)) else {
+            return false;
+        };
+        match self.mode {
+            DedupMode::Exact => first == content,
+            DedupMode::Normalized => normalize(&String::from_utf8_lossy(&first)) == normalize(&String::from_utf8_lossy(&content)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(mode: DedupMode, first: &str, second: &str) -> Option<String> {
+        let dir = tempfile::tempdir().unwrap();
+        let mut deduper = Deduper::new(mode);
+        for (name, content) in [("a", first), ("b", second)] {
+            fs::write(dir.path().join(name), content).unwrap();
+        }
+        assert!(deduper.check(&dir.path().join("a"), Path::new("a"), Fingerprint::of(first)).is_none());
+        deduper.check(&dir.path().join("b"), Path::new("b"), Fingerprint::of(second))
+    }
+
+    #[test]
+    fn test_crlf_copy_is_a_normalized_duplicate() {
+        let lf = "fn main() {\n    run();\n}\n";
+        let crlf = "fn main() {\r\n    run();\r\n}\r\n";
+
+        assert_eq!(note(DedupMode::Exact, lf, crlf), None);
+        assert_eq!(note(DedupMode::Normalized, lf, crlf).unwrap(), "identical to a after normalizing line endings");
+    }
+
+    #[test]
+    fn test_trailing_whitespace_is_named() {
+        let note = note(DedupMode::Normalized, "x = 1\ny = 2\n", "x = 1   \ny = 2\t\n").unwrap();
+        assert!(note.ends_with("after normalizing trailing whitespace"));
+    }
+
+    #[test]
+    fn test_different_content_is_not_conflated() {
+        // Leading whitespace and blank lines are meaningful
+        assert_eq!(note(DedupMode::Normalized, "a\n  b\n", "a\nb\n"), None);
+        assert_eq!(note(DedupMode::Normalized, "a\nb\n", "a\n\nb\n"), None);
+        assert_eq!(note(DedupMode::Normalized, "a\r\nb\r\n", "a\r\nc\r\n"), None);
+    }
+
+    #[test]
+    fn test_matching_hashes_alone_are_not_enough() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a"), "one\n").unwrap();
+        fs::write(dir.path().join("b"), "two\n").unwrap();
+
+        // As if the two had collided
+        let fingerprint = Fingerprint::of("one\n");
+        let mut deduper = Deduper::new(DedupMode::Exact);
+        assert!(deduper.check(&dir.path().join("a"), Path::new("a"), fingerprint).is_none());
+        assert!(deduper.check(&dir.path().join("b"), Path::new("b"), fingerprint).is_none());
+    }
+
+    #[test]
+    fn test_exact_duplicate_has_no_note() {
+        assert_eq!(note(DedupMode::Exact, "same\n", "same\n").unwrap(), "identical to a");
+    }
+}
@@ -0,0 +1,64 @@
+//! Guards against pathologically deep trees: the depth ceiling, and shortening
+//! paths too long to read in a file header
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::DirEntry;
+
+/// Deepest the scan and the tree go, whatever `--depth` says
+pub const DEFAULT_CEILING: usize = 64;
+
+/// Paths longer than this many characters are abbreviated in file headers
+pub const MAX_HEADER_PATH: usize = 200;
+
+/// Whether the walk stops at `entry`, a directory at the ceiling, with
+/// entries below it left unseen
+pub fn cut_off(entry: &DirEntry, ceiling: usize) -> bool {
+    entry.depth() == ceiling
+        && entry.file_type().is_dir()
+        && fs::read_dir(entry.path()).is_ok_and(|mut children| children.next().is_some())
+}
+
+/// Warn once about every directory the ceiling cut off
+pub fn warn_cut_off(ceiling: usize, dirs: &[PathBuf]) {
+    let Some(first) = dirs.first() else { return };
+    match dirs.len() {
+        1 => log::warn!("Max depth ceiling ({}) reached under {}", ceiling, first.display()),
+        n => log::warn!("Max depth ceiling ({}) reached under {} and {} other directories", ceiling, first.display(), n - 1),
+    }
+}
+
+/// `path` with its middle replaced by `…` when it's longer than
+/// `MAX_HEADER_PATH`, keeping the first component and the file name
+pub fn abbreviate(path: &Path) -> Option<String> {
+    let full = path.display().to_string();
+    if full.chars().count() <= MAX_HEADER_PATH {
+        return None;
+    }
+    let mut components = path.components();
+    let leaf = components.next_back()?;
+    let first = match components.next()? {
+        // Keep the first named directory of an absolute path, not just "/"
+        root @ std::path::Component::RootDir => Path::new(&root).join(components.next()?),
+        first => PathBuf::from(&first),
+    };
+    components.next()?;
+    Some(first.join("…").join(leaf).display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_long_paths_are_abbreviated() {
+        assert_eq!(abbreviate(Path::new("src/main.rs")), None);
+
+        let deep: PathBuf = ["src"].into_iter().chain(["nested"; 40]).chain(["leaf.rs"]).collect();
+        assert_eq!(abbreviate(&deep).as_deref(), Some("src/…/leaf.rs"));
+        assert_eq!(abbreviate(&Path::new("/tmp").join(&deep)).as_deref(), Some("/tmp/…/leaf.rs"));
+
+        // Nothing to cut from a single long name
+        assert_eq!(abbreviate(Path::new(&"x".repeat(300))), None);
+    }
+}
@@ -0,0 +1,129 @@
+//! Capping how much any one directory contributes
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// The most files and bytes a single directory may contribute
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirLimits {
+    pub files: Option<usize>,
+    pub bytes: Option<u64>,
+}
+
+impl DirLimits {
+    pub fn is_set(&self) -> bool {
+        self.files.is_some() || self.bytes.is_some()
+    }
+}
+
+/// A directory that had files left out to stay within the limits
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DirCap {
+    /// Directory relative to the root, empty for the root itself
+    pub dir: PathBuf,
+    pub included: usize,
+    pub total: usize,
+}
+
+impl DirCap {
+    /// The directory as shown in the output, with `.` for the root
+    pub fn name(&self) -> String {
+        if self.dir.as_os_str().is_empty() {
+            ".".to_string()
+        } else {
+            self.dir.to_string_lossy().replace('\\', "/")
+        }
+    }
+
+    /// The note written ahead of the directory's first file
+    pub fn note(&self) -> String {
+        format!("{}/ [directory capped: included {} of {} files]\n\n", self.name(), self.included, self.total)
+    }
+}
+
+/// The items kept and left out by [`cap`], and which directories were capped
+#[derive(Debug)]
+pub struct Capped<T> {
+    pub kept: Vec<T>,
+    pub dropped: Vec<T>,
+    pub dirs: Vec<DirCap>,
+}
+
+/// Keep the smallest files of each immediate parent directory within `limits`
+///
+/// `relative` gives each item's path under the root and `size` its length.
+/// Kept items stay in their original order, and capped directories are sorted
+/// by path.
+pub fn cap<T>(items: Vec<T>, limits: DirLimits, relative: impl Fn(&T) -> &Path, size: impl Fn(&T) -> u64) -> Capped<T> {
+    let mut dirs: HashMap<&Path, Vec<usize>> = HashMap::new();
+    for (i, item) in items.iter().enumerate() {
+        dirs.entry(relative(item).parent().unwrap_or(Path::new(""))).or_default().push(i);
+    }
+
+    let mut keep = vec![true; items.len()];
+    let mut capped = Vec::new();
+    for (dir, mut members) in dirs {
+        // Smallest first, so a cap keeps as many files as it can
+        members.sort_by(|&a, &b| size(&items[a]).cmp(&size(&items[b])).then_with(|| relative(&items[a]).cmp(relative(&items[b]))));
+        let mut bytes = 0;
+        let mut included = 0;
+        for &i in &members {
+            let over_files = limits.files.is_some_and(|max| included >= max);
+            let over_bytes = limits.bytes.is_some_and(|max| bytes + size(&items[i]) > max);
+            if over_files || over_bytes {
+                keep[i] = false;
+            } else {
+                bytes += size(&items[i]);
+                included += 1;
+            }
+        }
+        if included < members.len() {
+            capped.push(DirCap { dir: dir.to_path_buf(), included, total: members.len() });
+        }
+    }
+    capped.sort_by(|a, b| a.dir.cmp(&b.dir));
+
+    let mut result = Capped { kept: Vec::new(), dropped: Vec::new(), dirs: capped };
+    for (item, keep) in items.into_iter().zip(keep) {
+        if keep { result.kept.push(item) } else { result.dropped.push(item) }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> Vec<(PathBuf, u64)> {
+        let mut files: Vec<(PathBuf, u64)> = (0..20).map(|i| (PathBuf::from(format!("snapshots/{:02}.snap", i)), 100 - i)).collect();
+        files.push((PathBuf::from("src/main.rs"), 500));
+        files.push((PathBuf::from("README.md"), 50));
+        files
+    }
+
+    fn run(limits: DirLimits) -> Capped<(PathBuf, u64)> {
+        cap(tree(), limits, |(path, _)| path.as_path(), |(_, size)| *size)
+    }
+
+    #[test]
+    fn test_file_cap_keeps_smallest_in_order() {
+        let capped = run(DirLimits { files: Some(3), bytes: None });
+        let kept: Vec<&str> = capped.kept.iter().map(|(p, _)| p.to_str().unwrap()).collect();
+
+        assert_eq!(kept, ["snapshots/17.snap", "snapshots/18.snap", "snapshots/19.snap", "src/main.rs", "README.md"]);
+        assert_eq!(capped.dropped.len(), 17);
+        assert_eq!(capped.dirs, [DirCap { dir: PathBuf::from("snapshots"), included: 3, total: 20 }]);
+        assert_eq!(capped.dirs[0].note(), "snapshots/ [directory capped: included 3 of 20 files]\n\n");
+    }
+
+    #[test]
+    fn test_byte_cap_applies_per_directory() {
+        // 81 + 82 + ... fits four snapshots in 340 bytes, and main.rs alone is over
+        let capped = run(DirLimits { files: None, bytes: Some(340) });
+        let dirs: Vec<(&str, usize, usize)> = capped.dirs.iter().map(|c| (c.dir.to_str().unwrap(), c.included, c.total)).collect();
+
+        assert_eq!(dirs, [("snapshots", 4, 20), ("src", 0, 1)]);
+        assert!(capped.kept.iter().any(|(p, _)| p == Path::new("README.md")));
+    }
+}
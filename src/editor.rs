@@ -0,0 +1,87 @@
+use crate::error::YoinkError;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::process::Command;
+
+/// The user's preferred editor command, split into program and arguments
+///
+/// Checks `$VISUAL`, then `$EDITOR`, falling back to `vi`. Values such as
+/// `code --wait` are split on whitespace.
+pub fn editor_command() -> Vec<String> {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .map(|value| value.split_whitespace().map(str::to_string).collect::<Vec<_>>())
+        .find(|parts| !parts.is_empty())
+        .unwrap_or_else(|| vec!["vi".to_string()])
+}
+
+/// Fail early when there's no terminal for an interactive feature to use
+pub fn require_terminal(feature: &'static str) -> Result<(), YoinkError> {
+    if std::io::stdin().is_terminal() {
+        Ok(())
+    } else {
+        Err(YoinkError::NotATerminal { feature })
+    }
+}
+
+/// Open `path` in the user's editor and wait for it to exit
+///
+/// The edit is treated as abandoned if the editor exits nonzero or leaves the
+/// file empty.
+pub fn edit_file(path: &Path) -> Result<(), YoinkError> {
+    edit_file_with(&editor_command(), path)
+}
+
+fn edit_file_with(command: &[String], path: &Path) -> Result<(), YoinkError> {
+    let (program, args) = command.split_first().expect("editor command is never empty");
+
+    log::debug!("Opening {} with {}", path.display(), command.join(" "));
+    let status = Command::new(program)
+        .args(args)
+        .arg(path)
+        .status()
+        .map_err(|source| YoinkError::Editor { command: command.join(" "), source })?;
+
+    if !status.success() {
+        return Err(YoinkError::Aborted { reason: format!("editor exited with {}", status) });
+    }
+
+    let emptied = std::fs::metadata(path)
+        .map(|m| m.len() == 0)
+        .map_err(|source| YoinkError::Output { source })?;
+    if emptied {
+        return Err(YoinkError::Aborted { reason: "the edited file is empty".to_string() });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_script(script: &str, content: &str) -> (Result<(), YoinkError>, String) {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), content).unwrap();
+        let command = ["sh", "-c", script].map(String::from);
+        let result = edit_file_with(&command, file.path());
+        (result, std::fs::read_to_string(file.path()).unwrap())
+    }
+
+    #[test]
+    fn test_saved_edit_is_kept() {
+        let (result, content) = run_script("echo trimmed > \"$0\"", "original\n");
+        assert!(result.is_ok());
+        assert_eq!(content, "trimmed\n");
+    }
+
+    #[test]
+    fn test_failed_or_emptied_edit_aborts() {
+        let (result, _) = run_script("exit 3", "original\n");
+        assert!(matches!(result, Err(YoinkError::Aborted { .. })));
+
+        let (result, _) = run_script(": > \"$0\"", "original\n");
+        assert!(matches!(result, Err(YoinkError::Aborted { .. })));
+    }
+}
@@ -0,0 +1,249 @@
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Errors that can occur while yoinking
+#[derive(Debug, Error)]
+pub enum YoinkError {
+    /// The path to yoink does not exist
+    #[error("Path does not exist: {}", path.display())]
+    PathNotFound {
+        path: PathBuf,
+        /// A similarly named neighbour that was probably meant
+        suggestion: Option<PathBuf>,
+    },
+
+    /// The path named an unset variable or user, so it couldn't be expanded
+    #[error("Failed to expand path '{path}': {message}")]
+    PathExpansion { path: String, message: String },
+
+    /// Paths in a `--files-from` list that don't exist, under `--strict`
+    #[error("Listed in {} but missing: {}", list.display(), join_paths(missing))]
+    MissingListed { list: PathBuf, missing: Vec<PathBuf> },
+
+    /// The path to yoink is a FIFO, socket, or device rather than a regular file
+    #[error("{} is a {kind}, not a regular file or directory", path.display())]
+    SpecialFile { path: PathBuf, kind: &'static str },
+
+    /// Walking the directory tree failed at the root
+    #[error("Failed to scan {}: {source}", path.display())]
+    Scan {
+        path: PathBuf,
+        #[source]
+        source: walkdir::Error,
+    },
+
+    /// Reading or inspecting a single file failed
+    #[error("Failed to read {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    /// Piped input turned out to be binary
+    #[error("{name} looks like binary data (it contains null bytes); only text can be yoinked from stdin")]
+    BinaryInput { name: String },
+
+    /// The config file could not be read or written
+    #[error("Failed to access config file {}: {source}", path.display())]
+    Config {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    /// The config file could not be parsed or serialized
+    #[error("Invalid config file {}: {source}", path.display())]
+    ConfigFormat {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    /// The config file's rules contradict each other
+    #[error("Invalid rules in config file {}: {message}", path.display())]
+    ConfigRule { path: PathBuf, message: String },
+
+    /// `--profile` named a profile the config file doesn't have
+    #[error("No profile named '{name}' in {}; {}", path.display(), list_profiles(available))]
+    UnknownProfile { path: PathBuf, name: String, available: Vec<String> },
+
+    /// A file's content could not be formatted for output
+    #[error("Failed to format {}: {message}", path.display())]
+    Format { path: PathBuf, message: String },
+
+    /// Processing a single file panicked
+    #[error("Panicked while processing {}: {message}", path.display())]
+    Panicked { path: PathBuf, message: String },
+
+    /// Writing the assembled output, or reading it back from a spill file, failed
+    #[error("Output I/O failed: {source}")]
+    Output {
+        #[source]
+        source: io::Error,
+    },
+
+    /// The `--output` file couldn't be created or written
+    #[error("Failed to write {}: {source}", path.display())]
+    OutputFile {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    /// Watching the tree for changes failed
+    #[error("Failed to watch {}: {source}", path.display())]
+    Watch {
+        path: PathBuf,
+        #[source]
+        source: notify::Error,
+    },
+
+    /// An interactive feature was requested without a terminal to run it in
+    #[error("{feature} needs an interactive terminal on stdin")]
+    NotATerminal { feature: &'static str },
+
+    /// The editor could not be started
+    #[error("Failed to start editor '{command}': {source}")]
+    Editor {
+        command: String,
+        #[source]
+        source: io::Error,
+    },
+
+    /// The file picker could not be started
+    #[error("Failed to start file picker '{command}': {source}")]
+    Picker {
+        command: String,
+        #[source]
+        source: io::Error,
+    },
+
+    /// The user backed out before anything was copied
+    #[error("Aborted: {reason}")]
+    Aborted { reason: String },
+
+    /// The local HTTP server could not be started
+    #[error("Failed to serve output: {source}")]
+    Serve {
+        #[source]
+        source: io::Error,
+    },
+
+    /// The client fetching the output went away partway through it
+    #[error("Sending the output to {peer} failed partway: {source}")]
+    ServeInterrupted {
+        peer: String,
+        #[source]
+        source: io::Error,
+    },
+
+    /// Nobody fetched the served output in time
+    #[error("Nobody fetched the output within {seconds}s")]
+    ServeTimeout { seconds: u64 },
+
+    /// A manifest could not be read or written
+    #[error("Manifest {}: {message}", path.display())]
+    Manifest { path: PathBuf, message: String },
+
+    /// No workspace was found, or it has no member by the requested name
+    #[error("Workspace at {}: {message}", path.display())]
+    Workspace { path: PathBuf, message: String },
+
+    /// The `--output-dir` directory couldn't be used or written
+    #[error("Output directory {}: {message}", path.display())]
+    OutputDir { path: PathBuf, message: String },
+
+    /// The filters contradict each other and `--strict-filters` was given
+    #[error("Filters contradict each other: {message}")]
+    FilterConflict { message: String },
+
+    /// A `--label` names a path that isn't one of those given
+    #[error("--label {label}={path} doesn't name one of the paths given")]
+    Label { label: String, path: String },
+
+    /// Several paths were given along with stdin or a repository URL
+    #[error("Cannot yoink several paths: {message}")]
+    Paths { message: String },
+
+    /// `--search-regex` isn't a valid regular expression
+    #[error("Invalid search regex: {message}")]
+    SearchRegex { message: String },
+
+    /// `--select` indices don't line up with the last listing
+    #[error("Cannot select files: {message}")]
+    Selection { message: String },
+
+    /// `yoink rerun` asked for an invocation the log doesn't have
+    #[error("Cannot rerun: {message}")]
+    History { message: String },
+
+    /// `--symbol` named an item no file has, or a file it can't look inside
+    #[error("Cannot copy symbols: {message}")]
+    Symbol { message: String },
+
+    /// `--append` was asked for with an output format it can't join
+    #[error("--append only works with plain or markdown output, not --format {format}")]
+    AppendFormat { format: String },
+
+    /// `--chunk` asked for a chunk past the last one
+    #[error("Cannot pick a chunk: {message}")]
+    Chunk { message: String },
+
+    /// The run was interrupted before it finished
+    #[error("Interrupted after processing {processed} files")]
+    Cancelled { processed: usize },
+
+    /// A remote repository was given but `git` isn't installed
+    #[error("git is required to yoink a remote repository but was not found on PATH")]
+    GitNotFound,
+
+    /// `--git` was given for a path outside any git work tree
+    #[error("--git needs a git repository, but {} isn't inside one", path.display())]
+    NotARepository { path: PathBuf },
+
+    /// A git command failed, usually because the path isn't in a work tree
+    #[error("git failed: {message}")]
+    Git { message: String },
+
+    /// Cloning a remote repository failed
+    #[error("Failed to clone {url}: {message}")]
+    Clone { url: String, message: String },
+
+    /// None of the known clipboard utilities could be used
+    #[error("No clipboard utility found. Please make sure you have one of the following installed: xclip, xsel (Linux/X11), wl-copy (Wayland), pbcopy (macOS), or termux-clipboard-set (Android/Termux)")]
+    NoClipboard,
+
+    /// `--append` found something other than yoink output on the clipboard
+    #[error("The clipboard doesn't hold yoink output to append to")]
+    NotYoinkOutput,
+
+    /// A clipboard utility was found but failed
+    #[error("Clipboard command '{command}' failed: {source}")]
+    Clipboard {
+        command: String,
+        #[source]
+        source: io::Error,
+    },
+}
+
+impl YoinkError {
+    /// A missing path, along with a sibling whose name is close enough to be the likely typo
+    pub fn path_not_found(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let suggestion = crate::utils::closest_sibling(&path);
+        Self::PathNotFound { path, suggestion }
+    }
+}
+
+fn join_paths(paths: &[PathBuf]) -> String {
+    paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ")
+}
+
+fn list_profiles(names: &[String]) -> String {
+    match names {
+        [] => "no profiles have been saved".to_string(),
+        names => format!("available profiles: {}", names.join(", ")),
+    }
+}
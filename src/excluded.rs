@@ -0,0 +1,146 @@
+//! A one-line overview of each directory left out of the scan
+
+use crate::config::{Config, NestedRepoMode};
+use crate::git;
+use crate::utils;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Most children named for any one excluded directory
+pub const MAX_CHILDREN: usize = 30;
+
+/// An excluded directory and the names directly inside it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExcludedDir {
+    /// Directory relative to the root
+    pub dir: PathBuf,
+    /// Sorted names of the first children, with a trailing `/` on directories
+    pub children: Vec<String>,
+    /// Children in total, including any not named
+    pub total: usize,
+}
+
+impl ExcludedDir {
+    /// The directory's line in the overview section
+    pub fn line(&self) -> String {
+        let dir = self.dir.to_string_lossy().replace('\\', "/");
+        if self.total == 0 {
+            return format!("{}/: (empty)\n", dir);
+        }
+        let mut line = format!("{}/: {}", dir, self.children.join(", "));
+        if self.total > self.children.len() {
+            line.push_str(&format!(" (+{} more)", self.total - self.children.len()));
+        }
+        line.push('\n');
+        line
+    }
+}
+
+/// Directories under `root` pruned by `--exclude-paths`, `--exclude-glob`, or
+/// a skipping rule,
+/// each with a listing of its immediate children
+///
+/// The walk stops at every excluded directory, and its listing is a single
+/// `read_dir`, so nothing inside it is ever recursed into.
+pub fn overview(root: &Path, config: &Config) -> Vec<ExcludedDir> {
+    let base = utils::relative_base(root);
+    let mut found = Vec::new();
+    let walker = WalkDir::new(root)
+        .max_depth(config.max_depth as usize)
+        .follow_links(false)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            if e.depth() == 0 || !e.file_type().is_dir() {
+                return true;
+            }
+            if e.file_name() == ".git" || (config.skip_hidden && utils::is_hidden(e.path())) {
+                return false;
+            }
+            if config.nested_repos == NestedRepoMode::Skip && git::is_nested_repo(e.path()) {
+                return false;
+            }
+            let relative = e.path().strip_prefix(base).unwrap_or(e.path());
+            if is_excluded(config, relative) {
+                found.push(list(e.path(), relative, config.skip_hidden));
+                return false;
+            }
+            true
+        });
+    // Draining the walker is what runs the filter
+    walker.for_each(drop);
+    found
+}
+
+fn is_excluded(config: &Config, relative: &Path) -> bool {
+    let name = relative.file_name().map(|name| name.to_string_lossy());
+    let by_name = config.exclude_paths.as_ref().is_some_and(|paths| paths.iter().any(|p| Some(p.as_str()) == name.as_deref()));
+    by_name || config.excluding_glob(relative, true).is_some() || config.rule_for(relative).is_some_and(|rule| rule.skip)
+}
+
+// One level of `dir`, leaving out hidden entries when those are skipped
+fn list(dir: &Path, relative: &Path, skip_hidden: bool) -> ExcludedDir {
+    let mut names: Vec<String> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .filter(|entry| !skip_hidden || !utils::is_hidden(&entry.path()))
+            .map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if entry.file_type().is_ok_and(|t| t.is_dir()) { format!("{}/", name) } else { name }
+            })
+            .collect(),
+        Err(e) => {
+            log::warn!("Couldn't list excluded directory {}: {}", dir.display(), e);
+            Vec::new()
+        }
+    };
+    names.sort();
+    let total = names.len();
+    names.truncate(MAX_CHILDREN);
+    ExcludedDir { dir: relative.to_path_buf(), children: names, total }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PathRule;
+
+    #[test]
+    fn test_lists_excluded_dirs_one_level_deep() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for package in ["react", "lodash", "axios"] {
+            fs::create_dir_all(root.join("node_modules").join(package).join("lib")).unwrap();
+        }
+        fs::write(root.join("node_modules/.package-lock.json"), "{}").unwrap();
+        fs::create_dir_all(root.join("src/vendor")).unwrap();
+        fs::write(root.join("src/vendor/lib.c"), "").unwrap();
+        fs::create_dir_all(root.join("third_party")).unwrap();
+
+        let config = Config::builder()
+            .path(root.to_string_lossy())
+            .exclude_paths(["node_modules", "vendor"])
+            .skip_hidden(true)
+            .rule(PathRule { path: "third_party".to_string(), skip: true, ..PathRule::default() })
+            .build();
+        let lines: String = overview(root, &config).iter().map(ExcludedDir::line).collect();
+
+        assert_eq!(lines, "node_modules/: axios/, lodash/, react/\nsrc/vendor/: lib.c\nthird_party/: (empty)\n");
+    }
+
+    #[test]
+    fn test_long_listings_are_capped() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..45 {
+            fs::create_dir_all(dir.path().join(format!("vendor/pkg{:02}", i))).unwrap();
+        }
+        let config = Config::builder().path(dir.path().to_string_lossy()).exclude_paths(["vendor"]).build();
+        let found = overview(dir.path(), &config);
+
+        assert_eq!(found[0].children.len(), MAX_CHILDREN);
+        assert_eq!(found[0].total, 45);
+        assert!(found[0].line().ends_with("pkg29/ (+15 more)\n"));
+    }
+}
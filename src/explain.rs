@@ -0,0 +1,114 @@
+//! Why a file was or wasn't included, check by check
+
+use std::fmt;
+use std::path::PathBuf;
+
+/// Why a check rejected a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// Git's own metadata, such as a submodule's `.git` file
+    GitMetadata,
+    Hidden,
+    /// A path component matched `--exclude-paths`
+    ExcludedPath { component: String },
+    /// The path or a directory above it matched `--exclude-glob`
+    ExcludedGlob { pattern: String },
+    /// A config rule with `skip` covers the file
+    SkippedByRule { rule: String },
+    /// The extension isn't in the include list, from a rule if one decided it
+    NotIncluded { extension: Option<String>, rule: Option<String> },
+    /// The extension is in the exclude list, from a rule if one decided it
+    Excluded { extension: String, rule: Option<String> },
+    PatternMismatch { pattern: String },
+    /// The name matched `--exclude-pattern`
+    ExcludedPattern { pattern: String },
+    RegexMismatch { regex: String },
+    TooLarge { size: u64, limit: u64 },
+    Binary,
+    /// Matched a `--force-binary` glob
+    ForcedBinary { pattern: String },
+    /// A FIFO, socket, or device, which is never opened
+    SpecialFile { kind: &'static str },
+    /// Matched a test pattern under `--skip-tests`
+    TestFile { pattern: String },
+    /// Matched a generated-file pattern under `--no-generated`, from the
+    /// config's list if `configured`
+    Generated { pattern: String, configured: bool },
+    /// Matched a `.yoinkignore` rule, directly or through the directory `dir`
+    Ignored { rule: String, source: String, line: usize, dir: Option<PathBuf> },
+    /// Not listed by git under `--git`
+    Untracked,
+    /// Last modified before `--since`
+    ModifiedBefore { since: String },
+    /// Last modified after `--until`
+    ModifiedAfter { until: String },
+    /// The modification time couldn't be read to compare against `--since` or `--until`
+    UnknownModified { message: String },
+    /// Left out by `--max-files-per-dir` or `--max-bytes-per-dir`
+    DirectoryCapped { included: usize, total: usize },
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::GitMetadata => write!(f, "git metadata"),
+            SkipReason::Hidden => write!(f, "hidden file"),
+            SkipReason::ExcludedPath { component } => write!(f, "under excluded path '{}'", component),
+            SkipReason::ExcludedGlob { pattern } => write!(f, "matches excluded glob '{}'", pattern),
+            SkipReason::SkippedByRule { rule } => write!(f, "skipped by rule '{}'", rule),
+            SkipReason::NotIncluded { extension: Some(ext), .. } => write!(f, "extension '{}' is not included", ext),
+            SkipReason::NotIncluded { extension: None, .. } => write!(f, "no extension, and only listed extensions are included"),
+            SkipReason::Excluded { extension, .. } => write!(f, "extension '{}' is excluded", extension),
+            SkipReason::PatternMismatch { pattern } => write!(f, "name doesn't match pattern '{}'", pattern),
+            SkipReason::ExcludedPattern { pattern } => write!(f, "name matches excluded pattern '{}'", pattern),
+            SkipReason::RegexMismatch { regex } => write!(f, "name doesn't match regex '{}'", regex),
+            SkipReason::TooLarge { size, limit } => write!(
+                f,
+                "{} is over the {} limit",
+                crate::utils::format_size(*size),
+                crate::utils::format_size(*limit)
+            ),
+            SkipReason::Binary => write!(f, "binary content"),
+            SkipReason::ForcedBinary { pattern } => write!(f, "forced binary by '{}'", pattern),
+            SkipReason::SpecialFile { kind } => write!(f, "special file ({})", kind),
+            SkipReason::TestFile { pattern } => write!(f, "test file ('{}')", pattern),
+            SkipReason::Generated { pattern, configured: false } => write!(f, "generated file ('{}', --no-generated preset)", pattern),
+            SkipReason::Generated { pattern, configured: true } => write!(f, "generated file ('{}', generated_patterns)", pattern),
+            SkipReason::Ignored { rule, source, line, .. } => write!(f, "ignored by '{}' ({}:{})", rule, source, line),
+            SkipReason::Untracked => write!(f, "not tracked by git"),
+            SkipReason::ModifiedBefore { since } => write!(f, "last modified before {}", since),
+            SkipReason::ModifiedAfter { until } => write!(f, "last modified after {}", until),
+            SkipReason::UnknownModified { message } => write!(f, "modification time unreadable: {}", message),
+            SkipReason::DirectoryCapped { included, total } => {
+                write!(f, "directory capped: included {} of {} files", included, total)
+            }
+        }?;
+        match self {
+            SkipReason::NotIncluded { rule: Some(rule), .. } | SkipReason::Excluded { rule: Some(rule), .. } => {
+                write!(f, " (rule '{}')", rule)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The result of one named check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verdict {
+    pub check: &'static str,
+    pub result: Result<(), SkipReason>,
+}
+
+/// Every check a file went through, ending at the first rejection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub path: PathBuf,
+    pub verdicts: Vec<Verdict>,
+}
+
+impl Explanation {
+    /// The reason the file was left out, if it was
+    pub fn rejection(&self) -> Option<&SkipReason> {
+        self.verdicts.iter().find_map(|v| v.result.as_ref().err())
+    }
+}
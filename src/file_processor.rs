@@ -1,18 +1,97 @@
-use crate::cli::Config;
+use crate::append;
+use crate::cache::FileCache;
+use crate::cancel::CancelToken;
+use crate::chunk;
+use crate::config::{Config, OutputFormat, OversizeMode};
+use crate::deadline::{self, NetworkMounts};
+use crate::depth;
+use crate::dedup::{Deduper, Fingerprint};
+use crate::dir_cap::{self, Capped, DirCap, DirLimits};
+use crate::editor;
+use crate::error::YoinkError;
+use crate::excluded::{self, ExcludedDir};
+use crate::explain::{Explanation, SkipReason, Verdict};
+use crate::pager;
+use crate::preview::{Digest, Section};
+use crate::picker;
+use crate::progress;
 use crate::clipboard::ClipboardInterface;
 use crate::file_tree::DirectoryTreeBuilding;
-use crate::file_scanner::{FileScanning, FileEntry};
-use crate::text_processor::TextProcessing;
+use crate::files_list;
+use crate::formatter::{self, OutputFormatter};
+use crate::file_scanner::{FileEntry, FileScanning};
+use crate::test_code;
+use crate::text_processor::{self, LineSearch, SearchMatches, TextProcessing};
+use crate::utils;
+use crate::git::{DiffSet, FileChange, GitLog};
+use crate::header::HeaderTemplate;
+use crate::html::HtmlPage;
+use crate::manifest::{self, Manifest};
+use crate::markdown;
+use crate::media::{self, Asset};
+use crate::output_dir::OutputDir;
+use crate::fit::{self, FitStrategy};
+use crate::group::{self, GroupSummary};
+use crate::sample::{self, SampleSummary};
+use crate::report::{Chunks, DiffSummary, FileOutcome, FileRecord, ProcessReport, Truncation, WrittenFile};
+use crate::roots::{self, RootLabels};
+use crate::selection::{self, FileKind, ListedFile, Listing, LISTED_FILE_VERSION};
+use crate::serve::{self, ServeOptions, ServeReceipt};
+use crate::skip_log::SkipLog;
+use crate::symbol;
+use crate::target;
+use crate::workspace::Workspace;
+use crate::xml;
 use indicatif::{ProgressBar, ProgressStyle};
-use std::sync::{Arc, Mutex};
 use rayon::prelude::*;
+use std::any::Any;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Read, Seek, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Called with the scanned entries before any is read, so tests can change
+/// the tree mid-run
+#[cfg(test)]
+type ScanHook = dyn Fn(&[FileEntry]) + Send + Sync;
+
+/// A file's record along with its formatted chunk, or the error that stopped it
+type FileResult = (FileRecord, Result<Option<String>, YoinkError>);
+
+/// Assembled output of a run along with what went into it
+#[derive(Debug, Clone)]
+pub struct Output {
+    pub content: String,
+    pub report: ProcessReport,
+}
+
+/// Output that is ready to be copied, either in memory or in a file
+enum Finished<'a> {
+    Text(&'a str),
+    File(&'a mut File),
+}
 
 pub struct FileProcessor {
     config: Config,
     clipboard: Box<dyn ClipboardInterface>,
     file_scanner: Box<dyn FileScanning>,
-    text_processor: Box<dyn TextProcessing>,
+    // Shared so a read can outlive its worker when it times out
+    text_processor: Arc<dyn TextProcessing>,
     dir_tree_builder: Box<dyn DirectoryTreeBuilding>,
+    /// Lays out the output in place of the built-in formats
+    formatter: Option<Box<dyn OutputFormatter>>,
+    cancel: CancelToken,
+    cache: Option<Arc<FileCache>>,
+    /// Set once `--output` has been written, so a watch rerun may replace it
+    wrote_output: AtomicBool,
+    /// Called between scanning and processing, so tests can change files in between
+    #[cfg(test)]
+    after_scan: Option<Box<ScanHook>>,
 }
 
 impl FileProcessor {
@@ -27,255 +106,3563 @@ impl FileProcessor {
             config,
             clipboard,
             file_scanner,
-            text_processor,
+            text_processor: Arc::from(text_processor),
             dir_tree_builder,
+            formatter: None,
+            cancel: CancelToken::new(),
+            cache: None,
+            wrote_output: AtomicBool::new(false),
+            #[cfg(test)]
+            after_scan: None,
         }
     }
 
     /// Factory method to create FileProcessor with default dependencies
     pub fn with_defaults(config: Config) -> Self {
-        use crate::clipboard::ClipboardManager;
-        use crate::file_tree::DirectoryTreeBuilder;
-        use crate::file_scanner::FileScanner;
-        use crate::text_processor::TextProcessor;
-        
-        Self {
-            clipboard: Box::new(ClipboardManager::new(config.verbose)),
-            file_scanner: Box::new(FileScanner::new(&config)),
-            text_processor: Box::new(TextProcessor::new(&config)),
-            dir_tree_builder: Box::new(DirectoryTreeBuilder::new(&config)),
+        Self::builder(config).build()
+    }
+
+    /// Start building a processor, swapping in only the components you need
+    ///
+    /// Anything not set falls back to the real implementation.
+    ///
+    /// ```
+    /// use yoink::{Config, FileProcessor};
+    /// use yoink::text_processor::TextProcessor;
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// std::fs::write(dir.path().join("a.txt"), "alpha").unwrap();
+    ///
+    /// let config = Config::builder().path(dir.path().to_string_lossy()).build();
+    /// let processor = FileProcessor::builder(config.clone())
+    ///     .text_processor(TextProcessor::new(&config))
+    ///     .build();
+    ///
+    /// assert!(processor.text_processor_as::<TextProcessor>().is_some());
+    /// assert_eq!(processor.assemble().unwrap().report.text_files, 1);
+    /// ```
+    pub fn builder(config: Config) -> FileProcessorBuilder {
+        FileProcessorBuilder {
             config,
+            clipboard: None,
+            file_scanner: None,
+            text_processor: None,
+            dir_tree_builder: None,
+            formatter: None,
         }
     }
 
-    pub fn process(&mut self) -> Result<(usize, usize), String> {
-        let pb = self.setup_progress_bar();
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Whether a change to `path` could affect the output
+    pub fn matches_filters(&self, path: &Path) -> bool {
+        self.file_scanner.matches_filters(path)
+    }
+
+    /// The clipboard component, if it is a `T`
+    pub fn clipboard_as<T: ClipboardInterface>(&self) -> Option<&T> {
+        (self.clipboard.as_ref() as &dyn Any).downcast_ref()
+    }
+
+    /// The file scanner component, if it is a `T`
+    pub fn file_scanner_as<T: FileScanning>(&self) -> Option<&T> {
+        (self.file_scanner.as_ref() as &dyn Any).downcast_ref()
+    }
+
+    /// The text processor component, if it is a `T`
+    pub fn text_processor_as<T: TextProcessing>(&self) -> Option<&T> {
+        (self.text_processor.as_ref() as &dyn Any).downcast_ref()
+    }
+
+    /// The directory tree builder component, if it is a `T`
+    pub fn dir_tree_builder_as<T: DirectoryTreeBuilding>(&self) -> Option<&T> {
+        (self.dir_tree_builder.as_ref() as &dyn Any).downcast_ref()
+    }
+
+    /// Token that stops the run early when cancelled
+    ///
+    /// Files already being processed are finished, no new ones are started,
+    /// and the run returns `YoinkError::Cancelled`.
+    pub fn cancel_token(&self) -> CancelToken {
+        self.cancel.clone()
+    }
+
+    /// Scan, read, and format files, then copy the result to the clipboard
+    pub fn process(&mut self) -> Result<ProcessReport, YoinkError> {
+        // Fail before the run rather than after it when the output would be clobbered
+        let output = self.config.output.as_ref().map(|path| match self.config.chunk_size {
+            Some(_) => chunk::numbered(path, 1),
+            None => path.clone(),
+        });
+        if let Some(path) = output.filter(|path| !self.may_replace_output() && path.exists()) {
+            let source = io::Error::new(io::ErrorKind::AlreadyExists, "the file already exists");
+            return Err(YoinkError::OutputFile { path, source });
+        }
         
-        // Create thread-safe buffer and counters
-        let buffer = Arc::new(Mutex::new(String::new()));
-        let text_count = Arc::new(Mutex::new(0));
-        let binary_count = Arc::new(Mutex::new(0));
+        if self.config.pick {
+            editor::require_terminal("--pick")?;
+        }
         
-        // Add directory structure at the top
-        {
-            let mut buffer = buffer.lock().unwrap();
-            buffer.push_str("=== DIRECTORY STRUCTURE ===\n");
-            self.dir_tree_builder.build_directory_tree(&mut buffer)?;
-            buffer.push_str("\n=== TEXT FILES ===\n\n");
+        if self.config.edit || self.config.pager || self.config.preview {
+            if self.config.edit {
+                editor::require_terminal("--edit")?;
+            }
+            if self.config.pager {
+                editor::require_terminal("--pager")?;
+            }
+            return self.process_reviewed();
         }
-
-        // Collect and filter files first
-        let mut entries = self.file_scanner.collect_files();
         
-        if self.config.sort {
-            entries.sort_by_key(|e| e.path().to_path_buf());
+        if let Some(size) = self.config.chunk_size {
+            return self.process_chunked(size);
         }
         
-        // Setup progress tracking
-        let progress = self.setup_file_progress(entries.len());
+        if self.config.low_memory {
+            let mut spill = tempfile::tempfile().map_err(|source| YoinkError::Output { source })?;
+            let mut report = {
+                let mut writer = BufWriter::new(&mut spill);
+                let report = self.write_output(&mut writer)?;
+                writer.flush().map_err(|source| YoinkError::Output { source })?;
+                report
+            };
+            self.deliver(&mut report, Finished::File(&mut spill))?;
+            Ok(report)
+        } else {
+            let output = self.assemble()?;
+            let mut report = output.report;
+            self.deliver(&mut report, Finished::Text(&output.content))?;
+            Ok(report)
+        }
+    }
+
+    // Split the output between sections, then write every chunk to numbered
+    // files with --output, or deliver just the one asked for
+    fn process_chunked(&self, size: usize) -> Result<ProcessReport, YoinkError> {
+        let mut content = Vec::new();
+        let (mut report, starts) = self.write_sections(&mut content, None)?;
+        let content = String::from_utf8(content).expect("formatted output is built from UTF-8 strings");
+        let chunks = chunk::split(&content, &starts, size);
+        let number = self.config.chunk.unwrap_or(1);
+        if number > chunks.len() {
+            let made = if chunks.len() == 1 { "fits in one".to_string() } else { format!("only makes {}", chunks.len()) };
+            return Err(YoinkError::Chunk { message: format!("asked for chunk {}, but the output {}", number, made) });
+        }
+        if chunks.len() == 1 {
+            self.deliver(&mut report, Finished::Text(&content))?;
+            return Ok(report);
+        }
         
-        // Process files in parallel
-        entries.par_iter().for_each(|entry| {
-            let buffer = Arc::clone(&buffer);
-            let text_count = Arc::clone(&text_count);
-            let binary_count = Arc::clone(&binary_count);
-            let progress = Arc::clone(&progress);
-            
-            // Process each file
-            if let Err(e) = self.process_file_parallel(
-                entry, 
-                &buffer, 
-                &progress, 
-                &text_count, 
-                &binary_count
-            ) {
-                let mut progress = progress.lock().unwrap();
-                progress.println(format!("Error processing file {}: {}", entry.path().display(), e));
+        let mut split = Chunks { count: chunks.len(), size, delivered: None, files: Vec::new() };
+        match &self.config.output {
+            Some(path) => {
+                for (index, chunk) in chunks.iter().enumerate() {
+                    split.files.push(self.write_to(&chunk::numbered(path, index + 1), &mut Finished::Text(chunk))?);
+                }
+            }
+            None => {
+                self.deliver(&mut report, Finished::Text(&chunks[number - 1]))?;
+                split.delivered = Some(number);
             }
-            
-            // Increment progress bar
-            let mut progress = progress.lock().unwrap();
-            progress.inc(1);
-        });
-        
-        // Finalize the output
-        {
-            let mut buffer = buffer.lock().unwrap();
-            buffer.push_str("\n=== SUMMARY ===\n");
-            let text_count = *text_count.lock().unwrap();
-            let binary_count = *binary_count.lock().unwrap();
-            buffer.push_str(&format!("Text files processed: {}\n", text_count));
-            buffer.push_str(&format!("Binary files skipped: {}\n", binary_count));
-            
-            // Copy to clipboard
-            progress.lock().unwrap().finish_and_clear();
-            self.clipboard.copy_to_clipboard(&buffer)?;
-            
-            Ok((text_count, binary_count))
         }
+        report.chunks = Some(split);
+        Ok(report)
     }
 
-    // This function processes a single file in parallel
-    fn process_file_parallel(
-        &self,
-        entry: &walkdir::DirEntry,
-        buffer: &Arc<Mutex<String>>,
-        progress: &Arc<Mutex<ProgressBar>>,
-        text_count: &Arc<Mutex<usize>>,
-        binary_count: &Arc<Mutex<usize>>,
-    ) -> Result<(), String> {
-        let path = entry.path();
+    /// Hand the finished output to the clipboard, or serve or write it when asked to
+    fn deliver(&self, report: &mut ProcessReport, mut output: Finished) -> Result<(), YoinkError> {
+        if self.config.serve {
+            report.served = Some(self.serve(&mut output).map_err(|e| match e {
+                YoinkError::Cancelled { .. } => YoinkError::Cancelled { processed: report.processed() },
+                e => e,
+            })?);
+            if !self.config.copy {
+                return Ok(());
+            }
+        }
+        
+        if let Some(path) = &self.config.output {
+            report.output_file = Some(self.write_to(path, &mut output)?);
+        }
+        
+        if self.config.stdout {
+            return Self::write_stdout(&mut output);
+        }
         
-        // Skip if not a file
-        if !path.is_file() {
+        if (self.config.output.is_some() || self.config.output_dir.is_some()) && !self.config.copy {
             return Ok(());
         }
         
-        // Check file size
-        let metadata = match path.metadata() {
-            Ok(metadata) => metadata,
-            Err(e) => {
-                return Err(format!("Failed to get metadata for {}: {}", path.display(), e));
+        let started = Instant::now();
+        if let Some(mode) = self.config.append {
+            let text = match output {
+                Finished::Text(text) => text.to_string(),
+                Finished::File(file) => {
+                    let mut text = String::new();
+                    file.rewind().and_then(|_| file.read_to_string(&mut text)).map_err(|source| YoinkError::Output { source })?;
+                    text
+                }
+            };
+            let appended = append::append(&self.clipboard.paste_from_clipboard()?, &text, mode)?;
+            report.clipboard = Some(self.clipboard.copy_to_clipboard(&appended.text)?);
+            report.appended_run = Some(appended.run);
+            report.durations.clipboard = started.elapsed();
+            return Ok(());
+        }
+        report.clipboard = Some(match output {
+            Finished::Text(text) => self.clipboard.copy_to_clipboard(text)?,
+            Finished::File(file) => self.clipboard.copy_from_file(file)?,
+        });
+        report.durations.clipboard = started.elapsed();
+        Ok(())
+    }
+
+    fn write_to(&self, path: &Path, output: &mut Finished) -> Result<WrittenFile, YoinkError> {
+        let error = |source| YoinkError::OutputFile { path: path.to_path_buf(), source };
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(error)?;
+        }
+        // Without --force, a file that appeared since the run started is still not replaced
+        let replace = self.may_replace_output();
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(replace)
+            .create_new(!replace)
+            .truncate(true)
+            .open(path)
+            .map_err(error)?;
+        let bytes = match output {
+            Finished::Text(text) => file.write_all(text.as_bytes()).map(|_| text.len() as u64),
+            Finished::File(spill) => spill.rewind().and_then(|_| io::copy(spill, &mut file)),
+        }
+        .map_err(error)?;
+        self.wrote_output.store(true, Ordering::Relaxed);
+        Ok(WrittenFile { path: path.to_path_buf(), bytes })
+    }
+
+    // A reader that stops early, like `head`, isn't an error
+    fn write_stdout(output: &mut Finished) -> Result<(), YoinkError> {
+        let mut stdout = io::stdout().lock();
+        let written = match output {
+            Finished::Text(text) => stdout.write_all(text.as_bytes()),
+            Finished::File(spill) => spill.rewind().and_then(|_| io::copy(spill, &mut stdout).map(drop)),
+        }
+        .and_then(|_| stdout.flush());
+        match written {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(YoinkError::Output { source: e }),
+            _ => Ok(()),
+        }
+    }
+
+    fn may_replace_output(&self) -> bool {
+        self.config.force || self.wrote_output.load(Ordering::Relaxed)
+    }
+
+    fn serve(&self, output: &mut Finished) -> Result<ServeReceipt, YoinkError> {
+        let options = ServeOptions { bind: self.config.bind, timeout: self.config.serve_timeout };
+        let on_ready = |url: &str| {
+            // Printed directly so the URL shows up even when stderr isn't a terminal
+            eprintln!("Serving once at {} (waiting up to {}s)", url, options.timeout.as_secs());
+            if options.bind.is_unspecified() {
+                eprintln!("Replace {} with this machine's address to fetch it from elsewhere", options.bind);
             }
         };
         
-        if metadata.len() > self.config.max_size {
-            if self.config.verbose {
-                progress.lock().unwrap().println(
-                    format!("Skipping large file: {} ({} bytes)", path.display(), metadata.len())
-                );
+        match output {
+            Finished::Text(text) => {
+                serve::serve_once(&mut text.as_bytes(), text.len() as u64, options, &self.cancel, &on_ready)
+            }
+            Finished::File(file) => {
+                file.rewind().map_err(|source| YoinkError::Output { source })?;
+                let len = file.metadata().map_err(|source| YoinkError::Output { source })?.len();
+                serve::serve_once(file, len, options, &self.cancel, &on_ready)
             }
-            return Ok(());
         }
+    }
+
+    // The output always goes through a named file here so the editor or pager
+    // can open it, which also keeps it out of memory while it's reviewed
+    fn process_reviewed(&mut self) -> Result<ProcessReport, YoinkError> {
+        let temp = tempfile::Builder::new()
+            .prefix("yoink-")
+            .suffix(".txt")
+            .tempfile()
+            .map_err(|source| YoinkError::Output { source })?;
         
-        // Process the file based on its type
-        let result = self.text_processor.process_file(path);
+        let mut digest = self.config.preview.then(Digest::default);
+        let mut report = {
+            let mut writer = BufWriter::new(temp.as_file());
+            let report = self.write_output_with(&mut writer, digest.as_mut())?;
+            writer.flush().map_err(|source| YoinkError::Output { source })?;
+            report
+        };
         
-        match result {
-            Ok(Some(content)) => {
-                // Update the buffer with the processed text content
-                let mut buffer = buffer.lock().unwrap();
-                let was_included = self.text_processor.format_text_content(path, &content, &mut buffer)?;
-                
-                if was_included {
-                    // Increment text count
-                    let mut text_count = text_count.lock().unwrap();
-                    *text_count += 1;
-                    
-                    if self.config.verbose {
-                        progress.lock().unwrap().println(
-                            format!("Processed text file: {}", path.display())
-                        );
-                    }
-                }
+        let path = temp.path().to_path_buf();
+        if self.config.keep_temp {
+            temp.keep().map_err(|e| YoinkError::Output { source: e.error })?;
+            let _ = progress::multi().println(format!("Keeping edited output at {}", path.display()));
+        }
+        
+        // Without a terminal there's nobody to accept the preview, so decline
+        if let Some(digest) = &digest {
+            eprint!("{}", digest.render());
+            if !io::stdin().is_terminal() {
+                return Err(YoinkError::Aborted { reason: "no terminal to confirm the preview".to_string() });
             }
-            Ok(None) => {
-                // It's a binary file or we're skipping it
-                let mut binary_count = binary_count.lock().unwrap();
-                *binary_count += 1;
-                
-                if self.config.verbose {
-                    progress.lock().unwrap().println(
-                        format!("Skipping binary file: {}", path.display())
-                    );
-                }
+            if !pager::confirm("Proceed?")? {
+                return Err(YoinkError::Aborted { reason: "declined after preview".to_string() });
             }
-            Err(e) => {
-                return Err(format!("Error processing {}: {}", path.display(), e));
+        }
+        
+        if self.config.edit {
+            editor::edit_file(&path)?;
+        }
+        
+        if self.config.pager {
+            progress::multi().suspend(|| pager::page_file(&path))?;
+            if !pager::confirm("Copy to clipboard?")? {
+                return Err(YoinkError::Aborted { reason: "declined to copy".to_string() });
             }
         }
         
-        Ok(())
+        // Editors often replace the file rather than write into it, so reopen by path
+        let mut edited = File::open(&path).map_err(|source| YoinkError::Output { source })?;
+        self.deliver(&mut report, Finished::File(&mut edited))?;
+        report.output_bytes = edited.metadata().map_err(|source| YoinkError::Output { source })?.len();
+        
+        Ok(report)
     }
 
-    fn setup_progress_bar(&self) -> ProgressBar {
-        // Create a progress bar with a spinner for the initial phase
-        let pb = ProgressBar::new_spinner();
-        pb.set_style(
-            ProgressStyle::default_spinner()
-                .template("{spinner:.green} {msg}")
-                .unwrap()
-                .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏")
-        );
-        pb.set_message("Scanning files...");
-        pb.enable_steady_tick(std::time::Duration::from_millis(80));
-        pb
+    /// Scan with the normal filters and number the results for `--select`
+    pub fn list_files(&self) -> Result<Listing, YoinkError> {
+        Ok(self.list_capped()?.0)
     }
 
-    fn setup_file_progress(&self, file_count: usize) -> Arc<Mutex<ProgressBar>> {
-        // Create a progress bar that tracks the number of files
-        let progress_style = ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} files ({eta})")
-            .unwrap()
-            .progress_chars("#>-");
-            
-        Arc::new(Mutex::new(
-            ProgressBar::new(file_count as u64)
-                .with_style(progress_style)
-        ))
+    // The listing, plus why each file the per-directory limits left out of
+    // it was dropped, keyed by path relative to the listing's base
+    fn list_capped(&self) -> Result<(Listing, HashMap<PathBuf, SkipReason>), YoinkError> {
+        let mut entries = self.file_scanner.collect_files()?;
+        self.restrict_to_packages(&mut entries)?;
+        let Capped { kept: mut entries, dropped, dirs } = self.cap_dirs(entries);
+        selection::sort_for_listing(&mut entries);
+        
+        let base = &self.config.base();
+        let dirs: HashMap<&Path, &DirCap> = dirs.iter().map(|cap| (cap.dir.as_path(), cap)).collect();
+        let capped = dropped
+            .iter()
+            .filter_map(|e| {
+                let relative = e.path().strip_prefix(base).unwrap_or(e.path());
+                let cap = dirs.get(relative.parent().unwrap_or(Path::new("")))?;
+                Some((relative.to_path_buf(), SkipReason::DirectoryCapped { included: cap.included, total: cap.total }))
+            })
+            .collect();
+        Ok((Listing::new(&self.config, &entries), capped))
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::clipboard::MockClipboardManager;
-    use crate::file_scanner::MockFileScanner;
-    use crate::text_processor::MockTextProcessor;
-    use crate::file_tree::MockDirectoryTreeBuilder;
-    use std::path::PathBuf;
-    use std::fs::{self, File};
-    use std::io::Write;
-    use tempfile::tempdir;
+    // Scan for candidates and narrow them down to the files to write, in
+    // the order to write them
+    fn gather(&self, report: &mut ProcessReport) -> Result<Vec<FileEntry>, YoinkError> {
+        // Collect and filter files first, counting matches on the spinner
+        let started = Instant::now();
+        let spinner = self.setup_scan_spinner();
+        let scanned = self.file_scanner.collect_files_with_progress(&|found| spinner.set_position(found as u64));
+        report.durations.scan = started.elapsed();
+        progress::finish(&spinner);
+        
+        let mut entries = scanned?;
+        if self.cancel.is_cancelled() {
+            return Err(YoinkError::Cancelled { processed: 0 });
+        }
+        report.skipped_repos = self.file_scanner.skipped_repos();
+        report.skipped.tests = self.file_scanner.skipped_tests();
+        report.skipped.by_time = self.file_scanner.skipped_by_time();
+        self.restrict_to_packages(&mut entries)?;
+        let capped = self.cap_dirs(entries);
+        entries = capped.kept;
+        report.capped_dirs = capped.dirs;
+        let _ = progress::multi().println(format!(
+            "Found {} files in {:.1}s",
+            entries.len(),
+            report.durations.scan.as_secs_f64()
+        ));
+        
+        // Sample before sorting so the chosen files still come out in order
+        if let Some(requested) = self.config.sample {
+            let seed = self.config.seed.unwrap_or_else(sample::random_seed);
+            let candidates = entries.len();
+            let base = &self.config.base();
+            entries = sample::sample(entries, requested, seed, |e| e.path().strip_prefix(base).unwrap_or(e.path()));
+            report.sample = Some(SampleSummary { requested, candidates, seed });
+        }
+        
+        if self.config.sort {
+            entries.sort_by_key(|e| e.path().to_path_buf());
+        }
+        
+        if let Some(indices) = &self.config.select {
+            entries = selection::select(&self.config, entries, indices)?;
+        }
+        
+        if self.config.pick {
+            let base = &self.config.base();
+            entries = progress::multi().suspend(|| picker::pick(base, entries, self.config.pick_preview))?;
+        }
+        
+        // Grouping decides the order, with files sorted by name within each group
+        if self.config.group_by_dir {
+            let base = &self.config.base();
+            entries.sort_by_cached_key(|e| {
+                let relative = e.path().strip_prefix(base).unwrap_or(e.path());
+                (group::order(relative, self.config.group_depth), relative.to_path_buf())
+            });
+        }
+        Ok(entries)
+    }
 
-    #[test]
-    fn test_processor_with_mocks() {
-        // Create a mock config
-        let config = crate::cli::Config {
-            path: "/mock/path".to_string(),
-            max_size: 1024 * 1024, // 1MB
-            verbose: false,
-            max_depth: 1,
-            include_extensions: None,
-            exclude_extensions: None,
-            exclude_paths: None,
-            pattern: None,
-            skip_hidden: false,
-            sort: false,
-            save_config: false,
-            search_text: None,
-            case_sensitive: false,
+    // The `--files-from` list's files, failing on missing ones under `--strict`
+    fn listed_files(&self) -> Result<Option<files_list::Resolved>, YoinkError> {
+        let Some(list) = &self.config.files_from else {
+            return Ok(None);
         };
+        let root = Path::new(&self.config.path);
+        if !root.exists() {
+            return Err(YoinkError::path_not_found(root));
+        }
+        let resolved = files_list::resolve(utils::relative_base(root), &files_list::read(list)?);
+        if self.config.strict && !resolved.missing.is_empty() {
+            return Err(YoinkError::MissingListed { list: list.clone(), missing: resolved.missing });
+        }
+        Ok(Some(resolved))
+    }
+
+    // Keep only the `--package` members' files and the root manifests
+    fn restrict_to_packages(&self, entries: &mut Vec<FileEntry>) -> Result<(), YoinkError> {
+        if self.config.packages.is_empty() {
+            return Ok(());
+        }
+        let root = Path::new(&self.config.path);
+        let scope = Workspace::discover(root)?.scope(&self.config.packages)?;
+        entries.retain(|e| scope.contains(e.path().strip_prefix(root).unwrap_or(e.path())));
+        Ok(())
+    }
+
+    // Apply `--max-files-per-dir` and `--max-bytes-per-dir`
+    fn cap_dirs(&self, entries: Vec<FileEntry>) -> Capped<FileEntry> {
+        let limits = DirLimits { files: self.config.max_files_per_dir, bytes: self.config.max_bytes_per_dir };
+        if !limits.is_set() {
+            return Capped { kept: entries, dropped: Vec::new(), dirs: Vec::new() };
+        }
+        let base = &self.config.base();
+        dir_cap::cap(entries, limits, |e| e.path().strip_prefix(base).unwrap_or(e.path()), entry_size)
+    }
+
+    /// The listing with each file's size, kind, and any reason it would be skipped
+    ///
+    /// With `all`, files the filters reject are included too, without an index.
+    pub fn list_files_detailed(&self, all: bool) -> Result<Vec<ListedFile>, YoinkError> {
+        let (listing, capped) = self.list_capped()?;
+        let base = &self.config.base();
+        let indices: HashMap<&Path, usize> = listing.paths.iter().enumerate().map(|(i, p)| (p.as_path(), i + 1)).collect();
         
-        // Create mock components
-        let mock_clipboard = MockClipboardManager::new(false);
-        let mut mock_file_scanner = MockFileScanner::new();
-        let mut mock_text_processor = MockTextProcessor::new();
-        let mut mock_dir_tree_builder = MockDirectoryTreeBuilder::new();
+        let paths = if all {
+            let mut paths: Vec<PathBuf> = self.config.roots()
+                .flat_map(|root| walk_files(Path::new(root)))
+                .map(|p| p.strip_prefix(base).map(Path::to_path_buf).unwrap_or(p))
+                .collect();
+            paths.sort();
+            paths.dedup();
+            paths
+        } else {
+            listing.paths.clone()
+        };
         
-        // Setup mock directory tree
-        mock_dir_tree_builder.set_mock_tree("📁 mock/\n  📄 test.txt\n");
+        Ok(paths
+            .into_par_iter()
+            .map(|relative| {
+                let path = base.join(&relative);
+                let index = indices.get(relative.as_path()).copied();
+                let mut verdicts = match (index, capped.get(relative.as_path())) {
+                    (Some(_), _) => Vec::new(),
+                    (None, Some(reason)) => vec![Verdict { check: "directory cap", result: Err(reason.clone()) }],
+                    (None, None) => self.file_scanner.explain(&path),
+                };
+                if verdicts.iter().all(|v| v.result.is_ok()) {
+                    verdicts.extend(self.explain_content(&path));
+                }
+                let skipped = verdicts.iter().find_map(|v| v.result.as_ref().err());
+                let binary = match self.config.forced_kind(&relative) {
+                    Some((text, _)) => !text,
+                    None => match skipped {
+                        Some(SkipReason::Binary | SkipReason::SpecialFile { .. }) => true,
+                        _ => !utils::detect_text_file(&path, self.config.sniff_all).unwrap_or(true),
+                    },
+                };
+                ListedFile {
+                    version: LISTED_FILE_VERSION,
+                    index,
+                    path: relative.to_string_lossy().replace('\\', "/"),
+                    size: path.metadata().map_or(0, |m| m.len()),
+                    kind: if binary { FileKind::Binary } else { FileKind::Text },
+                    skipped: skipped.map(ToString::to_string),
+                }
+            })
+            .collect())
+    }
+
+    /// The files a run would include and skip, counted as it would count them,
+    /// without reading their content
+    ///
+    /// Only the start of each file is sniffed to tell text from binary. The
+    /// report's records are relative to the scan root, in listing order.
+    pub fn dry_run(&self) -> Result<ProcessReport, YoinkError> {
+        let mut entries = self.file_scanner.collect_files()?;
+        let mut report = ProcessReport {
+            skipped_repos: self.file_scanner.skipped_repos(),
+            ..ProcessReport::default()
+        };
+        report.skipped.tests = self.file_scanner.skipped_tests();
+        report.skipped.by_time = self.file_scanner.skipped_by_time();
+        self.restrict_to_packages(&mut entries)?;
+        let capped = self.cap_dirs(entries);
+        report.capped_dirs = capped.dirs;
+        let mut entries = capped.kept;
+        selection::sort_for_listing(&mut entries);
         
-        // Setup mock text processor
-        mock_text_processor.add_text_file("/mock/path/test.txt", "This is test content");
+        let base = &self.config.base();
+        let records: Vec<FileRecord> = entries
+            .par_iter()
+            .map(|entry| {
+                let path = entry.path();
+                let size = entry_size(entry);
+                let verdicts = self.explain_content(path);
+                let outcome = match verdicts.iter().find_map(|v| v.result.as_ref().err()) {
+                    Some(SkipReason::TooLarge { .. }) => FileOutcome::TooLarge,
+                    Some(_) => FileOutcome::Binary,
+                    None if size > self.config.max_size_for(path.strip_prefix(base).unwrap_or(path)) => FileOutcome::Summarized,
+                    None => FileOutcome::Included,
+                };
+                FileRecord { path: path.strip_prefix(base).unwrap_or(path).to_path_buf(), size, outcome, git: None, lines: 0, chars: 0 }
+            })
+            .collect();
+        for record in records {
+            report.total_bytes += record.size;
+            report.record(record);
+        }
+        Ok(report)
+    }
+
+    /// Run `target`, or every file under it, through the same checks as a real run
+    pub fn explain(&self, target: &Path) -> Result<Vec<Explanation>, YoinkError> {
+        if !target.exists() {
+            return Err(YoinkError::path_not_found(target));
+        }
         
-        // Create the processor with mocked dependencies
-        let mut processor = FileProcessor::new(
-            config,
-            Box::new(mock_clipboard),
-            Box::new(mock_file_scanner),
-            Box::new(mock_text_processor),
-            Box::new(mock_dir_tree_builder),
-        );
+        let paths = if target.is_dir() { walk_files(target) } else { vec![target.to_path_buf()] };
         
-        // Process the mock files
-        let result = processor.process();
+        Ok(paths
+            .into_iter()
+            .map(|path| {
+                let mut verdicts = self.file_scanner.explain(&path);
+                if verdicts.iter().all(|v| v.result.is_ok()) {
+                    verdicts.extend(self.explain_content(&path));
+                }
+                Explanation { path, verdicts }
+            })
+            .collect())
+    }
+
+    // The checks made once a file has been read, mirroring `classify_file`
+    fn explain_content(&self, path: &Path) -> Vec<Verdict> {
+        let base = &self.config.base();
+        let relative = path.strip_prefix(base).unwrap_or(path);
+        let size = path.metadata().map_or(0, |m| m.len());
+        let limit = self.config.max_size_for(relative);
+        if size > limit && self.config.oversize == OversizeMode::Skip {
+            return vec![Verdict { check: "size", result: Err(SkipReason::TooLarge { size, limit }) }];
+        }
         
-        // Since we're using empty mock file scanner that returns no files,
-        // expect zero processed files
-        assert!(result.is_ok());
-        let (text_count, binary_count) = result.unwrap();
-        assert_eq!(text_count, 0);
-        assert_eq!(binary_count, 0);
+        // Files whose type can't be sniffed fall back to a content check that
+        // only fails on unreadable files
+        let text = match self.config.forced_kind(relative) {
+            Some((true, _)) => Ok(()),
+            Some((false, pattern)) => Err(SkipReason::ForcedBinary { pattern: pattern.to_string() }),
+            None if utils::detect_text_file(path, self.config.sniff_all).unwrap_or(true) => Ok(()),
+            None => Err(SkipReason::Binary),
+        };
+        vec![
+            Verdict { check: "size", result: Ok(()) },
+            Verdict { check: "text", result: text },
+        ]
+    }
+
+    /// Scan, read, and format files without touching the clipboard
+    ///
+    /// ```
+    /// use yoink::{Config, FileProcessor};
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// std::fs::write(dir.path().join("notes.md"), "# Notes").unwrap();
+    ///
+    /// let config = Config::builder().path(dir.path().to_string_lossy()).build();
+    /// let output = FileProcessor::with_defaults(config).assemble().unwrap();
+    ///
+    /// assert_eq!(output.report.text_files, 1);
+    /// assert!(output.content.contains("# Notes"));
+    /// ```
+    pub fn assemble(&self) -> Result<Output, YoinkError> {
+        let mut content = Vec::new();
+        let report = self.write_output(&mut content)?;
+
+        Ok(Output {
+            content: String::from_utf8(content).expect("formatted output is built from UTF-8 strings"),
+            report,
+        })
+    }
+
+    /// Scan, read, and format files, writing the output to `out` in order
+    ///
+    /// With `low_memory` set, files are processed in small parallel batches and
+    /// written as each batch completes, so only a handful of formatted files are
+    /// held in memory at once.
+    ///
+    /// ```
+    /// use yoink::{Config, FileProcessor};
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// std::fs::write(dir.path().join("log.txt"), "line one").unwrap();
+    ///
+    /// let config = Config::builder()
+    ///     .path(dir.path().to_string_lossy())
+    ///     .low_memory(true)
+    ///     .build();
+    /// let mut out = Vec::new();
+    /// let report = FileProcessor::with_defaults(config).write_output(&mut out).unwrap();
+    ///
+    /// assert_eq!(report.text_files, 1);
+    /// assert!(String::from_utf8(out).unwrap().contains("line one"));
+    /// ```
+    pub fn write_output(&self, out: &mut dyn Write) -> Result<ProcessReport, YoinkError> {
+        self.write_output_with(out, None)
     }
-}
\ No newline at end of file
+
+    // Same as `write_output`, also filling in a digest of what was written
+    fn write_output_with(&self, out: &mut dyn Write, digest: Option<&mut Digest>) -> Result<ProcessReport, YoinkError> {
+        self.write_sections(out, digest).map(|(report, _)| report)
+    }
+
+    // Same as `write_output_with`, also returning the offset each section of
+    // the output starts at
+    fn write_sections(&self, out: &mut dyn Write, digest: Option<&mut Digest>) -> Result<(ProcessReport, Vec<usize>), YoinkError> {
+        // An invalid --search-regex is reported before any file is read
+        LineSearch::from_config(&self.config)?;
+        
+        if self.config.reads_stdin() {
+            let stdin = io::stdin();
+            if stdin.is_terminal() {
+                eprintln!("Reading from stdin (Ctrl-D to finish)");
+            }
+            let mut input = Vec::new();
+            stdin.lock().read_to_end(&mut input).map_err(|source| YoinkError::Io { path: PathBuf::from("-"), source })?;
+            return self.write_document(&input, out, digest);
+        }
+        
+        // Asking for an item from a file it can't be found in would otherwise copy nothing
+        let target = Path::new(&self.config.path);
+        if !self.config.symbols.is_empty() && target.is_file() && symbol::Language::of(target).is_none() {
+            return Err(YoinkError::Symbol {
+                message: format!("can't find items in {}; only Rust, Python, JavaScript, and TypeScript files are supported", target.display()),
+            });
+        }
+        
+        let mut report = ProcessReport::default();
+        let cache_hits_before = self.cache_hits();
+        let condensed_before = self.text_processor.condensed_bytes();
+        let mut out = CountingWriter { inner: out, written: 0, chars: 0, digest, starts: Vec::new() };
+        
+        // Add directory structure at the top
+        let started = Instant::now();
+        // The tree is only context, so losing it doesn't stop the files being
+        // gathered; a missing root, though, fails here as it would in the scan
+        let mut header = String::from("=== DIRECTORY STRUCTURE ===\n");
+        let mut tree = String::new();
+        let listed = self.listed_files()?;
+        let built = match &listed {
+            // A list only shows the files it names
+            Some(listed) => {
+                let base = &self.config.base();
+                let files: Vec<PathBuf> = listed.entries.iter().map(|e| e.path().strip_prefix(base).unwrap_or(e.path()).to_path_buf()).collect();
+                tree = files_list::tree(base, &files);
+                Ok(())
+            }
+            None => self.dir_tree_builder.build_directory_tree(&mut tree),
+        };
+        match built {
+            Ok(()) if self.config.format == OutputFormat::Markdown => header.push_str(&markdown::code_block("", &tree)),
+            Ok(()) => header.push_str(&tree),
+            Err(e) if self.config.strict || matches!(e, YoinkError::PathNotFound { .. }) => return Err(e),
+            Err(e) => {
+                log::warn!("Couldn't build the directory tree: {}", e);
+                tree = format!("[unavailable: {}]\n", e);
+                header.push_str(&tree);
+            }
+        }
+        if self.config.excluded_overview {
+            report.excluded_dirs = excluded::overview(Path::new(&self.config.path), &self.config);
+            if !report.excluded_dirs.is_empty() {
+                header.push_str("\n=== EXCLUDED (overview) ===\n");
+                header.extend(report.excluded_dirs.iter().map(ExcludedDir::line));
+            }
+        }
+        header.push_str("\n=== TEXT FILES ===\n\n");
+        // XML output has no banners; the documents follow the tree inside the root
+        if self.config.format == OutputFormat::Xml {
+            header = format!("<documents>\n{}", xml::element("directory_tree", &tree));
+            if !report.excluded_dirs.is_empty() {
+                header.push_str(&xml::element("excluded_overview", &report.excluded_dirs.iter().map(ExcludedDir::line).collect::<String>()));
+            }
+            header.push('\n');
+        }
+        if let Some(formatter) = &self.formatter {
+            header = formatter.tree_section(&tree);
+        }
+        out.write_chunk(Section::Header, &header)?;
+        report.durations.tree = started.elapsed();
+
+        let entries = match listed {
+            Some(listed) => {
+                report.missing_listed = listed.missing;
+                listed.entries
+            }
+            None => self.gather(&mut report)?,
+        };
+        
+        #[cfg(test)]
+        if let Some(hook) = &self.after_scan {
+            hook(&entries);
+        }
+        
+        // Find what changed up front with a single git call rather than per file
+        let diffs = match &self.config.diff_content {
+            Some(rev) => Some(DiffSet::load(Path::new(&self.config.path), rev)?),
+            None => None,
+        };
+        
+        // Last-commit info for every file, also from a single git call
+        let git_log = if self.config.git_meta { GitLog::load(Path::new(&self.config.path)) } else { None };
+        
+        let root = Path::new(&self.config.path);
+        let manifest_diff = match &self.config.manifest_diff {
+            Some(location) => {
+                let location = location.resolve(root);
+                Some((Manifest::load(&location)?, location))
+            }
+            None => None,
+        };
+        
+        // Setup progress tracking by bytes so large files weigh accordingly
+        report.total_bytes = entries.iter().map(entry_size).sum();
+        let progress = self.setup_file_progress(report.total_bytes);
+        
+        // In low-memory mode only one file per worker is in flight at a time,
+        // unless a token budget or the match index needs to see every file at
+        // once. Search output is only the context around matches, so holding
+        // all of it is cheap.
+        let batch_size = if self.config.low_memory && self.config.max_tokens.is_none() && self.config.search_text.is_none() {
+            rayon::current_num_threads()
+        } else {
+            entries.len().max(1)
+        };
+        
+        // Discard anything left over in the workers' per-file timers
+        rayon::broadcast(|_| text_processor::take_io_timings());
+        
+        let started = Instant::now();
+        let failed = AtomicBool::new(false);
+        let state = RunState {
+            budget: self.config.memory_limit.map(MemoryBudget::new),
+            total: self.config.max_total.map(MemoryBudget::new),
+            diffs,
+            git_log,
+            base: self.config.base(),
+            labels: RootLabels::new(&self.config),
+            mounts: self.config.file_timeout.is_none().then(NetworkMounts::load),
+            previous: manifest_diff.as_ref().map(|(manifest, _)| manifest),
+            hashes: (self.config.manifest_save.is_some() || manifest_diff.is_some()).then(Mutex::default),
+            fingerprints: self.config.dedup.is_some().then(Mutex::default),
+            matches: self.config.search_text.is_some().then(Mutex::default),
+            test_modules: self.config.skip_tests.then(AtomicUsize::default),
+            symbols: (!self.config.symbols.is_empty()).then(Mutex::default),
+            skips: SkipLog::new(module_path!(), &self.config.base()),
+        };
+        let mut deduper = self.config.dedup.map(Deduper::new);
+        // Each capped directory's note goes ahead of its first file
+        let mut cap_notes: HashMap<PathBuf, String> = report.capped_dirs.iter().map(|cap| (cap.dir.clone(), cap.note())).collect();
+        let mut output_dir = match &self.config.output_dir {
+            Some(dir) => Some(OutputDir::create(dir, self.config.force)?),
+            None => None,
+        };
+        for batch in entries.chunks(batch_size) {
+            // Each file is formatted into its own chunk so no lock is held while
+            // formatting, and collecting keeps chunks in the same order as entries
+            let mut results: Vec<Option<FileResult>> = batch.par_iter().map(|entry| {
+                // Stop picking up new files once cancelled, or after a failure in strict mode
+                if self.cancel.is_cancelled() || failed.load(Ordering::Relaxed) {
+                    return None;
+                }
+                
+                let result = self.process_file_parallel(entry, &state);
+                if self.config.strict && matches!(result, Some((_, Err(_)))) {
+                    failed.store(true, Ordering::Relaxed);
+                }
+                
+                // Advance by the file's size whether it was included or skipped
+                progress.inc(entry_size(entry));
+                result
+            }).collect();
+            
+            if let (Some(deduper), Some(fingerprints)) = (&mut deduper, &state.fingerprints) {
+                let plain = HeaderTemplate::default();
+                let header = match self.config.format {
+                    OutputFormat::Plain => (&self.config.header_format, self.has_footers()),
+                    _ => (&plain, false),
+                };
+                report.deduped_bytes += collapse_duplicates(&mut results, &mut fingerprints.lock().unwrap(), deduper, header, &|path| self.shown(path, &state));
+            }
+            
+            // Stubs for summarized, omitted, and duplicate files are built plain
+            if self.config.format != OutputFormat::Plain {
+                for (_, chunk) in results.iter_mut().flatten() {
+                    if let Ok(Some(chunk)) = chunk {
+                        if chunk.starts_with("=== ") {
+                            *chunk = match self.config.format {
+                                OutputFormat::Xml => xml::from_plain(chunk),
+                                _ => markdown::from_plain(chunk),
+                            };
+                        }
+                    }
+                }
+            }
+            
+            // The tree, group headers, cap notes, and match index count against
+            // the budget too; the index can only shrink once files are dropped
+            let matches = state.matches.as_ref().map(|matches| matches.lock().unwrap());
+            if let Some(tokens) = self.config.max_tokens {
+                let headers = if self.config.group_by_dir { self.group_headers_len(&results, &report.groups, &state.base) } else { 0 };
+                let index = matches.as_ref().map_or(0, |matches| self.text_chunk("match_index", &match_index(&results, matches, &|path| self.shown(path, &state))).len() as u64);
+                let notes: u64 = cap_notes.values().map(|note| self.text_chunk("note", note).len() as u64).sum();
+                let budget = tokens.saturating_mul(fit::CHARS_PER_TOKEN).saturating_sub(out.written + headers + notes + index);
+                report.truncated = self.fit_to_budget(&mut results, budget);
+            }
+            
+            if let Some(formatter) = &self.formatter {
+                for (record, chunk) in results.iter_mut().flatten() {
+                    if let Ok(Some(chunk)) = chunk {
+                        *chunk = formatter::restyle(formatter.as_ref(), &self.shown(&record.path, &state), chunk);
+                    }
+                }
+            }
+            
+            if let Some(matches) = matches {
+                let index = match_index(&results, &matches, &|path| self.shown(path, &state));
+                if !index.is_empty() {
+                    out.write_chunk(Section::Header, &self.text_chunk("match_index", &index))?;
+                }
+            }
+            
+            for (mut record, chunk) in results.into_iter().flatten() {
+                match chunk {
+                    Ok(Some(mut chunk)) => {
+                        let shown = self.shown(&record.path, &state);
+                        if self.abbreviate_header(&mut chunk, &shown, report.abbreviated_paths.len() + 1) {
+                            report.abbreviated_paths.push(shown.to_path_buf());
+                        }
+                        if self.config.group_by_dir {
+                            self.enter_group(&mut out, &mut report.groups, &record, &state.base)?;
+                        }
+                        let relative = record.path.strip_prefix(&state.base).unwrap_or(&record.path);
+                        if let Some(note) = cap_notes.remove(relative.parent().unwrap_or(Path::new(""))) {
+                            out.write_chunk(Section::File, &self.text_chunk("note", &note))?;
+                        }
+                        out.write_chunk(Section::File, &chunk)?;
+                        record.lines = chunk.lines().count();
+                        record.chars = chunk.chars().count();
+                        log::info!("~{} tokens: {}", fit::estimate_tokens(record.chars as u64), relative.display());
+                        if let Some(dir) = &mut output_dir {
+                            if matches!(record.outcome, FileOutcome::Included | FileOutcome::Summarized) {
+                                dir.write(relative, record.size, &chunk)?;
+                            }
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) if self.config.strict => {
+                        progress::finish(&progress);
+                        return Err(e);
+                    }
+                    Err(_) => {}
+                }
+                report.record(record);
+            }
+        }
+        report.durations.process = started.elapsed();
+        state.skips.finish();
+        report.test_modules_stripped = state.test_modules.as_ref().map_or(0, |count| count.load(Ordering::Relaxed));
+        if let Some(dir) = output_dir {
+            report.output_dir = Some(dir.finish(&self.config.path)?);
+        }
+        for (sniff, read) in rayon::broadcast(|_| text_processor::take_io_timings()) {
+            report.durations.sniff += sniff;
+            report.durations.read += read;
+        }
+        report.cache_hits = self.cache_hits() - cache_hits_before;
+        report.condensed_bytes = self.text_processor.condensed_bytes() - condensed_before;
+        if let Some(diffs) = &state.diffs {
+            report.diff = Some(summarize_diffs(diffs, &report.files));
+        }
+        if let Some(labels) = &state.labels {
+            report.roots = labels.summarize(&report.files);
+        }
+        if let Some((manifest, location)) = &manifest_diff {
+            let scanned = entries.iter().map(|e| manifest::key(&state.base, e.path())).collect();
+            report.manifest = Some(manifest.compare(location, &state.base, &report.files, &scanned));
+        }
+        
+        if self.cancel.is_cancelled() {
+            progress.abandon();
+            progress::multi().remove(&progress);
+            return Err(YoinkError::Cancelled { processed: report.processed() });
+        }
+        if let Some(seen) = &state.symbols {
+            if let Err(message) = seen.lock().unwrap().check(&self.config.symbols) {
+                progress::finish(&progress);
+                return Err(YoinkError::Symbol { message });
+            }
+        }
+        
+        // Images are described by their headers rather than dropped silently
+        if self.config.media_info {
+            let mut assets: Vec<Asset> = report.files
+                .par_iter()
+                .filter(|f| f.outcome == FileOutcome::Binary)
+                .filter_map(|f| Asset::read(&f.path, f.size))
+                .collect();
+            assets.sort_by(|a, b| a.path.cmp(&b.path));
+            if !assets.is_empty() {
+                out.write_chunk(Section::Assets, &self.text_chunk("assets", &media::format_assets(&assets, &state.base)))?;
+            }
+            report.assets = assets;
+        }
+        
+        // Files left out for the size limit are listed so it's clear what's missing
+        if report.skipped.over_total > 0 {
+            let mut listing = String::from("\n=== OMITTED (size limit) ===\n");
+            for record in report.files.iter().filter(|f| f.outcome == FileOutcome::OverTotal) {
+                listing.push_str(&format!("{}\n", self.shown(&record.path, &state).display()));
+            }
+            out.write_chunk(Section::Summary, &self.text_chunk("omitted", &listing))?;
+        }
+        
+        // Paths shortened in headers are given in full once, by footnote number
+        if !report.abbreviated_paths.is_empty() {
+            let mut footnotes = String::from("\n=== FULL PATHS ===\n");
+            for (number, path) in report.abbreviated_paths.iter().enumerate() {
+                footnotes.push_str(&format!("[{}] {}\n", number + 1, path.display()));
+            }
+            out.write_chunk(Section::Summary, &self.text_chunk("full_paths", &footnotes))?;
+        }
+        
+        // Finalize the output
+        let summary = self.finish_summary(&mut report, out.chars);
+        out.write_chunk(Section::Summary, &summary)?;
+        report.output_bytes = out.written;
+        
+        progress::finish(&progress);
+        
+        if let (Some(location), Some(hashes)) = (&self.config.manifest_save, state.hashes) {
+            let location = location.resolve(root);
+            Manifest::new(hashes.into_inner().unwrap()).save(&location)?;
+            report.manifest_saved = Some(location);
+        }
+        
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache.save() {
+                log::warn!("Failed to save cache: {}", e);
+            }
+        }
+        
+        Ok((report, out.starts))
+    }
+
+    // Cut included chunks down to `budget` bytes, returning the files that were truncated
+    fn fit_to_budget(&self, results: &mut [Option<FileResult>], budget: u64) -> Vec<Truncation> {
+        let mut included: Vec<(&mut FileRecord, &mut Option<String>)> = results
+            .iter_mut()
+            .flatten()
+            .filter(|(record, _)| record.outcome == FileOutcome::Included)
+            .filter_map(|(record, chunk)| match chunk {
+                Ok(chunk @ Some(_)) => Some((record, chunk)),
+                _ => None,
+            })
+            .collect();
+        
+        if self.config.fit == FitStrategy::Drop {
+            let sizes: Vec<u64> = included.iter().map(|(_, chunk)| chunk.as_ref().map_or(0, |c| c.len() as u64)).collect();
+            for ((record, chunk), fits) in included.into_iter().zip(fit::fit_whole(&sizes, budget)) {
+                if !fits {
+                    log::debug!("Dropping {}: over the token budget", record.path.display());
+                    record.outcome = FileOutcome::OverBudget;
+                    *chunk = None;
+                }
+            }
+            return Vec::new();
+        }
+        
+        // Headers and markers are kept whatever happens, so only bodies are shared
+        // out
+        let footer = self.has_footers();
+        let marker = format!("{}\n\n", fit::TRUNCATION_MARKER);
+        let (overheads, mut costs): (Vec<u64>, Vec<Vec<u64>>) = included
+            .iter()
+            .map(|(_, chunk)| {
+                let (header, body, footer) = split_chunk(chunk.as_deref().unwrap_or_default(), footer);
+                let overhead = (header.len() + footer.len() + 1 + marker.len()) as u64;
+                (overhead, body.lines().map(|line| line.len() as u64 + 1).collect())
+            })
+            .unzip();
+        
+        // Files that can't have even their header and first lines are dropped
+        // whole, lowest priority first, rather than kept as empty stubs
+        let floor = self.config.fit_min_lines;
+        let needs: Vec<u64> = overheads.iter().zip(&costs).map(|(overhead, lines)| overhead + lines.iter().take(floor).sum::<u64>()).collect();
+        let mut fitting = included.len();
+        let mut need: u64 = needs.iter().sum();
+        while fitting > 0 && need > budget {
+            fitting -= 1;
+            need -= needs[fitting];
+        }
+        for (record, chunk) in included.drain(fitting..) {
+            log::debug!("Dropping {}: no room left to truncate it into", record.path.display());
+            record.outcome = FileOutcome::OverBudget;
+            *chunk = None;
+        }
+        costs.truncate(fitting);
+        let overhead: u64 = overheads[..fitting].iter().sum();
+        let kept = fit::allocate(&costs, budget.saturating_sub(overhead), floor);
+        
+        let mut truncated = Vec::new();
+        for ((record, chunk), (kept, lines)) in included.into_iter().zip(kept.into_iter().zip(&costs)) {
+            if kept == lines.len() {
+                continue;
+            }
+            let (header, body, footer) = split_chunk(chunk.as_deref().unwrap_or_default(), footer);
+            let mut fitted = header.to_string();
+            for line in body.lines().take(kept) {
+                fitted.push_str(line);
+                fitted.push('\n');
+            }
+            if !footer.is_empty() {
+                fitted.push_str(footer);
+                fitted.push('\n');
+            }
+            fitted.push_str(&marker);
+            *chunk = Some(fitted);
+            truncated.push(Truncation { path: record.path.clone(), kept, omitted: lines.len() - kept });
+        }
+        truncated
+    }
+
+    // Piped input is a single document, so there's no tree, scan, or progress
+    fn write_document(&self, input: &[u8], out: &mut dyn Write, digest: Option<&mut Digest>) -> Result<(ProcessReport, Vec<usize>), YoinkError> {
+        let name = self.config.stdin_name.as_deref().unwrap_or("<stdin>");
+        if input.contains(&0) {
+            return Err(YoinkError::BinaryInput { name: name.to_string() });
+        }
+        
+        let mut report = ProcessReport::default();
+        let mut out = CountingWriter { inner: out, written: 0, chars: 0, digest, starts: Vec::new() };
+        let header = match &self.formatter {
+            Some(formatter) => formatter.tree_section(""),
+            None if self.config.format == OutputFormat::Xml => "<documents>\n".to_string(),
+            None => "=== TEXT FILES ===\n\n".to_string(),
+        };
+        out.write_chunk(Section::Header, &header)?;
+        
+        let started = Instant::now();
+        let content = String::from_utf8_lossy(input);
+        let mut chunk = String::new();
+        let included = self.text_processor.format_text_content(Path::new(name), &content, &mut chunk)?;
+        if included {
+            if let Some(formatter) = &self.formatter {
+                chunk = formatter::restyle(formatter.as_ref(), Path::new(name), &chunk);
+            }
+            out.write_chunk(Section::File, &chunk)?;
+        }
+        report.durations.process = started.elapsed();
+        
+        report.total_bytes = input.len() as u64;
+        report.record(FileRecord {
+            path: PathBuf::from(name),
+            size: input.len() as u64,
+            outcome: if included { FileOutcome::Included } else { FileOutcome::NoMatch },
+            git: None,
+            lines: chunk.lines().count(),
+            chars: chunk.chars().count(),
+        });
+        
+        let summary = self.finish_summary(&mut report, out.chars);
+        out.write_chunk(Section::Summary, &summary)?;
+        report.output_bytes = out.written;
+        Ok((report, out.starts))
+    }
+
+    // The group a written file belongs to, named relative to the root
+    fn group_of(&self, record: &FileRecord, base: &Path) -> String {
+        group::key(record.path.strip_prefix(base).unwrap_or(&record.path), self.config.group_depth)
+    }
+    
+    // Shorten a path too long to read in the chunk's header, marking it with its
+    // footnote number; returns whether it was shortened
+    fn abbreviate_header(&self, chunk: &mut String, path: &Path, footnote: usize) -> bool {
+        let Some(short) = depth::abbreviate(path) else { return false };
+        let (mut full, mut short) = (path.display().to_string(), format!("{} [{}]", short, footnote));
+        if self.config.format == OutputFormat::Xml {
+            (full, short) = (xml::escape(&full), xml::escape(&short));
+        }
+        let header_end = chunk.find('\n').unwrap_or(chunk.len());
+        match chunk[..header_end].find(&full) {
+            Some(at) => {
+                chunk.replace_range(at..at + full.len(), &short);
+                true
+            }
+            None => false,
+        }
+    }
+    
+    // `path` as its header shows it: relative to the run's `base` unless
+    // --absolute-paths is set
+    fn shown<'a>(&self, path: &'a Path, state: &RunState) -> Cow<'a, Path> {
+        roots::shown(state.labels.as_ref(), path, self.header_base(&state.base))
+    }
+    
+    fn header_base<'a>(&self, base: &'a Path) -> Option<&'a Path> {
+        (!self.config.absolute_paths).then_some(base)
+    }
+    
+    // Whether file chunks end in a --footer-format line; footers are only
+    // written below whole files
+    fn has_footers(&self) -> bool {
+        self.config.footer_format.is_some() && self.config.search_text.is_none() && self.config.diff_content.is_none()
+    }
+    
+    // Text written between files, escaped inside an element of its own in XML
+    // output, or laid out by a custom formatter
+    fn text_chunk(&self, name: &str, text: &str) -> String {
+        if let Some(formatter) = &self.formatter {
+            return formatter.text_section(name, text);
+        }
+        match self.config.format {
+            OutputFormat::Xml => xml::element(name, text),
+            _ => text.to_string(),
+        }
+    }
+    
+    // The summary section; a custom formatter is given just its lines, and XML
+    // output carries the counts as attributes too and closes the root after it
+    fn summary(&self, report: &ProcessReport) -> String {
+        let base = &self.config.base();
+        let summary = summary(report, self.config.detailed_summary, self.header_base(base));
+        if let Some(formatter) = &self.formatter {
+            return formatter.summary(summary.trim_start_matches('\n').trim_start_matches("=== SUMMARY ===\n"));
+        }
+        if self.config.format != OutputFormat::Xml {
+            return summary;
+        }
+        let counts = format!(
+            "<summary text_files=\"{}\" binary_files=\"{}\" skipped_for_size=\"{}\" failed=\"{}\" estimated_tokens=\"{}\">",
+            report.text_files, report.binary_files, report.skipped.too_large, report.skipped.failed, report.estimated_tokens
+        );
+        let lines = summary.trim_start_matches('\n').trim_start_matches("=== SUMMARY ===\n");
+        format!("{}\n{}</summary>\n</documents>\n", counts, xml::escape(lines))
+    }
+    
+    // The summary once the token estimate is in it, counting everything written
+    // so far and the summary itself
+    fn finish_summary(&self, report: &mut ProcessReport, written_chars: u64) -> String {
+        // The estimate's own digits add to the summary, so it's worked out twice
+        for _ in 0..2 {
+            report.estimated_tokens = fit::estimate_tokens(written_chars + self.summary(report).chars().count() as u64);
+        }
+        if let Some(target) = &self.config.target {
+            let base = &self.config.base();
+            report.over_target = target::overflow(target, report.estimated_tokens, &report.files, self.header_base(base));
+        }
+        self.summary(report)
+    }
+    
+    // Write a group's header before its first file, and count the file towards it
+    fn enter_group(&self, out: &mut CountingWriter, groups: &mut Vec<GroupSummary>, record: &FileRecord, base: &Path) -> Result<(), YoinkError> {
+        let name = self.group_of(record, base);
+        if groups.last().is_none_or(|group| group.name != name) {
+            out.write_chunk(Section::File, &self.text_chunk("group", &group::header(&name)))?;
+            groups.push(GroupSummary { name, files: 0, bytes: 0 });
+        }
+        let group = groups.last_mut().expect("a group was just entered");
+        group.files += 1;
+        group.bytes += record.size;
+        Ok(())
+    }
+    
+    // Bytes of the headers a batch could start, which is an upper bound since
+    // a group whose files are all dropped gets no header
+    fn group_headers_len(&self, results: &[Option<FileResult>], groups: &[GroupSummary], base: &Path) -> u64 {
+        let mut current = groups.last().map(|group| group.name.clone());
+        let mut len = 0;
+        for (record, _) in results.iter().flatten().filter(|(_, chunk)| matches!(chunk, Ok(Some(_)))) {
+            let name = self.group_of(record, base);
+            if current.as_ref() != Some(&name) {
+                len += self.text_chunk("group", &group::header(&name)).len() as u64;
+                current = Some(name);
+            }
+        }
+        len
+    }
+
+    // This function processes a single file in parallel, returning what happened
+    // to it along with its formatted chunk if it was included
+    fn process_file_parallel(
+        &self,
+        entry: &walkdir::DirEntry,
+        state: &RunState,
+    ) -> Option<FileResult> {
+        let path = entry.path();
+        
+        // A file deleted since the scan is counted rather than reported as an error
+        match path.symlink_metadata() {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                log::debug!("File vanished since the scan: {}", path.display());
+                return Some((FileRecord { path: path.to_path_buf(), size: 0, outcome: FileOutcome::Vanished, git: None, lines: 0, chars: 0 }, Ok(None)));
+            }
+            // Skip if not a file
+            _ if !path.is_file() => return None,
+            _ => {}
+        }
+        
+        // A panic on one odd file is recorded as a failure for that file
+        // rather than unwinding through rayon and losing the whole run
+        let result = panic::catch_unwind(AssertUnwindSafe(|| self.classify_file(path, state)))
+            .unwrap_or_else(|payload| Err(YoinkError::Panicked {
+                path: path.to_path_buf(),
+                message: panic_message(payload.as_ref()),
+            }));
+        
+        let size = entry_size(entry);
+        let (outcome, chunk) = match result {
+            Ok((outcome, chunk)) => (outcome, Ok(chunk)),
+            Err(YoinkError::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                log::debug!("File vanished while processing: {}", path.display());
+                (FileOutcome::Vanished, Ok(None))
+            }
+            Err(e) => {
+                log::error!("Error processing file: {}", e);
+                (FileOutcome::Failed(e.to_string()), Err(e))
+            }
+        };
+        
+        let git = state.git_log.as_ref().map(|log| log.meta(path));
+        Some((FileRecord { path: path.to_path_buf(), size, outcome, git, lines: 0, chars: 0 }, chunk))
+    }
+
+    fn classify_file(
+        &self,
+        path: &Path,
+        state: &RunState,
+    ) -> Result<(FileOutcome, Option<String>), YoinkError> {
+        let budget = state.budget.as_ref();
+        let total = state.total.as_ref();
+        let change = state.diffs.as_ref().map(|diffs| diffs.change(path));
+        if let Some(FileChange::Unchanged) = change {
+            log::debug!("Skipping unchanged file: {}", path.display());
+            return Ok((FileOutcome::Unchanged, None));
+        }
+        
+        // Once the output is full there's no point reading anything more
+        if total.is_some_and(MemoryBudget::is_exhausted) {
+            return Ok(over_total(path));
+        }
+        
+        // Check file size
+        let metadata = path.metadata()
+            .map_err(|source| YoinkError::Io { path: path.to_path_buf(), source })?;
+        
+        let relative = path.strip_prefix(&state.base).unwrap_or(path);
+        let shown = self.shown(path, state);
+        let shown = shown.as_ref();
+        if metadata.len() > self.config.max_size_for(relative) {
+            if self.config.oversize == OversizeMode::Summarize {
+                return self.summarize_oversized(path, shown, self.read_timeout(path, state));
+            }
+            match self.config.rule_for(relative).filter(|rule| rule.max_size.is_some()) {
+                Some(rule) => log::warn!("Skipping large file: {} ({} bytes, rule '{}')", path.display(), metadata.len(), rule.path),
+                None => log::warn!("Skipping large file: {} ({} bytes)", path.display(), metadata.len()),
+            }
+            return Ok((FileOutcome::TooLarge, None));
+        }
+        
+        // Process the file based on its type
+        let Some(read) = self.read_with_timeout(path, self.read_timeout(path, state), |processor, path| processor.process_file(path)) else {
+            return Ok(timed_out(path));
+        };
+        match read? {
+            Some(content) => {
+                // A writer may have truncated or extended the file mid-read
+                let changed = path.metadata().map_or(true, |after| {
+                    after.len() != metadata.len() || after.modified().ok() != metadata.modified().ok()
+                });
+                
+                // Hash the content already in hand so the file isn't read twice
+                if let Some(hashes) = &state.hashes {
+                    let key = manifest::key(&state.base, path);
+                    let hash = manifest::hash_hex(&content);
+                    let unchanged = state.previous.is_some_and(|m| m.is_unchanged(&key, &hash));
+                    hashes.lock().unwrap().insert(key, hash);
+                    if unchanged {
+                        log::debug!("Skipping file unchanged since manifest: {}", path.display());
+                        return Ok((FileOutcome::Unchanged, None));
+                    }
+                }
+                
+                let content = match &state.test_modules {
+                    Some(count) if path.extension().is_some_and(|ext| ext == "rs") => {
+                        let (stripped, modules) = test_code::strip_rust_test_modules(&content);
+                        count.fetch_add(modules, Ordering::Relaxed);
+                        stripped
+                    }
+                    _ => content,
+                };
+                
+                // Only the named items are kept, from files they can be found in
+                let mut picked = None;
+                let content = match &state.symbols {
+                    Some(seen) => {
+                        let Some(language) = symbol::Language::of(path) else {
+                            return Ok((FileOutcome::NoMatch, None));
+                        };
+                        let items = symbol::items(language, &content);
+                        seen.lock().unwrap().add(&items, &self.config.symbols);
+                        let selection = symbol::select(&content, &items, &self.config.symbols);
+                        if selection.items.is_empty() {
+                            return Ok((FileOutcome::NoMatch, None));
+                        }
+                        picked = Some(selection.note());
+                        selection.text
+                    }
+                    None => content,
+                };
+                
+                // Reserve room for the body before formatting it, so parallel
+                // workers can't collectively overshoot the limit
+                let reserved = content.len() as u64;
+                if total.is_some_and(|total| !total.reserve(reserved)) {
+                    return Ok(over_total(path));
+                }
+                if budget.is_some_and(|budget| !budget.reserve(reserved)) {
+                    total.inspect(|total| total.release(reserved));
+                    return Ok(omitted(path, shown));
+                }
+                
+                // Format into a local chunk rather than the shared output
+                let mut chunk = String::new();
+                let formatted = match change {
+                    Some(FileChange::Modified(patch)) if self.config.format == OutputFormat::Markdown => {
+                        chunk.push_str(&markdown::file_chunk(&shown.display().to_string(), "diff", &patch.text));
+                        Ok(true)
+                    }
+                    Some(FileChange::Modified(patch)) if self.config.format == OutputFormat::Xml => {
+                        chunk.push_str(&xml::document(&shown.display().to_string(), &[("view", "diff")], &patch.text));
+                        Ok(true)
+                    }
+                    Some(FileChange::Modified(patch)) => {
+                        chunk.push_str(&format!("=== {} ===\n{}\n", shown.display(), patch.text));
+                        Ok(true)
+                    }
+                    Some(FileChange::Untracked) if self.config.format == OutputFormat::Markdown => {
+                        let language = markdown::language(path).unwrap_or_default();
+                        chunk.push_str(&markdown::file_chunk(&format!("{} (new file)", shown.display()), language, &content));
+                        Ok(true)
+                    }
+                    Some(FileChange::Untracked) if self.config.format == OutputFormat::Xml => {
+                        chunk.push_str(&xml::document(&shown.display().to_string(), &[("note", "new file")], &content));
+                        Ok(true)
+                    }
+                    Some(FileChange::Untracked) => {
+                        chunk.push_str(&format!("=== {} (new file) ===\n{}\n\n", shown.display(), content));
+                        Ok(true)
+                    }
+                    _ => self.text_processor.format_text_content(path, &content, &mut chunk),
+                };
+                
+                let was_included = match formatted {
+                    Ok(was_included) => was_included,
+                    Err(e) => {
+                        budget.inspect(|budget| budget.release(reserved));
+                        total.inspect(|total| total.release(reserved));
+                        return Err(e);
+                    }
+                };
+                
+                if !was_included {
+                    budget.inspect(|budget| budget.release(reserved));
+                    total.inspect(|total| total.release(reserved));
+                    return Ok((FileOutcome::NoMatch, None));
+                }
+                
+                if let Some(note) = &picked {
+                    annotate_header(&mut chunk, note);
+                }
+                if let Some(log) = &state.git_log {
+                    annotate_header(&mut chunk, &log.meta(path));
+                }
+                if changed {
+                    log::warn!("File changed while reading: {}", path.display());
+                    annotate_header(&mut chunk, &"file changed while reading");
+                }
+                
+                // Settle the reservations against the real formatted size
+                let actual = chunk.len() as u64;
+                if let Some(total) = total {
+                    if actual > reserved && !total.reserve(actual - reserved) {
+                        total.release(reserved);
+                        budget.inspect(|budget| budget.release(reserved));
+                        return Ok(over_total(path));
+                    }
+                    total.release(reserved.saturating_sub(actual));
+                }
+                if let Some(budget) = budget {
+                    if actual > reserved && !budget.reserve(actual - reserved) {
+                        budget.release(reserved);
+                        total.inspect(|total| total.release(actual));
+                        return Ok(omitted(path, shown));
+                    }
+                    budget.release(reserved.saturating_sub(actual));
+                }
+                
+                if let Some(fingerprints) = &state.fingerprints {
+                    fingerprints.lock().unwrap().insert(path.to_path_buf(), Fingerprint::of(&content));
+                }
+                if let (Some(matches), Some(found)) = (&state.matches, self.text_processor.search_matches(&content)) {
+                    matches.lock().unwrap().insert(path.to_path_buf(), found);
+                }
+                
+                log::debug!("Processed text file: {}", path.display());
+                Ok((FileOutcome::Included, Some(chunk)))
+            }
+            None => {
+                // It's a binary file or we're skipping it
+                state.skips.record(path, &SkipReason::Binary);
+                Ok((FileOutcome::Binary, None))
+            }
+        }
+    }
+
+    // A stub for a file too large to include, read without holding it in memory
+    fn summarize_oversized(&self, path: &Path, shown: &Path, timeout: Option<Duration>) -> Result<(FileOutcome, Option<String>), YoinkError> {
+        let Some(read) = self.read_with_timeout(path, timeout, |processor, path| processor.summarize_file(path)) else {
+            return Ok(timed_out(path));
+        };
+        let Some(excerpt) = read? else {
+            return Ok((FileOutcome::TooLarge, None));
+        };
+        if excerpt.matches == Some(0) {
+            return Ok((FileOutcome::NoMatch, None));
+        }
+        log::debug!("Summarized large file: {}", path.display());
+        Ok((FileOutcome::Summarized, Some(excerpt.render(shown))))
+    }
+
+    // How long reading `path` may take: whatever --file-timeout says for every
+    // file, or a default for files on network mounts only, whose reads are the
+    // ones that hang
+    fn read_timeout(&self, path: &Path, state: &RunState) -> Option<Duration> {
+        match self.config.file_timeout {
+            Some(timeout) => (!timeout.is_zero()).then_some(timeout),
+            None => state.mounts.as_ref().is_some_and(|mounts| mounts.holds(path)).then_some(deadline::NETWORK_TIMEOUT),
+        }
+    }
+
+    // Run `read` on a helper thread so a read that hangs can be abandoned
+    // after `timeout`; see `deadline::run` for what that leaves behind.
+    // Without a timeout it's read right here on the worker
+    fn read_with_timeout<T: Send + 'static>(
+        &self,
+        path: &Path,
+        timeout: Option<Duration>,
+        read: impl FnOnce(&dyn TextProcessing, &Path) -> T + Send + 'static,
+    ) -> Option<T> {
+        let Some(timeout) = timeout else {
+            return Some(read(self.text_processor.as_ref(), path));
+        };
+        let processor = Arc::clone(&self.text_processor);
+        let path = path.to_path_buf();
+        let (result, timings) = deadline::run(timeout, move || {
+            let result = read(processor.as_ref(), &path);
+            (result, text_processor::take_io_timings())
+        })?;
+        text_processor::add_io_timings(timings);
+        Some(result)
+    }
+
+    fn cache_hits(&self) -> usize {
+        self.cache.as_ref().map_or(0, |cache| cache.hits())
+    }
+
+    fn setup_scan_spinner(&self) -> ProgressBar {
+        // Spinner for the scanning phase, whose position is the number of
+        // matching files found so far
+        let pb = progress::add(ProgressBar::new_spinner());
+        pb.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner:.green} Scanning files... {pos} found")
+                .unwrap()
+                .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏")
+        );
+        pb.enable_steady_tick(std::time::Duration::from_millis(80));
+        pb
+    }
+
+    fn setup_file_progress(&self, total_bytes: u64) -> ProgressBar {
+        // Create a progress bar that tracks the number of bytes processed
+        let progress_style = ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
+            .unwrap()
+            .progress_chars("#>-");
+            
+        // Drawn through the shared display, which serializes log lines
+        // from worker threads with bar redraws
+        progress::add(ProgressBar::new(total_bytes).with_style(progress_style))
+    }
+}
+
+/// Builder for `FileProcessor`, created with `FileProcessor::builder`
+pub struct FileProcessorBuilder {
+    config: Config,
+    clipboard: Option<Box<dyn ClipboardInterface>>,
+    file_scanner: Option<Box<dyn FileScanning>>,
+    text_processor: Option<Box<dyn TextProcessing>>,
+    dir_tree_builder: Option<Box<dyn DirectoryTreeBuilding>>,
+    formatter: Option<Box<dyn OutputFormatter>>,
+}
+
+impl FileProcessorBuilder {
+    pub fn clipboard(mut self, clipboard: impl ClipboardInterface) -> Self {
+        self.clipboard = Some(Box::new(clipboard));
+        self
+    }
+
+    pub fn file_scanner(mut self, file_scanner: impl FileScanning) -> Self {
+        self.file_scanner = Some(Box::new(file_scanner));
+        self
+    }
+
+    pub fn text_processor(mut self, text_processor: impl TextProcessing) -> Self {
+        self.text_processor = Some(Box::new(text_processor));
+        self
+    }
+
+    pub fn dir_tree_builder(mut self, dir_tree_builder: impl DirectoryTreeBuilding) -> Self {
+        self.dir_tree_builder = Some(Box::new(dir_tree_builder));
+        self
+    }
+
+    /// Lay the output out with `formatter` rather than the configured format
+    ///
+    /// The formatter is given the pieces the plain format would write, so
+    /// `Config::format` is set back to plain.
+    pub fn formatter(mut self, formatter: impl OutputFormatter) -> Self {
+        self.formatter = Some(Box::new(formatter));
+        self
+    }
+
+    pub fn build(self) -> FileProcessor {
+        use crate::clipboard::ClipboardManager;
+        use crate::file_tree::DirectoryTreeBuilder;
+        use crate::file_scanner::FileScanner;
+        use crate::text_processor::TextProcessor;
+        
+        let mut config = self.config;
+        let formatter = self.formatter.or_else(|| {
+            config.format_template.clone().map(|template| Box::new(template) as Box<dyn OutputFormatter>)
+        }).or_else(|| {
+            (config.format == OutputFormat::Html).then(|| Box::new(HtmlPage::for_root(Path::new(&config.path))) as Box<dyn OutputFormatter>)
+        });
+        // Formatters find each file's path in the plain header
+        if formatter.is_some() {
+            config.format = OutputFormat::Plain;
+            config.header_format = HeaderTemplate::default();
+            config.footer_format = None;
+        }
+        let cancel = CancelToken::new();
+        
+        let clipboard = self.clipboard.unwrap_or_else(|| Box::new(ClipboardManager::new()));
+        let file_scanner = self.file_scanner
+            .unwrap_or_else(|| Box::new(FileScanner::new(&config).with_cancel(cancel.clone())));
+        
+        // The cache is only consulted by the real text processor, and not when
+        // sniffing everything, since verdicts kept from the fast path would differ
+        let mut cache = None;
+        let text_processor = self.text_processor.unwrap_or_else(|| {
+            let mut text_processor = TextProcessor::new(&config);
+            if config.cache && !config.reads_stdin() && !config.sniff_all {
+                let opened = Arc::new(FileCache::open(Path::new(&config.path)));
+                text_processor = text_processor.with_cache(Arc::clone(&opened));
+                cache = Some(opened);
+            }
+            Box::new(text_processor)
+        });
+        let text_processor = Arc::from(text_processor);
+        let dir_tree_builder = self.dir_tree_builder
+            .unwrap_or_else(|| Box::new(DirectoryTreeBuilder::new(&config)));
+        
+        FileProcessor {
+            config,
+            clipboard,
+            file_scanner,
+            text_processor,
+            dir_tree_builder,
+            formatter,
+            cancel,
+            cache,
+            wrote_output: AtomicBool::new(false),
+            #[cfg(test)]
+            after_scan: None,
+        }
+    }
+}
+
+// Size used for progress accounting; unreadable metadata counts as zero
+fn entry_size(entry: &walkdir::DirEntry) -> u64 {
+    entry.metadata().map(|m| m.len()).unwrap_or(0)
+}
+
+// Header-only chunk for a text file whose body didn't fit in the memory limit,
+// headed by its `shown` path
+fn omitted(path: &Path, shown: &Path) -> (FileOutcome, Option<String>) {
+    log::debug!("Omitting body of {}: memory limit reached", path.display());
+    let chunk = format!("=== {} ===\n[omitted: memory limit reached]\n\n", shown.display());
+    (FileOutcome::Omitted, Some(chunk))
+}
+
+// A file left out because the output had reached `--max-total`
+fn over_total(path: &Path) -> (FileOutcome, Option<String>) {
+    log::debug!("Leaving out {}: output size limit reached", path.display());
+    (FileOutcome::OverTotal, None)
+}
+
+// A file whose read didn't finish within the file timeout
+fn timed_out(path: &Path) -> (FileOutcome, Option<String>) {
+    log::warn!("Gave up reading {}: it took longer than the file timeout", path.display());
+    (FileOutcome::TimedOut, None)
+}
+
+// The summary's lines, naming files as their headers do
+fn summary(report: &ProcessReport, detailed: bool, base: Option<&Path>) -> String {
+    let mut summary = String::from("\n=== SUMMARY ===\n");
+    summary.push_str(&format!("Text files processed: {}\n", report.text_files));
+    summary.push_str(&format!("Binary files skipped: {}\n", report.binary_files));
+    if report.estimated_tokens > 0 {
+        summary.push_str(&format!("Estimated tokens: {}\n", report.estimated_tokens));
+    }
+    if let Some(largest) = report.largest_too_large() {
+        summary.push_str(&format!(
+            "Files skipped for size: {} (largest: {}, {})\n",
+            report.skipped.too_large,
+            utils::header_path(&largest.path, base).display(),
+            utils::format_size(largest.size)
+        ));
+    }
+    if report.skipped.failed > 0 {
+        summary.push_str(&format!("Failed files: {}\n", report.skipped.failed));
+    }
+    if let Some(diff) = &report.diff {
+        summary.push_str(&format!(
+            "Files with changes since {}: {} (+{} -{}), new files: {}\n",
+            diff.rev, diff.files, diff.added, diff.removed, diff.new_files
+        ));
+    }
+    if let Some(manifest) = &report.manifest {
+        summary.push_str(&format!(
+            "Files changed since manifest: {}, new files: {}, deleted files: {}\n",
+            manifest.changed, manifest.new_files, manifest.deleted.len()
+        ));
+        for path in &manifest.deleted {
+            summary.push_str(&format!("  deleted: {}\n", path));
+        }
+    }
+    if let Some(sample) = &report.sample {
+        summary.push_str(&format!(
+            "Sampled {} of {} files (seed {}, stratified by top-level directory)\n",
+            sample.requested.min(sample.candidates), sample.candidates, sample.seed
+        ));
+    }
+    if !report.roots.is_empty() {
+        summary.push_str("Files by root:\n");
+        for root in &report.roots {
+            summary.push_str(&format!("  [{}]: {} files, {} ({} skipped)\n", root.label, root.text_files, utils::format_size(root.bytes), root.skipped));
+        }
+    }
+    if !report.groups.is_empty() {
+        summary.push_str("Files by group:\n");
+        for group in &report.groups {
+            summary.push_str(&format!("  {}: {} files, {}\n", group.name, group.files, utils::format_size(group.bytes)));
+        }
+    }
+    if report.skipped_repos > 0 {
+        summary.push_str(&format!("Nested repositories skipped: {}\n", report.skipped_repos));
+    }
+    if report.skipped.tests > 0 {
+        summary.push_str(&format!("Test files skipped: {}\n", report.skipped.tests));
+    }
+    if report.skipped.by_time > 0 {
+        summary.push_str(&format!("Files outside the modification time range: {}\n", report.skipped.by_time));
+    }
+    if report.test_modules_stripped > 0 {
+        summary.push_str(&format!("Test modules stripped: {}\n", report.test_modules_stripped));
+    }
+    if !report.capped_dirs.is_empty() {
+        summary.push_str("Directories capped:\n");
+        for cap in &report.capped_dirs {
+            summary.push_str(&format!("  {}: included {} of {} files\n", cap.name(), cap.included, cap.total));
+        }
+    }
+    if report.skipped.vanished > 0 {
+        summary.push_str(&format!("Files that vanished during the run: {}\n", report.skipped.vanished));
+    }
+    if report.skipped.timed_out > 0 {
+        summary.push_str(&format!("Files abandoned after the read timeout: {}\n", report.skipped.timed_out));
+    }
+    if report.skipped.summarized > 0 {
+        summary.push_str(&format!("Files summarized for size: {}\n", report.skipped.summarized));
+    }
+    if report.skipped.duplicates > 0 {
+        summary.push_str(&format!(
+            "Duplicate files collapsed: {} ({} saved)\n",
+            report.skipped.duplicates,
+            utils::format_size(report.deduped_bytes)
+        ));
+    }
+    if report.skipped.over_budget > 0 {
+        summary.push_str(&format!("Files dropped to fit token budget: {}\n", report.skipped.over_budget));
+    }
+    if report.skipped.over_total > 0 {
+        summary.push_str(&format!("Files left out at the output size limit: {}\n", report.skipped.over_total));
+    }
+    if !report.truncated.is_empty() {
+        summary.push_str(&format!("Files truncated to fit token budget: {}\n", report.truncated.len()));
+        for truncation in &report.truncated {
+            summary.push_str(&format!(
+                "  {}: kept {} lines, omitted {}\n",
+                utils::header_path(&truncation.path, base).display(), truncation.kept, truncation.omitted
+            ));
+        }
+    }
+    if report.skipped.omitted > 0 {
+        summary.push_str(&format!(
+            "Memory limit reached: {} file bodies omitted (use --low-memory or tighter filters to include them)\n",
+            report.skipped.omitted
+        ));
+    }
+    if detailed {
+        summary.push_str(&file_sizes(report, base));
+    }
+    summary
+}
+
+// Each written file's share of the output, biggest first, with the totals
+fn file_sizes(report: &ProcessReport, base: Option<&Path>) -> String {
+    let mut written: Vec<&FileRecord> = report.files.iter().filter(|f| f.chars > 0).collect();
+    if written.is_empty() {
+        return String::new();
+    }
+    written.sort_by(|a, b| b.chars.cmp(&a.chars).then_with(|| a.path.cmp(&b.path)));
+    let (lines, chars) = written.iter().fold((0, 0), |(lines, chars), f| (lines + f.lines, chars + f.chars));
+    let names: Vec<String> = written.iter().map(|f| utils::header_path(&f.path, base).display().to_string()).collect();
+    let name_width = names.iter().map(|name| name.chars().count()).max().unwrap_or(0).max("Total".len());
+    let (line_width, char_width) = (lines.to_string().len(), chars.to_string().len());
+    
+    let mut table = String::from("Lines and characters by file:\n");
+    for (file, name) in written.iter().zip(&names) {
+        table.push_str(&format!(
+            "  {:<name_width$}  {:>line_width$} lines  {:>char_width$} chars\n",
+            name, file.lines, file.chars, name_width = name_width, line_width = line_width, char_width = char_width
+        ));
+    }
+    table.push_str(&format!(
+        "  {:<name_width$}  {:>line_width$} lines  {:>char_width$} chars\n",
+        "Total", lines, chars, name_width = name_width, line_width = line_width, char_width = char_width
+    ));
+    table
+}
+
+// One line per included file that matched the search, in output order, with
+// paths shown as their headers show them
+fn match_index(results: &[Option<FileResult>], matches: &HashMap<PathBuf, SearchMatches>, shown: &dyn Fn(&Path) -> Cow<'_, Path>) -> String {
+    let mut index = String::new();
+    for (record, _) in results.iter().flatten().filter(|(record, _)| record.outcome == FileOutcome::Included) {
+        if let Some(found) = matches.get(&record.path) {
+            index.push_str(&format!(
+                "{}: {} matching {}, first at line {}: {}\n",
+                shown(&record.path).display(),
+                found.lines,
+                if found.lines == 1 { "line" } else { "lines" },
+                found.first_line,
+                found.first
+            ));
+        }
+    }
+    if index.is_empty() {
+        return index;
+    }
+    format!("=== MATCH INDEX ===\n{}\n", index)
+}
+
+// Every file under `root` whatever the filters say, other than git's own metadata
+fn walk_files(root: &Path) -> Vec<PathBuf> {
+    walkdir::WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || e.file_name() != ".git")
+        .filter_map(Result::ok)
+        .filter(|e| !e.file_type().is_dir())
+        .map(walkdir::DirEntry::into_path)
+        .collect()
+}
+
+// Replace included files whose content was already seen with a stub, in output
+// order so the first copy is always the one kept; stubs carry the same header
+// line as the files, whose chunks end in a footer when `header` says so, and
+// show paths as `shown` has them. Returns the bytes of content the stubs left
+// out.
+fn collapse_duplicates(results: &mut [Option<FileResult>], fingerprints: &mut HashMap<PathBuf, Fingerprint>, deduper: &mut Deduper, header: (&HeaderTemplate, bool), shown: &dyn Fn(&Path) -> Cow<'_, Path>) -> u64 {
+    let (header, footer) = header;
+    let mut saved = 0;
+    for (record, chunk) in results.iter_mut().flatten() {
+        let Ok(chunk @ Some(_)) = chunk else { continue };
+        if record.outcome != FileOutcome::Included {
+            continue;
+        }
+        let Some(fingerprint) = fingerprints.remove(&record.path) else { continue };
+        let shown = shown(&record.path);
+        if let Some(note) = deduper.check(&record.path, &shown, fingerprint) {
+            log::debug!("Collapsing duplicate: {}", record.path.display());
+            record.outcome = FileOutcome::Duplicate;
+            saved += fingerprint.len;
+            let (_, body, _) = split_chunk(chunk.as_deref().unwrap_or_default(), footer);
+            *chunk = Some(format!("{} ({})\n\n", header.render(&record.path, &shown, body), note));
+        }
+    }
+    saved
+}
+
+// A chunk's header, its body without the trailing separator, and what has to
+// follow the body: a Markdown chunk's closing fence, an XML one's closing tag,
+// the last line when plain chunks have a `footer`, or nothing
+fn split_chunk(chunk: &str, footer: bool) -> (&str, &str, &str) {
+    if let Some((header, body, footer)) = markdown::split(chunk).or_else(|| xml::split(chunk)) {
+        return (header, body, footer.trim_end_matches('\n'));
+    }
+    let header_end = chunk.find('\n').map_or(chunk.len(), |i| i + 1);
+    let (header, body) = chunk.split_at(header_end);
+    let body = body.trim_end_matches('\n');
+    match body.rsplit_once('\n') {
+        Some((body, last)) if footer => (header, body, last),
+        _ => (header, body, ""),
+    }
+}
+
+// Add a note to the first line of a chunk, inside its "=== ... ===" header,
+// in its XML note attribute, or at the end of a Markdown heading or a header
+// from --header-format
+fn annotate_header(chunk: &mut String, meta: &dyn std::fmt::Display) {
+    let header_end = chunk.find('\n').unwrap_or(chunk.len());
+    if chunk[..header_end].ends_with(" ===") {
+        chunk.insert_str(header_end - " ===".len(), &format!(" ({})", meta));
+    } else if chunk.starts_with("<document ") {
+        xml::annotate(chunk, &meta.to_string());
+    } else {
+        chunk.insert_str(header_end, &format!(" ({})", meta));
+    }
+}
+
+/// State shared by the workers for the duration of one run
+struct RunState<'a> {
+    budget: Option<MemoryBudget>,
+    /// File content written so far, against `--max-total`
+    total: Option<MemoryBudget>,
+    /// Changes to diff against when only patches are wanted
+    diffs: Option<DiffSet>,
+    /// Commit info to annotate file headers with
+    git_log: Option<GitLog>,
+    /// Directory that manifest keys are relative to
+    base: PathBuf,
+    /// Labels headers show files from several roots under
+    labels: Option<RootLabels>,
+    /// Where network mounts are, when only reads from them are timed
+    mounts: Option<NetworkMounts>,
+    /// Manifest whose unchanged files are left out
+    previous: Option<&'a Manifest>,
+    /// Content hashes of text files, collected when a manifest is involved
+    hashes: Option<Mutex<BTreeMap<String, String>>>,
+    /// Fingerprints of included files, collected when deduplicating
+    fingerprints: Option<Mutex<HashMap<PathBuf, Fingerprint>>>,
+    /// Where the search text was found in each included file, for the match index
+    matches: Option<Mutex<HashMap<PathBuf, SearchMatches>>>,
+    /// Test modules cut from Rust files so far
+    test_modules: Option<AtomicUsize>,
+    /// Items seen so far, when only named ones are wanted
+    symbols: Option<Mutex<symbol::Seen>>,
+    /// Verbose messages for files found to be binary
+    skips: SkipLog,
+}
+
+// Totals for the files whose patches made it into the output
+fn summarize_diffs(diffs: &DiffSet, files: &[FileRecord]) -> DiffSummary {
+    let mut summary = DiffSummary { rev: diffs.rev.clone(), ..DiffSummary::default() };
+    for file in files.iter().filter(|f| f.outcome == FileOutcome::Included) {
+        match diffs.change(&file.path) {
+            FileChange::Modified(patch) => {
+                summary.files += 1;
+                summary.added += patch.added;
+                summary.removed += patch.removed;
+            }
+            FileChange::Untracked => summary.new_files += 1,
+            FileChange::Unchanged => {}
+        }
+    }
+    summary
+}
+
+/// Running total of formatted content, held in memory or written, shared by
+/// the workers
+struct MemoryBudget {
+    limit: u64,
+    used: AtomicU64,
+    exhausted: AtomicBool,
+}
+
+impl MemoryBudget {
+    fn new(limit: u64) -> Self {
+        Self { limit, used: AtomicU64::new(0), exhausted: AtomicBool::new(false) }
+    }
+
+    /// Claim `bytes` if they fit; once anything has been refused, nothing more is granted
+    fn reserve(&self, bytes: u64) -> bool {
+        if self.exhausted.load(Ordering::Relaxed) {
+            return false;
+        }
+        
+        let granted = self.used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                used.checked_add(bytes).filter(|&total| total <= self.limit)
+            })
+            .is_ok();
+        if !granted {
+            self.exhausted.store(true, Ordering::Relaxed);
+        }
+        granted
+    }
+
+    fn release(&self, bytes: u64) {
+        self.used.fetch_sub(bytes, Ordering::AcqRel);
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.exhausted.load(Ordering::Relaxed)
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+// Output sink that remembers how many bytes have gone through it, and feeds
+// a digest when one is being built
+struct CountingWriter<'a> {
+    inner: &'a mut dyn Write,
+    written: u64,
+    chars: u64,
+    digest: Option<&'a mut Digest>,
+    /// Where each chunk started, for `--chunk-size` to split between
+    starts: Vec<usize>,
+}
+
+impl CountingWriter<'_> {
+    fn write_chunk(&mut self, section: Section, chunk: &str) -> Result<(), YoinkError> {
+        self.starts.push(self.written as usize);
+        self.inner.write_all(chunk.as_bytes()).map_err(|source| YoinkError::Output { source })?;
+        self.written += chunk.len() as u64;
+        self.chars += chunk.chars().count() as u64;
+        if let Some(digest) = self.digest.as_deref_mut() {
+            digest.observe(section, chunk);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NestedRepoMode;
+    use crate::dedup::DedupMode;
+    use crate::formatter::FormatTemplate;
+    use crate::append::AppendMode;
+    use crate::clipboard::MockClipboardManager;
+    use crate::file_scanner::MockFileScanner;
+    use crate::text_processor::MockTextProcessor;
+    use crate::file_tree::MockDirectoryTreeBuilder;
+    use crate::manifest::ManifestLocation;
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_processor_with_mocks() {
+        let fixture = crate::fixture! {
+            "test.txt" => "This is test content",
+            "img.png" => [0x89, b'P', b'N', b'G', 0, 0],
+        };
+        let config = fixture.config()
+            .max_size(1024 * 1024) // 1MB
+            .build();
+        
+        // Create mock components
+        let mock_clipboard = MockClipboardManager::new();
+        let mock_file_scanner = MockFileScanner::with_entries(fixture.entries());
+        let mut mock_text_processor = MockTextProcessor::new();
+        let mut mock_dir_tree_builder = MockDirectoryTreeBuilder::new();
+        
+        // Setup mock directory tree
+        mock_dir_tree_builder.set_mock_tree("📁 mock/\n  📄 img.png\n  📄 test.txt\n");
+        
+        // Setup mock text processor
+        let text_path = fixture.join("test.txt");
+        let image_path = fixture.join("img.png");
+        mock_text_processor.add_text_file(&text_path.to_string_lossy(), "This is test content");
+        mock_text_processor.add_binary_file(&image_path.to_string_lossy());
+        
+        // Create the processor with mocked dependencies
+        let mut processor = FileProcessor::builder(config)
+            .clipboard(mock_clipboard)
+            .file_scanner(mock_file_scanner)
+            .text_processor(mock_text_processor)
+            .dir_tree_builder(mock_dir_tree_builder)
+            .build();
+        
+        let report = processor.process().unwrap();
+        assert_eq!(report.text_files, 1);
+        assert_eq!(report.binary_files, 1);
+        assert_eq!(report.files.len(), 2);
+        
+        // The mock clipboard received the whole output
+        let receipt = report.clipboard.expect("clipboard receipt");
+        assert_eq!(receipt.method, "mock");
+        assert_eq!(receipt.bytes, report.output_bytes);
+        
+        let copied = processor.clipboard_as::<MockClipboardManager>().unwrap().get_copied_text().unwrap();
+        let expected = format!(
+            "=== DIRECTORY STRUCTURE ===\n📁 mock/\n  📄 img.png\n  📄 test.txt\n\n\
+             === TEXT FILES ===\n\n\
+             === {} ===\nThis is test content\n\n\n\
+             === SUMMARY ===\nText files processed: 1\nBinary files skipped: 1\nEstimated tokens: {}\n",
+            text_path.display(),
+            report.estimated_tokens
+        );
+        assert_eq!(copied, expected);
+        // The estimate covers the whole output, the summary included
+        assert_eq!(report.estimated_tokens, fit::estimate_tokens(copied.chars().count() as u64));
+    }
+
+    #[test]
+    fn test_failed_tree_is_noted_and_files_still_copied() {
+        let fixture = crate::fixture! { "a.txt" => "alpha" };
+        let mut tree = MockDirectoryTreeBuilder::new();
+        tree.set_failure(io::ErrorKind::PermissionDenied);
+        let mut processor = FileProcessor::builder(fixture.config().build())
+            .clipboard(MockClipboardManager::new())
+            .dir_tree_builder(tree)
+            .build();
+
+        let report = processor.process().unwrap();
+        assert_eq!(report.text_files, 1);
+
+        let copied = processor.clipboard_as::<MockClipboardManager>().unwrap().get_copied_text().unwrap();
+        assert!(copied.starts_with("=== DIRECTORY STRUCTURE ===\n[unavailable: "));
+        assert!(copied.contains("a.txt ===\nalpha\n"));
+    }
+
+    #[test]
+    fn test_failed_tree_aborts_in_strict_mode() {
+        let fixture = crate::fixture! { "a.txt" => "alpha" };
+        let mut tree = MockDirectoryTreeBuilder::new();
+        tree.set_failure(io::ErrorKind::PermissionDenied);
+        let processor = FileProcessor::builder(fixture.config().strict(true).build()).dir_tree_builder(tree).build();
+
+        assert!(matches!(processor.assemble(), Err(YoinkError::Io { .. })));
+    }
+
+    #[test]
+    fn test_files_from_includes_exactly_the_listed_files() {
+        let fixture = crate::fixture! {
+            "src/main.rs" => "fn main() {}",
+            "notes.log" => "started",
+            "README.md" => "# app",
+        };
+        let list = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(list.path(), "# repro\nnotes.log\nsrc/gone.rs\nsrc/main.rs\n").unwrap();
+        let config = fixture.config().include_extensions(["rs"]).files_from(list.path()).build();
+        let output = FileProcessor::with_defaults(config.clone()).assemble().unwrap();
+
+        let name = fixture.path().file_name().unwrap().to_string_lossy();
+        assert!(output.content.starts_with(&format!(
+            "=== DIRECTORY STRUCTURE ===\n📁 {}/\n  📄 notes.log\n  📁 src/\n    📄 main.rs\n\n",
+            name
+        )));
+        let log = output.content.find("notes.log ===").unwrap();
+        assert!(log < output.content.find("main.rs ===").unwrap());
+        assert!(!output.content.contains("README.md"));
+        assert_eq!(output.report.missing_listed, [PathBuf::from("src/gone.rs")]);
+
+        let strict = FileProcessor::with_defaults(Config { strict: true, ..config });
+        assert!(matches!(strict.assemble(), Err(YoinkError::MissingListed { .. })));
+    }
+
+    #[test]
+    fn test_markdown_format_fences_each_file() {
+        let fixture = crate::fixture! {
+            "main.rs" => "fn main() {}\n",
+            "README.md" => "```sh\nls\n```\n",
+        };
+        let config = fixture.config().format(OutputFormat::Markdown).build();
+        let content = FileProcessor::with_defaults(config).assemble().unwrap().content;
+
+        assert!(content.starts_with("=== DIRECTORY STRUCTURE ===\n```\n📁 "));
+        assert!(content.contains("## README.md\n\n````markdown\n```sh\nls\n```\n````\n\n"));
+        assert!(content.contains("## main.rs\n\n```rust\nfn main() {}\n```\n\n"));
+    }
+
+    #[test]
+    fn test_xml_format_wraps_each_file_in_a_document() {
+        let fixture = crate::fixture! {
+            "a&b.rs" => "if a < b { c]]>d }\n",
+        };
+        let config = fixture.config().format(OutputFormat::Xml).build();
+        let content = FileProcessor::with_defaults(config).assemble().unwrap().content;
+
+        assert!(content.starts_with("<documents>\n<directory_tree>\n📁 "));
+        assert!(content.contains("📄 a&amp;b.rs\n</directory_tree>\n\n<document path="));
+        assert!(content.contains("<document path=\"a&amp;b.rs\"><![CDATA[\nif a < b { c]]]]><![CDATA[>d }\n]]></document>\n\n"));
+        assert!(content.contains("<summary text_files=\"1\" binary_files=\"0\" skipped_for_size=\"0\" failed=\"0\" estimated_tokens=\""));
+        assert!(content.contains("\">\nText files processed: 1\n"));
+        assert!(content.ends_with("</summary>\n</documents>\n"));
+        assert!(!content.contains("==="));
+    }
+
+    #[test]
+    fn test_html_format_renders_a_page() {
+        let fixture = crate::fixture! {
+            "main.rs" => "// <entry>\nfn main() { if 1 < 2 {} }\n",
+        };
+        let config = fixture.config().format(OutputFormat::Html).build();
+        let content = FileProcessor::with_defaults(config).assemble().unwrap().content;
+
+        assert!(content.starts_with("<!DOCTYPE html>\n"));
+        assert!(content.contains("<ul class=\"tree\">\n"));
+        assert!(content.contains(
+            "<summary>main.rs</summary>\n<pre><code class=\"language-rust\"><span class=\"c\">// &lt;entry&gt;</span>\nfn main() { if 1 &lt; 2 {} }\n"
+        ));
+        assert!(content.contains("<h2>Summary</h2>\n<pre>Text files processed: 1\n"));
+        assert!(content.ends_with("</body>\n</html>\n"));
+        assert!(!content.contains("==="));
+    }
+
+    #[test]
+    fn test_depth_ceiling_stops_the_scan_and_the_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut level = dir.path().to_path_buf();
+        for depth in 1..=6 {
+            std::fs::write(level.join(format!("at_{}.txt", depth)), "x").unwrap();
+            level.push(format!("d{}", depth));
+            std::fs::create_dir(&level).unwrap();
+        }
+        let config = Config::builder().path(dir.path().to_string_lossy()).depth_ceiling(3).build();
+        let content = FileProcessor::with_defaults(config).assemble().unwrap().content;
+
+        assert!(content.contains("📄 at_3.txt\n"));
+        assert!(!content.contains("at_4.txt"));
+        assert!(content.contains("      📁 d3/ (depth ceiling reached)\n"));
+    }
+
+    #[test]
+    fn test_exclude_globs_apply_to_the_scan_and_the_tree() {
+        let fixture = crate::fixture! {
+            "src/lib.rs" => "pub mod parser;\n",
+            "src/parser_generated.rs" => "// generated\n",
+            "src/__snapshots__/lib.snap" => "snapshot\n",
+            "web/__snapshots__/deep/app.snap" => "snapshot\n",
+            "web/app_test/case.js" => "test()\n",
+            "app_test.js" => "test()\n",
+        };
+        let config = fixture.config()
+            .exclude_glob(glob::Pattern::new("**/__snapshots__/**").unwrap())
+            .exclude_glob(glob::Pattern::new("**/*_generated.rs").unwrap())
+            .exclude_glob(glob::Pattern::new("**/*_test").unwrap())
+            .build();
+        let output = FileProcessor::with_defaults(config).assemble().unwrap();
+
+        assert_eq!(output.report.text_files, 2);
+        assert!(output.content.contains("=== src/lib.rs ===") && output.content.contains("=== app_test.js ==="));
+        for left_out in ["__snapshots__", "generated", "app_test/", "case.js"] {
+            assert!(!output.content.contains(left_out), "{} is still in the output", left_out);
+        }
+    }
+
+    #[test]
+    fn test_exclude_patterns_apply_to_the_scan_and_the_tree() {
+        let fixture = crate::fixture! {
+            "web/app.ts" => "export {}\n",
+            "web/app.spec.ts" => "describe()\n",
+            "server/handler.go" => "package server\n",
+            "server/handler_test.go" => "package server\n",
+        };
+        let config = fixture.config()
+            .pattern(glob::Pattern::new("*.*s").unwrap())
+            .exclude_pattern(glob::Pattern::new("*_test.go").unwrap())
+            .exclude_pattern(glob::Pattern::new("*.spec.ts").unwrap())
+            .build();
+        let output = FileProcessor::with_defaults(config).assemble().unwrap();
+
+        assert_eq!(output.report.text_files, 1);
+        assert!(output.content.contains("=== web/app.ts ==="));
+        for left_out in ["app.spec.ts", "handler_test.go"] {
+            assert!(!output.content.contains(left_out), "{} is still in the output", left_out);
+        }
+        assert!(output.content.contains("handler.go"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_links_walks_symlinked_directories_once() {
+        let fixture = crate::fixture! {
+            "shared/button.rs" => "pub struct Button;\n",
+            "app/main.rs" => "fn main() {}\n",
+        };
+        std::os::unix::fs::symlink(fixture.join("shared"), fixture.join("app/components")).unwrap();
+        std::os::unix::fs::symlink(fixture.join("shared"), fixture.join("app/widgets")).unwrap();
+        let app = || fixture.config().path(fixture.join("app").to_string_lossy());
+
+        let unfollowed = FileProcessor::with_defaults(app().build()).assemble().unwrap();
+        assert!(!unfollowed.content.contains("pub struct Button;"));
+
+        let followed = FileProcessor::with_defaults(app().follow_links(true).build()).assemble().unwrap();
+        assert_eq!(followed.report.text_files, 2);
+        assert_eq!(followed.content.matches("pub struct Button;").count(), 1);
+        assert!(followed.content.contains("=== components/button.rs ==="));
+        assert!(followed.content.contains("📁 widgets/\n    📄 button.rs\n"), "{}", followed.content);
+    }
+
+    #[test]
+    fn test_git_mode_keeps_only_what_git_lists() {
+        let fixture = crate::fixture! {
+            ".gitignore" => "target/\n",
+            "src/lib.rs" => "pub fn lib() {}\n",
+        };
+        let config = fixture.config().git_tracked(false).build();
+        assert!(matches!(FileProcessor::with_defaults(config).assemble(), Err(YoinkError::NotARepository { .. })));
+
+        if !fixture.init_git_repo() {
+            return;
+        }
+        fs::create_dir_all(fixture.join("target/debug")).unwrap();
+        fixture.write("target/debug/build.rs", "// artifact\n");
+        fixture.write("scratch.txt", "notes\n");
+
+        let tracked = FileProcessor::with_defaults(fixture.config().git_tracked(false).build()).assemble().unwrap();
+        assert_eq!(tracked.report.text_files, 2);
+        assert!(tracked.content.contains("=== src/lib.rs ==="));
+        for left_out in ["scratch.txt", "📁 target", "build.rs"] {
+            assert!(!tracked.content.contains(left_out), "{} is still in the output", left_out);
+        }
+
+        let others = FileProcessor::with_defaults(fixture.config().git_tracked(true).build()).assemble().unwrap();
+        assert!(others.content.contains("=== scratch.txt ===") && !others.content.contains("build.rs"));
+    }
+
+    #[test]
+    fn test_since_and_until_compare_modification_times() {
+        let fixture = crate::fixture! {
+            "old.rs" => "fn old() {}\n",
+            "new.rs" => "fn new() {}\n",
+        };
+        let ten_days_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(10 * 24 * 60 * 60);
+        fs::File::options().write(true).open(fixture.join("old.rs")).unwrap().set_modified(ten_days_ago).unwrap();
+        let three_days = || Some(crate::modified::TimeBound::parse("3d").unwrap());
+
+        let recent = FileProcessor::with_defaults(fixture.config().modified(three_days(), None).build()).assemble().unwrap();
+        assert!(recent.content.contains("=== new.rs ===") && !recent.content.contains("=== old.rs ==="));
+        assert_eq!(recent.report.skipped.by_time, 1);
+        assert!(recent.content.contains("Files outside the modification time range: 1\n"));
+
+        let older = FileProcessor::with_defaults(fixture.config().modified(None, three_days()).build()).assemble().unwrap();
+        assert!(older.content.contains("=== old.rs ===") && !older.content.contains("=== new.rs ==="));
+    }
+
+    #[test]
+    fn test_several_roots_share_one_output() {
+        let fixture = crate::fixture! {
+            "src/main.rs" => "fn main() {}\n",
+            "src/lib.rs" => "pub fn lib() {}\n",
+            "docs/guide.md" => "# Guide\n",
+            "scripts/build.sh" => "make\n",
+        };
+        let config = fixture.config()
+            .path(fixture.join("src").to_string_lossy())
+            .extra_path(fixture.join("docs").to_string_lossy())
+            .extra_path(fixture.join("src/lib.rs").to_string_lossy())
+            .build();
+        let output = FileProcessor::with_defaults(config).assemble().unwrap();
+
+        assert_eq!(output.report.text_files, 3);
+        assert_eq!(output.content.matches("=== [src] lib.rs ===").count(), 1);
+        assert!(output.content.contains("=== [src] main.rs ===") && output.content.contains("=== [docs] guide.md ==="));
+        assert!(!output.content.contains("build.sh"));
+        let src = output.content.find("📁 [src]/").unwrap();
+        let docs = output.content.find("📁 [docs]/").unwrap();
+        assert!(src < docs, "{}", output.content);
+    }
+
+    #[test]
+    fn test_roots_of_the_same_name_are_told_apart() {
+        let fixture = crate::fixture! {
+            "web/api/main.ts" => "serve()\n",
+            "mobile/api/main.ts" => "fetch()\n",
+            "mobile/api/extra.bin" => "\0\0\0",
+        };
+        let config = fixture.config()
+            .path(fixture.join("web/api").to_string_lossy())
+            .extra_path(fixture.join("mobile/api").to_string_lossy())
+            .build();
+        let output = FileProcessor::with_defaults(config).assemble().unwrap();
+
+        assert!(output.content.contains("=== [api] main.ts ===\nserve()"), "{}", output.content);
+        assert!(output.content.contains("=== [api-2] main.ts ===\nfetch()"), "{}", output.content);
+        assert!(output.content.contains("📁 [api]/") && output.content.contains("📁 [api-2]/"));
+
+        let counts: Vec<_> = output.report.roots.iter().map(|root| (root.label.as_str(), root.text_files, root.skipped)).collect();
+        assert_eq!(counts, [("api", 1, 0), ("api-2", 1, 1)]);
+        assert!(output.content.contains("Files by root:\n  [api]: 1 files"), "{}", output.content);
+    }
+
+    #[test]
+    fn test_label_names_a_root() {
+        let fixture = crate::fixture! {
+            "client/src/main.ts" => "render()\n",
+            "server/main.go" => "package main\n",
+        };
+        let config = fixture.config()
+            .path(fixture.join("client").to_string_lossy())
+            .extra_path(fixture.join("server").to_string_lossy())
+            .label("frontend", fixture.join("client").to_string_lossy())
+            .build();
+        let output = FileProcessor::with_defaults(config).assemble().unwrap();
+
+        assert!(output.content.contains("=== [frontend] src/main.ts ==="), "{}", output.content);
+        assert!(output.content.contains("=== [server] main.go ==="), "{}", output.content);
+        assert!(output.content.contains("📁 [frontend]/"));
+    }
+
+    #[test]
+    fn test_yoinkignore_leaves_paths_out_of_the_scan_and_the_tree() {
+        let fixture = crate::fixture! {
+            ".yoinkignore" => "fixtures/\n*.snap\n!keep.snap\n",
+            "src/main.rs" => "fn main() {}\n",
+            "src/__snapshots__/main.snap" => "snapshot\n",
+            "src/__snapshots__/keep.snap" => "kept\n",
+            "tests/fixtures/big.json" => "{}\n",
+        };
+        let content = FileProcessor::with_defaults(fixture.config().build()).assemble().unwrap().content;
+
+        assert!(content.contains("📄 keep.snap\n") && content.contains("kept\n"));
+        assert!(!content.contains("main.snap") && !content.contains("📁 fixtures/") && !content.contains("big.json"));
+
+        let config = fixture.config().yoinkignore(false).build();
+        let content = FileProcessor::with_defaults(config).assemble().unwrap().content;
+        assert!(content.contains("main.snap") && content.contains("big.json"));
+    }
+
+    #[test]
+    fn test_long_paths_are_abbreviated_in_headers() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested: PathBuf = (0..30).map(|level| format!("level_{:02}", level)).collect();
+        std::fs::create_dir_all(dir.path().join(&nested)).unwrap();
+        let leaf = nested.join("leaf.rs");
+        std::fs::write(dir.path().join(&leaf), "fn leaf() {}").unwrap();
+        std::fs::write(dir.path().join("short.rs"), "fn short() {}").unwrap();
+        let output = FileProcessor::with_defaults(Config::builder().path(dir.path().to_string_lossy()).build()).assemble().unwrap();
+
+        let short = depth::abbreviate(&leaf).unwrap();
+        assert!(short.ends_with("/…/leaf.rs"));
+        assert!(output.content.contains(&format!("=== {} [1] ===\nfn leaf() {{}}\n", short)));
+        assert!(output.content.contains("=== short.rs ===\n"));
+        assert!(output.content.contains(&format!("\n=== FULL PATHS ===\n[1] {}\n", leaf.display())));
+        assert_eq!(output.content.matches(&leaf.display().to_string()).count(), 1);
+        assert_eq!(output.report.abbreviated_paths, [leaf]);
+    }
+
+    #[test]
+    fn test_line_numbers_align_and_skip_search_excerpts() {
+        let content: String = (1..=10).map(|n| format!("line {}\n", n)).collect();
+        let fixture = crate::fixture! { "ten.txt" => content.as_str() };
+        let numbered = FileProcessor::with_defaults(fixture.config().line_numbers(true).build()).assemble().unwrap().content;
+        assert!(numbered.contains(" 1 | line 1\n 2 | line 2\n"));
+        assert!(numbered.contains("10 | line 10\n"));
+
+        let searched = FileProcessor::with_defaults(fixture.config().line_numbers(true).search_text("line 10").build()).assemble().unwrap().content;
+        assert!(searched.contains("10: line 10\n"));
+        assert!(!searched.contains(" | "));
+    }
+
+    #[test]
+    fn test_search_regex_matches_like_literal_search() {
+        let content: String = (1..=10).map(|n| format!("fn handler_{}() {{}}\n", n)).collect();
+        let fixture = crate::fixture! {
+            "handlers.rs" => content.as_str(),
+            "other.rs" => "fn service() {}\n",
+        };
+        let config = fixture.config().search_regex(r"HANDLER_(2|9)\(").build();
+        let searched = FileProcessor::with_defaults(config).assemble().unwrap();
+        assert_eq!(searched.report.text_files, 1);
+        assert!(searched.content.contains("=== MATCH IN: handlers.rs ===\n1: fn handler_1() {}\n"));
+        assert!(searched.content.contains("9: fn handler_9() {}\n10: fn handler_10() {}\n"));
+        assert!(!searched.content.contains("other.rs ==="));
+
+        let config = fixture.config().search_regex(r"HANDLER_(2|9)\(").case_sensitive(true).build();
+        assert!(!FileProcessor::with_defaults(config).assemble().unwrap().content.contains("MATCH IN"));
+        let config = fixture.config().search_regex("handler_(").build();
+        assert!(matches!(FileProcessor::with_defaults(config).assemble(), Err(YoinkError::SearchRegex { .. })));
+    }
+
+    #[test]
+    fn test_max_lines_cuts_only_longer_files() {
+        let long: String = (1..=10).map(|n| format!("line {}\n", n)).collect();
+        let fixture = crate::fixture! {
+            "long.txt" => long.as_str(),
+            "short.txt" => "a\r\nb\r\nc",
+        };
+        let output = FileProcessor::with_defaults(fixture.config().max_lines(3).build()).assemble().unwrap().content;
+        assert!(output.contains("=== long.txt ===\nline 1\nline 2\nline 3\n... [truncated 7 more lines, 10 in all]\n\n"));
+        assert!(output.contains("=== short.txt ===\na\r\nb\r\nc\n\n"));
+
+        let numbered = FileProcessor::with_defaults(fixture.config().max_lines(3).line_numbers(true).build()).assemble().unwrap().content;
+        assert!(numbered.contains("3 | line 3\n... [truncated 7 more lines, 10 in all]\n"));
+    }
+
+    #[test]
+    fn test_strip_comments_keys_off_the_extension() {
+        let fixture = crate::fixture! {
+            "lib.rs" => "//! Crate docs\nfn main() { // entry\n    /* open\n    println!(\"//\");\n",
+            "notes.txt" => "// kept\n",
+        };
+        let output = FileProcessor::with_defaults(fixture.config().strip_comments(true).build()).assemble().unwrap().content;
+        assert!(output.contains("=== lib.rs ===\nfn main() {\n\n"));
+        assert!(output.contains("=== notes.txt ===\n// kept\n"));
+
+        let cut = FileProcessor::with_defaults(fixture.config().strip_comments(true).max_lines(3).build()).assemble().unwrap().content;
+        assert!(cut.contains("=== lib.rs ===\nfn main() {\n... [truncated 1 more lines, 4 in all]\n"));
+    }
+
+    #[test]
+    fn test_condense_collapses_blank_lines_but_search_sees_the_original() {
+        let fixture = crate::fixture! {
+            "a.txt" => "one  \n\n \n\t\ntwo\t\r\n\r\n\r\nthree",
+        };
+        let assembled = FileProcessor::with_defaults(fixture.config().condense(true).build()).assemble().unwrap();
+        assert!(assembled.content.contains("=== a.txt ===\none\n\ntwo\r\n\r\nthree\n\n"));
+        assert_eq!(assembled.report.condensed_bytes, 9);
+
+        let searched = FileProcessor::with_defaults(fixture.config().condense(true).search_text("two\t").build()).assemble().unwrap();
+        assert!(searched.content.contains("5: two\t\n"));
+        assert_eq!(searched.report.condensed_bytes, 0);
+    }
+
+    #[test]
+    fn test_format_template_lays_out_every_section() {
+        let fixture = crate::fixture! {
+            "a.rs" => "fn a() {}",
+            "b.rs" => "fn a() {}",
+        };
+        let text = "[header]\n<context>\n[file]\n<file path=\"{path}\">{content}</file>\n[skipped]\n<skipped path=\"{path}\" why=\"{note}\"/>\n[footer]\n</context>\n";
+        let template = FormatTemplate::parse(Path::new("prompt.tmpl"), text).unwrap();
+        let config = Config { format_template: Some(template), ..fixture.config().dedup(DedupMode::Exact).build() };
+        let content = FileProcessor::with_defaults(config).assemble().unwrap().content;
+
+        assert_eq!(
+            content,
+            "<context>\n<file path=\"a.rs\">fn a() {}</file>\n<skipped path=\"b.rs\" why=\"identical to a.rs\"/>\n</context>\n"
+        );
+    }
+
+    #[test]
+    fn test_sniff_all_includes_misnamed_text_and_skips_misnamed_images() {
+        let fixture = crate::fixture! {
+            "data.bin" => "id,name\n1,alpha\n",
+            "image.txt" => &b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01\0\0\0\x01\x08\x06\0\0\0"[..],
+        };
+        let fast = FileProcessor::with_defaults(fixture.config().build()).assemble().unwrap().report;
+        assert_eq!((fast.text_files, fast.binary_files), (0, 2));
+
+        let sniffed = FileProcessor::with_defaults(fixture.config().sniff_all(true).build()).assemble().unwrap();
+        assert_eq!((sniffed.report.text_files, sniffed.report.binary_files), (1, 1));
+        assert!(sniffed.content.contains("data.bin ===\nid,name\n1,alpha\n"));
+        assert!(!sniffed.content.contains("image.txt ==="));
+    }
+
+    #[test]
+    fn test_append_adds_a_run_to_the_clipboard() {
+        let first = crate::fixture! { "a.txt" => "alpha" };
+        let second = crate::fixture! { "b.txt" => "beta" };
+        let clipboard = MockClipboardManager::new();
+        clipboard.copy_to_clipboard(&FileProcessor::with_defaults(first.config().build()).assemble().unwrap().content).unwrap();
+        let mut processor = FileProcessor::builder(second.config().append(AppendMode::Strict).build()).clipboard(clipboard).build();
+        let report = processor.process().unwrap();
+        assert_eq!(report.appended_run, Some(2));
+
+        let copied = processor.clipboard_as::<MockClipboardManager>().unwrap().get_copied_text().unwrap();
+        let divider = copied.find("=== ADDITIONAL FILES (run 2) ===").unwrap();
+        assert!(copied.find("a.txt ===\nalpha").unwrap() < divider);
+        assert!(copied.find("b.txt ===\nbeta").unwrap() > divider);
+        assert!(copied.contains("=== SUMMARY ===\nRuns combined: 2\nText files processed: 2\nBinary files skipped: 0\nEstimated tokens: "));
+    }
+
+    #[test]
+    fn test_output_file_replaces_the_clipboard() {
+        let fixture = crate::fixture! { "a.txt" => "alpha" };
+        let out = tempfile::tempdir().unwrap();
+        let target = out.path().join("nested/dir/out.txt");
+        for low_memory in [false, true] {
+            let config = fixture.config().low_memory(low_memory).output(&target, low_memory).build();
+            let mut processor = FileProcessor::builder(config).clipboard(MockClipboardManager::new()).build();
+            let report = processor.process().unwrap();
+
+            let written = std::fs::read_to_string(&target).unwrap();
+            assert!(written.contains("a.txt ===\nalpha\n"));
+            assert!(written.contains("=== SUMMARY ===\nText files processed: 1\n"));
+            assert_eq!(report.output_file, Some(WrittenFile { path: target.clone(), bytes: written.len() as u64 }));
+            assert!(report.clipboard.is_none());
+            assert!(processor.clipboard_as::<MockClipboardManager>().unwrap().get_copied_text().is_none());
+        }
+    }
+
+    #[test]
+    fn test_chunk_size_copies_one_chunk_or_writes_them_all() {
+        // Each file's section is 165 characters, so no chunk holds both
+        let (a, b) = ("alpha\n".repeat(25), "bravo\n".repeat(25));
+        let fixture = crate::fixture! {
+            "a.txt" => a.as_str(),
+            "b.txt" => b.as_str(),
+        };
+        let config = fixture.config().chunk_size(300).chunk(2).build();
+        let mut processor = FileProcessor::builder(config).clipboard(MockClipboardManager::new()).build();
+        let report = processor.process().unwrap();
+        let copied = processor.clipboard_as::<MockClipboardManager>().unwrap().get_copied_text().unwrap();
+        let chunks = report.chunks.unwrap();
+        assert_eq!((chunks.count, chunks.delivered), (2, Some(2)));
+        assert!(copied.starts_with("[chunk 2/2]\n=== "));
+        assert!(copied.contains("=== SUMMARY ==="));
+        assert!(!copied.contains("DIRECTORY STRUCTURE"));
+
+        let out = tempfile::tempdir().unwrap();
+        let config = fixture.config().chunk_size(300).output(out.path().join("yoink.txt"), false).build();
+        let report = FileProcessor::builder(config).clipboard(MockClipboardManager::new()).build().process().unwrap();
+        let files = report.chunks.unwrap().files;
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[1].path, out.path().join("yoink-2.txt"));
+        assert!(std::fs::read_to_string(&files[0].path).unwrap().starts_with("[chunk 1/2]\n=== DIRECTORY STRUCTURE ==="));
+
+        let config = fixture.config().chunk_size(300).chunk(3).build();
+        let missing = FileProcessor::builder(config).clipboard(MockClipboardManager::new()).build().process();
+        assert!(matches!(missing, Err(YoinkError::Chunk { .. })));
+    }
+
+    #[test]
+    fn test_output_file_is_not_overwritten_without_force() {
+        let fixture = crate::fixture! { "a.txt" => "alpha" };
+        let out = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(out.path(), "keep me").unwrap();
+        let mut processor = FileProcessor::with_defaults(fixture.config().output(out.path(), false).build());
+
+        match processor.process() {
+            Err(YoinkError::OutputFile { source, .. }) => assert_eq!(source.kind(), io::ErrorKind::AlreadyExists),
+            other => panic!("expected OutputFile, got {:?}", other),
+        }
+        assert_eq!(std::fs::read_to_string(out.path()).unwrap(), "keep me");
+    }
+
+    #[test]
+    fn test_builder_mixes_mock_clipboard_with_real_components() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "alpha").unwrap();
+        std::fs::write(dir.path().join("b.png"), [0x89, b'P', b'N', b'G', 0, 0]).unwrap();
+
+        let config = Config::builder().path(dir.path().to_string_lossy()).build();
+        let mut processor = FileProcessor::builder(config)
+            .clipboard(MockClipboardManager::new())
+            .build();
+
+        let report = processor.process().unwrap();
+        assert_eq!(report.text_files, 1);
+        assert_eq!(report.binary_files, 1);
+
+        let copied = processor.clipboard_as::<MockClipboardManager>().unwrap().get_copied_text().unwrap();
+        assert!(copied.contains("a.txt ===\nalpha\n"));
+        assert!(processor.file_scanner_as::<MockFileScanner>().is_none());
+    }
+
+    #[test]
+    fn test_assemble_does_not_touch_clipboard() {
+        let config = Config::builder().path("/mock/path").build();
+
+        let mut mock_dir_tree_builder = MockDirectoryTreeBuilder::new();
+        mock_dir_tree_builder.set_mock_tree("📁 mock/\n");
+
+        let processor = FileProcessor::new(
+            config,
+            Box::new(MockClipboardManager::new()),
+            Box::new(MockFileScanner::new()),
+            Box::new(MockTextProcessor::new()),
+            Box::new(mock_dir_tree_builder),
+        );
+
+        let output = processor.assemble().unwrap();
+        assert!(output.content.starts_with("=== DIRECTORY STRUCTURE ===\n📁 mock/\n"));
+        assert!(output.content.contains("=== SUMMARY ==="));
+        assert_eq!(output.report.processed(), 0);
+        assert_eq!(output.report.output_bytes, output.content.len() as u64);
+        assert!(output.report.clipboard.is_none());
+    }
+
+    #[test]
+    fn test_missing_path_is_reported() {
+        let config = Config::builder().path("/nonexistent/yoink/path").build();
+        let processor = FileProcessor::with_defaults(config);
+
+        match processor.assemble() {
+            Err(YoinkError::PathNotFound { path, suggestion }) => {
+                assert_eq!(path, PathBuf::from("/nonexistent/yoink/path"));
+                assert_eq!(suggestion, None);
+            }
+            other => panic!("expected PathNotFound, got {:?}", other.map(|o| o.report)),
+        }
+    }
+
+    #[test]
+    fn test_parallel_output_is_complete_and_ordered() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..300 {
+            std::fs::write(dir.path().join(format!("file_{:03}.txt", i)), format!("content {}", i)).unwrap();
+        }
+        for i in 0..20 {
+            std::fs::write(dir.path().join(format!("image_{:02}.png", i)), [0x89, b'P', b'N', b'G', 0, 0]).unwrap();
+        }
+
+        let config = Config::builder()
+            .path(dir.path().to_string_lossy())
+            .sort(true)
+            .build();
+        let output = FileProcessor::with_defaults(config).assemble().unwrap();
+
+        assert_eq!(output.report.text_files, 300);
+        assert_eq!(output.report.binary_files, 20);
+        assert_eq!(output.report.files.len(), 320);
+
+        // Every file's chunk appears exactly once, in sorted order
+        let mut last_pos = 0;
+        for i in 0..300 {
+            let header = format!("file_{:03}.txt ===\ncontent {}\n", i, i);
+            let pos = output.content.find(&header).unwrap_or_else(|| panic!("missing {}", header));
+            assert_eq!(output.content.matches(&header).count(), 1);
+            assert!(pos > last_pos, "file_{:03}.txt is out of order", i);
+            last_pos = pos;
+        }
+    }
+
+    #[test]
+    fn test_cancelled_run_stops_without_output() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "alpha").unwrap();
+
+        let config = Config::builder().path(dir.path().to_string_lossy()).build();
+        let processor = FileProcessor::with_defaults(config);
+        processor.cancel_token().cancel();
+
+        match processor.assemble() {
+            Err(YoinkError::Cancelled { processed }) => assert_eq!(processed, 0),
+            other => panic!("expected Cancelled, got {:?}", other.map(|o| o.report)),
+        }
+    }
+
+    // Text processor that panics on one particular file
+    struct PanickingTextProcessor {
+        inner: crate::text_processor::TextProcessor,
+    }
+
+    impl TextProcessing for PanickingTextProcessor {
+        fn process_file(&self, path: &Path) -> Result<Option<String>, YoinkError> {
+            if path.ends_with("bad.txt") {
+                panic!("index out of bounds");
+            }
+            self.inner.process_file(path)
+        }
+
+        fn format_text_content(&self, path: &Path, content: &str, buffer: &mut String) -> Result<bool, YoinkError> {
+            self.inner.format_text_content(path, content, buffer)
+        }
+    }
+
+    fn panicking_processor(strict: bool) -> (tempfile::TempDir, FileProcessor) {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a.txt", "bad.txt", "c.txt"] {
+            std::fs::write(dir.path().join(name), name).unwrap();
+        }
+
+        let config = Config::builder()
+            .path(dir.path().to_string_lossy())
+            .sort(true)
+            .strict(strict)
+            .build();
+        let processor = FileProcessor::builder(config.clone())
+            .text_processor(PanickingTextProcessor { inner: crate::text_processor::TextProcessor::new(&config) })
+            .build();
+        (dir, processor)
+    }
+
+    #[test]
+    fn test_panicking_file_is_recorded_and_run_completes() {
+        let (dir, processor) = panicking_processor(false);
+        let output = processor.assemble().unwrap();
+
+        assert_eq!(output.report.text_files, 2);
+        assert_eq!(output.report.skipped.failed, 1);
+        assert!(output.content.contains("Failed files: 1\n"));
+
+        let failed = output.report.files.iter().find(|f| f.path == dir.path().join("bad.txt")).unwrap();
+        match &failed.outcome {
+            crate::report::FileOutcome::Failed(message) => assert!(message.contains("index out of bounds")),
+            other => panic!("expected Failed, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_strict_mode_aborts_on_panic() {
+        let (dir, processor) = panicking_processor(true);
+
+        match processor.assemble() {
+            Err(YoinkError::Panicked { path, message }) => {
+                assert_eq!(path, dir.path().join("bad.txt"));
+                assert_eq!(message, "index out of bounds");
+            }
+            other => panic!("expected Panicked, got {:?}", other.map(|o| o.report)),
+        }
+    }
+
+    #[test]
+    fn test_memory_limit_omits_bodies_without_overshooting() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..40 {
+            std::fs::write(dir.path().join(format!("file_{:02}.txt", i)), "x".repeat(100)).unwrap();
+        }
+
+        let limit = 1000;
+        let config = Config::builder()
+            .path(dir.path().to_string_lossy())
+            .memory_limit(limit)
+            .build();
+        let output = FileProcessor::with_defaults(config).assemble().unwrap();
+        let report = &output.report;
+
+        assert!(report.skipped.omitted > 0);
+        assert_eq!(report.text_files + report.skipped.omitted, 40);
+        assert_eq!(output.content.matches("[omitted: memory limit reached]").count(), report.skipped.omitted);
+        assert!(output.content.contains("Memory limit reached:"));
+
+        // Included bodies never add up to more than the limit
+        let included: usize = output.content.matches(&"x".repeat(100)).count();
+        assert_eq!(included, report.text_files);
+        assert!(included as u64 * 100 <= limit);
+    }
+
+    #[test]
+    fn test_max_total_lists_the_files_left_out() {
+        let fixture = crate::fixture! {
+            "a.txt" => "a".repeat(400),
+            "b.txt" => "b".repeat(400),
+            "c.txt" => "c".repeat(400),
+        };
+        let config = fixture.config().max_total(Some(1000)).build();
+        let output = FileProcessor::with_defaults(config).assemble().unwrap();
+        let report = &output.report;
+
+        assert_eq!(report.text_files, 2);
+        assert_eq!(report.skipped.over_total, 1);
+        let left_out = report.files.iter().find(|f| f.outcome == FileOutcome::OverTotal).unwrap();
+        let name = left_out.path.file_name().unwrap().to_string_lossy();
+        assert!(!output.content.contains(&name[..1].repeat(400)));
+        assert!(output.content.contains(&format!("\n=== OMITTED (size limit) ===\n{}\n", name)));
+        assert!(output.content.contains("Files left out at the output size limit: 1\n"));
+
+        let config = fixture.config().max_total(None).build();
+        let output = FileProcessor::with_defaults(config).assemble().unwrap();
+        assert_eq!(output.report.text_files, 3);
+        assert!(!output.content.contains("=== OMITTED"));
+    }
+
+    #[test]
+    fn test_submodules_are_skipped_or_included() {
+        let fixture = crate::fixture! {
+            "main.rs" => "fn main() {}",
+            "vendor/lib/.git" => "gitdir: ../../.git/modules/lib",
+            "vendor/lib/lib.rs" => "pub fn vendored() {}",
+        };
+
+        let skipped = FileProcessor::with_defaults(fixture.config().build()).assemble().unwrap();
+        assert_eq!(skipped.report.text_files, 1);
+        assert!(skipped.content.contains("    📁 lib/ (submodule, skipped)\n"));
+        assert!(!skipped.content.contains("vendored"));
+
+        let config = fixture.config().submodules(crate::config::SubmoduleMode::Include).build();
+        let included = FileProcessor::with_defaults(config).assemble().unwrap();
+        assert_eq!(included.report.text_files, 2);
+        assert!(included.content.contains("pub fn vendored() {}"));
+        assert!(!included.content.contains("gitdir:"));
+    }
+
+    #[test]
+    fn test_nested_repos_are_skipped_included_or_skimmed() {
+        let fixture = crate::fixture! {
+            "notes.md" => "# Projects",
+            "clones/tool/.git/HEAD" => "ref: refs/heads/main",
+            "clones/tool/README.md" => "# Tool",
+            "clones/tool/Cargo.toml" => "[package]",
+            "clones/tool/src/main.rs" => "fn tool() {}",
+        };
+        let run = |mode| FileProcessor::with_defaults(fixture.config().nested_repos(mode).build()).assemble().unwrap();
+
+        let skipped = run(NestedRepoMode::Skip);
+        assert_eq!(skipped.report.text_files, 1);
+        assert_eq!(skipped.report.skipped_repos, 1);
+        assert!(skipped.content.contains("    📁 tool/ (separate repository)\n"));
+        assert!(!skipped.content.contains("README.md"));
+        assert!(skipped.content.contains("Nested repositories skipped: 1\n"));
+
+        let included = run(NestedRepoMode::Include);
+        assert_eq!(included.report.text_files, 4);
+        assert!(!included.content.contains("ref: refs/heads/main"));
+
+        let shallow = run(NestedRepoMode::Shallow);
+        assert_eq!(shallow.report.text_files, 3);
+        assert_eq!(shallow.report.skipped_repos, 0);
+        assert!(shallow.content.contains("# Tool"));
+        assert!(!shallow.content.contains("fn tool() {}"));
+        assert!(!shallow.content.contains("📁 src/"));
+    }
+
+    #[test]
+    fn test_diff_content_includes_only_changes() {
+        let fixture = crate::fixture! {
+            "changed.txt" => "one\ntwo\n",
+            "same.txt" => "untouched\n",
+        };
+        if !fixture.init_git_repo() {
+            return;
+        }
+        fixture.write("changed.txt", "one\n2\nthree\n");
+        fixture.write("new.txt", "brand new\n");
+
+        let config = fixture.config().sort(true).diff_content("HEAD").build();
+        let output = FileProcessor::with_defaults(config).assemble().unwrap();
+
+        assert!(output.content.contains("-two\n+2\n+three\n"));
+        assert!(output.content.contains("new.txt (new file) ===\nbrand new\n"));
+        assert!(!output.content.contains("untouched"));
+        assert_eq!(output.report.skipped.unchanged, 1);
+
+        let diff = output.report.diff.unwrap();
+        assert_eq!((diff.files, diff.new_files, diff.added, diff.removed), (1, 1, 2, 1));
+        assert!(output.content.contains("Files with changes since HEAD: 1 (+2 -1), new files: 1\n"));
+    }
+
+    #[test]
+    fn test_git_meta_annotates_headers() {
+        let fixture = crate::fixture! { "tracked.txt" => "committed" };
+        let plain = FileProcessor::with_defaults(fixture.config().git_meta(true).build()).assemble().unwrap();
+        assert!(plain.content.contains("=== tracked.txt ===\n"));
+        assert!(plain.report.files[0].git.is_none());
+
+        if !fixture.init_git_repo() {
+            return;
+        }
+        fixture.write("new.txt", "untracked");
+
+        let config = fixture.config().sort(true).git_meta(true).build();
+        let output = FileProcessor::with_defaults(config).assemble().unwrap();
+
+        let tracked = output.report.files.iter().find(|f| f.path.ends_with("tracked.txt")).unwrap();
+        match &tracked.git {
+            Some(crate::git::GitMeta::Commit(commit)) => {
+                assert_eq!(commit.author, "yoink");
+                assert_eq!(commit.subject, "fixture");
+                let header = format!("=== tracked.txt ({} yoink, {}: fixture) ===\n", commit.hash, commit.date);
+                assert!(output.content.contains(&header), "missing {}", header);
+            }
+            other => panic!("expected commit info, got {:?}", other),
+        }
+        assert!(output.content.contains("=== new.txt (untracked) ===\n"));
+    }
+
+    #[test]
+    fn test_stdin_document_gets_header_and_summary() {
+        let config = Config::builder().path("-").stdin_name("build.log").build();
+        let processor = FileProcessor::with_defaults(config);
+
+        let mut out = Vec::new();
+        let (report, _) = processor.write_document(b"warning: unused\nerror: failed\n", &mut out, None).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("=== TEXT FILES ===\n\n=== build.log ===\nwarning: unused\nerror: failed\n"));
+        assert!(out.ends_with(&format!("=== SUMMARY ===\nText files processed: 1\nBinary files skipped: 0\nEstimated tokens: {}\n", report.estimated_tokens)));
+        assert!(!out.contains("DIRECTORY STRUCTURE"));
+        assert_eq!(report.output_bytes, out.len() as u64);
+
+        let config = Config::builder().path("-").search_text("error").build();
+        let mut out = Vec::new();
+        FileProcessor::with_defaults(config).write_document(b"ok\nerror: failed\n", &mut out, None).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("=== MATCH IN: <stdin> ===\n1: ok\n2: error: failed\n"));
+    }
+
+    #[test]
+    fn test_binary_stdin_is_an_error() {
+        let processor = FileProcessor::with_defaults(Config::builder().path("-").build());
+        let result = processor.write_document(b"\x7fELF\0\0", &mut Vec::new(), None);
+        assert!(matches!(result, Err(YoinkError::BinaryInput { .. })));
+    }
+
+    #[test]
+    fn test_manifest_diff_includes_only_new_and_changed_files() {
+        let fixture = crate::fixture! {
+            "same.txt" => "same",
+            "edit.txt" => "before",
+            "gone.txt" => "gone",
+        };
+        let elsewhere = tempfile::tempdir().unwrap();
+        let location = elsewhere.path().join("manifest.json");
+        let saving = fixture.config().sort(true).manifest_save(ManifestLocation::Path(location.clone())).build();
+        let report = FileProcessor::with_defaults(saving).assemble().unwrap().report;
+        assert_eq!(report.manifest_saved.as_deref(), Some(location.as_path()));
+
+        fixture.write("edit.txt", "after");
+        fixture.write("new.txt", "new");
+        std::fs::remove_file(fixture.join("gone.txt")).unwrap();
+
+        let diffing = fixture.config().sort(true).manifest_diff(ManifestLocation::Path(location.clone())).build();
+        let output = FileProcessor::with_defaults(diffing).assemble().unwrap();
+        assert!(output.content.contains("edit.txt ===\nafter"));
+        assert!(output.content.contains("new.txt ===\nnew"));
+        assert!(!output.content.contains("same.txt ==="));
+        assert!(output.content.contains("Files changed since manifest: 1, new files: 1, deleted files: 1\n  deleted: gone.txt\n"));
+
+        let manifest = output.report.manifest.unwrap();
+        assert_eq!((manifest.changed, manifest.new_files), (1, 1));
+        assert_eq!(output.report.skipped.unchanged, 1);
+    }
+
+    #[test]
+    fn test_oversized_files_are_reported_separately() {
+        let fixture = crate::fixture! {
+            "small.txt" => "tiny",
+            "data/dump.sql" => "x".repeat(300),
+            "big.log" => "y".repeat(200),
+        };
+        let config = fixture.config().max_size(100).build();
+        let output = FileProcessor::with_defaults(config).assemble().unwrap();
+
+        assert_eq!(output.report.text_files, 1);
+        assert_eq!(output.report.binary_files, 0);
+        assert_eq!(output.report.skipped.too_large, 2);
+        let largest = output.report.largest_too_large().unwrap();
+        assert!(largest.path.ends_with("data/dump.sql"));
+        assert!(output.content.contains("Files skipped for size: 2 (largest: data/dump.sql, 300 B)\n"));
+    }
+
+    #[test]
+    fn test_truncate_to_fit_token_budget() {
+        let long: String = (1..=200).map(|i| format!("line {:03}\n", i)).collect();
+        let fixture = crate::fixture! {
+            "a.txt" => long.clone(),
+            "b.txt" => "short\n",
+            "c.txt" => long,
+        };
+        let config = fixture.config()
+            .sort(true)
+            .max_tokens(600)
+            .fit(FitStrategy::Truncate)
+            .fit_min_lines(5)
+            .build();
+        let output = FileProcessor::with_defaults(config).assemble().unwrap();
+
+        assert_eq!(output.report.text_files, 3);
+        assert!(output.content.contains("short\n"));
+        assert_eq!(output.content.matches(fit::TRUNCATION_MARKER).count(), 2);
+        let truncated = &output.report.truncated;
+        assert_eq!(truncated.len(), 2);
+        assert!(truncated.iter().all(|t| t.kept >= 5 && t.kept + t.omitted == 200));
+        assert!(output.content.contains(&format!(
+            "  a.txt: kept {} lines, omitted {}\n",
+            truncated[0].kept, truncated[0].omitted
+        )));
+    }
+
+    #[test]
+    fn test_truncate_drops_files_with_no_room_left() {
+        let long: String = (1..=200).map(|i| format!("line {:03}\n", i)).collect();
+        let fixture = crate::testing::Fixture::new((1..=18).map(|i| (format!("file_{:02}.txt", i), long.clone().into_bytes())));
+        let config = fixture.config()
+            .sort(true)
+            .max_tokens(300)
+            .fit(FitStrategy::Truncate)
+            .fit_min_lines(5)
+            .build();
+        let output = FileProcessor::with_defaults(config).assemble().unwrap();
+        let report = &output.report;
+
+        // Headers and markers for all 18 would take more than the budget by themselves
+        assert!(report.skipped.over_budget > 0);
+        assert_eq!(report.truncated.len() + report.skipped.over_budget, 18);
+        assert!(report.truncated.iter().all(|t| t.kept >= 5), "{:?}", report.truncated);
+        assert!(output.content.contains("=== file_01.txt ===\nline 001\n"));
+        assert!(!output.content.contains("=== file_18.txt ==="));
+    }
+
+    #[test]
+    fn test_header_and_footer_formats_survive_truncation() {
+        let long: String = (1..=200).map(|i| format!("line {:03}\n", i)).collect();
+        let fixture = crate::fixture! {
+            "a.txt" => long,
+            "b.rs" => "fn b() {}\n",
+        };
+        let header = HeaderTemplate::parse("// FILE: {path} ({lines} lines)").unwrap();
+        let footer = HeaderTemplate::parse("// END: {name}").unwrap();
+        let config = fixture.config()
+            .sort(true)
+            .header_format(header, Some(footer))
+            .max_tokens(300)
+            .fit(FitStrategy::Truncate)
+            .build();
+        let content = FileProcessor::with_defaults(config).assemble().unwrap().content;
+
+        assert!(content.contains("// FILE: b.rs (1 lines)\nfn b() {}\n// END: b.rs\n\n"));
+        assert!(content.contains("// FILE: a.txt (200 lines)\nline 001\n"));
+        assert!(content.contains(&format!("\n// END: a.txt\n{}\n\n", fit::TRUNCATION_MARKER)));
+        assert!(!content.contains("=== a.txt") && !content.contains("line 200"));
+    }
+
+    #[test]
+    fn test_drop_files_over_token_budget() {
+        let fixture = crate::fixture! {
+            "a.txt" => "a".repeat(300),
+            "b.txt" => "b".repeat(2000),
+            "c.txt" => "c".repeat(300),
+        };
+        let config = fixture.config().sort(true).max_tokens(400).build();
+        let output = FileProcessor::with_defaults(config).assemble().unwrap();
+
+        assert_eq!(output.report.text_files, 2);
+        assert_eq!(output.report.skipped.over_budget, 1);
+        assert!(!output.content.contains("bbb"));
+        assert!(output.content.contains("Files dropped to fit token budget: 1\n"));
+    }
+
+    #[test]
+    fn test_dedup_normalized_keeps_first_copy() {
+        let fixture = crate::fixture! {
+            "a/config.yml" => "name: app\r\nport: 80\r\n",
+            "b/config.yml" => "name: app\nport: 80\n",
+            "c/config.yml" => "name: app\nport: 8080\n",
+        };
+        let config = fixture.config().sort(true).dedup(DedupMode::Normalized).build();
+        let output = FileProcessor::with_defaults(config).assemble().unwrap();
+
+        assert_eq!(output.report.text_files, 2);
+        assert_eq!(output.report.skipped.duplicates, 1);
+        assert!(output.content.contains("name: app\r\nport: 80\r\n"));
+        assert!(output.content.contains("port: 8080"));
+        assert!(output.content.contains("=== b/config.yml === (identical to a/config.yml after normalizing line endings)\n"));
+    }
+
+    #[test]
+    fn test_dedup_reports_the_bytes_saved() {
+        let license = "Permission is hereby granted, free of charge, to any person\n".repeat(20);
+        let fixture = crate::fixture! {
+            "LICENSE" => license.clone(),
+            "crates/core/LICENSE" => license.clone(),
+            "crates/cli/LICENSE" => license.clone(),
+            "crates/cli/LICENSE.old" => license.replace("free", "FREE"),
+        };
+        let config = fixture.config().sort(true).dedup(DedupMode::Exact).build();
+        let output = FileProcessor::with_defaults(config).assemble().unwrap();
+        let report = &output.report;
+
+        assert_eq!((report.text_files, report.skipped.duplicates), (2, 2));
+        assert!(output.content.contains("=== crates/core/LICENSE === (identical to LICENSE)\n"));
+        assert_eq!(output.content.matches("Permission is hereby granted, free").count(), 20);
+        assert_eq!(report.deduped_bytes, 2 * license.len() as u64);
+        let line = format!("Duplicate files collapsed: 2 ({} saved)\n", utils::format_size(report.deduped_bytes));
+        assert!(output.content.contains(&line));
+    }
+
+    #[test]
+    fn test_duplicate_stub_uses_the_header_format() {
+        let fixture = crate::fixture! {
+            "a.rs" => "fn a() {}\nfn b() {}\n",
+            "b.rs" => "fn a() {}\nfn b() {}\n",
+        };
+        let header = HeaderTemplate::parse("// FILE: {path} ({lines} lines)").unwrap();
+        let footer = HeaderTemplate::parse("// END: {name}").unwrap();
+        let config = fixture.config().sort(true).header_format(header, Some(footer)).dedup(DedupMode::Exact).build();
+        let content = FileProcessor::with_defaults(config).assemble().unwrap().content;
+
+        assert!(content.contains("// FILE: a.rs (2 lines)\nfn a() {}\nfn b() {}\n// END: a.rs\n\n"), "{}", content);
+        assert!(content.contains("// FILE: b.rs (2 lines) (identical to a.rs)\n\n"), "{}", content);
+        assert!(!content.contains("=== b.rs"));
+    }
+
+    #[test]
+    fn test_script_with_stray_bytes_is_kept_as_text() {
+        let fixture = crate::fixture! {
+            "deploy" => &b"#!/bin/sh\necho caf\xe9\n"[..],
+        };
+        for config in [fixture.config().build(), fixture.config().max_lines(10).build()] {
+            let output = FileProcessor::with_defaults(config).assemble().unwrap();
+
+            assert_eq!((output.report.text_files, output.report.binary_files), (1, 0));
+            assert!(output.content.contains("=== deploy ===\n#!/bin/sh\necho caf\u{fffd}\n"), "{}", output.content);
+        }
+    }
+
+    #[test]
+    fn test_oversized_files_can_be_summarized() {
+        let log: String = (1..=100).map(|i| format!("event {}\n", i)).collect();
+        let fixture = crate::fixture! {
+            "small.txt" => "tiny",
+            "app.log" => log,
+        };
+        let config = fixture.config().max_size(100).oversize(OversizeMode::Summarize).build();
+        let output = FileProcessor::with_defaults(config).assemble().unwrap();
+
+        assert_eq!(output.report.skipped.summarized, 1);
+        assert_eq!(output.report.skipped.too_large, 0);
+        assert!(output.content.contains("=== app.log === (summarized: "));
+        assert!(output.content.contains("event 20\n[... 60 lines omitted ...]\nevent 81\n"));
+        assert!(output.content.contains("Files summarized for size: 1\n"));
+    }
+
+    #[test]
+    fn test_sample_then_sort() {
+        let fixture = crate::fixture! {
+            "a/1.txt" => "a1", "a/2.txt" => "a2", "a/3.txt" => "a3",
+            "b/1.txt" => "b1", "b/2.txt" => "b2",
+            "top.txt" => "top",
+        };
+        let config = fixture.config().sample(3, 11).sort(true).build();
+        let output = FileProcessor::with_defaults(config.clone()).assemble().unwrap();
+
+        let paths: Vec<_> = output.report.files.iter().map(|f| f.path.clone()).collect();
+        assert_eq!(paths.len(), 3);
+        assert!(paths.windows(2).all(|w| w[0] < w[1]));
+        assert!(output.content.contains("Sampled 3 of 6 files (seed 11, stratified by top-level directory)\n"));
+        
+        let again = FileProcessor::with_defaults(config).assemble().unwrap();
+        assert_eq!(again.content, output.content);
+    }
+
+    #[test]
+    fn test_type_overrides_beat_detection() {
+        let fixture = crate::fixture! {
+            "data/payload.dat" => vec![b'{', 0, b'}'],
+            "notes/dump.txt" => "looks like text",
+            "notes/real.txt" => "is text",
+        };
+        let config = fixture.config()
+            .force_text(glob::Pattern::new("data/*.dat").unwrap())
+            .force_binary(glob::Pattern::new("**/dump.txt").unwrap())
+            .build();
+        let processor = FileProcessor::with_defaults(config);
+        let output = processor.assemble().unwrap();
+
+        assert_eq!((output.report.text_files, output.report.binary_files), (2, 1));
+        assert!(output.content.contains("{\u{0}}"));
+        assert!(!output.content.contains("looks like text"));
+
+        let explained = processor.explain(&fixture.join("notes/dump.txt")).unwrap();
+        assert_eq!(
+            explained[0].rejection(),
+            Some(&SkipReason::ForcedBinary { pattern: "**/dump.txt".into() })
+        );
+    }
+
+    #[test]
+    fn test_file_deleted_after_scan_is_counted_as_vanished() {
+        let fixture = crate::fixture! {
+            "a.txt" => "alpha",
+            "b.txt" => "beta",
+        };
+        let doomed = fixture.join("b.txt");
+        let mut processor = FileProcessor::with_defaults(fixture.config().strict(true).sort(true).build());
+        processor.after_scan = Some(Box::new(move |_| {
+            let doomed = doomed.clone();
+            std::thread::spawn(move || fs::remove_file(doomed).unwrap()).join().unwrap();
+        }));
+        let output = processor.assemble().unwrap();
+
+        assert_eq!(output.report.text_files, 1);
+        assert_eq!(output.report.skipped.vanished, 1);
+        assert_eq!(output.report.skipped.failed, 0);
+        assert!(output.content.contains("Files that vanished during the run: 1\n"));
+    }
+
+    // Appends to each file as it's read, like a writer racing the run
+    struct RacingTextProcessor;
+
+    impl TextProcessing for RacingTextProcessor {
+        fn process_file(&self, path: &Path) -> Result<Option<String>, YoinkError> {
+            let content = fs::read_to_string(path).unwrap();
+            fs::OpenOptions::new().append(true).open(path).unwrap().write_all(b"more").unwrap();
+            Ok(Some(content))
+        }
+
+        fn format_text_content(&self, path: &Path, content: &str, buffer: &mut String) -> Result<bool, YoinkError> {
+            buffer.push_str(&format!("=== {} ===\n{}\n\n", path.display(), content));
+            Ok(true)
+        }
+    }
+
+    #[test]
+    fn test_file_changed_while_reading_is_flagged() {
+        let fixture = crate::fixture! { "live.log" => "line\n" };
+        let output = FileProcessor::builder(fixture.config().build())
+            .text_processor(RacingTextProcessor)
+            .build()
+            .assemble()
+            .unwrap();
+
+        assert_eq!(output.report.text_files, 1);
+        let header = format!("=== {} (file changed while reading) ===\nline\n", fixture.join("live.log").display());
+        assert!(output.content.contains(&header));
+    }
+
+    // Hangs on one particular file, like a read from a dead network mount
+    struct StallingTextProcessor;
+
+    impl TextProcessing for StallingTextProcessor {
+        fn process_file(&self, path: &Path) -> Result<Option<String>, YoinkError> {
+            if path.ends_with("stuck.txt") {
+                std::thread::sleep(std::time::Duration::from_secs(10));
+            }
+            Ok(Some(fs::read_to_string(path).unwrap()))
+        }
+
+        fn format_text_content(&self, path: &Path, content: &str, buffer: &mut String) -> Result<bool, YoinkError> {
+            buffer.push_str(&format!("=== {} ===\n{}\n\n", path.display(), content));
+            Ok(true)
+        }
+    }
+
+    #[test]
+    fn test_stalled_read_is_abandoned_after_timeout() {
+        let fixture = crate::fixture! {
+            "a.txt" => "alpha",
+            "stuck.txt" => "never read",
+        };
+        let config = fixture.config().file_timeout(Some(std::time::Duration::from_millis(100))).build();
+        let started = Instant::now();
+        let output = FileProcessor::builder(config).text_processor(StallingTextProcessor).build().assemble().unwrap();
+
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        assert_eq!(output.report.text_files, 1);
+        assert_eq!(output.report.skipped.timed_out, 1);
+        assert!(!output.content.contains("never read"));
+        assert!(output.content.contains("Files abandoned after the read timeout: 1\n"));
+    }
+
+    // Notes whether each file was read on one of the reader threads that
+    // enforce the file timeout
+    struct ThreadNotingTextProcessor(Arc<AtomicUsize>);
+
+    impl TextProcessing for ThreadNotingTextProcessor {
+        fn process_file(&self, path: &Path) -> Result<Option<String>, YoinkError> {
+            if std::thread::current().name() == Some("yoink-read") {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+            Ok(Some(fs::read_to_string(path).unwrap()))
+        }
+
+        fn format_text_content(&self, path: &Path, content: &str, buffer: &mut String) -> Result<bool, YoinkError> {
+            buffer.push_str(&format!("=== {} ===\n{}\n\n", path.display(), content));
+            Ok(true)
+        }
+    }
+
+    #[test]
+    fn test_local_reads_only_get_a_thread_with_an_explicit_timeout() {
+        let fixture = crate::fixture! {
+            "a.txt" => "alpha",
+            "b.txt" => "beta",
+        };
+        for (timeout, threaded) in [(None, 0), (Some(std::time::Duration::ZERO), 0), (Some(std::time::Duration::from_secs(30)), 2)] {
+            let on_threads = Arc::new(AtomicUsize::new(0));
+            let config = fixture.config().file_timeout(timeout).build();
+            let processor = ThreadNotingTextProcessor(Arc::clone(&on_threads));
+            let output = FileProcessor::builder(config).text_processor(processor).build().assemble().unwrap();
+
+            assert_eq!(output.report.text_files, 2);
+            assert_eq!(on_threads.load(Ordering::Relaxed), threaded, "{:?}", timeout);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fifo_in_tree_never_blocks_the_run() {
+        let fixture = crate::fixture! { "a.txt" => "alpha" };
+        crate::testing::make_fifo(&fixture.join("pipe"));
+        let processor = FileProcessor::with_defaults(fixture.config().build());
+
+        // Opening the FIFO would block forever, so run on a thread with a deadline
+        let (done, finished) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let listed = processor.list_files_detailed(true).unwrap();
+            done.send((processor.assemble().unwrap(), listed)).unwrap();
+        });
+        let (output, listed) = finished.recv_timeout(std::time::Duration::from_secs(10)).expect("run blocked on the FIFO");
+
+        assert_eq!(output.report.text_files, 1);
+        let pipe = listed.iter().find(|f| f.path == "pipe").unwrap();
+        assert_eq!(pipe.skipped.as_deref(), Some("special file (FIFO)"));
+    }
+
+    #[test]
+    fn test_group_by_dir_writes_headers_in_group_order() {
+        let fixture = crate::fixture! {
+            "crates/parser/src/lib.rs" => "parse",
+            "Cargo.toml" => "[workspace]",
+            "crates/lexer/src/lib.rs" => "lex",
+            "crates/lexer/build.rs" => "build",
+        };
+        let output = FileProcessor::with_defaults(fixture.config().group_by_dir(2).build()).assemble().unwrap();
+
+        // Group headers, and file headers, whose names all have an extension
+        let headers: Vec<&str> = output.content
+            .lines()
+            .filter(|l| l.starts_with("=== PACKAGE: ") || (l.starts_with("=== ") && l.contains('.')))
+            .collect();
+        let expected = [
+            "=== PACKAGE: (root) ===",
+            "=== Cargo.toml ===",
+            "=== PACKAGE: crates/lexer ===",
+            "=== crates/lexer/build.rs ===",
+            "=== crates/lexer/src/lib.rs ===",
+            "=== PACKAGE: crates/parser ===",
+            "=== crates/parser/src/lib.rs ===",
+        ];
+        assert_eq!(headers, expected);
+        assert!(output.content.contains("Files by group:\n  (root): 1 files, 11 B\n  crates/lexer: 2 files, 8 B\n"));
+    }
+
+    #[test]
+    fn test_dir_limits_cap_each_directory_and_show_in_listing() {
+        let fixture = crate::fixture! {
+            "snapshots/a.snap" => "a",
+            "snapshots/b.snap" => "bb",
+            "snapshots/c.snap" => "ccc",
+            "src/main.rs" => "fn main() {}",
+        };
+        let processor = FileProcessor::with_defaults(fixture.config().sort(true).dir_limits(Some(2), None).build());
+
+        let output = processor.assemble().unwrap();
+        assert_eq!(output.report.text_files, 3);
+        assert!(output.content.contains("snapshots/ [directory capped: included 2 of 3 files]\n\n=== snapshots/a.snap ===\na\n"));
+        assert!(!output.content.contains("ccc"));
+        assert!(output.content.contains("Directories capped:\n  snapshots: included 2 of 3 files\n"));
+
+        let listed = processor.list_files_detailed(true).unwrap();
+        let capped = listed.iter().find(|f| f.path == "snapshots/c.snap").unwrap();
+        assert_eq!(capped.index, None);
+        assert_eq!(capped.skipped.as_deref(), Some("directory capped: included 2 of 3 files"));
+        assert!(listed.iter().find(|f| f.path == "snapshots/b.snap").unwrap().index.is_some());
+    }
+
+    #[test]
+    fn test_dry_run_counts_match_a_real_run() {
+        let fixture = crate::fixture! {
+            "src/main.rs" => "fn main() {}",
+            "README.md" => "# app",
+            "logo.png" => "\u{89}PNG\r\n\u{1a}\n\0\0",
+            "big.log" => "x".repeat(64),
+            "tests/cli.rs" => "#[test] fn cli() {}",
+        };
+        let processor = FileProcessor::with_defaults(fixture.config().max_size(32).skip_tests(&[]).build());
+
+        let dry = processor.dry_run().unwrap();
+        let paths: Vec<_> = dry.files.iter().filter(|f| f.outcome == FileOutcome::Included).map(|f| f.path.to_str().unwrap()).collect();
+        assert_eq!(paths, ["README.md", "src/main.rs"]);
+
+        let real = processor.assemble().unwrap().report;
+        assert_eq!((dry.text_files, dry.binary_files, dry.skipped), (real.text_files, real.binary_files, real.skipped));
+        assert_eq!((dry.text_files, dry.binary_files, dry.skipped.too_large, dry.skipped.tests), (2, 1, 1, 1));
+    }
+
+    #[test]
+    fn test_detailed_summary_lists_files_biggest_first() {
+        let fixture = crate::fixture! {
+            "a.txt" => "one\ntwo\nthree",
+            "b.txt" => "x",
+        };
+        let plain = FileProcessor::with_defaults(fixture.config().build()).assemble().unwrap();
+        assert!(!plain.content.contains("Lines and characters by file:"));
+
+        let output = FileProcessor::with_defaults(fixture.config().detailed_summary(true).build()).assemble().unwrap();
+        let a_chars = "=== a.txt ===\none\ntwo\nthree\n\n".chars().count();
+        let b_chars = "=== b.txt ===\nx\n\n".chars().count();
+        let digits = (a_chars + b_chars).to_string().len();
+        assert!(output.content.contains("Text files processed: 2\nBinary files skipped: 0\n"));
+        assert!(output.content.ends_with(&format!(
+            "Lines and characters by file:\n  a.txt  5 lines  {:>digits$} chars\n  b.txt  3 lines  {:>digits$} chars\n  Total  8 lines  {:>digits$} chars\n",
+            a_chars, b_chars, a_chars + b_chars, digits = digits
+        )));
+        assert_eq!(output.report.files.iter().map(|f| f.lines).sum::<usize>(), 8);
+    }
+
+    #[test]
+    fn test_headers_are_relative_unless_absolute_paths() {
+        let fixture = crate::fixture! {
+            "src/main.rs" => "fn main() {}",
+        };
+        let relative = FileProcessor::with_defaults(fixture.config().build()).assemble().unwrap();
+        assert!(relative.content.contains("=== src/main.rs ===\nfn main() {}\n"));
+        assert!(!relative.content.contains(&fixture.path().display().to_string()));
+
+        let absolute = FileProcessor::with_defaults(fixture.config().absolute_paths(true).build()).assemble().unwrap();
+        assert!(absolute.content.contains(&format!("=== {} ===\n", fixture.join("src/main.rs").display())));
+
+        let single = FileProcessor::with_defaults(fixture.config().path(fixture.join("src/main.rs").to_string_lossy()).build()).assemble().unwrap();
+        assert!(single.content.contains("=== main.rs ===\nfn main() {}\n"));
+    }
+
+    #[test]
+    fn test_target_overflow_is_reported_without_changing_the_output() {
+        let fixture = crate::fixture! {
+            "big.txt" => "x".repeat(4_000),
+            "small.txt" => "y".repeat(100),
+        };
+        let plain = FileProcessor::with_defaults(fixture.config().build()).assemble().unwrap();
+        let target = crate::target::Target::parse("500").unwrap();
+        let output = FileProcessor::with_defaults(fixture.config().target(target).build()).assemble().unwrap();
+
+        assert_eq!(output.content, plain.content);
+        let over = output.report.over_target.expect("output is over the window");
+        assert_eq!(over.tokens, output.report.estimated_tokens - 500);
+        let largest: Vec<_> = over.largest.iter().map(|file| file.path.to_str().unwrap()).collect();
+        assert_eq!(largest, ["big.txt"]);
+        assert!(plain.report.over_target.is_none());
+    }
+
+    #[test]
+    fn test_symbols_keep_only_the_named_items() {
+        let fixture = crate::fixture! {
+            "src/lib.rs" => "use std::fmt;\n\n/// Adds\npub fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\nfn sub() {}\n",
+            "tools/gen.py" => "def other():\n    pass\n",
+            "README.md" => "# add\n",
+        };
+        let output = FileProcessor::with_defaults(fixture.config().symbol("add").build()).assemble().unwrap();
+
+        assert_eq!(output.report.text_files, 1);
+        assert_eq!(output.report.skipped.no_match, 2);
+        assert!(output.content.contains("=== src/lib.rs (lines 3-6) ===\n/// Adds\npub fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\n"));
+        assert!(!output.content.contains("fn sub"));
+
+        let missing = FileProcessor::with_defaults(fixture.config().symbol("othr").build()).assemble();
+        assert!(matches!(missing, Err(YoinkError::Symbol { message }) if message.contains("did you mean `other`?")));
+        let unsupported = FileProcessor::with_defaults(fixture.config().path(fixture.join("README.md").to_string_lossy()).symbol("add").build()).assemble();
+        assert!(matches!(unsupported, Err(YoinkError::Symbol { .. })));
+    }
+
+    #[test]
+    fn test_skip_tests_drops_test_files_and_rust_test_modules() {
+        let fixture = crate::fixture! {
+            "src/lib.rs" => "pub fn add() {}\n\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn adds() {}\n}\n",
+            "tests/cli.rs" => "#[test] fn cli() {}",
+            "server/handler_test.go" => "package server",
+            "server/handler.go" => "package server",
+        };
+        let output = FileProcessor::with_defaults(fixture.config().skip_tests(&[]).build()).assemble().unwrap();
+
+        assert_eq!(output.report.text_files, 2);
+        assert_eq!(output.report.skipped.tests, 2);
+        assert_eq!(output.report.test_modules_stripped, 1);
+        assert!(output.content.contains("pub fn add() {}\n\n\n"));
+        assert!(!output.content.contains("fn adds"));
+        assert!(output.content.contains("Test files skipped: 2\nTest modules stripped: 1\n"));
+    }
+
+    #[test]
+    fn test_output_dir_mirrors_included_files_and_skips_clipboard() {
+        let fixture = crate::fixture! {
+            "src/main.rs" => "fn main() {}",
+            "README.md" => "# Readme",
+            "logo.png" => b"\x89PNG\0\0",
+        };
+        let out = tempfile::tempdir().unwrap();
+        let config = fixture.config().output_dir(out.path().join("context"), false).build();
+        let mut processor = FileProcessor::builder(config).clipboard(MockClipboardManager::new()).build();
+
+        let report = processor.process().unwrap();
+        let written = report.output_dir.expect("output dir summary");
+        assert_eq!(written.files, 2);
+        assert!(report.clipboard.is_none());
+        assert!(processor.clipboard_as::<MockClipboardManager>().unwrap().get_copied_text().is_none());
+
+        let main = std::fs::read_to_string(written.path.join("src/main.rs.txt")).unwrap();
+        assert_eq!(main, "=== src/main.rs ===\nfn main() {}\n\n");
+        let index: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(written.path.join("index.json")).unwrap()).unwrap();
+        let mut sources: Vec<&str> = index["files"].as_array().unwrap().iter().map(|f| f["source"].as_str().unwrap()).collect();
+        sources.sort();
+        assert_eq!(sources, ["README.md", "src/main.rs"]);
+
+        // A second run needs --force now that the directory has files in it
+        let config = fixture.config().output_dir(&written.path, false).build();
+        assert!(matches!(FileProcessor::with_defaults(config).assemble(), Err(YoinkError::OutputDir { .. })));
+    }
+
+    #[test]
+    fn test_search_writes_match_index_before_matches() {
+        let fixture = crate::fixture! {
+            "a.rs" => "fn main() {}\n    // TODO: error handling\nlet todo = 1;\n",
+            "b.rs" => "nothing here\n",
+            "c.md" => "# Notes\n- todo: docs\n",
+        };
+        let output = FileProcessor::with_defaults(fixture.config().search_text("todo").sort(true).build()).assemble().unwrap();
+
+        let index = "=== MATCH INDEX ===\na.rs: 2 matching lines, first at line 2: // TODO: error handling\nc.md: 1 matching line, first at line 2: - todo: docs\n\n";
+        let at = output.content.find(index).expect("match index is written");
+        assert!(at < output.content.find("=== MATCH IN:").unwrap());
+    }
+}
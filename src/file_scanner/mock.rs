@@ -1,30 +1,32 @@
-use std::path::{Path, PathBuf};
-use walkdir::DirEntry;
 use super::{FileScanning, FileEntry};
+use crate::error::YoinkError;
 
 /// Mock implementation of FileScanning for testing
+///
+/// Returns a fixed list of entries, typically taken from a
+/// `testing::Fixture` since `DirEntry` can't be constructed directly.
+#[derive(Default)]
 pub struct MockFileScanner {
-    files: Vec<PathBuf>,
+    entries: Vec<FileEntry>,
 }
 
 impl MockFileScanner {
     pub fn new() -> Self {
         Self {
-            files: Vec::new(),
+            entries: Vec::new(),
         }
     }
 
-    /// Add a mock file to the scanner
-    pub fn add_file(&mut self, path: PathBuf) {
-        self.files.push(path);
+    /// Create a scanner that returns these entries
+    pub fn with_entries(entries: &[FileEntry]) -> Self {
+        Self {
+            entries: entries.to_vec(),
+        }
     }
 }
 
 impl FileScanning for MockFileScanner {
-    fn collect_files(&self) -> Vec<FileEntry> {
-        // This is a simplified mock implementation that doesn't actually
-        // create real DirEntry objects, since they're hard to construct.
-        // In real tests, you might want to use tempfile to create actual files.
-        vec![]
+    fn collect_files(&self) -> Result<Vec<FileEntry>, YoinkError> {
+        Ok(self.entries.clone())
     }
-} 
\ No newline at end of file
+}
@@ -8,8 +8,48 @@ pub use walkdir::DirEntry as FileEntry;
 #[cfg(test)]
 pub use mock::MockFileScanner;
 
+use std::any::Any;
+use crate::error::YoinkError;
+use crate::explain::Verdict;
+use std::path::Path;
+
 /// Trait defining the file scanning operations interface
-pub trait FileScanning {
+pub trait FileScanning: Any + Send + Sync {
     /// Collect files from the specified path according to filters
-    fn collect_files(&self) -> Vec<FileEntry>;
+    fn collect_files(&self) -> Result<Vec<FileEntry>, YoinkError>;
+
+    /// Like `collect_files`, calling `on_found` with the running number of
+    /// matching files as the walk goes so callers can show progress
+    fn collect_files_with_progress(&self, on_found: &dyn Fn(usize)) -> Result<Vec<FileEntry>, YoinkError> {
+        let files = self.collect_files()?;
+        on_found(files.len());
+        Ok(files)
+    }
+
+    /// Whether a path would pass this scanner's filters, used by watch mode
+    /// to ignore changes that can't affect the output
+    fn matches_filters(&self, _path: &Path) -> bool {
+        true
+    }
+
+    /// Each filter check applied to `path` in order, stopping at the first
+    /// rejection; empty when the scanner doesn't break its decision down
+    fn explain(&self, _path: &Path) -> Vec<Verdict> {
+        Vec::new()
+    }
+
+    /// Nested git repositories the last scan left out
+    fn skipped_repos(&self) -> usize {
+        0
+    }
+
+    /// Test files the last scan left out under `--skip-tests`
+    fn skipped_tests(&self) -> usize {
+        0
+    }
+
+    /// Files the last scan left out as modified outside `--since` and `--until`
+    fn skipped_by_time(&self) -> usize {
+        0
+    }
 } 
\ No newline at end of file
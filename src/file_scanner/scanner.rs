@@ -1,123 +1,596 @@
-use crate::cli::Config;
+use crate::config::{Config, NestedRepoMode, PathRule, SubmoduleMode};
+use crate::depth;
+use crate::explain::{SkipReason, Verdict};
+use crate::generated::GeneratedPatterns;
+use crate::git::{self, Submodules, TrackedFiles};
+use crate::skip_log::SkipLog;
+use crate::test_code::TestPatterns;
+use crate::yoinkignore::IgnoreRules;
 use walkdir::WalkDir;
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use super::{FileScanning, FileEntry};
+use crate::cancel::CancelToken;
+use crate::error::YoinkError;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 pub struct FileScanner {
     config: Config,
+    cancel: CancelToken,
+    // Rules are matched against paths relative to this
+    base: PathBuf,
+    skipped_repos: AtomicUsize,
+    tests: Option<TestPatterns>,
+    generated: Option<GeneratedPatterns>,
+    skipped_tests: AtomicUsize,
+    skipped_by_time: AtomicUsize,
+    ignore: IgnoreRules,
 }
 
 impl FileScanner {
     pub fn new(config: &Config) -> Self {
+        let base = config.base();
         Self {
             config: config.clone(),
+            cancel: CancelToken::new(),
+            skipped_repos: AtomicUsize::new(0),
+            tests: config.skip_tests.then(|| TestPatterns::new(&config.test_patterns)),
+            generated: config.skip_generated.then(|| GeneratedPatterns::new(&config.generated_patterns)),
+            skipped_tests: AtomicUsize::new(0),
+            skipped_by_time: AtomicUsize::new(0),
+            ignore: if config.yoinkignore { IgnoreRules::load(&base) } else { IgnoreRules::default() },
+            base,
         }
     }
 
-    fn should_process_file(&self, entry: &FileEntry) -> bool {
-        if self.config.skip_hidden && entry.file_name().to_string_lossy().starts_with('.') {
-            if self.config.verbose {
-                println!("Skipping hidden file: {}", entry.path().display());
+    /// Stop walking early once `cancel` is triggered
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    // Each check in the order they're applied, so --explain and the real
+    // filtering can't drift apart
+    const CHECKS: [(&'static str, Check); 15] = [
+        ("git metadata", Self::check_git_metadata),
+        ("hidden", Self::check_hidden),
+        ("excluded paths", Self::check_excluded_paths),
+        ("excluded globs", Self::check_excluded_globs),
+        ("rules", Self::check_rule_skip),
+        ("yoinkignore", Self::check_yoinkignore),
+        ("included extensions", Self::check_included_extension),
+        ("excluded extensions", Self::check_excluded_extension),
+        ("pattern", Self::check_pattern),
+        ("exclude pattern", Self::check_exclude_pattern),
+        ("regex", Self::check_regex),
+        ("tests", Self::check_tests),
+        ("generated", Self::check_generated),
+        // Last, since they're the only checks that have to stat the file
+        ("special file", Self::check_special_file),
+        ("modified", Self::check_modified),
+    ];
+
+    fn should_process_file(&self, path: &Path) -> Result<(), SkipReason> {
+        Self::CHECKS.iter().try_for_each(|(_, check)| check(self, path))
+    }
+
+    fn check_git_metadata(&self, path: &Path) -> Result<(), SkipReason> {
+        // A submodule's `.git` is a file pointing into the superproject, never content
+        match path.file_name() {
+            Some(name) if name == ".git" => Err(SkipReason::GitMetadata),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_hidden(&self, path: &Path) -> Result<(), SkipReason> {
+        if self.config.skip_hidden && crate::utils::is_hidden(path) {
+            return Err(SkipReason::Hidden);
+        }
+        Ok(())
+    }
+
+    fn check_excluded_paths(&self, path: &Path) -> Result<(), SkipReason> {
+        let Some(ref exclude_paths) = self.config.exclude_paths else {
+            return Ok(());
+        };
+        let path_str = path.to_string_lossy();
+        
+        // Compare path components to avoid partial matching issues
+        match exclude_paths.iter().find(|excluded| path_str.split('/').any(|component| component == *excluded)) {
+            Some(component) => Err(SkipReason::ExcludedPath { component: component.clone() }),
+            None => Ok(()),
+        }
+    }
+
+    fn check_excluded_globs(&self, path: &Path) -> Result<(), SkipReason> {
+        match self.config.excluding_glob(path.strip_prefix(&self.base).unwrap_or(path), false) {
+            Some(pattern) => Err(SkipReason::ExcludedGlob { pattern: pattern.as_str().to_string() }),
+            None => Ok(()),
+        }
+    }
+
+    fn rule(&self, path: &Path) -> Option<&PathRule> {
+        self.config.rule_for(path.strip_prefix(&self.base).unwrap_or(path))
+    }
+
+    fn check_rule_skip(&self, path: &Path) -> Result<(), SkipReason> {
+        match self.rule(path) {
+            Some(rule) if rule.skip => Err(SkipReason::SkippedByRule { rule: rule.path.clone() }),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_yoinkignore(&self, path: &Path) -> Result<(), SkipReason> {
+        match self.ignore.ignored(path.strip_prefix(&self.base).unwrap_or(path), false) {
+            Some((rule, dir)) => Err(SkipReason::Ignored {
+                rule: rule.text.clone(),
+                source: rule.source.display().to_string(),
+                line: rule.line,
+                dir,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    // The extension lists that apply to `path`, with the rule that supplied them
+    fn extension_filters(&self, path: &Path) -> (&Option<Vec<String>>, &Option<Vec<String>>, Option<String>) {
+        match self.rule(path) {
+            Some(rule) if rule.filters_extensions() => {
+                (&rule.include_extensions, &rule.exclude_extensions, Some(rule.path.clone()))
             }
-            return false;
+            _ => (&self.config.include_extensions, &self.config.exclude_extensions, None),
+        }
+    }
+
+    fn check_included_extension(&self, path: &Path) -> Result<(), SkipReason> {
+        let (Some(include_exts), _, rule) = self.extension_filters(path) else {
+            return Ok(());
+        };
+        let extension = extension(path);
+        if extension.as_ref().is_some_and(|ext| include_exts.contains(ext)) {
+            return Ok(());
         }
+        Err(SkipReason::NotIncluded { extension, rule })
+    }
+
+    fn check_excluded_extension(&self, path: &Path) -> Result<(), SkipReason> {
+        let (_, Some(exclude_exts), rule) = self.extension_filters(path) else {
+            return Ok(());
+        };
+        match extension(path) {
+            Some(extension) if exclude_exts.contains(&extension) => Err(SkipReason::Excluded { extension, rule }),
+            _ => Ok(()),
+        }
+    }
 
-        if let Some(ref exclude_paths) = self.config.exclude_paths {
-            let path_str = entry.path().to_string_lossy();
+    fn check_pattern(&self, path: &Path) -> Result<(), SkipReason> {
+        let Some(ref pattern) = self.config.pattern else {
+            return Ok(());
+        };
+        let filename = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("");
             
-            // Use literal path component comparison
-            if exclude_paths.iter().any(|excluded| {
-                // Compare path components to avoid partial matching issues
-                path_str.split('/').any(|component| component == excluded)
-            }) {
-                if self.config.verbose {
-                    println!("Skipping excluded path: {}", entry.path().display());
-                }
-                return false;
-            }
+        if !pattern.matches(filename) {
+            return Err(SkipReason::PatternMismatch { pattern: pattern.as_str().to_string() });
         }
+        Ok(())
+    }
 
-        let extension = entry.path()
-            .extension()
-            .and_then(|e| e.to_str())
-            .map(|e| e.to_lowercase());
+    fn check_exclude_pattern(&self, path: &Path) -> Result<(), SkipReason> {
+        let filename = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        match self.config.excluding_pattern(&filename) {
+            Some(pattern) => Err(SkipReason::ExcludedPattern { pattern: pattern.as_str().to_string() }),
+            None => Ok(()),
+        }
+    }
 
-        if let Some(ref include_exts) = self.config.include_extensions {
-            if extension
-                .as_ref()
-                .map(|ext| !include_exts.contains(ext))
-                .unwrap_or(true) {
-                    if self.config.verbose {
-                        println!("Skipping non-included extension: {}", entry.path().display());
-                    }
-                    return false;
-                }
+    fn check_regex(&self, path: &Path) -> Result<(), SkipReason> {
+        let Some(ref regex) = self.config.regex else {
+            return Ok(());
+        };
+        let filename = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        if !regex.is_match(&filename) {
+            return Err(SkipReason::RegexMismatch { regex: regex.as_str().to_string() });
         }
+        Ok(())
+    }
 
-        if let Some(ref exclude_exts) = self.config.exclude_extensions {
-            if extension
-                .as_ref()
-                .map(|ext| exclude_exts.contains(ext))
-                .unwrap_or(false) {
-                    if self.config.verbose {
-                        println!("Skipping excluded extension: {}", entry.path().display());
-                    }
-                    return false;
-                }
+    fn check_tests(&self, path: &Path) -> Result<(), SkipReason> {
+        let Some(tests) = &self.tests else {
+            return Ok(());
+        };
+        match tests.find(path.strip_prefix(&self.base).unwrap_or(path)) {
+            Some(pattern) => Err(SkipReason::TestFile { pattern }),
+            None => Ok(()),
         }
+    }
 
-        if let Some(ref pattern) = self.config.pattern {
-            let filename = entry.path()
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("");
-                
-            if !pattern.matches(filename) {
-                if self.config.verbose {
-                    println!("Skipping non-matching pattern: {}", entry.path().display());
-                }
-                return false;
-            }
+    fn check_generated(&self, path: &Path) -> Result<(), SkipReason> {
+        let Some(generated) = &self.generated else {
+            return Ok(());
+        };
+        let filename = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        match generated.find(&filename) {
+            Some((pattern, configured)) => Err(SkipReason::Generated { pattern, configured }),
+            None => Ok(()),
         }
+    }
 
-        true
+    fn check_special_file(&self, path: &Path) -> Result<(), SkipReason> {
+        match crate::utils::special_file_kind(path) {
+            Some(kind) => Err(SkipReason::SpecialFile { kind }),
+            None => Ok(()),
+        }
+    }
+
+    // Only the walk knows which root's listing applies, so this isn't one of
+    // the CHECKS
+    fn check_tracked(&self, tracked: Option<&TrackedFiles>, path: &Path) -> Result<(), SkipReason> {
+        match tracked {
+            Some(tracked) if !tracked.contains(path) => Err(SkipReason::Untracked),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_modified(&self, path: &Path) -> Result<(), SkipReason> {
+        if self.config.since.is_none() && self.config.until.is_none() {
+            return Ok(());
+        }
+        let modified = match std::fs::metadata(path).and_then(|m| m.modified()) {
+            Ok(modified) => modified,
+            Err(e) => {
+                log::warn!("Skipping {}: couldn't read its modification time: {}", path.display(), e);
+                return Err(SkipReason::UnknownModified { message: e.to_string() });
+            }
+        };
+        if let Some(since) = self.config.since.as_ref().filter(|since| modified < since.time()) {
+            return Err(SkipReason::ModifiedBefore { since: since.to_string() });
+        }
+        if let Some(until) = self.config.until.as_ref().filter(|until| modified > until.time()) {
+            return Err(SkipReason::ModifiedAfter { until: until.to_string() });
+        }
+        Ok(())
     }
 }
 
+type Check = fn(&FileScanner, &Path) -> Result<(), SkipReason>;
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+}
+
 impl FileScanning for FileScanner {
-    fn collect_files(&self) -> Vec<FileEntry> {
+    fn collect_files(&self) -> Result<Vec<FileEntry>, YoinkError> {
+        self.collect_files_with_progress(&|_| {})
+    }
+
+    fn collect_files_with_progress(&self, on_found: &dyn Fn(usize)) -> Result<Vec<FileEntry>, YoinkError> {
         // Use PathBuf to properly handle special characters
-        let path = PathBuf::from(&self.config.path);
+        let roots: Vec<PathBuf> = self.config.roots().map(PathBuf::from).collect();
         
-        // Check if path exists before walking
-        if !path.exists() {
-            if self.config.verbose {
-                eprintln!("Path does not exist: {}", path.display());
+        for path in &roots {
+            // Check if path exists before walking
+            if !path.exists() {
+                return Err(YoinkError::path_not_found(path.clone()));
+            }
+            
+            // Named directly, a special file is a mistake rather than something to skip
+            if let Some(kind) = crate::utils::special_file_kind(path) {
+                return Err(YoinkError::SpecialFile { path: path.clone(), kind });
             }
-            return Vec::new();
         }
         
-        WalkDir::new(path)
-            .max_depth(self.config.max_depth as usize)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|entry| {
+        let skips = SkipLog::new(module_path!(), &self.base);
+        let mut files = Vec::new();
+        let mut skipped_repos = 0;
+        let mut skipped_tests = 0;
+        let mut skipped_by_time = 0;
+        // A file reachable from more than one root, or through more than one
+        // followed link, is kept once, from the first
+        let mut seen = (roots.len() > 1 || self.config.follow_links).then(HashSet::new);
+        // The ceiling only needs reporting where it, rather than --depth, stops the walk
+        let ceiling = self.config.depth_ceiling;
+        let mut cut_off = Vec::new();
+        for path in roots {
+            let submodules = (self.config.submodules == SubmoduleMode::Skip).then(|| Submodules::load(&path));
+            // Nested repositories being skimmed, whose subdirectories are pruned
+            let mut shallow_repos: Vec<PathBuf> = Vec::new();
+            // With --git, directories holding nothing git lists aren't walked at all
+            let tracked = match self.config.git_tracked {
+                true => Some(TrackedFiles::load(&path, self.config.git_others)?),
+                false => None,
+            };
+            for entry in WalkDir::new(&path)
+                .max_depth((self.config.max_depth as usize).min(ceiling))
+                .follow_links(self.config.follow_links)
+                .into_iter()
+                .filter_entry(|e| {
+                    if e.depth() == 0 {
+                        return true;
+                    }
+                    if e.path().parent().is_some_and(|parent| shallow_repos.iter().any(|repo| repo == parent)) {
+                        return !e.file_type().is_dir() && git::is_repo_overview(&e.file_name().to_string_lossy());
+                    }
+                    if !e.file_type().is_dir() {
+                        return true;
+                    }
+                    if e.path_is_symlink() {
+                        let target = std::fs::read_link(e.path()).unwrap_or_default();
+                        log::debug!("Following symlinked directory: {} -> {}", e.path().display(), target.display());
+                    }
+                    // Git's own metadata is never content
+                    if e.file_name() == ".git" {
+                        return false;
+                    }
+                    if tracked.as_ref().is_some_and(|tracked| !tracked.holds(e.path())) {
+                        return false;
+                    }
+                    let submodule = submodules.as_ref().is_some_and(|s| s.contains(e.path()));
+                    if submodule {
+                        log::debug!("Skipping submodule: {}", e.path().display());
+                        return false;
+                    }
+                    if self.config.nested_repos == NestedRepoMode::Include || !git::is_nested_repo(e.path()) {
+                        return true;
+                    }
+                    if self.config.nested_repos == NestedRepoMode::Shallow {
+                        log::debug!("Taking only the overview of nested repository: {}", e.path().display());
+                        shallow_repos.push(e.path().to_path_buf());
+                        return true;
+                    }
+                    log::debug!("Skipping nested repository: {}", e.path().display());
+                    skipped_repos += 1;
+                    false
+                })
+            {
+                if self.cancel.is_cancelled() {
+                    break;
+                }
+                
                 match entry {
-                    Ok(e) => {
-                        if !e.file_type().is_dir() && self.should_process_file(&e) {
-                            Some(e)
-                        } else {
-                            None
+                    Ok(e) if e.file_type().is_dir() => {
+                        if ceiling < self.config.max_depth as usize && depth::cut_off(&e, ceiling) {
+                            cut_off.push(e.into_path());
+                        }
+                    }
+                    Ok(e) => match self.check_tracked(tracked.as_ref(), e.path()).and_then(|()| self.should_process_file(e.path())) {
+                        Ok(()) => {
+                            if let Some(seen) = seen.as_mut() {
+                                if !seen.insert(e.path().canonicalize().unwrap_or_else(|_| e.path().to_path_buf())) {
+                                    continue;
+                                }
+                            }
+                            files.push(e);
+                            on_found(files.len());
+                        }
+                        Err(reason) => {
+                            match reason {
+                                SkipReason::TestFile { .. } => skipped_tests += 1,
+                                SkipReason::ModifiedBefore { .. } | SkipReason::ModifiedAfter { .. } => skipped_by_time += 1,
+                                _ => {}
+                            }
+                            skips.record(e.path(), &reason);
                         }
                     },
+                    // The root itself being unreadable means there is nothing to yoink
+                    Err(err) if err.depth() == 0 => {
+                        return Err(YoinkError::Scan { path, source: err });
+                    },
                     Err(err) => {
-                        if self.config.verbose {
-                            eprintln!("Error accessing path: {}", err);
-                        }
-                        None
+                        log::warn!("Error accessing path: {}", err);
                     }
                 }
+            }
+        }
+        
+        skips.finish();
+        depth::warn_cut_off(ceiling, &cut_off);
+        self.skipped_repos.store(skipped_repos, Ordering::Relaxed);
+        self.skipped_tests.store(skipped_tests, Ordering::Relaxed);
+        self.skipped_by_time.store(skipped_by_time, Ordering::Relaxed);
+        Ok(files)
+    }
+
+    fn matches_filters(&self, path: &Path) -> bool {
+        self.should_process_file(path)
+            .inspect_err(|reason| log::debug!("Skipping {}: {}", path.display(), reason))
+            .is_ok()
+    }
+
+    fn skipped_repos(&self) -> usize {
+        self.skipped_repos.load(Ordering::Relaxed)
+    }
+
+    fn skipped_tests(&self) -> usize {
+        self.skipped_tests.load(Ordering::Relaxed)
+    }
+
+    fn skipped_by_time(&self) -> usize {
+        self.skipped_by_time.load(Ordering::Relaxed)
+    }
+
+    fn explain(&self, path: &Path) -> Vec<Verdict> {
+        let mut verdicts = Vec::new();
+        for (check, run) in Self::CHECKS {
+            let result = run(self, path);
+            let rejected = result.is_err();
+            verdicts.push(Verdict { check, result });
+            if rejected {
+                break;
+            }
+        }
+        verdicts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_progress_counts_matching_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a.rs", "b.rs", "c.md", "d.rs"] {
+            std::fs::write(dir.path().join(name), "x").unwrap();
+        }
+
+        let config = Config::builder()
+            .path(dir.path().to_string_lossy())
+            .include_extensions(["rs"])
+            .build();
+        let seen = Mutex::new(Vec::new());
+        let files = FileScanner::new(&config)
+            .collect_files_with_progress(&|found| seen.lock().unwrap().push(found))
+            .unwrap();
+
+        assert_eq!(files.len(), 3);
+        assert_eq!(seen.into_inner().unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_most_specific_rule_decides() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["docs/guide.md", "docs/adr/0001.md", "docs/adr/notes.txt", "docs/generated/api.md", "src/lib.rs"] {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "x").unwrap();
+        }
+
+        let config = Config::builder()
+            .path(dir.path().to_string_lossy())
+            .exclude_extensions(["md"])
+            .rule(PathRule {
+                path: "docs".into(),
+                exclude_extensions: Some(vec!["txt".into()]),
+                ..PathRule::default()
+            })
+            .rule(PathRule {
+                path: "./docs/adr/".into(),
+                include_extensions: Some(vec!["md".into()]),
+                ..PathRule::default()
             })
-            .collect()
+            .rule(PathRule { path: "docs/generated".into(), skip: true, ..PathRule::default() })
+            .build();
+        let mut found: Vec<_> = FileScanner::new(&config)
+            .collect_files()
+            .unwrap()
+            .iter()
+            .map(|e| e.path().strip_prefix(dir.path()).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        found.sort();
+
+        assert_eq!(found, vec!["docs/adr/0001.md", "docs/guide.md", "src/lib.rs"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fifo_is_skipped_and_rejected_as_root() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "x").unwrap();
+        let fifo = crate::testing::make_fifo(&dir.path().join("pipe"));
+
+        let config = Config::builder().path(dir.path().to_string_lossy()).build();
+        let scanner = FileScanner::new(&config);
+        let files = scanner.collect_files().unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            scanner.explain(&fifo).last().unwrap().result,
+            Err(SkipReason::SpecialFile { kind: "FIFO" })
+        );
+
+        let config = Config::builder().path(fifo.to_string_lossy()).build();
+        assert!(matches!(
+            FileScanner::new(&config).collect_files(),
+            Err(YoinkError::SpecialFile { kind: "FIFO", .. })
+        ));
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_explain_stops_at_first_rejection() {
+        let config = Config::builder()
+            .include_extensions(["rs"])
+            .exclude_paths(["target"])
+            .rule(PathRule { path: "docs".into(), include_extensions: Some(vec!["md".into()]), ..PathRule::default() })
+            .build();
+        let scanner = FileScanner::new(&config);
+
+        let verdicts = scanner.explain(Path::new("target/debug/build.rs"));
+        assert_eq!(verdicts.len(), 3);
+        assert_eq!(verdicts[2].check, "excluded paths");
+        assert_eq!(verdicts[2].result, Err(SkipReason::ExcludedPath { component: "target".into() }));
+
+        let verdicts = scanner.explain(Path::new("docs/guide.txt"));
+        let reason = verdicts.last().unwrap().result.clone().unwrap_err();
+        assert_eq!(reason.to_string(), "extension 'txt' is not included (rule 'docs')");
+
+        let verdicts = scanner.explain(Path::new("src/lib.rs"));
+        assert_eq!(verdicts.len(), FileScanner::CHECKS.len());
+        assert!(verdicts.iter().all(|v| v.result.is_ok()));
+    }
+
+    #[test]
+    fn test_explain_names_the_yoinkignore_rule() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".yoinkignore"), "# generated\nvendor/docs/\n").unwrap();
+        let config = Config::builder().path(dir.path().to_string_lossy()).build();
+        let scanner = FileScanner::new(&config);
+
+        let reason = scanner.explain(&dir.path().join("vendor/docs/api/index.md")).pop().unwrap().result.unwrap_err();
+        assert_eq!(reason.to_string(), format!("ignored by 'vendor/docs/' ({}:2)", dir.path().join(".yoinkignore").display()));
+        assert!(scanner.explain(&dir.path().join("vendor/lib.rs")).iter().all(|v| v.result.is_ok()));
+    }
+
+    #[test]
+    fn test_pattern_and_regex_must_both_match() {
+        let config = Config::builder()
+            .pattern(glob::Pattern::new("*_*.rs").unwrap())
+            .regex(crate::name_regex::NameRegex::parse(r"^(handler|service)_").unwrap())
+            .build();
+        let scanner = FileScanner::new(&config);
+
+        assert!(scanner.should_process_file(Path::new("src/handler_auth.rs")).is_ok());
+        assert_eq!(
+            scanner.should_process_file(Path::new("src/handler_auth.py")),
+            Err(SkipReason::PatternMismatch { pattern: "*_*.rs".into() })
+        );
+        let reason = scanner.should_process_file(Path::new("src/model_user.rs")).unwrap_err();
+        assert_eq!(reason.to_string(), "name doesn't match regex '^(handler|service)_'");
+    }
+
+    #[test]
+    fn test_exclude_pattern_wins_over_pattern() {
+        let config = Config::builder()
+            .pattern(glob::Pattern::new("*.go").unwrap())
+            .exclude_pattern(glob::Pattern::new("*_test.go").unwrap())
+            .build();
+        let scanner = FileScanner::new(&config);
+
+        assert!(scanner.should_process_file(Path::new("server/handler.go")).is_ok());
+        let reason = scanner.should_process_file(Path::new("server/handler_test.go")).unwrap_err();
+        assert_eq!(reason.to_string(), "name matches excluded pattern '*_test.go'");
+        let verdicts = scanner.explain(Path::new("server/handler_test.go"));
+        assert_eq!(verdicts.last().unwrap().check, "exclude pattern");
+        assert!(verdicts.iter().any(|v| v.check == "pattern" && v.result.is_ok()));
+    }
+
+    #[test]
+    fn test_generated_files_are_skipped_with_their_pattern() {
+        let scanner = FileScanner::new(&Config::builder().skip_generated(&["*.pb.go"]).build());
+
+        let reason = scanner.should_process_file(Path::new("Cargo.lock")).unwrap_err();
+        assert_eq!(reason.to_string(), "generated file ('Cargo.lock', --no-generated preset)");
+        let reason = scanner.should_process_file(Path::new("web/dist/app.min.js")).unwrap_err();
+        assert_eq!(reason, SkipReason::Generated { pattern: "*.min.js".into(), configured: false });
+        let reason = scanner.should_process_file(Path::new("api/user.pb.go")).unwrap_err();
+        assert_eq!(reason.to_string(), "generated file ('*.pb.go', generated_patterns)");
+        assert!(scanner.should_process_file(Path::new("Cargo.toml")).is_ok());
+
+        let scanner = FileScanner::new(&Config::default());
+        assert!(scanner.should_process_file(Path::new("Cargo.lock")).is_ok());
+    }
+}
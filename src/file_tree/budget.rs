@@ -0,0 +1,175 @@
+//! Shrinking the directory tree to fit `--tree-budget`
+
+use crate::utils;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Most the directory-structure section may take up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum TreeBudget {
+    Bytes(u64),
+    Lines(usize),
+}
+
+impl TreeBudget {
+    /// Parse a size such as "4K" (plain numbers are MB, as elsewhere) or a
+    /// line count such as "300 lines"
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        let lower = input.to_ascii_lowercase();
+        match lower.strip_suffix("lines").or_else(|| lower.strip_suffix("line")) {
+            Some(count) => count.trim().parse().map(Self::Lines).map_err(|_| format!("invalid line count '{}'", input)),
+            None => utils::parse_size(input).map(Self::Bytes),
+        }
+    }
+
+    fn allows(&self, tree: &str) -> bool {
+        match *self {
+            Self::Bytes(bytes) => tree.len() as u64 <= bytes,
+            Self::Lines(lines) => tree.lines().count() <= lines,
+        }
+    }
+}
+
+impl fmt::Display for TreeBudget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Bytes(bytes) => write!(f, "{}", utils::format_size(bytes)),
+            Self::Lines(lines) => write!(f, "{} lines", lines),
+        }
+    }
+}
+
+impl TryFrom<String> for TreeBudget {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        Self::parse(&text)
+    }
+}
+
+impl From<TreeBudget> for String {
+    fn from(budget: TreeBudget) -> Self {
+        match budget {
+            TreeBudget::Bytes(bytes) if bytes % 1024 == 0 => format!("{}K", bytes / 1024),
+            TreeBudget::Bytes(bytes) => format!("{}B", bytes),
+            TreeBudget::Lines(lines) => format!("{} lines", lines),
+        }
+    }
+}
+
+/// One line of the tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+    /// Levels below the root, which is at 0
+    pub depth: usize,
+    pub name: String,
+    pub is_dir: bool,
+    /// Why a directory is shown collapsed, such as a skipped submodule
+    pub note: Option<&'static str>,
+}
+
+impl TreeNode {
+    pub fn line(&self) -> String {
+        let indent = "  ".repeat(self.depth);
+        match (self.is_dir, self.note) {
+            (true, Some(note)) => format!("{}📁 {}/ ({})\n", indent, self.name, note),
+            (true, None) => format!("{}📁 {}/\n", indent, self.name),
+            (false, _) => format!("{}📄 {}\n", indent, self.name),
+        }
+    }
+}
+
+/// The tree as text, cut down until it fits `budget`
+///
+/// Files go first, leaving only directories; then the deepest remaining
+/// level goes, one level at a time, until the tree fits or only the root is
+/// left. Any cut ends with a note saying what's shown. The note itself isn't
+/// counted against the budget.
+pub fn render(nodes: &[TreeNode], budget: Option<TreeBudget>) -> String {
+    let full = lines(nodes.iter());
+    let Some(budget) = budget.filter(|budget| !budget.allows(&full)) else {
+        return full;
+    };
+
+    let dirs: Vec<&TreeNode> = nodes.iter().filter(|node| node.is_dir).collect();
+    let dirs_only = lines(dirs.iter().copied());
+    if budget.allows(&dirs_only) {
+        return format!("{}[tree truncated to fit {} budget: showing directories only]\n", dirs_only, budget);
+    }
+
+    let deepest = dirs.iter().map(|node| node.depth).max().unwrap_or(0);
+    let (depth, tree) = (0..deepest)
+        .rev()
+        .map(|depth| (depth, lines(dirs.iter().copied().filter(|node| node.depth <= depth))))
+        .find(|(depth, tree)| *depth == 0 || budget.allows(tree))
+        .unwrap_or_else(|| (0, lines(dirs.iter().copied().filter(|node| node.depth == 0))));
+    format!("{}[tree truncated to fit {} budget: showing directories to depth {}]\n", tree, budget, depth)
+}
+
+fn lines<'a>(nodes: impl Iterator<Item = &'a TreeNode>) -> String {
+    nodes.map(TreeNode::line).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(depth: usize, name: &str, is_dir: bool) -> TreeNode {
+        TreeNode { depth, name: name.to_string(), is_dir, note: None }
+    }
+
+    // app/ { Cargo.toml, src/ { main.rs, net/ { http.rs } } }
+    fn sample() -> Vec<TreeNode> {
+        vec![
+            node(0, "app", true),
+            node(1, "Cargo.toml", false),
+            node(1, "src", true),
+            node(2, "main.rs", false),
+            node(2, "net", true),
+            node(3, "http.rs", false),
+        ]
+    }
+
+    #[test]
+    fn test_parse_sizes_and_line_counts() {
+        assert_eq!(TreeBudget::parse("4K"), Ok(TreeBudget::Bytes(4096)));
+        assert_eq!(TreeBudget::parse("300 lines"), Ok(TreeBudget::Lines(300)));
+        assert_eq!(TreeBudget::parse("1line"), Ok(TreeBudget::Lines(1)));
+        assert!(TreeBudget::parse("many lines").is_err());
+        assert_eq!(String::from(TreeBudget::Bytes(4096)), "4K");
+    }
+
+    #[test]
+    fn test_tree_within_budget_is_untouched() {
+        let full = lines(sample().iter());
+        assert_eq!(render(&sample(), None), full);
+        assert_eq!(render(&sample(), Some(TreeBudget::Lines(6))), full);
+        assert_eq!(render(&sample(), Some(TreeBudget::Bytes(full.len() as u64))), full);
+    }
+
+    #[test]
+    fn test_files_are_dropped_before_depth() {
+        assert_eq!(
+            render(&sample(), Some(TreeBudget::Lines(3))),
+            "📁 app/\n  📁 src/\n    📁 net/\n[tree truncated to fit 3 lines budget: showing directories only]\n"
+        );
+    }
+
+    #[test]
+    fn test_depth_shrinks_until_the_tree_fits() {
+        assert_eq!(
+            render(&sample(), Some(TreeBudget::Lines(2))),
+            "📁 app/\n  📁 src/\n[tree truncated to fit 2 lines budget: showing directories to depth 1]\n"
+        );
+        assert_eq!(
+            render(&sample(), Some(TreeBudget::Bytes(24))),
+            "📁 app/\n  📁 src/\n[tree truncated to fit 24 B budget: showing directories to depth 1]\n"
+        );
+        assert_eq!(
+            render(&sample(), Some(TreeBudget::Lines(0))),
+            "📁 app/\n[tree truncated to fit 0 lines budget: showing directories to depth 0]\n"
+        );
+    }
+}
@@ -1,21 +1,35 @@
-use crate::cli::Config;
+use crate::config::{Config, NestedRepoMode, SubmoduleMode};
+use crate::depth;
+use crate::git::{self, Submodules, TrackedFiles};
 use walkdir::WalkDir;
-use std::path::PathBuf;
-use super::DirectoryTreeBuilding;
+use crate::yoinkignore::IgnoreRules;
+use std::path::{Path, PathBuf};
+use super::{budget, DirectoryTreeBuilding, TreeNode};
+use crate::error::YoinkError;
+use crate::roots::RootLabels;
 
 pub struct DirectoryTreeBuilder {
     config: Config,
+    // .yoinkignore rules are matched against paths relative to this
+    base: PathBuf,
+    ignore: IgnoreRules,
+    // With several roots, each one's section is named after its label
+    labels: Option<RootLabels>,
 }
 
 impl DirectoryTreeBuilder {
     pub fn new(config: &Config) -> Self {
+        let base = config.base();
         Self {
             config: config.clone(),
+            ignore: if config.yoinkignore { IgnoreRules::load(&base) } else { IgnoreRules::default() },
+            labels: RootLabels::new(config),
+            base,
         }
     }
 
     fn should_include_in_tree(&self, entry: &walkdir::DirEntry) -> bool {
-        if self.config.skip_hidden && entry.file_name().to_string_lossy().starts_with('.') {
+        if self.config.skip_hidden && crate::utils::is_hidden(entry.path()) {
             return false;
         }
 
@@ -29,57 +43,140 @@ impl DirectoryTreeBuilder {
             }
         }
 
+        let relative = entry.path().strip_prefix(&self.base).unwrap_or(entry.path());
+        if entry.depth() > 0 && self.config.excluding_glob(relative, entry.file_type().is_dir()).is_some() {
+            return false;
+        }
+        
+        if !entry.file_type().is_dir() && self.config.excluding_pattern(&entry.file_name().to_string_lossy()).is_some() {
+            return false;
+        }
+
         true
     }
 }
 
 impl DirectoryTreeBuilding for DirectoryTreeBuilder {
-    fn build_directory_tree(&self, buffer: &mut String) -> Result<(), String> {
+    fn build_directory_tree(&self, buffer: &mut String) -> Result<(), YoinkError> {
         // Create a PathBuf to handle special characters properly
-        let base_path = PathBuf::from(&self.config.path);
+        let roots: Vec<PathBuf> = self.config.roots().map(PathBuf::from).collect();
         
         // Check if path exists before processing
-        if !base_path.exists() {
-            return Err(format!("Path does not exist: {}", base_path.display()));
+        if let Some(missing) = roots.iter().find(|root| !root.exists()) {
+            return Err(YoinkError::path_not_found(missing.clone()));
         }
         
-        let entries: Vec<_> = WalkDir::new(&base_path)
-            .into_iter()
-            .filter_map(|e| {
+        let mut entries = Vec::new();
+        for (index, base_path) in roots.iter().enumerate() {
+            // A root inside one given earlier is already in that one's tree
+            if roots[..index].iter().any(|earlier| within(base_path, earlier)) {
+                continue;
+            }
+            
+            let submodules = (self.config.submodules == SubmoduleMode::Skip).then(|| Submodules::load(base_path));
+            
+            // Skipped submodules and nested repositories are kept as a single
+            // collapsed entry, and skimmed repositories show only their overview
+            let tracked = match self.config.git_tracked {
+                true => Some(TrackedFiles::load(base_path, self.config.git_others)?),
+                false => None,
+            };
+            let mut root_entries = Vec::new();
+            let mut shallow_repos: Vec<PathBuf> = Vec::new();
+            let ceiling = self.config.depth_ceiling;
+            let mut walker = WalkDir::new(base_path).max_depth(ceiling).follow_links(self.config.follow_links).into_iter();
+            while let Some(e) = walker.next() {
                 match e {
                     Ok(entry) => {
-                        if self.should_include_in_tree(&entry) {
-                            Some(entry)
-                        } else {
-                            None
+                        if entry.depth() > 0 && entry.file_name() == ".git" {
+                            if entry.file_type().is_dir() {
+                                walker.skip_current_dir();
+                            }
+                            continue;
+                        }
+                        if !self.should_include_in_tree(&entry) {
+                            continue;
+                        }
+                        let is_dir = entry.file_type().is_dir();
+                        // With --git, the tree shows only what git lists and the directories holding it
+                        let listed = |tracked: &TrackedFiles| if is_dir { tracked.holds(entry.path()) } else { tracked.contains(entry.path()) };
+                        if entry.depth() > 0 && tracked.as_ref().is_some_and(|tracked| !listed(tracked)) {
+                            if is_dir {
+                                walker.skip_current_dir();
+                            }
+                            continue;
+                        }
+                        if entry.depth() > 0 && self.ignore.ignored(entry.path().strip_prefix(&self.base).unwrap_or(entry.path()), is_dir).is_some() {
+                            if is_dir {
+                                walker.skip_current_dir();
+                            }
+                            continue;
+                        }
+                        
+                        let in_shallow_repo = entry.path().parent().is_some_and(|parent| shallow_repos.iter().any(|repo| repo == parent));
+                        if in_shallow_repo && (is_dir || !git::is_repo_overview(&entry.file_name().to_string_lossy())) {
+                            if is_dir {
+                                walker.skip_current_dir();
+                            }
+                            continue;
+                        }
+                        
+                        let nested_dir = is_dir && entry.depth() > 0;
+                        let submodule = nested_dir && submodules.as_ref().is_some_and(|s| s.contains(entry.path()));
+                        let repo = nested_dir
+                            && !submodule
+                            && self.config.nested_repos != NestedRepoMode::Include
+                            && git::is_nested_repo(entry.path());
+                        if submodule || (repo && self.config.nested_repos == NestedRepoMode::Skip) {
+                            walker.skip_current_dir();
+                        } else if repo {
+                            shallow_repos.push(entry.path().to_path_buf());
                         }
+                        
+                        let note = match (submodule, repo) {
+                            (true, _) => Some("submodule, skipped"),
+                            (_, true) => Some("separate repository"),
+                            _ if depth::cut_off(&entry, ceiling) => Some("depth ceiling reached"),
+                            _ => None,
+                        };
+                        root_entries.push((entry, note));
                     },
                     Err(err) => {
-                        if self.config.verbose {
-                            eprintln!("Error accessing path: {}", err);
-                        }
-                        None
+                        log::warn!("Error accessing path: {}", err);
                     }
                 }
-            })
-            .collect();
-
-        // Sort entries to get a consistent tree view
-        let mut sorted_entries = entries;
-        sorted_entries.sort_by_key(|e| e.path().to_path_buf());
-
-        for entry in sorted_entries {
-            let depth = entry.depth();
-            let indent = "  ".repeat(depth);
-            let name = entry.file_name().to_string_lossy();
-
-            if entry.file_type().is_dir() {
-                buffer.push_str(&format!("{}📁 {}/\n", indent, name));
-            } else {
-                buffer.push_str(&format!("{}📄 {}\n", indent, name));
             }
+
+            // Sort entries to get a consistent tree view, keeping each root
+            // as its own section in the order it was given
+            root_entries.sort_by(|(a, _), (b, _)| a.path().cmp(b.path()));
+            entries.append(&mut root_entries);
         }
 
+        let nodes: Vec<TreeNode> = entries
+            .into_iter()
+            .map(|(entry, note)| TreeNode {
+                depth: entry.depth(),
+                name: self
+                    .labels
+                    .as_ref()
+                    .filter(|_| entry.depth() == 0)
+                    .and_then(|labels| labels.node_name(entry.path()))
+                    .unwrap_or_else(|| entry.file_name().to_string_lossy().into_owned()),
+                is_dir: entry.file_type().is_dir(),
+                note,
+            })
+            .collect();
+        buffer.push_str(&budget::render(&nodes, self.config.tree_budget));
+
         Ok(())
     }
-} 
\ No newline at end of file
+} 
+
+// Whether `path` is `root` or somewhere below it
+fn within(path: &Path, root: &Path) -> bool {
+    match (path.canonicalize(), root.canonicalize()) {
+        (Ok(path), Ok(root)) => path.starts_with(root),
+        _ => path.starts_with(root),
+    }
+}
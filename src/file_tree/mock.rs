@@ -1,14 +1,20 @@
 use super::DirectoryTreeBuilding;
+use crate::error::YoinkError;
+use std::io;
+use std::path::PathBuf;
 
 /// Mock implementation of DirectoryTreeBuilding for testing
+#[derive(Default)]
 pub struct MockDirectoryTreeBuilder {
     mock_tree: String,
+    failure: Option<io::ErrorKind>,
 }
 
 impl MockDirectoryTreeBuilder {
     pub fn new() -> Self {
         Self {
             mock_tree: String::new(),
+            failure: None,
         }
     }
 
@@ -16,10 +22,18 @@ impl MockDirectoryTreeBuilder {
     pub fn set_mock_tree(&mut self, tree: &str) {
         self.mock_tree = tree.to_string();
     }
+
+    /// Make building the tree fail with an I/O error of this kind
+    pub fn set_failure(&mut self, kind: io::ErrorKind) {
+        self.failure = Some(kind);
+    }
 }
 
 impl DirectoryTreeBuilding for MockDirectoryTreeBuilder {
-    fn build_directory_tree(&self, buffer: &mut String) -> Result<(), String> {
+    fn build_directory_tree(&self, buffer: &mut String) -> Result<(), YoinkError> {
+        if let Some(kind) = self.failure {
+            return Err(YoinkError::Io { path: PathBuf::from("mock"), source: io::Error::from(kind) });
+        }
         // Just append the predefined mock tree structure
         buffer.push_str(&self.mock_tree);
         Ok(())
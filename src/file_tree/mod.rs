@@ -1,14 +1,19 @@
+pub mod budget;
 pub mod builder;
 #[cfg(test)]
 pub mod mock;
 
 // Re-export the implementation
+pub use budget::{TreeBudget, TreeNode};
 pub use builder::DirectoryTreeBuilder;
 #[cfg(test)]
 pub use mock::MockDirectoryTreeBuilder;
 
+use std::any::Any;
+use crate::error::YoinkError;
+
 /// Trait defining the directory tree building operations interface
-pub trait DirectoryTreeBuilding {
+pub trait DirectoryTreeBuilding: Any + Send + Sync {
     /// Build a text representation of the directory tree structure
-    fn build_directory_tree(&self, buffer: &mut String) -> Result<(), String>;
+    fn build_directory_tree(&self, buffer: &mut String) -> Result<(), YoinkError>;
 } 
\ No newline at end of file
@@ -0,0 +1,122 @@
+//! Lists of exact files to yoink, for `--files-from` and `--emit-files-list`
+
+use crate::error::YoinkError;
+use crate::file_scanner::FileEntry;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// First line of an emitted list, so a saved one explains itself
+pub const HEADER: &str = "# yoink files list: one path per line, relative to the scan root; lines starting with # are ignored";
+
+/// The listed files that exist, in list order, and the entries that don't
+#[derive(Debug)]
+pub struct Resolved {
+    pub entries: Vec<FileEntry>,
+    pub missing: Vec<PathBuf>,
+}
+
+/// Read the paths in the list at `path`
+pub fn read(path: &Path) -> Result<Vec<PathBuf>, YoinkError> {
+    let text = fs::read_to_string(path).map_err(|source| YoinkError::Io { path: path.to_path_buf(), source })?;
+    Ok(parse(&text))
+}
+
+/// One path per non-blank line, skipping lines that start with `#`
+pub fn parse(text: &str) -> Vec<PathBuf> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// A list naming `paths`, readable by [`parse`]
+pub fn format(paths: &[PathBuf]) -> String {
+    let mut list = format!("{}\n", HEADER);
+    for path in paths {
+        list.push_str(&path.to_string_lossy().replace('\\', "/"));
+        list.push('\n');
+    }
+    list
+}
+
+/// Look up each listed path under `base`, keeping the first of any repeats
+pub fn resolve(base: &Path, listed: &[PathBuf]) -> Resolved {
+    let mut resolved = Resolved { entries: Vec::new(), missing: Vec::new() };
+    for relative in listed {
+        let path = base.join(relative);
+        if resolved.entries.iter().any(|e: &FileEntry| e.path() == path) {
+            continue;
+        }
+        // A zero-depth walk is the only way to get a `DirEntry` for one path
+        let entry = WalkDir::new(&path).max_depth(0).into_iter().next().and_then(Result::ok);
+        match entry {
+            Some(entry) if entry.file_type().is_file() => resolved.entries.push(entry),
+            _ => resolved.missing.push(relative.clone()),
+        }
+    }
+    resolved
+}
+
+/// A directory tree in the same layout as the full one, showing only `files`
+/// and the directories that lead to them
+pub fn tree(root: &Path, files: &[PathBuf]) -> String {
+    // Each path maps to whether it's a directory
+    let mut nodes: BTreeMap<&Path, bool> = BTreeMap::new();
+    for file in files {
+        nodes.insert(file, false);
+        for dir in file.ancestors().skip(1).filter(|dir| !dir.as_os_str().is_empty()) {
+            nodes.insert(dir, true);
+        }
+    }
+
+    let name = root.file_name().unwrap_or(root.as_os_str()).to_string_lossy();
+    let mut tree = format!("📁 {}/\n", name);
+    for (path, is_dir) in nodes {
+        let indent = "  ".repeat(path.components().count());
+        let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+        if is_dir {
+            tree.push_str(&format!("{}📁 {}/\n", indent, name));
+        } else {
+            tree.push_str(&format!("{}📄 {}\n", indent, name));
+        }
+    }
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_skips_comments_and_blanks_and_round_trips() {
+        let paths = parse("# repro\nsrc/main.rs\n\n  Cargo.toml  \n#src/lib.rs\n");
+        assert_eq!(paths, [PathBuf::from("src/main.rs"), PathBuf::from("Cargo.toml")]);
+        assert_eq!(parse(&format(&paths)), paths);
+    }
+
+    #[test]
+    fn test_resolve_keeps_list_order_and_reports_missing() {
+        let fixture = crate::fixture! {
+            "src/main.rs" => "fn main() {}",
+            "Cargo.toml" => "[package]",
+        };
+        let listed = parse("Cargo.toml\nsrc/gone.rs\nsrc/main.rs\nsrc\nCargo.toml\n");
+        let resolved = resolve(fixture.path(), &listed);
+
+        let found: Vec<&Path> = resolved.entries.iter().map(|e| e.path().strip_prefix(fixture.path()).unwrap()).collect();
+        assert_eq!(found, [Path::new("Cargo.toml"), Path::new("src/main.rs")]);
+        assert_eq!(resolved.missing, [PathBuf::from("src/gone.rs"), PathBuf::from("src")]);
+    }
+
+    #[test]
+    fn test_tree_shows_only_listed_files() {
+        let files = parse("src/net/http.rs\nCargo.toml\nsrc/main.rs\n");
+        assert_eq!(
+            tree(Path::new("/work/app"), &files),
+            "📁 app/\n  📄 Cargo.toml\n  📁 src/\n    📄 main.rs\n    📁 net/\n      📄 http.rs\n"
+        );
+    }
+}
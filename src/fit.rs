@@ -0,0 +1,153 @@
+//! Fitting file contents into a token budget
+//!
+//! The allocation here only sees line costs in bytes, so it knows nothing
+//! about how files are formatted. Files are given in priority order.
+
+use serde::{Deserialize, Serialize};
+
+/// Marker left where lines were cut to fit the budget
+pub const TRUNCATION_MARKER: &str = "[truncated to fit budget]";
+
+/// Characters per token assumed by `--max-tokens` and the token estimates
+pub const CHARS_PER_TOKEN: u64 = 4;
+
+/// Rough token count for `chars` characters of output
+pub fn estimate_tokens(chars: u64) -> u64 {
+    chars.div_ceil(CHARS_PER_TOKEN)
+}
+
+/// How to make the output fit under `--max-tokens`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FitStrategy {
+    /// Leave out whole files that no longer fit
+    #[default]
+    Drop,
+    /// Keep the start of every file and share the rest of the budget out
+    Truncate,
+}
+
+/// Which files fit whole, taking them in priority order and skipping any that don't
+pub fn fit_whole(sizes: &[u64], budget: u64) -> Vec<bool> {
+    let mut remaining = budget;
+    sizes
+        .iter()
+        .map(|&size| {
+            let fits = size <= remaining;
+            if fits {
+                remaining -= size;
+            }
+            fits
+        })
+        .collect()
+}
+
+/// Number of leading lines to keep from each file
+///
+/// `files` holds the byte cost of each line of each file. Every file first
+/// gets up to `floor` lines, then what's left of the budget is shared out in
+/// proportion to how much of each file is still missing. Anything left over
+/// after rounding goes to files in priority order.
+pub fn allocate(files: &[Vec<u64>], budget: u64, floor: usize) -> Vec<usize> {
+    let mut kept = vec![0; files.len()];
+    let mut remaining = budget;
+
+    // Minimum floor
+    for (lines, kept) in files.iter().zip(kept.iter_mut()) {
+        let want = floor.min(lines.len());
+        remaining -= extend(lines, kept, want, remaining);
+    }
+
+    // Proportional top-up
+    let missing: Vec<u64> = files
+        .iter()
+        .zip(&kept)
+        .map(|(lines, &kept)| lines[kept..].iter().sum())
+        .collect();
+    let total_missing: u64 = missing.iter().sum();
+    if total_missing <= remaining {
+        return files.iter().map(Vec::len).collect();
+    }
+    let pool = remaining;
+    for ((lines, kept), &missing) in files.iter().zip(kept.iter_mut()).zip(&missing) {
+        let share = (pool as u128 * missing as u128 / total_missing as u128) as u64;
+        remaining -= extend(lines, kept, lines.len(), share);
+    }
+
+    // Rounding leftovers
+    for (lines, kept) in files.iter().zip(kept.iter_mut()) {
+        remaining -= extend(lines, kept, lines.len(), remaining);
+    }
+
+    kept
+}
+
+// Keep further lines up to `limit` while they fit in `allowance`, returning the bytes used
+fn extend(lines: &[u64], kept: &mut usize, limit: usize, allowance: u64) -> u64 {
+    let mut used = 0;
+    while *kept < limit && used + lines[*kept] <= allowance {
+        used += lines[*kept];
+        *kept += 1;
+    }
+    used
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uniform(lines: usize, cost: u64) -> Vec<u64> {
+        vec![cost; lines]
+    }
+
+    #[test]
+    fn test_everything_fits() {
+        let files = vec![uniform(5, 10), uniform(50, 10)];
+        assert_eq!(allocate(&files, 1000, 20), vec![5, 50]);
+    }
+
+    #[test]
+    fn test_floor_comes_before_top_up() {
+        // 30 lines of floor cost 300, leaving 100 for the top-up
+        let files = vec![uniform(100, 10), uniform(100, 10), uniform(100, 10)];
+        let kept = allocate(&files, 400, 10);
+
+        assert!(kept.iter().all(|&k| k >= 10));
+        assert_eq!(kept.iter().sum::<usize>(), 40);
+    }
+
+    #[test]
+    fn test_top_up_is_proportional_to_what_is_missing() {
+        // After a floor of 2 lines, 8 and 98 lines are missing
+        let files = vec![uniform(10, 1), uniform(100, 1)];
+        let kept = allocate(&files, 57, 2);
+
+        assert_eq!(kept.iter().sum::<usize>(), 57);
+        assert_eq!(kept[0], 2 + 4);
+        assert_eq!(kept[1], 2 + 49);
+    }
+
+    #[test]
+    fn test_leftovers_go_to_earlier_files() {
+        // Shares of 1.5 lines each round down, leaving one line for the first file
+        let files = vec![uniform(10, 1), uniform(10, 1)];
+        assert_eq!(allocate(&files, 3, 0), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_floor_is_cut_short_when_budget_runs_out() {
+        let files = vec![uniform(50, 10), uniform(50, 10), uniform(50, 10)];
+        assert_eq!(allocate(&files, 250, 10), vec![10, 10, 5]);
+    }
+
+    #[test]
+    fn test_long_line_does_not_block_later_files() {
+        let files = vec![vec![1000, 1], uniform(5, 1)];
+        assert_eq!(allocate(&files, 10, 2), vec![0, 5]);
+    }
+
+    #[test]
+    fn test_fit_whole_skips_files_that_do_not_fit() {
+        assert_eq!(fit_whole(&[40, 80, 30, 40], 100), vec![true, false, true, false]);
+    }
+}
@@ -0,0 +1,228 @@
+//! Custom output formats, plugged in with `FileProcessorBuilder::formatter`
+//! or `--format custom:<template>`
+//!
+//! A custom formatter sees the output the plain format would produce, one
+//! piece at a time: the tree, each file with any note attached to it, stubs
+//! standing in for files, and the summary. Group headers, cap notes, and the
+//! match index are written as they are unless `text_section` says otherwise.
+
+use crate::markdown;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Lays out the sections of the output
+pub trait OutputFormatter: Any + Send + Sync {
+    /// What comes before the files, given the directory tree
+    fn tree_section(&self, tree: &str) -> String;
+    /// An included file, with any note on it such as "matches" or its last commit
+    fn file_chunk(&self, path: &Path, note: Option<&str>, content: &str) -> String;
+    /// A file stood in for by a note, such as a duplicate of an earlier one
+    fn skipped_entry(&self, path: &Path, note: &str) -> String;
+    /// What comes after the files, given the summary's lines
+    fn summary(&self, summary: &str) -> String;
+    /// Text written between files, such as a group header or the match
+    /// index, which `name` says
+    fn text_section(&self, _name: &str, text: &str) -> String {
+        text.to_string()
+    }
+}
+
+/// A formatter read from a template file with `[header]`, `[file]`,
+/// `[skipped]`, and `[footer]` sections
+///
+/// Each section runs until the next section line. `{tree}` in the header,
+/// `{path}`, `{note}`, `{language}`, and `{content}` in the file section,
+/// `{path}` and `{note}` in the skipped section, and `{summary}` in the
+/// footer are replaced. Only `[file]` is required; a missing section
+/// leaves that part out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "PathBuf", into = "PathBuf")]
+pub struct FormatTemplate {
+    path: PathBuf,
+    header: String,
+    file: String,
+    skipped: String,
+    footer: String,
+}
+
+impl FormatTemplate {
+    /// Read and parse the template at `path`
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+        Self::parse(path, &text).map_err(|message| format!("{}: {}", path.display(), message))
+    }
+
+    /// Parse template `text`, remembering `path` as where it came from
+    pub fn parse(path: &Path, text: &str) -> Result<Self, String> {
+        let mut template = Self { path: path.to_path_buf(), header: String::new(), file: String::new(), skipped: String::new(), footer: String::new() };
+        let mut section: Option<&mut String> = None;
+        let mut has_file = false;
+        for line in text.split_inclusive('\n') {
+            let name = line.trim_end().strip_prefix('[').and_then(|rest| rest.strip_suffix(']'));
+            if let Some(name) = name {
+                section = Some(match name {
+                    "header" => &mut template.header,
+                    "file" => {
+                        has_file = true;
+                        &mut template.file
+                    }
+                    "skipped" => &mut template.skipped,
+                    "footer" => &mut template.footer,
+                    _ => return Err(format!("unknown section [{}]", name)),
+                });
+                continue;
+            }
+            match &mut section {
+                Some(section) => section.push_str(line),
+                None if line.trim().is_empty() => {}
+                None => return Err("text before the first section".to_string()),
+            }
+        }
+        if !has_file {
+            return Err("no [file] section".to_string());
+        }
+        Ok(template)
+    }
+
+    /// Where the template was read from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl OutputFormatter for FormatTemplate {
+    fn tree_section(&self, tree: &str) -> String {
+        fill(&self.header, &[("tree", tree)])
+    }
+
+    fn file_chunk(&self, path: &Path, note: Option<&str>, content: &str) -> String {
+        let shown = path.display().to_string();
+        let language = markdown::language(path).unwrap_or_default();
+        fill(&self.file, &[("path", &shown), ("note", note.unwrap_or_default()), ("language", language), ("content", content)])
+    }
+
+    fn skipped_entry(&self, path: &Path, note: &str) -> String {
+        fill(&self.skipped, &[("path", &path.display().to_string()), ("note", note)])
+    }
+
+    fn summary(&self, summary: &str) -> String {
+        fill(&self.footer, &[("summary", summary)])
+    }
+}
+
+impl TryFrom<PathBuf> for FormatTemplate {
+    type Error = String;
+
+    fn try_from(path: PathBuf) -> Result<Self, String> {
+        Self::load(&path)
+    }
+}
+
+impl From<FormatTemplate> for PathBuf {
+    fn from(template: FormatTemplate) -> Self {
+        template.path
+    }
+}
+
+// Replace each `{name}` in one pass, so values are never themselves filled in
+pub(crate) fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        filled.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let value = rest.find('}').and_then(|close| {
+            let name = &rest[1..close];
+            values.iter().find(|(key, _)| *key == name).map(|(_, value)| (close, *value))
+        });
+        match value {
+            Some((close, value)) => {
+                filled.push_str(value);
+                rest = &rest[close + 1..];
+            }
+            None => {
+                filled.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// Pass a chunk built in the plain format for `path` to `formatter`
+///
+/// Notes in the header, such as "(new file)" or a duplicate's
+/// "(identical to ...)", are handed over as the note. A chunk with nothing
+/// under its header is a skipped entry.
+pub(crate) fn restyle(formatter: &dyn OutputFormatter, path: &Path, chunk: &str) -> String {
+    let (first, body) = chunk.split_once('\n').unwrap_or((chunk, ""));
+    let heading = first.strip_prefix("=== ").unwrap_or(first);
+    let (heading, matches) = match heading.strip_prefix("MATCH IN: ") {
+        Some(heading) => (heading, true),
+        None => (heading, false),
+    };
+    let shown = path.display().to_string();
+    let rest = heading.strip_prefix(shown.as_str()).unwrap_or(heading);
+    let mut notes: Vec<&str> = rest
+        .split("===")
+        .flat_map(|part| part.split(") ("))
+        .map(|note| note.trim().trim_start_matches('(').trim_end_matches(')'))
+        .filter(|note| !note.is_empty())
+        .collect();
+    if matches {
+        notes.insert(0, "matches");
+    }
+    let note = notes.join("; ");
+    let content = body.strip_suffix("\n\n").or_else(|| body.strip_suffix('\n')).unwrap_or(body);
+    if content.is_empty() {
+        formatter.skipped_entry(path, &note)
+    } else {
+        formatter.file_chunk(path, (!note.is_empty()).then_some(note.as_str()), content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template() -> FormatTemplate {
+        let text = "[header]\n<context>\n{tree}\n[file]\n<file path=\"{path}\" lang=\"{language}\" note=\"{note}\">\n{content}\n</file>\n[skipped]\n<skipped path=\"{path}\">{note}</skipped>\n[footer]\n{summary}</context>\n";
+        FormatTemplate::parse(Path::new("prompt.tmpl"), text).unwrap()
+    }
+
+    #[test]
+    fn test_template_sections_are_filled() {
+        let template = template();
+        assert_eq!(template.tree_section("📁 app/\n"), "<context>\n📁 app/\n\n");
+        assert_eq!(
+            template.file_chunk(Path::new("src/a.rs"), None, "let s = \"{path}\";"),
+            "<file path=\"src/a.rs\" lang=\"rust\" note=\"\">\nlet s = \"{path}\";\n</file>\n"
+        );
+        assert_eq!(template.summary("Text files processed: 1\n"), "Text files processed: 1\n</context>\n");
+    }
+
+    #[test]
+    fn test_template_errors() {
+        assert!(FormatTemplate::parse(Path::new("t"), "[header]\n{tree}\n").unwrap_err().contains("[file]"));
+        assert!(FormatTemplate::parse(Path::new("t"), "[file]\n{content}\n[body]\n").unwrap_err().contains("[body]"));
+        assert!(FormatTemplate::parse(Path::new("t"), "intro\n[file]\n{content}\n").is_err());
+    }
+
+    #[test]
+    fn test_plain_chunks_are_restyled() {
+        let template = template();
+        let path = Path::new("src/b.rs");
+        assert_eq!(
+            restyle(&template, path, "=== src/b.rs (abc1234, 2024-01-01) ===\nfn b() {}\n\n"),
+            "<file path=\"src/b.rs\" lang=\"rust\" note=\"abc1234, 2024-01-01\">\nfn b() {}\n</file>\n"
+        );
+        assert_eq!(
+            restyle(&template, path, "=== MATCH IN: src/b.rs ===\n1: fn b() {}\n\n"),
+            "<file path=\"src/b.rs\" lang=\"rust\" note=\"matches\">\n1: fn b() {}\n</file>\n"
+        );
+        assert_eq!(restyle(&template, path, "=== src/b.rs === (identical to src/a.rs)\n\n"), "<skipped path=\"src/b.rs\">identical to src/a.rs</skipped>\n");
+    }
+}
@@ -0,0 +1,65 @@
+//! Recognizing lockfiles and build output that `--no-generated` leaves out
+
+/// Generated files by ecosystem, as globs matched against the file name
+pub const GENERATED_PATTERNS: [(&str, &[&str]); 7] = [
+    ("Rust", &["Cargo.lock"]),
+    ("JavaScript", &["package-lock.json", "npm-shrinkwrap.json", "yarn.lock", "pnpm-lock.yaml", "bun.lockb", "*.min.js", "*.min.css"]),
+    ("Source maps", &["*.map"]),
+    ("Python", &["poetry.lock", "Pipfile.lock", "pdm.lock", "uv.lock"]),
+    ("Ruby", &["Gemfile.lock"]),
+    ("PHP", &["composer.lock"]),
+    ("Go", &["go.sum"]),
+];
+
+/// The built-in generated-file patterns plus any from the config
+#[derive(Debug, Clone)]
+pub struct GeneratedPatterns {
+    builtin: Vec<glob::Pattern>,
+    extra: Vec<glob::Pattern>,
+}
+
+impl GeneratedPatterns {
+    /// Add `extra` name globs to the built-in ones
+    pub fn new(extra: &[String]) -> Self {
+        let builtin = GENERATED_PATTERNS
+            .iter()
+            .flat_map(|(_, patterns)| patterns.iter())
+            .map(|p| glob::Pattern::new(p).expect("built-in generated patterns are valid"))
+            .collect();
+        let extra = extra
+            .iter()
+            .filter_map(|p| {
+                glob::Pattern::new(p)
+                    .inspect_err(|e| log::warn!("Ignoring invalid generated pattern '{}': {}", p, e))
+                    .ok()
+            })
+            .collect();
+        Self { builtin, extra }
+    }
+
+    /// The pattern that marks a file called `name` as generated, and whether
+    /// it came from the config rather than the built-in list
+    pub fn find(&self, name: &str) -> Option<(String, bool)> {
+        if let Some(pattern) = self.builtin.iter().find(|p| p.matches(name)) {
+            return Some((pattern.as_str().to_string(), false));
+        }
+        self.extra.iter().find(|p| p.matches(name)).map(|p| (p.as_str().to_string(), true))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lockfiles_and_build_output_are_generated() {
+        let patterns = GeneratedPatterns::new(&["*.pb.go".to_string(), "[".to_string()]);
+
+        assert_eq!(patterns.find("Cargo.lock"), Some(("Cargo.lock".to_string(), false)));
+        assert_eq!(patterns.find("vendor.min.js"), Some(("*.min.js".to_string(), false)));
+        assert_eq!(patterns.find("app.js.map"), Some(("*.map".to_string(), false)));
+        assert_eq!(patterns.find("api.pb.go"), Some(("*.pb.go".to_string(), true)));
+        assert_eq!(patterns.find("Cargo.toml"), None);
+        assert_eq!(patterns.find("main.js"), None);
+    }
+}
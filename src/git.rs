@@ -0,0 +1,401 @@
+use crate::error::YoinkError;
+use std::collections::{HashMap, HashSet};
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Submodule checkouts under a root directory
+pub struct Submodules {
+    root: PathBuf,
+    /// Paths declared in the root's `.gitmodules`, relative to the root
+    declared: HashSet<PathBuf>,
+}
+
+impl Submodules {
+    /// Read `.gitmodules` under `root`, if there is one
+    pub fn load(root: &Path) -> Self {
+        let declared = fs::read_to_string(root.join(".gitmodules"))
+            .map(|contents| parse_gitmodules(&contents))
+            .unwrap_or_default();
+
+        Self { root: root.to_path_buf(), declared }
+    }
+
+    /// Whether `dir` is the root of a submodule, either because `.gitmodules`
+    /// declares it or because its `.git` is a file pointing into the
+    /// superproject. The scan root itself never counts.
+    pub fn contains(&self, dir: &Path) -> bool {
+        if dir == self.root {
+            return false;
+        }
+
+        let declared = dir
+            .strip_prefix(&self.root)
+            .is_ok_and(|relative| self.declared.contains(relative));
+        declared || dir.join(".git").is_file()
+    }
+}
+
+/// Whether `dir` is the root of a separate repository nested under the scan
+/// root, with a `.git` directory of its own rather than a submodule's `.git` file
+pub fn is_nested_repo(dir: &Path) -> bool {
+    dir.join(".git").is_dir()
+}
+
+/// Whether a file at the top of a nested repository is kept by
+/// `--nested-repos shallow`: its README or a package manifest
+pub fn is_repo_overview(name: &str) -> bool {
+    const MANIFESTS: [&str; 9] = [
+        "Cargo.toml",
+        "package.json",
+        "pyproject.toml",
+        "setup.py",
+        "go.mod",
+        "pom.xml",
+        "build.gradle",
+        "Gemfile",
+        "composer.json",
+    ];
+    name.to_ascii_lowercase().starts_with("readme") || MANIFESTS.contains(&name)
+}
+
+// Pull the `path = ...` values out of a .gitmodules file
+fn parse_gitmodules(contents: &str) -> HashSet<PathBuf> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            (key.trim() == "path").then(|| PathBuf::from(value.trim()))
+        })
+        .collect()
+}
+
+/// Run `git` in `dir` and return its stdout
+pub(crate) fn run_git(dir: &Path, args: &[&str]) -> Result<String, YoinkError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => YoinkError::GitNotFound,
+            _ => YoinkError::Git { message: e.to_string() },
+        })?;
+
+    if !output.status.success() {
+        return Err(YoinkError::Git {
+            message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The files git tracks under a root, and the directories holding them, for `--git`
+pub struct TrackedFiles {
+    files: HashSet<PathBuf>,
+    dirs: HashSet<PathBuf>,
+}
+
+impl TrackedFiles {
+    /// List what `git ls-files` reports under `root`, adding untracked files
+    /// that aren't ignored when `others` is set
+    pub fn load(root: &Path, others: bool) -> Result<Self, YoinkError> {
+        // A single file is listed from its parent, as with diffs
+        let (dir, pathspec) = match (root.is_file(), root.parent(), root.file_name()) {
+            (true, Some(parent), Some(name)) => (parent, name.to_string_lossy().into_owned()),
+            _ => (root, ".".to_string()),
+        };
+        let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+
+        match run_git(dir, &["rev-parse", "--is-inside-work-tree"]) {
+            Ok(inside) if inside.trim() == "true" => {}
+            Err(YoinkError::GitNotFound) => return Err(YoinkError::GitNotFound),
+            _ => return Err(YoinkError::NotARepository { path: root.to_path_buf() }),
+        }
+
+        let mut args = vec!["ls-files", "-z", "--cached"];
+        if others {
+            args.extend(["--others", "--exclude-standard"]);
+        }
+        args.extend(["--", &pathspec]);
+        let listed = run_git(dir, &args)?;
+
+        let files: HashSet<PathBuf> = listed.split('\0').filter(|name| !name.is_empty()).map(|name| dir.join(name)).collect();
+        let mut dirs = HashSet::new();
+        for file in &files {
+            for parent in file.ancestors().skip(1) {
+                if !dirs.insert(parent.to_path_buf()) || parent == dir {
+                    break;
+                }
+            }
+        }
+        Ok(Self { files, dirs })
+    }
+
+    /// Whether the file at `path`, as walked from the root, is listed
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.contains(path)
+    }
+
+    /// Whether the directory at `path` holds a listed file somewhere below it
+    pub fn holds(&self, dir: &Path) -> bool {
+        self.dirs.contains(dir)
+    }
+}
+
+/// One file's unified diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilePatch {
+    pub text: String,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// How a file compares to the revision being diffed against
+pub enum FileChange<'a> {
+    Modified(&'a FilePatch),
+    /// Not tracked by git, so there is nothing to diff against
+    Untracked,
+    Unchanged,
+}
+
+/// Every change under a root relative to a revision, loaded with a single
+/// `git diff` rather than one subprocess per file
+pub struct DiffSet {
+    pub rev: String,
+    patches: HashMap<PathBuf, FilePatch>,
+    untracked: HashSet<PathBuf>,
+}
+
+impl DiffSet {
+    pub fn load(root: &Path, rev: &str) -> Result<Self, YoinkError> {
+        // git needs a directory to run in; a single file is diffed from its parent
+        let (dir, pathspec) = match (root.is_file(), root.parent(), root.file_name()) {
+            (true, Some(parent), Some(name)) => (parent, name.to_string_lossy().into_owned()),
+            _ => (root, ".".to_string()),
+        };
+        let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+
+        let diff = run_git(dir, &["diff", "--no-color", "--no-renames", "--no-prefix", "--relative", rev, "--", &pathspec])?;
+        let patches = parse_diff(&diff)
+            .into_iter()
+            .map(|(relative, patch)| (dir.join(relative), patch))
+            .collect();
+
+        let untracked = run_git(dir, &["ls-files", "--others", "--exclude-standard", "--", &pathspec])?
+            .lines()
+            .map(|relative| dir.join(relative))
+            .collect();
+
+        Ok(Self { rev: rev.to_string(), patches, untracked })
+    }
+
+    pub fn change(&self, path: &Path) -> FileChange<'_> {
+        if let Some(patch) = self.patches.get(path) {
+            FileChange::Modified(patch)
+        } else if self.untracked.contains(path) {
+            FileChange::Untracked
+        } else {
+            FileChange::Unchanged
+        }
+    }
+}
+
+// Split `git diff --no-prefix` output into per-file patches keyed by path
+fn parse_diff(diff: &str) -> Vec<(PathBuf, FilePatch)> {
+    let mut patches = Vec::new();
+    let mut current: Option<(Option<PathBuf>, FilePatch)> = None;
+    let mut in_hunk = false;
+
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") {
+            if let Some((Some(path), patch)) = current.take() {
+                patches.push((path, patch));
+            }
+            current = Some((None, FilePatch { text: String::new(), added: 0, removed: 0 }));
+        }
+
+        let Some((path, patch)) = current.as_mut() else {
+            continue;
+        };
+        if line.starts_with("diff --git ") {
+            in_hunk = false;
+        } else if line.starts_with("@@") {
+            in_hunk = true;
+        } else if in_hunk {
+            if line.starts_with('+') {
+                patch.added += 1;
+            } else if line.starts_with('-') {
+                patch.removed += 1;
+            }
+        } else if let Some(new_path) = line.strip_prefix("+++ ") {
+            // Deleted files have no path on disk to attach the patch to
+            if new_path != "/dev/null" {
+                *path = Some(PathBuf::from(new_path));
+            }
+        }
+        patch.text.push_str(line);
+        patch.text.push('\n');
+    }
+
+    if let Some((Some(path), patch)) = current {
+        patches.push((path, patch));
+    }
+    patches
+}
+
+/// Last commit to touch a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommitInfo {
+    pub hash: String,
+    pub author: String,
+    /// Relative date as git renders it, e.g. "3 days ago"
+    pub date: String,
+    pub subject: String,
+}
+
+/// What git knows about a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GitMeta {
+    Commit(CommitInfo),
+    Untracked,
+}
+
+impl fmt::Display for GitMeta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GitMeta::Commit(c) => write!(f, "{} {}, {}: {}", c.hash, c.author, c.date, c.subject),
+            GitMeta::Untracked => write!(f, "untracked"),
+        }
+    }
+}
+
+/// Last-commit info for every tracked file under a root
+pub struct GitLog {
+    commits: HashMap<PathBuf, CommitInfo>,
+}
+
+impl GitLog {
+    /// Gather commit info with one `git log --name-only` pass, newest first,
+    /// stopping as soon as every tracked file has been seen
+    ///
+    /// Returns `None` outside a git work tree or when git isn't installed.
+    pub fn load(root: &Path) -> Option<Self> {
+        let dir = if root.is_file() { root.parent()? } else { root };
+        let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+
+        let tracked: HashSet<String> = run_git(dir, &["-c", "core.quotePath=false", "ls-files"])
+            .ok()?
+            .lines()
+            .map(str::to_string)
+            .collect();
+
+        let mut child = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "core.quotePath=false", "log", "--relative", "--name-only"])
+            .arg("--format=%x1e%h%x1f%an%x1f%ar%x1f%s")
+            .arg("--")
+            .arg(".")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        let stdout = child.stdout.take()?;
+
+        let mut commits = HashMap::new();
+        let mut current: Option<CommitInfo> = None;
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else { break };
+            if let Some(header) = line.strip_prefix(COMMIT_MARKER) {
+                let mut fields = header.splitn(4, '\x1f').map(str::to_string);
+                current = Some(CommitInfo {
+                    hash: fields.next().unwrap_or_default(),
+                    author: fields.next().unwrap_or_default(),
+                    date: fields.next().unwrap_or_default(),
+                    subject: fields.next().unwrap_or_default(),
+                });
+            } else if let Some(commit) = current.as_ref().filter(|_| tracked.contains(&line)) {
+                commits.entry(dir.join(&line)).or_insert_with(|| commit.clone());
+                if commits.len() == tracked.len() {
+                    break;
+                }
+            }
+        }
+        let _ = child.kill();
+        let _ = child.wait();
+
+        Some(Self { commits })
+    }
+
+    pub fn meta(&self, path: &Path) -> GitMeta {
+        match self.commits.get(path) {
+            Some(commit) => GitMeta::Commit(commit.clone()),
+            None => GitMeta::Untracked,
+        }
+    }
+}
+
+// Starts each commit header line in `git log` output (`%x1e` in the format);
+// file names can't contain it
+const COMMIT_MARKER: &str = "\x1e";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_declared_and_checked_out_submodules() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join(".gitmodules"),
+            "[submodule \"lib\"]\n\tpath = vendor/lib\n\turl = https://example.com/lib.git\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("vendor/lib")).unwrap();
+        fs::create_dir_all(root.join("third_party/tool")).unwrap();
+        fs::write(root.join("third_party/tool/.git"), "gitdir: ../../.git/modules/tool\n").unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+
+        let submodules = Submodules::load(root);
+        assert!(submodules.contains(&root.join("vendor/lib")));
+        assert!(submodules.contains(&root.join("third_party/tool")));
+        assert!(!submodules.contains(&root.join("src")));
+        assert!(!submodules.contains(root));
+    }
+
+    #[test]
+    fn test_parse_diff_splits_files_and_counts_lines() {
+        let diff = "\
+diff --git src/a.rs src/a.rs
+index 1111111..2222222 100644
+--- src/a.rs
++++ src/a.rs
+@@ -1,2 +1,2 @@
+-old line
+--- not a header
++new line
++another
+ context
+diff --git gone.rs gone.rs
+deleted file mode 100644
+--- gone.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+";
+        let patches = parse_diff(diff);
+        assert_eq!(patches.len(), 1);
+        let (path, patch) = &patches[0];
+        assert_eq!(path, &PathBuf::from("src/a.rs"));
+        assert_eq!((patch.added, patch.removed), (2, 2));
+        assert!(patch.text.starts_with("diff --git src/a.rs src/a.rs\n"));
+    }
+}
@@ -0,0 +1,65 @@
+//! Grouping output files under the directories they belong to
+
+use serde::Serialize;
+use std::path::Path;
+
+/// The group for files directly under the root, which always comes first
+pub const ROOT_GROUP: &str = "(root)";
+
+/// How many files and bytes one group contributed to the output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GroupSummary {
+    pub name: String,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// The group of a file: the first `depth` directories of its relative path
+///
+/// Files in shallower directories are grouped by the directories they have.
+pub fn key(relative: &Path, depth: usize) -> String {
+    let dirs: Vec<_> = relative
+        .parent()
+        .into_iter()
+        .flat_map(Path::iter)
+        .take(depth.max(1))
+        .map(|component| component.to_string_lossy())
+        .collect();
+    if dirs.is_empty() {
+        ROOT_GROUP.to_string()
+    } else {
+        dirs.join("/")
+    }
+}
+
+/// Sort key that puts the root group first, then groups and files by name
+pub fn order(relative: &Path, depth: usize) -> (bool, String) {
+    let key = key(relative, depth);
+    (key != ROOT_GROUP, key)
+}
+
+/// The header written before a group's first file
+pub fn header(name: &str) -> String {
+    format!("=== PACKAGE: {} ===\n\n", name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_keys_follow_depth() {
+        assert_eq!(key(Path::new("Cargo.toml"), 1), ROOT_GROUP);
+        assert_eq!(key(Path::new("crates/parser/src/lib.rs"), 1), "crates");
+        assert_eq!(key(Path::new("crates/parser/src/lib.rs"), 2), "crates/parser");
+        assert_eq!(key(Path::new("crates/README.md"), 2), "crates");
+    }
+
+    #[test]
+    fn test_root_group_leads() {
+        let mut paths: Vec<PathBuf> = ["b/x.rs", "README.md", "a/z.rs", "a/y.rs"].map(PathBuf::from).into();
+        paths.sort_by_cached_key(|p| (order(p, 1), p.clone()));
+        assert_eq!(paths, ["README.md", "a/y.rs", "a/z.rs", "b/x.rs"].map(PathBuf::from));
+    }
+}
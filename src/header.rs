@@ -0,0 +1,101 @@
+//! File headers and footers for the plain format, from `--header-format` and
+//! `--footer-format`
+//!
+//! A template is checked when it's read, so a misspelled placeholder stops
+//! the run before any file is scanned rather than turning up in the output.
+
+use crate::formatter::fill;
+use crate::utils::format_size;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const PLACEHOLDERS: [&str; 5] = ["path", "name", "size", "lines", "ext"];
+
+/// One line written above or below each file, with `{path}`, `{name}`,
+/// `{size}`, `{lines}`, and `{ext}` replaced
+///
+/// Braces around anything but a name, as in `{ }`, are kept as they are.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct HeaderTemplate(String);
+
+impl HeaderTemplate {
+    /// Check `text` for a single line and known placeholders
+    pub fn parse(text: &str) -> Result<Self, String> {
+        if text.contains('\n') {
+            return Err("must be a single line".to_string());
+        }
+        for name in placeholders(text) {
+            if !PLACEHOLDERS.contains(&name) {
+                return Err(format!("unknown placeholder {{{}}}; expected {{path}}, {{name}}, {{size}}, {{lines}}, or {{ext}}", name));
+            }
+        }
+        Ok(Self(text.to_string()))
+    }
+
+    /// The line for the file at `path`, shown as `shown`, whose content as
+    /// written is `content`
+    pub fn render(&self, path: &Path, shown: &Path, content: &str) -> String {
+        let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        let ext = path.extension().map(|ext| ext.to_string_lossy()).unwrap_or_default();
+        fill(
+            &self.0,
+            &[
+                ("path", &shown.display().to_string()),
+                ("name", &name),
+                ("size", &format_size(content.len() as u64)),
+                ("lines", &content.lines().count().to_string()),
+                ("ext", &ext),
+            ],
+        )
+    }
+}
+
+/// The `=== path ===` header the plain format has always had
+impl Default for HeaderTemplate {
+    fn default() -> Self {
+        Self("=== {path} ===".to_string())
+    }
+}
+
+impl TryFrom<String> for HeaderTemplate {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        Self::parse(&text)
+    }
+}
+
+impl From<HeaderTemplate> for String {
+    fn from(template: HeaderTemplate) -> Self {
+        template.0
+    }
+}
+
+// Each `{name}` in `text` made only of letters, digits, and underscores
+fn placeholders(text: &str) -> impl Iterator<Item = &str> {
+    text.split('{').skip(1).filter_map(|rest| {
+        let name = &rest[..rest.find('}')?];
+        (!name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')).then_some(name)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholders_are_checked_and_filled() {
+        let path = Path::new("/work/app/src/main.rs");
+        let template = HeaderTemplate::parse("// FILE: {path} ({name}, {ext}, {lines} lines, {size}) { }").unwrap();
+        assert_eq!(
+            template.render(path, Path::new("src/main.rs"), "fn main() {}\n\n"),
+            "// FILE: src/main.rs (main.rs, rs, 2 lines, 14 B) { }"
+        );
+        assert_eq!(HeaderTemplate::default().render(path, Path::new("src/main.rs"), ""), "=== src/main.rs ===");
+
+        let unknown = HeaderTemplate::parse("--- {file} ---").unwrap_err();
+        assert!(unknown.starts_with("unknown placeholder {file};"), "{}", unknown);
+        assert!(HeaderTemplate::parse("a\nb").is_err());
+    }
+}
@@ -0,0 +1,242 @@
+//! The opt-in invocation log behind `yoink history` and `yoink rerun`
+
+use crate::config::Config;
+use crate::error::YoinkError;
+use crate::report::ProcessReport;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Most invocations kept; the oldest are dropped as new ones are recorded
+pub const MAX_ENTRIES: usize = 500;
+
+/// Stands in for a sensitive value left out of the log
+pub const REDACTED: &str = "<redacted>";
+
+// Flags whose values may be sensitive, in every form clap accepts
+const SENSITIVE_LONG: &[&str] = &["--search", "--search-regex"];
+const SENSITIVE_SHORT: &[&str] = &["-S"];
+
+/// One recorded run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Invocation {
+    /// RFC 3339, in local time
+    pub timestamp: String,
+    /// Directory yoink was run from
+    pub cwd: PathBuf,
+    /// The arguments as given, after the program name
+    pub args: Vec<String>,
+    /// The effective settings, in the saved config's format
+    pub settings: serde_json::Value,
+    /// Whether sensitive values were left out of `args` and `settings`
+    #[serde(default)]
+    pub redacted: bool,
+    pub text_files: usize,
+    pub binary_files: usize,
+    pub skipped_for_size: usize,
+    pub estimated_tokens: u64,
+}
+
+impl Invocation {
+    /// Describe a finished run, leaving out sensitive values unless the
+    /// config's `history_full` is set
+    pub fn new(config: &Config, args: impl IntoIterator<Item = String>, report: &ProcessReport) -> Self {
+        let args: Vec<String> = args.into_iter().collect();
+        let (args, redacted) = if config.history_full {
+            (args, false)
+        } else {
+            let kept = redact_args(&args);
+            let redacted = kept != args || config.search_text.is_some();
+            (kept, redacted)
+        };
+        Self {
+            timestamp: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            cwd: std::env::current_dir().unwrap_or_default(),
+            args,
+            settings: config.to_history_settings(),
+            redacted,
+            text_files: report.text_files,
+            binary_files: report.binary_files,
+            skipped_for_size: report.skipped.too_large,
+            estimated_tokens: report.estimated_tokens,
+        }
+    }
+
+    /// The config this invocation ran with, loaded the way the saved config is
+    pub fn config(&self, log: &Path) -> Result<Config, YoinkError> {
+        Config::from_history_settings(log, &self.settings)
+    }
+
+    /// The command line as it was typed, quoting arguments with spaces
+    pub fn command_line(&self) -> String {
+        std::iter::once("yoink".to_string())
+            .chain(self.args.iter().map(|arg| if arg.is_empty() || arg.contains(char::is_whitespace) { format!("'{}'", arg) } else { arg.clone() }))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// When the run happened, to the minute
+    pub fn when(&self) -> String {
+        chrono::DateTime::parse_from_rfc3339(&self.timestamp)
+            .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|_| self.timestamp.clone())
+    }
+}
+
+/// The log file, one JSON invocation per line, oldest first
+#[derive(Debug, Clone)]
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    /// The log under the state directory, or the local data directory where
+    /// the platform has no separate one
+    pub fn open() -> Self {
+        let mut path = dirs::state_dir().or_else(dirs::data_local_dir).unwrap_or_else(std::env::temp_dir);
+        path.push("yoink");
+        path.push("history.jsonl");
+        Self::at(path)
+    }
+
+    /// The log at `path`
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Every recorded invocation, oldest first, skipping lines that don't parse
+    pub fn entries(&self) -> Result<Vec<Invocation>, YoinkError> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(source) => return Err(YoinkError::Io { path: self.path.clone(), source }),
+        };
+        Ok(text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    log::warn!("Skipping unreadable history entry in {}: {}", self.path.display(), e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// Add `entry`, dropping the oldest ones past `MAX_ENTRIES`
+    pub fn record(&self, entry: &Invocation) -> io::Result<()> {
+        let mut entries = self.entries().unwrap_or_default();
+        entries.push(entry.clone());
+        let start = entries.len().saturating_sub(MAX_ENTRIES);
+        let mut text = String::new();
+        for entry in &entries[start..] {
+            text.push_str(&serde_json::to_string(entry).map_err(io::Error::other)?);
+            text.push('\n');
+        }
+
+        // Written aside and renamed over, so concurrent runs never see half a log
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(dir)?;
+        let temp = tempfile::NamedTempFile::new_in(dir)?;
+        fs::write(temp.path(), text)?;
+        temp.persist(&self.path).map_err(|e| e.error)?;
+        Ok(())
+    }
+
+    /// Invocations made from `dir`, most recent first
+    pub fn recent_in(&self, dir: &Path) -> Result<Vec<Invocation>, YoinkError> {
+        let mut entries = self.entries()?;
+        entries.retain(|entry| entry.cwd == dir);
+        entries.reverse();
+        Ok(entries)
+    }
+
+    /// The `n`th most recent invocation made from `dir`, counting from 1
+    pub fn nth_in(&self, dir: &Path, n: usize) -> Result<Invocation, YoinkError> {
+        let entries = self.recent_in(dir)?;
+        let message = match entries.len() {
+            0 => format!("no invocations recorded in {}", dir.display()),
+            count if n == 0 || n > count => format!("only {} invocations recorded in {}", count, dir.display()),
+            _ => return Ok(entries[n - 1].clone()),
+        };
+        Err(YoinkError::History { message })
+    }
+}
+
+/// `args` with the values of sensitive flags replaced by `REDACTED`
+pub fn redact_args(args: &[String]) -> Vec<String> {
+    let mut redacted = Vec::with_capacity(args.len());
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if SENSITIVE_LONG.contains(&arg.as_str()) || SENSITIVE_SHORT.contains(&arg.as_str()) {
+            redacted.push(arg.clone());
+            if args.next().is_some() {
+                redacted.push(REDACTED.to_string());
+            }
+        } else if let Some(flag) = SENSITIVE_LONG.iter().find(|flag| arg.starts_with(&format!("{}=", flag))) {
+            redacted.push(format!("{}={}", flag, REDACTED));
+        } else if let Some(flag) = SENSITIVE_SHORT.iter().find(|flag| arg.starts_with(*flag)) {
+            redacted.push(format!("{}{}", flag, REDACTED));
+        } else {
+            redacted.push(arg.clone());
+        }
+    }
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_search_terms_are_redacted() {
+        assert_eq!(
+            redact_args(&args(&["src", "--search", "api_key", "-x", "md", "--search=token", "-Ssecret", "-S", "pw"])),
+            args(&["src", "--search", REDACTED, "-x", "md", "--search=<redacted>", "-S<redacted>", "-S", REDACTED])
+        );
+    }
+
+    #[test]
+    fn test_log_is_capped_and_replays_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = History::at(dir.path().join("state/history.jsonl"));
+        let config = Config::builder().path("src").include_extensions(["rs"]).search_text("password").build();
+        let report = ProcessReport { text_files: 3, ..ProcessReport::default() };
+
+        let mut entry = Invocation::new(&config, args(&["src", "-e", "rs", "-S", "password"]), &report);
+        let full_log: String = (0..=MAX_ENTRIES)
+            .map(|n| serde_json::to_string(&Invocation { text_files: n, ..entry.clone() }).unwrap() + "\n")
+            .collect();
+        fs::create_dir_all(dir.path().join("state")).unwrap();
+        fs::write(history.path(), full_log).unwrap();
+        entry.text_files = MAX_ENTRIES + 1;
+        history.record(&entry).unwrap();
+        let entries = history.entries().unwrap();
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0].text_files, 2);
+
+        let cwd = std::env::current_dir().unwrap();
+        let latest = history.nth_in(&cwd, 1).unwrap();
+        assert_eq!(latest.text_files, MAX_ENTRIES + 1);
+        assert!(latest.redacted);
+        assert_eq!(latest.command_line(), "yoink src -e rs -S <redacted>");
+
+        let replayed = latest.config(history.path()).unwrap();
+        assert_eq!(replayed.path, "src");
+        assert_eq!(replayed.include_extensions, Some(vec!["rs".to_string()]));
+        assert_eq!(replayed.search_text, None);
+
+        assert!(matches!(history.nth_in(&cwd, MAX_ENTRIES + 1), Err(YoinkError::History { .. })));
+        assert!(matches!(history.nth_in(dir.path(), 1), Err(YoinkError::History { .. })));
+    }
+}
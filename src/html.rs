@@ -0,0 +1,144 @@
+//! HTML output: a self-contained page with the tree as a nested list and a
+//! collapsible `<details>` element per file
+//!
+//! The page is laid out by `HtmlPage`, a built-in `OutputFormatter`, so it
+//! sees the same pieces a custom template does.
+
+use crate::comments::{self, Syntax, Token};
+use crate::formatter::OutputFormatter;
+use crate::markdown;
+use crate::xml::escape;
+use std::path::Path;
+
+// Enough to read the page comfortably; colors are for comments and strings
+const STYLE: &str = "\
+body { font-family: system-ui, sans-serif; margin: 2rem auto; max-width: 72rem; padding: 0 1rem; color: #222; }
+ul.tree, ul.tree ul { list-style: none; padding-left: 1.2rem; font-family: ui-monospace, monospace; font-size: 0.9rem; }
+details { border: 1px solid #ddd; border-radius: 4px; margin: 0.5rem 0; }
+summary { cursor: pointer; padding: 0.4rem 0.6rem; background: #f6f8fa; font-family: ui-monospace, monospace; }
+.note { color: #777; font-style: italic; }
+pre { margin: 0; padding: 0.6rem; overflow-x: auto; font-size: 0.85rem; line-height: 1.4; }
+.skipped { margin: 0.5rem 0; padding: 0.4rem 0.6rem; font-family: ui-monospace, monospace; color: #777; }
+.c { color: #6a737d; font-style: italic; }
+.s { color: #032f62; }
+";
+
+/// Lays the output out as a single HTML page titled after the scanned root
+pub struct HtmlPage {
+    title: String,
+}
+
+impl HtmlPage {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self { title: title.into() }
+    }
+
+    /// A page titled after the directory or file at `root`
+    pub fn for_root(root: &Path) -> Self {
+        let name = root.canonicalize().ok().and_then(|root| root.file_name().map(|name| name.to_string_lossy().into_owned()));
+        Self::new(name.unwrap_or_else(|| root.display().to_string()))
+    }
+}
+
+impl OutputFormatter for HtmlPage {
+    fn tree_section(&self, tree: &str) -> String {
+        let title = escape(&self.title);
+        let mut page = format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+            title, STYLE, title
+        );
+        if !tree.trim().is_empty() {
+            page.push_str(&tree_list(tree));
+        }
+        page.push_str("<h2>Files</h2>\n");
+        page
+    }
+
+    fn file_chunk(&self, path: &Path, note: Option<&str>, content: &str) -> String {
+        let language = markdown::language(path).unwrap_or_default();
+        let class = if language.is_empty() { String::new() } else { format!(" class=\"language-{}\"", language) };
+        format!(
+            "<details>\n<summary>{}{}</summary>\n<pre><code{}>{}\n</code></pre>\n</details>\n",
+            escape(&path.display().to_string()),
+            note.map(|note| format!(" <span class=\"note\">({})</span>", escape(note))).unwrap_or_default(),
+            class,
+            highlight(path, content)
+        )
+    }
+
+    fn skipped_entry(&self, path: &Path, note: &str) -> String {
+        format!(
+            "<div class=\"skipped\">{} <span class=\"note\">({})</span></div>\n",
+            escape(&path.display().to_string()),
+            escape(note)
+        )
+    }
+
+    fn text_section(&self, _name: &str, text: &str) -> String {
+        format!("<pre>{}</pre>\n", escape(text.trim_matches('\n')))
+    }
+
+    fn summary(&self, summary: &str) -> String {
+        format!("<h2>Summary</h2>\n<pre>{}</pre>\n</body>\n</html>\n", escape(summary.trim_end_matches('\n')))
+    }
+}
+
+// The indented tree as nested lists, one level per two spaces of indent; each
+// item's closing tag is left implicit, so a nested list sits inside it
+fn tree_list(tree: &str) -> String {
+    let mut list = String::new();
+    let mut depth = 0;
+    for line in tree.lines().filter(|line| !line.trim().is_empty()) {
+        let level = (line.len() - line.trim_start().len()) / 2 + 1;
+        while depth < level {
+            list.push_str(if depth == 0 { "<ul class=\"tree\">\n" } else { "<ul>\n" });
+            depth += 1;
+        }
+        while depth > level {
+            list.push_str("</ul>\n");
+            depth -= 1;
+        }
+        list.push_str(&format!("<li>{}\n", escape(line.trim())));
+    }
+    for _ in 0..depth {
+        list.push_str("</ul>\n");
+    }
+    list
+}
+
+// `content` escaped, with comments and string literals marked for the page's
+// style where the language is known
+fn highlight(path: &Path, content: &str) -> String {
+    let Some(syntax) = Syntax::of(path) else {
+        return escape(content);
+    };
+    let mut highlighted = String::with_capacity(content.len());
+    for (token, text) in comments::tokens(syntax, content) {
+        match token {
+            Token::Code => highlighted.push_str(&escape(text)),
+            Token::Comment => highlighted.push_str(&format!("<span class=\"c\">{}</span>", escape(text))),
+            Token::String => highlighted.push_str(&format!("<span class=\"s\">{}</span>", escape(text))),
+        }
+    }
+    highlighted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_nests_and_content_is_escaped() {
+        let page = HtmlPage::new("app");
+        assert_eq!(
+            tree_list("📁 app/\n  📁 src/\n    📄 main.rs\n  📄 <odd>.txt\n"),
+            "<ul class=\"tree\">\n<li>📁 app/\n<ul>\n<li>📁 src/\n<ul>\n<li>📄 main.rs\n</ul>\n<li>📄 &lt;odd&gt;.txt\n</ul>\n</ul>\n"
+        );
+        assert_eq!(
+            page.file_chunk(Path::new("src/main.rs"), Some("matches"), "if a < b { \"<b>\" } // done"),
+            "<details>\n<summary>src/main.rs <span class=\"note\">(matches)</span></summary>\n<pre><code class=\"language-rust\">if a &lt; b { \
+             <span class=\"s\">&quot;&lt;b&gt;&quot;</span> } <span class=\"c\">// done</span>\n</code></pre>\n</details>\n"
+        );
+        assert!(page.summary("Text files processed: 1\n").ends_with("<pre>Text files processed: 1</pre>\n</body>\n</html>\n"));
+    }
+}
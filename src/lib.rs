@@ -0,0 +1,111 @@
+//! Gather text content from files and directories into a single formatted blob.
+//!
+//! The `yoink` binary copies the result to the clipboard; embedders can call
+//! [`run`] to get the assembled text and a [`ProcessReport`] back instead.
+//!
+//! ```
+//! let dir = tempfile::tempdir().unwrap();
+//! std::fs::write(dir.path().join("main.rs"), "fn main() {}").unwrap();
+//!
+//! let config = yoink::Config::builder()
+//!     .path(dir.path().to_string_lossy())
+//!     .include_extensions(["rs"])
+//!     .build();
+//!
+//! let output = yoink::run(config).unwrap();
+//! assert!(output.content.contains("fn main() {}"));
+//! ```
+
+pub mod append;
+pub mod cache;
+pub mod cancel;
+pub mod chunk;
+pub mod cli;
+pub mod clipboard;
+pub mod comments;
+pub mod compare;
+pub mod config;
+pub mod dedup;
+pub mod deadline;
+pub mod depth;
+pub mod dir_cap;
+pub mod editor;
+pub mod error;
+pub mod excluded;
+pub mod explain;
+pub mod file_processor;
+pub mod file_scanner;
+pub mod file_tree;
+pub mod files_list;
+pub mod fit;
+pub mod formatter;
+pub mod generated;
+pub mod git;
+pub mod group;
+pub mod header;
+pub mod history;
+pub mod html;
+pub mod pager;
+pub mod picker;
+pub mod manifest;
+pub mod markdown;
+pub mod media;
+pub mod modified;
+pub mod name_regex;
+pub mod output_dir;
+pub mod preview;
+pub mod progress;
+pub mod remote;
+pub mod report;
+pub mod roots;
+pub mod sample;
+pub mod selection;
+pub mod serve;
+pub mod skip_log;
+pub mod stats;
+pub mod symbol;
+pub mod target;
+pub mod test_code;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+pub mod text_processor;
+mod utils;
+pub mod watch;
+pub mod workspace;
+pub mod xml;
+pub mod yoinkignore;
+
+pub use cancel::CancelToken;
+pub use clipboard::ClipboardInterface;
+pub use config::{Config, ConfigBuilder, FilterConflict, NestedRepoMode, OversizeMode, PathRule, SubmoduleMode};
+pub use error::YoinkError;
+pub use file_processor::{FileProcessor, FileProcessorBuilder, Output};
+pub use file_scanner::FileScanning;
+pub use file_tree::DirectoryTreeBuilding;
+pub use formatter::OutputFormatter;
+pub use report::{FileOutcome, FileRecord, ProcessReport};
+pub use text_processor::TextProcessing;
+pub use utils::format_size;
+
+/// Run the full scan-and-format pipeline with the default components
+///
+/// Returns the assembled text and a report of the run without touching the clipboard.
+///
+/// ```
+/// let dir = tempfile::tempdir().unwrap();
+/// std::fs::write(dir.path().join("a.txt"), "alpha").unwrap();
+/// std::fs::write(dir.path().join("b.txt"), "beta").unwrap();
+///
+/// let config = yoink::Config::builder()
+///     .path(dir.path().to_string_lossy())
+///     .sort(true)
+///     .build();
+/// let output = yoink::run(config).unwrap();
+///
+/// assert_eq!(output.report.text_files, 2);
+/// assert!(output.content.contains("alpha"));
+/// assert!(output.content.contains("beta"));
+/// ```
+pub fn run(config: Config) -> Result<Output, YoinkError> {
+    FileProcessor::with_defaults(config).assemble()
+}
@@ -1,63 +1,946 @@
-mod cli;
-mod clipboard;
-mod file_processor;
-mod file_scanner;
-mod file_tree;
-mod text_processor;
-mod utils;
-
 use colored::*;
-use file_processor::FileProcessor;
+use indicatif_log_bridge::LogWrapper;
+use log::LevelFilter;
+use std::time::{Duration, Instant};
+use yoink::cache::FileCache;
+use yoink::compare::{self, Comparison};
+use yoink::explain::Explanation;
+use yoink::history::{History, Invocation};
+use yoink::remote::RemoteRepo;
+use yoink::report::{FileOutcome, PhaseDurations};
+use yoink::roots;
+use yoink::selection::Listing;
+use yoink::workspace::Workspace;
+use yoink::{cli, files_list, progress, Config, FileProcessor, ProcessReport, YoinkError};
+
+// Log to stderr through the shared progress display so lines don't tear the
+// bars. -v enables per-file decisions, -vv adds clipboard probing, and
+// YOINK_LOG accepts env_logger filters such as "yoink::file_scanner=debug".
+fn init_logging(verbosity: u8) {
+    let level = match verbosity {
+        0 => LevelFilter::Error,
+        1 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    
+    let logger = env_logger::Builder::new()
+        .filter_level(level)
+        .parse_env("YOINK_LOG")
+        .format_timestamp(None)
+        .build();
+    let max_level = logger.filter();
+    
+    if LogWrapper::new(progress::multi().clone(), logger).try_init().is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+// Exit codes follow the BSD sysexits conventions
+fn exit_code(error: &YoinkError) -> i32 {
+    match error {
+        YoinkError::PathNotFound { .. } | YoinkError::PathExpansion { .. } | YoinkError::MissingListed { .. } | YoinkError::SpecialFile { .. } => 66, // EX_NOINPUT
+        YoinkError::BinaryInput { .. } | YoinkError::NotYoinkOutput => 65, // EX_DATAERR
+        YoinkError::Scan { .. } | YoinkError::Io { .. } | YoinkError::Output { .. } | YoinkError::Watch { .. } | YoinkError::Manifest { .. } => 74, // EX_IOERR
+        YoinkError::Config { .. } | YoinkError::ConfigFormat { .. } | YoinkError::ConfigRule { .. } => 78, // EX_CONFIG
+        YoinkError::Format { .. } | YoinkError::Panicked { .. } => 70, // EX_SOFTWARE
+        YoinkError::NoClipboard | YoinkError::Clipboard { .. } | YoinkError::GitNotFound | YoinkError::Git { .. } | YoinkError::Clone { .. } => 69, // EX_UNAVAILABLE
+        YoinkError::Cancelled { .. } => 130, // 128 + SIGINT
+        YoinkError::NotATerminal { .. } | YoinkError::Selection { .. } | YoinkError::History { .. } | YoinkError::Symbol { .. } | YoinkError::Chunk { .. } | YoinkError::Workspace { .. } | YoinkError::FilterConflict { .. } | YoinkError::Paths { .. } | YoinkError::Label { .. } | YoinkError::SearchRegex { .. } | YoinkError::NotARepository { .. } | YoinkError::UnknownProfile { .. } | YoinkError::AppendFormat { .. } => 64, // EX_USAGE
+        YoinkError::Editor { .. } | YoinkError::Picker { .. } => 69, // EX_UNAVAILABLE
+        YoinkError::Aborted { .. } => 1,
+        YoinkError::Serve { .. } | YoinkError::ServeInterrupted { .. } => 69, // EX_UNAVAILABLE
+        YoinkError::ServeTimeout { .. } => 75, // EX_TEMPFAIL
+        YoinkError::OutputDir { .. } | YoinkError::OutputFile { .. } => 73, // EX_CANTCREAT
+    }
+}
+
+fn report_error(error: &YoinkError) {
+    if let YoinkError::Cancelled { .. } | YoinkError::Aborted { .. } | YoinkError::ServeTimeout { .. } = error {
+        eprintln!("{} {}", error.to_string().yellow(), "- nothing was copied".yellow());
+        return;
+    }
+    
+    eprintln!("{}: {}", "Error".red(), error);
+
+    match error {
+        YoinkError::Config { .. } | YoinkError::ConfigFormat { .. } | YoinkError::ConfigRule { .. } => {
+            eprintln!("{}: Run with --no-config to ignore the saved configuration", "Hint".blue());
+        }
+        YoinkError::PathNotFound { suggestion: Some(suggestion), .. } => {
+            eprintln!("{}: Did you mean {}?", "Hint".blue(), suggestion.display());
+        }
+        YoinkError::NotYoinkOutput => {
+            eprintln!("{}: Pass --append=force to keep what's there above the new output", "Hint".blue());
+        }
+        YoinkError::PathExpansion { .. } => {
+            eprintln!("{}: Set the variables the path uses, or quote it to keep it literal", "Hint".blue());
+        }
+        YoinkError::OutputFile { source, .. } if source.kind() == std::io::ErrorKind::AlreadyExists => {
+            eprintln!("{}: Pass --force to overwrite it", "Hint".blue());
+        }
+        YoinkError::SpecialFile { .. } => {
+            eprintln!("{}: To yoink what a FIFO or device produces, pipe it in with `yoink -`", "Hint".blue());
+        }
+        YoinkError::Workspace { .. } => {
+            eprintln!("{}: Run with --list-packages to see the members yoink found", "Hint".blue());
+        }
+        YoinkError::Editor { .. } => {
+            eprintln!("{}: Set $VISUAL or $EDITOR to the editor you want to use", "Hint".blue());
+        }
+        YoinkError::Picker { .. } => {
+            eprintln!("{}: Install fzf or point $YOINK_PICKER at another picker; --pattern and --extensions narrow files without one", "Hint".blue());
+        }
+        YoinkError::Clipboard { .. } => {
+            eprintln!("{}: Check that your clipboard utility can reach the display server", "Hint".blue());
+        }
+        _ => {}
+    }
+}
+
+fn run_config(matches: &clap::ArgMatches) -> i32 {
+    let Some(("edit", _)) = matches.subcommand() else {
+        unreachable!("clap requires a config subcommand");
+    };
+    
+    let edited = yoink::editor::require_terminal("config edit").and_then(|_| Config::edit_saved());
+    match edited {
+        Ok(path) => {
+            eprintln!("{} Saved {}", "✓".green(), path.display());
+            0
+        }
+        Err(e @ (YoinkError::ConfigFormat { .. } | YoinkError::ConfigRule { .. })) => {
+            eprintln!("{}: {}", "Error".red(), e);
+            eprintln!("{}: The saved config is unchanged; run `yoink config edit` again to fix the edit", "Hint".blue());
+            exit_code(&e)
+        }
+        Err(e) => {
+            report_error(&e);
+            exit_code(&e)
+        }
+    }
+}
+
+fn run_history(matches: &clap::ArgMatches) -> i32 {
+    let history = History::open();
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let all_dirs = matches.get_flag("all-dirs");
+    let entries = if all_dirs {
+        history.entries().map(|mut entries| {
+            entries.reverse();
+            entries
+        })
+    } else {
+        history.recent_in(&cwd)
+    };
+    let entries = match entries {
+        Ok(entries) => entries,
+        Err(e) => {
+            report_error(&e);
+            return exit_code(&e);
+        }
+    };
+    
+    if entries.is_empty() {
+        println!("{}", format!("No runs recorded in {}", if all_dirs { "any directory".to_string() } else { cwd.display().to_string() }).yellow());
+        if !Config::load_from_file().ok().flatten().is_some_and(|config| config.history) {
+            println!("{}: Set \"history\": true in the config (yoink config edit) to start recording", "Hint".blue());
+        }
+        return 0;
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        let counts = format!("{} text, {} binary, ~{} tokens", entry.text_files, entry.binary_files, entry.estimated_tokens);
+        // Only runs from here can be rerun, so only they get a number
+        let label = if all_dirs { entry.cwd.display().to_string() } else { format!("{:>3}", i + 1) };
+        println!("{}  {}  {}  {}", label.cyan(), entry.when().dimmed(), entry.command_line(), format!("({})", counts).dimmed());
+    }
+    0
+}
+
+// The config the `n`th most recent run from here used
+fn rerun_config(n: usize) -> Result<Config, YoinkError> {
+    let history = History::open();
+    let entry = history.nth_in(&std::env::current_dir().unwrap_or_default(), n)?;
+    if entry.redacted {
+        return Err(YoinkError::History {
+            message: format!("`{}` was recorded without its search term; set \"history_full\": true in the config to keep it", entry.command_line()),
+        });
+    }
+    eprintln!("{} {} {}", "↻".cyan(), entry.command_line(), format!("(from {})", entry.when()).dimmed());
+    entry.config(history.path())
+}
 
 fn main() {
+    // Exit from here rather than deep inside `run`, so a temporary clone is
+    // cleaned up before the process ends
+    std::process::exit(run());
+}
+
+fn run() -> i32 {
     let matches = cli::build_cli().get_matches();
-    let mut config = cli::Config::from_matches(&matches);
+    match matches.subcommand() {
+        Some(("config", sub)) => return run_config(sub),
+        Some(("history", sub)) => return run_history(sub),
+        _ => {}
+    }
     
-    // Expand any environment variables and tilde (~) in the path
-    match shellexpand::full(&config.path) {
-        Ok(expanded_path) => {
-            config.path = expanded_path.into_owned();
+    let config_started = Instant::now();
+    // A rerun replays what's already in the log rather than adding to it
+    let rerun = match matches.subcommand() {
+        Some(("rerun", sub)) => Some(*sub.get_one::<usize>("n").expect("N has a default")),
+        _ => None,
+    };
+    let mut config = match rerun.map(rerun_config) {
+        Some(Ok(config)) => config,
+        Some(Err(e)) => {
+            report_error(&e);
+            return exit_code(&e);
+        }
+        None => match Config::from_matches(&matches) {
+            Ok(config) => config,
+            Err(e) => {
+                report_error(&e);
+                return exit_code(&e);
+            }
         },
-        Err(e) => {
-            eprintln!("{}: Failed to expand path '{}': {}", "Error".red(), config.path, e);
-            std::process::exit(1);
+    };
+    let config_time = config_started.elapsed();
+    let timings = matches.get_flag("timings");
+    let report_path = matches.get_one::<String>("report").cloned();
+    
+    // A saved verbose setting counts as a single -v
+    let verbosity = matches.get_count("verbose").max(config.verbose as u8);
+    init_logging(verbosity);
+    
+    // Expand any environment variables and tilde (~) in the paths
+    for path in std::iter::once(&mut config.path).chain(config.extra_paths.iter_mut()).chain(config.labels.iter_mut().map(|(_, path)| path)) {
+        match shellexpand::full(path) {
+            Ok(expanded_path) => {
+                *path = expanded_path.into_owned();
+            },
+            Err(e) => {
+                let e = YoinkError::PathExpansion { path: path.clone(), message: e.to_string() };
+                report_error(&e);
+                return exit_code(&e);
+            }
+        }
+    }
+    
+    // Stdin and a cloned repository each stand in for the whole tree
+    if !config.extra_paths.is_empty() {
+        if let Some(path) = config.roots().find(|path| *path == "-" || RemoteRepo::parse(path).is_some()) {
+            let e = YoinkError::Paths { message: format!("'{}' can't be combined with other paths", path) };
+            report_error(&e);
+            return exit_code(&e);
+        }
+    }
+    if let Err(e) = roots::check_labels(&config) {
+        report_error(&e);
+        return exit_code(&e);
+    }
+    
+    // A git URL or `org/repo` is cloned and then yoinked like a local directory
+    let branch = matches.get_one::<String>("branch").map(String::as_str);
+    let mut checkout = None;
+    if let Some(repo) = RemoteRepo::parse(&config.path) {
+        eprintln!("{} Cloning {}...", "⬇".cyan(), repo.url);
+        let mut cloned = match repo.clone_into_temp(branch) {
+            Ok(cloned) => cloned,
+            Err(e) => {
+                report_error(&e);
+                return exit_code(&e);
+            }
+        };
+        
+        if matches.get_flag("keep-clone") {
+            cloned.keep();
+            eprintln!("{} Keeping clone at {}", "📂".cyan(), cloned.path().display());
+        }
+        
+        config.path = cloned.path().to_string_lossy().into_owned();
+        checkout = Some(cloned);
+    } else if branch.is_some() {
+        log::warn!("--branch only applies when yoinking a git repository URL");
+    }
+    
+    if config.reads_stdin() && config.watch {
+        eprintln!("{}: --watch has nothing to watch when reading stdin", "Warning".yellow());
+        config.watch = false;
+    }
+    
+    // Contradictory filters would otherwise end in a puzzling "No files found"
+    let conflicts = config.filter_conflicts();
+    if config.strict_filters && !conflicts.is_empty() {
+        let message = conflicts.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ");
+        let e = YoinkError::FilterConflict { message };
+        report_error(&e);
+        return exit_code(&e);
+    }
+    for conflict in &conflicts {
+        eprintln!("{}: {}", "Warning".yellow(), conflict);
+    }
+    
+    if matches.get_flag("clear-cache") {
+        if let Err(e) = FileCache::clear(std::path::Path::new(&config.path)) {
+            eprintln!("{}: Failed to clear cache: {}", "Warning".yellow(), e);
         }
     }
     
     // Create processor with default dependencies using the factory method
     let mut processor = FileProcessor::with_defaults(config);
     
+    // First Ctrl-C stops gracefully, a second one exits immediately
+    let cancel = processor.cancel_token();
+    let handler = ctrlc::set_handler(move || {
+        if cancel.is_cancelled() {
+            std::process::exit(130);
+        }
+        cancel.cancel();
+        let _ = progress::multi().println("Stopping... (Ctrl-C again to quit now)");
+    });
+    if let Err(e) = handler {
+        log::warn!("Could not install Ctrl-C handler: {}", e);
+    }
+    
+    if processor.config().list_packages {
+        return match Workspace::discover(std::path::Path::new(&processor.config().path)) {
+            Ok(workspace) => {
+                print_packages(&workspace);
+                0
+            }
+            Err(e) => {
+                report_error(&e);
+                exit_code(&e)
+            }
+        };
+    }
+    
+    if let Some(flags) = matches.get_one::<String>("compare-with") {
+        return run_compare(&processor, flags);
+    }
+    
+    if processor.config().dry_run {
+        return match processor.dry_run() {
+            Ok(report) => print_dry_run(&report, verbosity > 0),
+            Err(e) => {
+                report_error(&e);
+                exit_code(&e)
+            }
+        };
+    }
+    
+    if processor.config().stats {
+        return match processor.dry_run() {
+            Ok(report) => {
+                print_stats(&report);
+                0
+            }
+            Err(e) => {
+                report_error(&e);
+                exit_code(&e)
+            }
+        };
+    }
+    
+    if processor.config().list && processor.config().list_json {
+        return match processor.list_files_detailed(processor.config().list_all) {
+            Ok(files) => {
+                for file in &files {
+                    println!("{}", serde_json::to_string(file).expect("listed files always serialize"));
+                }
+                0
+            }
+            Err(e) => {
+                report_error(&e);
+                exit_code(&e)
+            }
+        };
+    }
+    
+    if processor.config().list {
+        return match processor.list_files() {
+            Ok(listing) if processor.config().emit_files_list => {
+                print!("{}", files_list::format(&listing.paths));
+                0
+            }
+            Ok(listing) => {
+                print_listing(&listing);
+                0
+            }
+            Err(e) => {
+                report_error(&e);
+                exit_code(&e)
+            }
+        };
+    }
+    
+    if let Some(target) = processor.config().explain.clone() {
+        return match processor.explain(&target) {
+            Ok(explanations) => {
+                print_explanations(&explanations);
+                0
+            }
+            Err(e) => {
+                report_error(&e);
+                exit_code(&e)
+            }
+        };
+    }
+    
+    let watch = processor.config().watch;
+    // Status lines would end up mixed into the piped output
+    let quiet = processor.config().stdout;
+    
     match processor.process() {
-        Ok((text_count, binary_count)) => {
-            if text_count == 0 && binary_count == 0 {
-                println!("{}", "No files found".yellow());
-                return;
-            }
-            
-            if text_count > 0 {
-                println!(
-                    "{} {} {} {}",
-                    "✨".green(),
-                    "Yoinked".green().bold(),
-                    text_count,
-                    if text_count == 1 { "text file!" } else { "text files!" }.green()
-                );
-            }
-            
-            if binary_count > 0 {
-                println!(
-                    "{} {} {}",
-                    "📊".yellow(),
-                    binary_count,
-                    if binary_count == 1 { "binary file was skipped" } else { "binary files were skipped" }.yellow()
-                );
-            }
-            
-            println!("{} Content copied to clipboard", "📋".cyan());
+        Ok(mut report) => {
+            report.durations.config = config_time;
+            if processor.config().history && rerun.is_none() {
+                let entry = Invocation::new(processor.config(), std::env::args().skip(1), &report);
+                if let Err(e) = History::open().record(&entry) {
+                    log::warn!("Could not record this run in the history: {}", e);
+                }
+            }
+            print_warnings(&report);
+            if !quiet {
+                print_summary(&report);
+            }
+            print_limits(&report);
+            if timings {
+                print_timings(&report.durations);
+            }
+            if let Some(path) = &report_path {
+                write_report(path, &report);
+            }
         }
         Err(e) => {
-            eprintln!("{}: {}", "Error".red(), e);
-            std::process::exit(1);
+            report_error(&e);
+            return exit_code(&e);
+        }
+    }
+    
+    if watch {
+        println!("{} Watching for changes (Ctrl-C to stop)", "👀".cyan());
+        
+        let result = yoink::watch::watch(&mut processor, |result| {
+            let timestamp = chrono::Local::now().format("%H:%M:%S");
+            print!("{} ", format!("[{}]", timestamp).dimmed());
+            match result {
+                Ok(report) => {
+                    print_warnings(&report);
+                    print_summary(&report);
+                    print_limits(&report);
+                    if timings {
+                        print_timings(&report.durations);
+                    }
+                    if let Some(path) = &report_path {
+                        write_report(path, &report);
+                    }
+                }
+                Err(e) => report_error(&e),
+            }
+        });
+        
+        if let Err(e) = result {
+            report_error(&e);
+            return exit_code(&e);
+        }
+    }
+    
+    drop(checkout);
+    0
+}
+
+fn print_warnings(report: &ProcessReport) {
+    for missing in &report.missing_listed {
+        eprintln!("{}: {} is listed but doesn't exist; left out", "Warning".yellow(), missing.display());
+    }
+}
+
+// Paths to stdout so they can be piped, everything else to stderr; exits 1
+// when nothing would be yoinked
+fn print_dry_run(report: &ProcessReport, verbose: bool) -> i32 {
+    let included = |outcome: &FileOutcome| matches!(outcome, FileOutcome::Included | FileOutcome::Summarized);
+    for file in report.files.iter().filter(|f| included(&f.outcome)) {
+        println!("{}", file.path.display().to_string().replace('\\', "/"));
+    }
+    if verbose {
+        for file in report.files.iter().filter(|f| !included(&f.outcome)) {
+            let reason = match file.outcome {
+                FileOutcome::TooLarge => "too large",
+                _ => "binary",
+            };
+            eprintln!("{} {} ({}, {})", "skip".yellow(), file.path.display(), reason, yoink::format_size(file.size));
         }
     }
-}
\ No newline at end of file
+    
+    let skipped = &report.skipped;
+    let mut counts = vec![format!("{} text", report.text_files)];
+    if skipped.summarized > 0 {
+        counts.push(format!("{} summarized for size", skipped.summarized));
+    }
+    counts.push(format!("{} binary", report.binary_files));
+    counts.push(format!("{} too large", skipped.too_large));
+    if skipped.tests > 0 {
+        counts.push(format!("{} tests", skipped.tests));
+    }
+    if skipped.by_time > 0 {
+        counts.push(format!("{} outside the time range", skipped.by_time));
+    }
+    if report.skipped_repos > 0 {
+        counts.push(format!("{} nested repos", report.skipped_repos));
+    }
+    let capped: usize = report.capped_dirs.iter().map(|cap| cap.total - cap.included).sum();
+    if capped > 0 {
+        counts.push(format!("{} over directory limits", capped));
+    }
+    eprintln!("{} Dry run: {} ({} total)", "🔍".cyan(), counts.join(", "), yoink::format_size(report.total_bytes));
+    
+    if report.text_files + skipped.summarized > 0 {
+        0
+    } else {
+        1
+    }
+}
+
+fn print_summary(report: &ProcessReport) {
+    let (text_count, binary_count) = (report.text_files, report.binary_files);
+    if text_count == 0 && binary_count == 0 && report.skipped.too_large == 0 && report.skipped.summarized == 0 && report.skipped.over_budget == 0 && report.skipped.over_total == 0 {
+        match (&report.diff, &report.manifest) {
+            (Some(diff), _) => println!("{}", format!("No changes since {}", diff.rev).yellow()),
+            (None, Some(manifest)) if manifest.deleted.is_empty() => println!("{}", "No changes since the manifest".yellow()),
+            (None, Some(manifest)) => println!("{}", format!("No changes since the manifest other than {} deleted", manifest.deleted.len()).yellow()),
+            (None, None) => println!("{}", "No files found".yellow()),
+        }
+        return;
+    }
+    
+    if let Some(sample) = &report.sample {
+        println!(
+            "{} Sampled {} of {} files (--seed {} to repeat)",
+            "🎲".cyan(),
+            sample.requested.min(sample.candidates),
+            sample.candidates,
+            sample.seed
+        );
+    }
+    
+    if text_count > 0 {
+        println!(
+            "{} {} {} {}",
+            "✨".green(),
+            "Yoinked".green().bold(),
+            text_count,
+            if text_count == 1 { "text file!" } else { "text files!" }.green()
+        );
+    }
+    
+    for root in &report.roots {
+        println!("   {} [{}]: {} files, {}, {} skipped", "🗂".cyan(), root.label, root.text_files, yoink::format_size(root.bytes), root.skipped);
+    }
+    
+    for group in &report.groups {
+        println!("   {} {}: {} files, {}", "📦".cyan(), group.name, group.files, yoink::format_size(group.bytes));
+    }
+    
+    if binary_count > 0 {
+        println!(
+            "{} {} {}",
+            "📊".yellow(),
+            binary_count,
+            if binary_count == 1 { "binary file was skipped" } else { "binary files were skipped" }.yellow()
+        );
+    }
+    
+    if let Some(largest) = report.largest_too_large() {
+        let too_large = report.skipped.too_large;
+        println!(
+            "{} {} {} (largest: {}, {})",
+            "📏".yellow(),
+            too_large,
+            if too_large == 1 { "file was skipped for size" } else { "files were skipped for size" }.yellow(),
+            largest.path.display(),
+            yoink::format_size(largest.size)
+        );
+    }
+    
+    let summarized = report.skipped.summarized;
+    if summarized > 0 {
+        println!(
+            "{} {} {}",
+            "📏".cyan(),
+            summarized,
+            if summarized == 1 { "large file was summarized" } else { "large files were summarized" }
+        );
+    }
+    
+    let vanished = report.skipped.vanished;
+    if vanished > 0 {
+        println!(
+            "{} {} {}",
+            "👻".yellow(),
+            vanished,
+            if vanished == 1 { "file vanished during the run" } else { "files vanished during the run" }.yellow()
+        );
+    }
+    
+    let timed_out = report.skipped.timed_out;
+    if timed_out > 0 {
+        println!(
+            "{} {} {}",
+            "⏱️".yellow(),
+            timed_out,
+            if timed_out == 1 {
+                "file took too long to read and was abandoned (--file-timeout to wait longer)"
+            } else {
+                "files took too long to read and were abandoned (--file-timeout to wait longer)"
+            }.yellow()
+        );
+    }
+    
+    if report.skipped_repos > 0 {
+        println!(
+            "{} {} {}",
+            "📚".yellow(),
+            report.skipped_repos,
+            if report.skipped_repos == 1 {
+                "nested repository was skipped (--nested-repos include to walk it)"
+            } else {
+                "nested repositories were skipped (--nested-repos include to walk them)"
+            }.yellow()
+        );
+    }
+    
+    if report.skipped.tests > 0 || report.test_modules_stripped > 0 {
+        println!(
+            "{} Left out {} test {} and stripped {} test {}",
+            "🧪".cyan(),
+            report.skipped.tests,
+            if report.skipped.tests == 1 { "file" } else { "files" },
+            report.test_modules_stripped,
+            if report.test_modules_stripped == 1 { "module" } else { "modules" }
+        );
+    }
+    
+    if report.skipped.by_time > 0 {
+        println!(
+            "{} Left out {} {} modified outside --since/--until",
+            "🕒".cyan(),
+            report.skipped.by_time,
+            if report.skipped.by_time == 1 { "file" } else { "files" }
+        );
+    }
+    
+    for cap in &report.capped_dirs {
+        println!(
+            "{} {}: included {} of {} files (directory capped)",
+            "🗂️".yellow(),
+            cap.name(),
+            cap.included,
+            cap.total
+        );
+    }
+    
+    let failed = report.skipped.failed;
+    if failed > 0 {
+        println!(
+            "{} {} {}",
+            "⚠️".red(),
+            failed,
+            if failed == 1 { "file failed to process" } else { "files failed to process" }.red()
+        );
+    }
+    
+    if let Some(diff) = &report.diff {
+        println!(
+            "{} {} changed since {} ({} {}), {} new",
+            "±".cyan(),
+            diff.files,
+            diff.rev,
+            format!("+{}", diff.added).green(),
+            format!("-{}", diff.removed).red(),
+            diff.new_files
+        );
+    }
+    
+    if let Some(manifest) = &report.manifest {
+        println!(
+            "{} Since the manifest: {} changed, {} new, {} deleted",
+            "±".cyan(),
+            manifest.changed,
+            manifest.new_files,
+            manifest.deleted.len()
+        );
+        for path in &manifest.deleted {
+            println!("    {} {}", "-".red(), path);
+        }
+    }
+    
+    let duplicates = report.skipped.duplicates;
+    if duplicates > 0 {
+        println!(
+            "{} {} {}, saving {}",
+            "🔁".cyan(),
+            duplicates,
+            if duplicates == 1 { "duplicate file was collapsed" } else { "duplicate files were collapsed" },
+            yoink::format_size(report.deduped_bytes)
+        );
+    }
+    
+    let over_budget = report.skipped.over_budget;
+    if over_budget > 0 {
+        // Files truncated alongside mean --fit truncate ran out of room too
+        println!(
+            "{} {} {} to fit the token budget - {}",
+            "✂️".yellow(),
+            over_budget,
+            if over_budget == 1 { "file was dropped" } else { "files were dropped" }.yellow(),
+            if report.truncated.is_empty() { "try --fit truncate" } else { "raise --max-tokens" }
+        );
+    }
+    
+    if !report.truncated.is_empty() {
+        let omitted: usize = report.truncated.iter().map(|t| t.omitted).sum();
+        println!(
+            "{} {} truncated to fit the token budget ({} lines omitted)",
+            "✂️".yellow(),
+            if report.truncated.len() == 1 { "1 file was".to_string() } else { format!("{} files were", report.truncated.len()) }.yellow(),
+            omitted
+        );
+    }
+    
+    if report.cache_hits > 0 {
+        println!("{} {} served from cache", "⚡".cyan(), report.cache_hits);
+    }
+    
+    if report.condensed_bytes > 0 {
+        println!("{} Condensing saved {}", "🗜️".cyan(), yoink::format_size(report.condensed_bytes));
+    }
+    
+    if let Some(path) = &report.manifest_saved {
+        println!("{} Saved manifest to {}", "💾".cyan(), path.display());
+    }
+    
+    if let Some(output_dir) = &report.output_dir {
+        println!("{} Wrote {} files to {}", "📂".cyan(), output_dir.files, output_dir.path.display());
+    }
+    
+    if let Some(written) = &report.output_file {
+        println!("{} Wrote {} bytes to {}", "💾".cyan(), written.bytes, written.path.display());
+    }
+    
+    if let Some(chunks) = &report.chunks {
+        println!("{} Output split into {} chunks of at most {} characters", "📦".cyan(), chunks.count, chunks.size);
+        for written in &chunks.files {
+            println!("{} Wrote {} bytes to {}", "💾".cyan(), written.bytes, written.path.display());
+        }
+        if let Some(number) = chunks.delivered {
+            println!(
+                "{} This is chunk {} of {} - run again with --chunk <1-{}> for the others",
+                "👉".cyan(),
+                number,
+                chunks.count,
+                chunks.count
+            );
+        }
+    }
+    
+    if let Some(served) = &report.served {
+        println!("{} Served {} bytes to {}", "🌐".cyan(), served.bytes, served.peer);
+    }
+    
+    if let Some(receipt) = &report.clipboard {
+        log::debug!("Copied {} bytes using {}", receipt.bytes, receipt.method);
+        match report.appended_run {
+            Some(run) if run > 1 => println!("{} Appended to the clipboard as run {}", "📋".cyan(), run),
+            _ => println!("{} Content copied to clipboard", "📋".cyan()),
+        }
+    }
+    
+    if report.estimated_tokens > 0 {
+        println!(
+            "{} ~{} tokens (estimated at {} characters per token)",
+            "🔢".cyan(),
+            report.estimated_tokens,
+            yoink::fit::CHARS_PER_TOKEN
+        );
+    }
+}
+
+// Limits the output ran into go to stderr, so they're seen even when the
+// output itself is piped with --stdout
+fn print_limits(report: &ProcessReport) {
+    let omitted = report.skipped.omitted;
+    if omitted > 0 {
+        eprintln!(
+            "{} Memory limit reached: {} file {} omitted - try --low-memory or tighter filters",
+            "⚠️".yellow(),
+            omitted,
+            if omitted == 1 { "body was" } else { "bodies were" }
+        );
+    }
+    
+    let over_total = report.skipped.over_total;
+    if over_total > 0 {
+        eprintln!(
+            "{} Output size limit reached: {} {} left out - see the OMITTED list, or raise --max-total",
+            "⚠️".yellow(),
+            over_total,
+            if over_total == 1 { "file was" } else { "files were" }
+        );
+    }
+    
+    if let Some(over) = &report.over_target {
+        eprintln!(
+            "{} {}",
+            "⚠️".red(),
+            format!("~{} tokens over the {} window", over.tokens, over.target).red().bold()
+        );
+        eprintln!("    Leaving out {} would fit:", if over.largest.len() == 1 { "this file" } else { "these files" });
+        for file in &over.largest {
+            eprintln!("      {} (~{} tokens)", file.path.display(), file.tokens);
+        }
+        eprintln!("    or run with --fit to drop or truncate files automatically");
+    }
+}
+
+// List the files again with `flags` added to the command line, printing
+// only what differs
+fn run_compare(processor: &FileProcessor, flags: &str) -> i32 {
+    let args = match compare::other_args(std::env::args_os(), flags) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}: Invalid --compare-with flags: {}", "Error".red(), message);
+            return 64;
+        }
+    };
+    let other_matches = match cli::build_cli().args_override_self(true).try_get_matches_from(args) {
+        Ok(matches) => matches,
+        Err(e) => {
+            eprintln!("{}: Invalid --compare-with flags:", "Error".red());
+            let _ = e.print();
+            return 64;
+        }
+    };
+    let mut other = match Config::from_matches(&other_matches) {
+        Ok(other) => other,
+        Err(e) => {
+            report_error(&e);
+            return exit_code(&e);
+        }
+    };
+    other.path = processor.config().path.clone();
+    other.extra_paths = processor.config().extra_paths.clone();
+    
+    let listings = processor.list_files().and_then(|current| FileProcessor::with_defaults(other).list_files().map(|other| (current, other)));
+    let (current, other) = match listings {
+        Ok(listings) => listings,
+        Err(e) => {
+            report_error(&e);
+            return exit_code(&e);
+        }
+    };
+    let comparison = Comparison::of_listings(&processor.config().base(), &current.paths, &other.paths);
+    print_comparison(&comparison, flags);
+    0
+}
+
+fn print_comparison(comparison: &Comparison, flags: &str) {
+    let sides = [
+        ("Only without", &comparison.only_current, "-".red()),
+        ("Only with", &comparison.only_other, "+".green()),
+    ];
+    for (label, files, sign) in sides {
+        println!(
+            "{} {} ({} {}, {})",
+            label.bold(),
+            flags.bold(),
+            files.len(),
+            if files.len() == 1 { "file" } else { "files" },
+            yoink::format_size(compare::total_size(files))
+        );
+        for file in files {
+            println!("  {} {} {}", sign, file.path.display(), format!("({})", yoink::format_size(file.size)).dimmed());
+        }
+    }
+    println!(
+        "{}",
+        format!("{} {} included either way", comparison.shared, if comparison.shared == 1 { "file" } else { "files" }).dimmed()
+    );
+}
+
+fn print_explanations(explanations: &[Explanation]) {
+    for explanation in explanations {
+        println!("{}", explanation.path.display().to_string().bold());
+        for verdict in &explanation.verdicts {
+            match &verdict.result {
+                Ok(()) => println!("  {} {}", "✓".green(), verdict.check),
+                Err(reason) => println!("  {} {}: {}", "✗".red(), verdict.check, reason),
+            }
+        }
+        match explanation.rejection() {
+            Some(_) => println!("  {} skipped", "→".red()),
+            None => println!("  {} included", "→".green()),
+        }
+    }
+}
+
+fn print_packages(workspace: &Workspace) {
+    let width = workspace.members.iter().map(|m| m.name.len()).max().unwrap_or(0);
+    for member in &workspace.members {
+        println!("{:<width$}  {}", member.name, member.path.display().to_string().dimmed(), width = width);
+    }
+}
+
+fn print_stats(report: &ProcessReport) {
+    let stats = yoink::stats::by_extension(&report.files);
+    let files: usize = stats.iter().map(|s| s.files).sum();
+    let bytes: u64 = stats.iter().map(|s| s.bytes).sum();
+    let width = stats.iter().map(|s| s.extension.len()).chain(["EXTENSION".len()]).max().unwrap_or(0);
+    println!("{}", format!("{:<width$}  {:>7}  {:>10}", "EXTENSION", "FILES", "SIZE", width = width).bold());
+    for s in &stats {
+        println!("{:<width$}  {:>7}  {:>10}", s.extension, s.files, yoink::format_size(s.bytes), width = width);
+    }
+    println!("{}", format!("{:<width$}  {:>7}  {:>10}", "TOTAL", files, yoink::format_size(bytes), width = width).bold());
+    
+    let (binary, too_large) = (report.binary_files, report.skipped.too_large);
+    if binary + too_large > 0 {
+        eprintln!("{}", format!("Not counted: {} binary, {} too large", binary, too_large).dimmed());
+    }
+}
+
+fn print_listing(listing: &Listing) {
+    let width = listing.paths.len().to_string().len();
+    println!("{}", format!("# filters {}", listing.filters).dimmed());
+    for (i, path) in listing.paths.iter().enumerate() {
+        println!("{:>width$}  {}", i + 1, path.display(), width = width);
+    }
+}
+
+// A report that can't be written shouldn't fail a run that already copied
+fn write_report(path: &str, report: &ProcessReport) {
+    let written = serde_json::to_string_pretty(report)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(path, json).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        eprintln!("{}: Failed to write report to {}: {}", "Warning".yellow(), path, e);
+    }
+}
+
+fn print_timings(durations: &PhaseDurations) {
+    let phases = [
+        ("config", durations.config),
+        ("tree", durations.tree),
+        ("scan", durations.scan),
+        ("process", durations.process),
+        ("clipboard", durations.clipboard),
+    ];
+    let total: Duration = phases.iter().map(|(_, d)| *d).sum();
+    
+    eprintln!("{}", "Timings".bold());
+    for (name, duration) in phases {
+        eprintln!("  {:<12} {:>9.3}s", name, duration.as_secs_f64());
+    }
+    // Summed across worker threads, so these can exceed the process wall time
+    eprintln!("  {:<12} {:>9.3}s {}", "  sniffing", durations.sniff.as_secs_f64(), "(summed across threads)".dimmed());
+    eprintln!("  {:<12} {:>9.3}s {}", "  reading", durations.read.as_secs_f64(), "(summed across threads)".dimmed());
+    eprintln!("  {:<12} {:>9.3}s", "total", total.as_secs_f64());
+}
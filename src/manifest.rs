@@ -0,0 +1,158 @@
+use crate::error::YoinkError;
+use crate::report::{FileOutcome, FileRecord};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Where a manifest is read from or written to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManifestLocation {
+    /// Under the cache directory, keyed by the scan root
+    Default,
+    Path(PathBuf),
+}
+
+impl ManifestLocation {
+    /// The file this location refers to for a scan of `root`
+    pub fn resolve(&self, root: &Path) -> PathBuf {
+        match self {
+            Self::Path(path) => path.clone(),
+            Self::Default => {
+                let canonical = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+                let mut hasher = DefaultHasher::new();
+                canonical.hash(&mut hasher);
+
+                let mut path = dirs::cache_dir().unwrap_or_else(std::env::temp_dir);
+                path.push("yoink");
+                path.push("manifests");
+                path.push(format!("{:016x}.json", hasher.finish()));
+                path
+            }
+        }
+    }
+}
+
+/// Content hashes of the files in a yoink, for finding what changed later
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    /// Hex content hash keyed by path relative to the scan root
+    pub files: BTreeMap<String, String>,
+}
+
+/// How the current files compare to a saved manifest
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ManifestDiff {
+    pub manifest: PathBuf,
+    /// Included files whose content changed since the manifest
+    pub changed: usize,
+    /// Included files the manifest didn't know about
+    pub new_files: usize,
+    /// Files in the manifest that are no longer there
+    pub deleted: Vec<String>,
+}
+
+impl Manifest {
+    pub const VERSION: u32 = 1;
+
+    pub fn new(files: BTreeMap<String, String>) -> Self {
+        Self { version: Self::VERSION, files }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, YoinkError> {
+        let error = |message: String| YoinkError::Manifest { path: path.to_path_buf(), message };
+        let data = fs::read(path).map_err(|e| error(e.to_string()))?;
+        let manifest: Self = serde_json::from_slice(&data).map_err(|e| error(e.to_string()))?;
+        if manifest.version != Self::VERSION {
+            return Err(error(format!("unsupported manifest version {}", manifest.version)));
+        }
+        Ok(manifest)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), YoinkError> {
+        let error = |message: String| YoinkError::Manifest { path: path.to_path_buf(), message };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| error(e.to_string()))?;
+        }
+        let data = serde_json::to_vec_pretty(self).map_err(|e| error(e.to_string()))?;
+        fs::write(path, data).map_err(|e| error(e.to_string()))
+    }
+
+    /// Whether `key` is recorded with exactly this hash
+    pub fn is_unchanged(&self, key: &str, hash: &str) -> bool {
+        self.files.get(key).is_some_and(|h| h == hash)
+    }
+
+    /// Compare this run's records to the manifest
+    ///
+    /// `scanned` holds the keys of every file found, whether or not it was
+    /// included, so that only files that are really gone count as deleted.
+    pub fn compare(&self, location: &Path, base: &Path, files: &[FileRecord], scanned: &BTreeSet<String>) -> ManifestDiff {
+        let mut diff = ManifestDiff { manifest: location.to_path_buf(), ..ManifestDiff::default() };
+        for file in files.iter().filter(|f| f.outcome == FileOutcome::Included) {
+            if self.files.contains_key(&key(base, &file.path)) {
+                diff.changed += 1;
+            } else {
+                diff.new_files += 1;
+            }
+        }
+        diff.deleted = self.files.keys().filter(|k| !scanned.contains(*k)).cloned().collect();
+        diff
+    }
+}
+
+/// Key for `path` in a manifest: relative to `base`, with `/` separators
+pub fn key(base: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(base).unwrap_or(path);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Hex form of a file's content hash as stored in a manifest
+pub fn hash_hex(content: &str) -> String {
+    format!("{:016x}", crate::cache::content_hash(content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(path: &str, outcome: FileOutcome) -> FileRecord {
+        FileRecord { path: PathBuf::from("/root").join(path), size: 1, outcome, git: None, lines: 0, chars: 0 }
+    }
+
+    #[test]
+    fn test_round_trip_and_compare() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = dir.path().join("nested/manifest.json");
+        let manifest = Manifest::new(BTreeMap::from([
+            ("src/a.rs".to_string(), hash_hex("a")),
+            ("gone.txt".to_string(), hash_hex("g")),
+        ]));
+        manifest.save(&location).unwrap();
+
+        let loaded = Manifest::load(&location).unwrap();
+        assert_eq!(loaded, manifest);
+        assert!(loaded.is_unchanged("src/a.rs", &hash_hex("a")));
+        assert!(!loaded.is_unchanged("src/a.rs", &hash_hex("b")));
+
+        let files = [record("src/a.rs", FileOutcome::Included), record("new.txt", FileOutcome::Included)];
+        let scanned = BTreeSet::from(["src/a.rs".to_string(), "new.txt".to_string()]);
+        let diff = loaded.compare(&location, Path::new("/root"), &files, &scanned);
+        assert_eq!((diff.changed, diff.new_files), (1, 1));
+        assert_eq!(diff.deleted, ["gone.txt"]);
+    }
+
+    #[test]
+    fn test_unknown_version_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = dir.path().join("manifest.json");
+        fs::write(&location, r#"{"version": 99, "files": {}}"#).unwrap();
+        assert!(matches!(Manifest::load(&location), Err(YoinkError::Manifest { .. })));
+    }
+}
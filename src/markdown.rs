@@ -0,0 +1,148 @@
+//! Markdown output: file headings over fenced code blocks
+
+use std::path::Path;
+
+/// Code fence language for a file, from its name or extension
+pub fn language(path: &Path) -> Option<&'static str> {
+    let name = path.file_name()?.to_string_lossy();
+    match name.as_ref() {
+        "Dockerfile" => return Some("dockerfile"),
+        "Makefile" | "GNUmakefile" => return Some("makefile"),
+        "CMakeLists.txt" => return Some("cmake"),
+        _ => {}
+    }
+    let extension = path.extension()?.to_string_lossy().to_ascii_lowercase();
+    let language = match extension.as_str() {
+        "rs" => "rust",
+        "py" | "pyi" => "python",
+        "ts" | "mts" | "cts" => "typescript",
+        "tsx" => "tsx",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "jsx",
+        "go" => "go",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "scala" => "scala",
+        "swift" => "swift",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" | "hxx" => "cpp",
+        "cs" => "csharp",
+        "rb" => "ruby",
+        "php" => "php",
+        "lua" => "lua",
+        "r" => "r",
+        "dart" => "dart",
+        "ex" | "exs" => "elixir",
+        "erl" | "hrl" => "erlang",
+        "hs" => "haskell",
+        "ml" | "mli" => "ocaml",
+        "clj" | "cljs" => "clojure",
+        "zig" => "zig",
+        "sh" | "bash" => "bash",
+        "zsh" => "zsh",
+        "fish" => "fish",
+        "ps1" => "powershell",
+        "sql" => "sql",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "scss" => "scss",
+        "vue" => "vue",
+        "svelte" => "svelte",
+        "json" => "json",
+        "yaml" | "yml" => "yaml",
+        "toml" => "toml",
+        "xml" => "xml",
+        "md" | "markdown" => "markdown",
+        "diff" | "patch" => "diff",
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// A fence longer than any run of backticks inside `content`, and at least three
+pub fn fence(content: &str) -> String {
+    let longest = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+/// `content` in a fenced block tagged with `info`, which may be empty
+pub fn code_block(info: &str, content: &str) -> String {
+    let fence = fence(content);
+    let mut block = format!("{}{}\n{}", fence, info, content);
+    if !content.is_empty() && !content.ends_with('\n') {
+        block.push('\n');
+    }
+    block.push_str(&fence);
+    block.push('\n');
+    block
+}
+
+/// A file's section: its heading, then its content in a fenced block
+pub fn file_chunk(heading: &str, info: &str, content: &str) -> String {
+    format!("## {}\n\n{}\n", heading, code_block(info, content))
+}
+
+/// Restyle a plain `=== heading === note` chunk, such as a summary or
+/// duplicate stub, as a Markdown one
+pub fn from_plain(chunk: &str) -> String {
+    let (first, body) = chunk.split_once('\n').unwrap_or((chunk, ""));
+    let heading = first.strip_prefix("=== ").unwrap_or(first);
+    let heading = match heading.split_once(" ===") {
+        Some((name, note)) => format!("{}{}", name, note),
+        None => heading.to_string(),
+    };
+    let body = body.trim_end_matches('\n');
+    if body.is_empty() {
+        format!("## {}\n\n", heading)
+    } else {
+        file_chunk(&heading, "", body)
+    }
+}
+
+/// A Markdown file chunk split into its heading and opening fence, the lines
+/// inside the block, and the closing fence, or `None` for any other chunk
+pub fn split(chunk: &str) -> Option<(&str, &str, &str)> {
+    if !chunk.starts_with("## ") {
+        return None;
+    }
+    let open = chunk.find("\n`")? + 1;
+    let body_start = open + chunk[open..].find('\n')? + 1;
+    let trimmed = chunk.trim_end_matches('\n');
+    let close = trimmed.rfind('\n')? + 1;
+    if close < body_start {
+        return None;
+    }
+    Some((&chunk[..body_start], &chunk[body_start..close], &chunk[close..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_from_extension_or_name() {
+        assert_eq!(language(Path::new("src/main.rs")), Some("rust"));
+        assert_eq!(language(Path::new("app/Component.TSX")), Some("tsx"));
+        assert_eq!(language(Path::new("Dockerfile")), Some("dockerfile"));
+        assert_eq!(language(Path::new("notes.txt")), None);
+        assert_eq!(language(Path::new("LICENSE")), None);
+    }
+
+    #[test]
+    fn test_fence_outgrows_backticks_in_content() {
+        assert_eq!(fence("plain"), "```");
+        assert_eq!(fence("```rust\nfn main() {}\n```"), "````");
+        assert_eq!(code_block("md", "a ````` b"), "``````md\na ````` b\n``````\n");
+    }
+
+    #[test]
+    fn test_chunks_split_and_restyle() {
+        let chunk = file_chunk("src/lib.rs", "rust", "pub fn a() {}\n");
+        assert_eq!(chunk, "## src/lib.rs\n\n```rust\npub fn a() {}\n```\n\n");
+        assert_eq!(split(&chunk), Some(("## src/lib.rs\n\n```rust\n", "pub fn a() {}\n", "```\n\n")));
+        assert_eq!(split("=== a ===\nbody\n"), None);
+
+        assert_eq!(from_plain("=== b.rs === (identical to a.rs)\n\n"), "## b.rs (identical to a.rs)\n\n");
+        assert_eq!(from_plain("=== big.log === (summarized: 2 MB)\nfirst\n\n"), "## big.log (summarized: 2 MB)\n\n```\nfirst\n```\n\n");
+    }
+}
@@ -0,0 +1,194 @@
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Extensions of raster images whose dimensions can be read from the header
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp"];
+
+/// An image left out of the text, described by its header
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Asset {
+    pub path: PathBuf,
+    pub size: u64,
+    /// Width and height in pixels, when the header could be read
+    pub dimensions: Option<(u32, u32)>,
+}
+
+impl Asset {
+    /// Describe `path` if it's an image, reading only its header
+    ///
+    /// A corrupt or unrecognized header still yields an entry, just without
+    /// dimensions.
+    pub fn read(path: &Path, size: u64) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        if !IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+            return None;
+        }
+
+        let dimensions = File::open(path)
+            .and_then(|file| image_dimensions(&mut BufReader::new(file)))
+            .map_err(|e| log::debug!("No dimensions for {}: {}", path.display(), e))
+            .ok()
+            .flatten();
+        Some(Self { path: path.to_path_buf(), size, dimensions })
+    }
+}
+
+/// Width and height from the header of a PNG, GIF, JPEG, or WebP image
+pub fn image_dimensions<R: Read + Seek>(reader: &mut R) -> io::Result<Option<(u32, u32)>> {
+    let mut magic = [0u8; 12];
+    reader.read_exact(&mut magic)?;
+
+    if magic.starts_with(b"\x89PNG\r\n\x1a\n") {
+        // The IHDR chunk always comes first
+        reader.seek(SeekFrom::Start(16))?;
+        return Ok(Some((read_u32_be(reader)?, read_u32_be(reader)?)));
+    }
+    if magic.starts_with(b"GIF8") {
+        let (w, h) = (u16::from_le_bytes([magic[6], magic[7]]), u16::from_le_bytes([magic[8], magic[9]]));
+        return Ok(Some((w as u32, h as u32)));
+    }
+    if magic.starts_with(&[0xff, 0xd8]) {
+        reader.seek(SeekFrom::Start(2))?;
+        return jpeg_dimensions(reader);
+    }
+    if magic.starts_with(b"RIFF") && &magic[8..12] == b"WEBP" {
+        return webp_dimensions(reader);
+    }
+    Ok(None)
+}
+
+// Walk the segment list until a start-of-frame marker, skipping each
+// segment's body rather than reading it
+fn jpeg_dimensions<R: Read + Seek>(reader: &mut R) -> io::Result<Option<(u32, u32)>> {
+    loop {
+        let mut marker = [0u8; 2];
+        reader.read_exact(&mut marker)?;
+        if marker[0] != 0xff {
+            return Ok(None);
+        }
+        match marker[1] {
+            // Padding and standalone markers have no length
+            0xff => {
+                reader.seek(SeekFrom::Current(-1))?;
+                continue;
+            }
+            0x01 | 0xd0..=0xd7 => continue,
+            0xd9 | 0xda => return Ok(None),
+            _ => {}
+        }
+
+        let length = read_u16_be(reader)?;
+        let is_frame = matches!(marker[1], 0xc0..=0xcf) && !matches!(marker[1], 0xc4 | 0xc8 | 0xcc);
+        if is_frame {
+            let mut precision = [0u8; 1];
+            reader.read_exact(&mut precision)?;
+            let height = read_u16_be(reader)?;
+            let width = read_u16_be(reader)?;
+            return Ok(Some((width as u32, height as u32)));
+        }
+        reader.seek(SeekFrom::Current(length as i64 - 2))?;
+    }
+}
+
+fn webp_dimensions<R: Read + Seek>(reader: &mut R) -> io::Result<Option<(u32, u32)>> {
+    let mut chunk = [0u8; 4];
+    reader.read_exact(&mut chunk)?;
+    reader.seek(SeekFrom::Current(4))?;
+    let mut data = [0u8; 10];
+    reader.read_exact(&mut data)?;
+
+    let dimensions = match &chunk {
+        // Lossy: 14-bit sizes after the frame tag and start code
+        b"VP8 " => (
+            u16::from_le_bytes([data[6], data[7]]) as u32 & 0x3fff,
+            u16::from_le_bytes([data[8], data[9]]) as u32 & 0x3fff,
+        ),
+        // Lossless: 14-bit sizes minus one, packed after the signature byte
+        b"VP8L" => {
+            let bits = u32::from_le_bytes([data[1], data[2], data[3], data[4]]);
+            ((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1)
+        }
+        // Extended: 24-bit sizes minus one after the flags
+        b"VP8X" => (
+            u32::from_le_bytes([data[4], data[5], data[6], 0]) + 1,
+            u32::from_le_bytes([data[7], data[8], data[9], 0]) + 1,
+        ),
+        _ => return Ok(None),
+    };
+    Ok(Some(dimensions))
+}
+
+fn read_u16_be(reader: &mut impl Read) -> io::Result<u16> {
+    let mut buf = [0u8; 2];
+    reader.read_exact(&mut buf)?;
+    Ok(u16::from_be_bytes(buf))
+}
+
+fn read_u32_be(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_be_bytes(buf))
+}
+
+/// The `=== ASSETS ===` section listing each image
+pub fn format_assets(assets: &[Asset], base: &Path) -> String {
+    let mut section = String::from("\n=== ASSETS ===\n");
+    for asset in assets {
+        let dimensions = asset
+            .dimensions
+            .map_or_else(|| "?".to_string(), |(w, h)| format!("{}×{}", w, h));
+        section.push_str(&format!(
+            "{:>11}  {:>9}  {}\n",
+            dimensions,
+            crate::utils::format_size(asset.size),
+            asset.path.strip_prefix(base).unwrap_or(&asset.path).display()
+        ));
+    }
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+        image_dimensions(&mut Cursor::new(bytes)).unwrap()
+    }
+
+    #[test]
+    fn test_reads_dimensions_from_headers() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend_from_slice(&640u32.to_be_bytes());
+        png.extend_from_slice(&480u32.to_be_bytes());
+        assert_eq!(dimensions(&png), Some((640, 480)));
+
+        let gif = b"GIF89a\x20\x03\x58\x02\0\0";
+        assert_eq!(dimensions(gif), Some((800, 600)));
+
+        // SOI, an APP0 segment to skip, then a baseline frame header
+        let jpeg = [
+            0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04, 0xaa, 0xbb,
+            0xff, 0xc0, 0x00, 0x11, 0x08, 0x01, 0x00, 0x02, 0x00,
+        ];
+        assert_eq!(dimensions(&jpeg), Some((512, 256)));
+
+        let mut webp = b"RIFF\0\0\0\0WEBPVP8X\0\0\0\0".to_vec();
+        webp.extend_from_slice(&[0, 0, 0, 0, 0x1f, 0x00, 0x00, 0x0f, 0x00, 0x00]);
+        assert_eq!(dimensions(&webp), Some((32, 16)));
+    }
+
+    #[test]
+    fn test_corrupt_header_keeps_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.png");
+        std::fs::write(&path, b"\x89PNG\r\n\x1a\n").unwrap();
+
+        let asset = Asset::read(&path, 8).unwrap();
+        assert_eq!(asset.dimensions, None);
+        assert_eq!(asset.size, 8);
+        assert!(Asset::read(&dir.path().join("data.bin"), 8).is_none());
+    }
+}
@@ -0,0 +1,124 @@
+//! Bounds on when a file was last modified, from `--since` and `--until`
+//!
+//! A bound is either a duration back from now, such as `2h`, `3d`, or `1w`,
+//! or a date, `2024-05-01`, meaning midnight at its start in local time. A
+//! duration is resolved when the bound is read, so one kept in the saved
+//! config always counts back from the current run.
+
+use chrono::{Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+/// A point in time that file modification times are compared against,
+/// along with how it was written
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeBound {
+    text: String,
+    time: SystemTime,
+    relative: bool,
+}
+
+impl TimeBound {
+    /// Read a duration like `3d` or a date like `2024-05-01`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        Self::parse_at(text, SystemTime::now())
+    }
+
+    // `parse`, counting durations back from `now`
+    fn parse_at(text: &str, now: SystemTime) -> Result<Self, String> {
+        let text = text.trim();
+        if let Some(ago) = parse_duration(text) {
+            let time = now.checked_sub(ago).ok_or_else(|| format!("'{}' reaches back too far", text))?;
+            return Ok(Self { text: text.to_string(), time, relative: true });
+        }
+        let date = NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .map_err(|_| format!("'{}' is neither a duration like 2h, 3d, or 1w nor a date like 2024-05-01", text))?;
+        let midnight = date.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+        let local = Local
+            .from_local_datetime(&midnight)
+            .earliest()
+            .ok_or_else(|| format!("midnight on {} doesn't exist in the local time zone", text))?;
+        Ok(Self { text: text.to_string(), time: local.into(), relative: false })
+    }
+
+    /// The instant the bound stands for
+    pub fn time(&self) -> SystemTime {
+        self.time
+    }
+}
+
+/// As written, with `ago` after a duration
+impl fmt::Display for TimeBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.relative {
+            write!(f, "{} ago", self.text)
+        } else {
+            write!(f, "{}", self.text)
+        }
+    }
+}
+
+/// Two bounds are the same when they're written the same, whenever they
+/// were resolved
+impl PartialEq for TimeBound {
+    fn eq(&self, other: &Self) -> bool {
+        self.text == other.text
+    }
+}
+
+impl TryFrom<String> for TimeBound {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        Self::parse(&text)
+    }
+}
+
+impl From<TimeBound> for String {
+    fn from(bound: TimeBound) -> Self {
+        bound.text
+    }
+}
+
+// A whole number followed by s, m, h, d, or w
+fn parse_duration(text: &str) -> Option<Duration> {
+    let split = text.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = text.split_at(split);
+    let amount: u64 = amount.parse().ok()?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    amount.checked_mul(seconds).map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_durations_and_dates() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(30 * 24 * 60 * 60);
+        let bound = |text: &str| TimeBound::parse_at(text, now);
+
+        assert_eq!(bound("2h").unwrap().time(), now - Duration::from_secs(2 * 60 * 60));
+        assert_eq!(bound("3d").unwrap().time(), now - Duration::from_secs(3 * 24 * 60 * 60));
+        assert_eq!(bound("1w").unwrap().time(), now - Duration::from_secs(7 * 24 * 60 * 60));
+        assert_eq!(bound("1w").unwrap().to_string(), "1w ago");
+
+        let date = bound("2024-05-01").unwrap();
+        assert_eq!(date.to_string(), "2024-05-01");
+        let local = Local.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        assert_eq!(date.time(), SystemTime::from(local));
+
+        assert!(bound("3 days").is_err());
+        assert!(bound("2024-13-01").is_err());
+        assert!(bound("h").is_err());
+    }
+}
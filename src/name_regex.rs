@@ -0,0 +1,67 @@
+//! File name matching with a regular expression, from `--regex`
+//!
+//! Unlike `--pattern`, an expression that doesn't compile stops the run
+//! rather than falling back to something that matches nothing.
+
+use serde::{Deserialize, Serialize};
+
+/// A compiled expression checked against each file's name, compiled once
+/// and shared by every thread that scans
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct NameRegex(regex::Regex);
+
+impl NameRegex {
+    /// Compile `text`, with the compiler's explanation when it's invalid
+    pub fn parse(text: &str) -> Result<Self, String> {
+        regex::Regex::new(text).map(Self).map_err(|e| e.to_string())
+    }
+
+    /// Whether the expression matches somewhere in `name`; anchor it with
+    /// `^` and `$` to match the whole name
+    pub fn is_match(&self, name: &str) -> bool {
+        self.0.is_match(name)
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+/// Two expressions are the same when they're written the same
+impl PartialEq for NameRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl TryFrom<String> for NameRegex {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        Self::parse(&text)
+    }
+}
+
+impl From<NameRegex> for String {
+    fn from(regex: NameRegex) -> Self {
+        regex.as_str().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_are_matched_and_bad_syntax_is_rejected() {
+        let regex = NameRegex::parse(r"^(handler|service)_.*\.rs$").unwrap();
+        assert!(regex.is_match("handler_auth.rs") && regex.is_match("service_.rs"));
+        assert!(!regex.is_match("my_handler_auth.rs") && !regex.is_match("service_auth.rs.bak"));
+
+        let error = NameRegex::parse("(handler|service").unwrap_err();
+        assert!(error.contains("unclosed group"), "{}", error);
+        assert_eq!(serde_json::from_str::<NameRegex>(r#""a+""#).unwrap(), NameRegex::parse("a+").unwrap());
+        assert!(serde_json::from_str::<NameRegex>(r#""a(""#).is_err());
+    }
+}
@@ -0,0 +1,220 @@
+//! Writing each included file's chunk to a file of its own
+
+use crate::error::YoinkError;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Version of the `index.json` format, bumped on incompatible changes
+pub const INDEX_VERSION: u32 = 1;
+
+const INDEX_FILE: &str = "index.json";
+
+/// One written chunk, as listed in `index.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IndexEntry {
+    /// Source path relative to the scan root
+    pub source: String,
+    /// Written file relative to the output directory
+    pub file: String,
+    /// Size of the source file
+    pub size: u64,
+    /// Size of the written chunk
+    pub bytes: u64,
+}
+
+/// Where the chunks went and how many there were
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OutputDirSummary {
+    pub path: PathBuf,
+    pub files: usize,
+}
+
+#[derive(Serialize)]
+struct Index<'a> {
+    version: u32,
+    root: &'a str,
+    files: &'a [IndexEntry],
+}
+
+/// A directory receiving one `.txt` file per included file, mirroring the source tree
+#[derive(Debug)]
+pub struct OutputDir {
+    root: PathBuf,
+    /// Lowercased paths already written, so names that differ only by case
+    /// don't overwrite each other on case-insensitive filesystems
+    used: HashSet<String>,
+    entries: Vec<IndexEntry>,
+}
+
+impl OutputDir {
+    /// Create `root`, refusing to write into one that already has files unless `force`
+    pub fn create(root: &Path, force: bool) -> Result<Self, YoinkError> {
+        let error = |message: String| YoinkError::OutputDir { path: root.to_path_buf(), message };
+
+        match fs::read_dir(root).map(|mut existing| existing.next().is_some()) {
+            Ok(true) if !force => {
+                return Err(error("the directory is not empty; pass --force to write into it anyway".to_string()));
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                fs::create_dir_all(root).map_err(|e| error(e.to_string()))?;
+            }
+            Err(e) => return Err(error(e.to_string())),
+        }
+
+        Ok(Self { root: root.to_path_buf(), used: HashSet::new(), entries: Vec::new() })
+    }
+
+    /// Write the chunk for the file at `relative` under the scan root
+    pub fn write(&mut self, relative: &Path, size: u64, chunk: &str) -> Result<(), YoinkError> {
+        let file = self.claim(sanitize(relative));
+        let target = self.root.join(&file);
+        self.prepare(&target)?;
+        fs::write(&target, chunk)
+            .map_err(|e| YoinkError::OutputDir { path: target.clone(), message: e.to_string() })?;
+
+        self.entries.push(IndexEntry {
+            source: relative.to_string_lossy().replace('\\', "/"),
+            file: file.to_string_lossy().replace('\\', "/"),
+            size,
+            bytes: chunk.len() as u64,
+        });
+        Ok(())
+    }
+
+    /// Write `index.json` describing every chunk written
+    pub fn finish(self, root: &str) -> Result<OutputDirSummary, YoinkError> {
+        let path = self.root.join(INDEX_FILE);
+        let index = Index { version: INDEX_VERSION, root, files: &self.entries };
+        let json = serde_json::to_string_pretty(&index).expect("the index always serializes");
+        self.prepare(&path)?;
+        fs::write(&path, json).map_err(|e| YoinkError::OutputDir { path, message: e.to_string() })?;
+        Ok(OutputDirSummary { path: self.root, files: self.entries.len() })
+    }
+
+    // The first free name for `file`, adding -2, -3, ... before `.txt` on a clash
+    fn claim(&mut self, file: PathBuf) -> PathBuf {
+        let stem = file.with_extension("");
+        let mut candidate = file;
+        let mut n = 1;
+        while !self.used.insert(candidate.to_string_lossy().to_lowercase()) {
+            n += 1;
+            candidate = PathBuf::from(format!("{}-{}.txt", stem.to_string_lossy(), n));
+        }
+        candidate
+    }
+
+    // Create the parents of `target`, refusing to follow any symlink that
+    // could lead outside the output directory
+    fn prepare(&self, target: &Path) -> Result<(), YoinkError> {
+        let escape = |path: &Path| YoinkError::OutputDir {
+            path: path.to_path_buf(),
+            message: "is a symlink, which could lead outside the output directory".to_string(),
+        };
+        let relative = target.strip_prefix(&self.root).expect("targets are built under the root");
+        let mut current = self.root.clone();
+        for component in relative.components() {
+            current.push(component);
+            match fs::symlink_metadata(&current) {
+                Ok(meta) if meta.file_type().is_symlink() => return Err(escape(&current)),
+                Ok(_) => {}
+                Err(_) if current != target => {
+                    fs::create_dir(&current)
+                        .map_err(|e| YoinkError::OutputDir { path: current.clone(), message: e.to_string() })?;
+                }
+                Err(_) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+// A relative path made of plain, portable components, ending in `.txt`
+fn sanitize(relative: &Path) -> PathBuf {
+    let mut sanitized: PathBuf = relative
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(sanitize_component(&name.to_string_lossy())),
+            Component::ParentDir => Some("_".to_string()),
+            _ => None,
+        })
+        .collect();
+    if sanitized.as_os_str().is_empty() {
+        sanitized.push("_");
+    }
+    let name = format!("{}.txt", sanitized.file_name().unwrap_or_default().to_string_lossy());
+    sanitized.set_file_name(name);
+    sanitized
+}
+
+fn sanitize_component(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    match cleaned.trim_end_matches(['.', ' ']) {
+        "" => "_".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitized_paths_stay_inside() {
+        assert_eq!(sanitize(Path::new("src/main.rs")), PathBuf::from("src/main.rs.txt"));
+        assert_eq!(sanitize(Path::new("../../etc/passwd")), PathBuf::from("_/_/etc/passwd.txt"));
+        assert_eq!(sanitize(Path::new("/abs/a:b?.rs")), PathBuf::from("abs/a_b_.rs.txt"));
+        assert_eq!(sanitize(Path::new("dir./..")), PathBuf::from("dir/_.txt"));
+    }
+
+    #[test]
+    fn test_collisions_are_disambiguated() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("out");
+        let mut out = OutputDir::create(&root, false).unwrap();
+        out.write(Path::new("a:b.rs"), 1, "first").unwrap();
+        out.write(Path::new("a_b.rs"), 1, "second").unwrap();
+        out.write(Path::new("A_B.rs"), 1, "third").unwrap();
+        let summary = out.finish("project").unwrap();
+
+        assert_eq!(summary.files, 3);
+        assert_eq!(fs::read_to_string(root.join("a_b.rs.txt")).unwrap(), "first");
+        assert_eq!(fs::read_to_string(root.join("a_b.rs-2.txt")).unwrap(), "second");
+        assert_eq!(fs::read_to_string(root.join("A_B.rs-3.txt")).unwrap(), "third");
+
+        let index: serde_json::Value = serde_json::from_str(&fs::read_to_string(root.join("index.json")).unwrap()).unwrap();
+        assert_eq!(index["version"], INDEX_VERSION);
+        assert_eq!(index["files"][1]["source"], "a_b.rs");
+        assert_eq!(index["files"][1]["file"], "a_b.rs-2.txt");
+    }
+
+    #[test]
+    fn test_non_empty_directory_needs_force() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("keep.txt"), "x").unwrap();
+
+        assert!(matches!(OutputDir::create(dir.path(), false), Err(YoinkError::OutputDir { .. })));
+        assert!(OutputDir::create(dir.path(), true).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_subdirectory_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("src")).unwrap();
+
+        let mut out = OutputDir::create(dir.path(), true).unwrap();
+        assert!(out.write(Path::new("src/main.rs"), 1, "x").is_err());
+        assert!(fs::read_dir(outside.path()).unwrap().next().is_none());
+    }
+}
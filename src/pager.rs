@@ -0,0 +1,91 @@
+use crate::error::YoinkError;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Lines shown when no pager could be started
+const FALLBACK_LINES: usize = 100;
+
+/// The user's pager command, split into program and arguments
+///
+/// Uses `$PAGER`, falling back to `less -R`.
+pub fn pager_command() -> Vec<String> {
+    std::env::var("PAGER")
+        .ok()
+        .map(|value| value.split_whitespace().map(str::to_string).collect::<Vec<_>>())
+        .filter(|parts| !parts.is_empty())
+        .unwrap_or_else(|| vec!["less".to_string(), "-R".to_string()])
+}
+
+/// Show the contents of `path` in the user's pager and wait for it to exit
+///
+/// If the pager can't be started, the first lines are printed instead.
+pub fn page_file(path: &Path) -> Result<(), YoinkError> {
+    let command = pager_command();
+    let (program, args) = command.split_first().expect("pager command is never empty");
+
+    let mut file = File::open(path).map_err(|source| YoinkError::Output { source })?;
+    let mut child = match Command::new(program).args(args).stdin(Stdio::piped()).spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            log::warn!("Could not start pager '{}': {}", command.join(" "), e);
+            return print_head(file, FALLBACK_LINES);
+        }
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // Quitting the pager early closes the pipe, which isn't an error
+        match io::copy(&mut file, &mut stdin) {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                return Err(YoinkError::Output { source: e });
+            }
+            _ => {}
+        }
+    }
+
+    child.wait().map_err(|source| YoinkError::Output { source })?;
+    Ok(())
+}
+
+fn print_head(file: File, lines: usize) -> Result<(), YoinkError> {
+    let mut stdout = io::stdout().lock();
+    for (shown, line) in BufReader::new(file).lines().enumerate() {
+        if shown == lines {
+            writeln!(stdout, "... (output truncated after {} lines)", lines)
+                .map_err(|source| YoinkError::Output { source })?;
+            break;
+        }
+        let line = line.map_err(|source| YoinkError::Output { source })?;
+        writeln!(stdout, "{}", line).map_err(|source| YoinkError::Output { source })?;
+    }
+    Ok(())
+}
+
+/// Ask a yes/no question on stderr, treating an empty answer as yes
+pub fn confirm(prompt: &str) -> Result<bool, YoinkError> {
+    eprint!("{} [Y/n] ", prompt);
+    io::stderr().flush().map_err(|source| YoinkError::Output { source })?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).map_err(|source| YoinkError::Output { source })?;
+    Ok(is_yes(&answer))
+}
+
+fn is_yes(answer: &str) -> bool {
+    matches!(answer.trim().to_lowercase().as_str(), "" | "y" | "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_answer_means_yes() {
+        assert!(is_yes("\n"));
+        assert!(is_yes(" Y \n"));
+        assert!(is_yes("yes"));
+        assert!(!is_yes("n\n"));
+        assert!(!is_yes("nope"));
+    }
+}
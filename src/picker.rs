@@ -0,0 +1,128 @@
+use crate::error::YoinkError;
+use crate::file_scanner::FileEntry;
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Exit status fzf uses when the selection is cancelled with Esc or Ctrl-C
+const FZF_CANCELLED: i32 = 130;
+
+/// The picker command, split into program and arguments
+///
+/// Uses `$YOINK_PICKER`, falling back to `fzf --multi`. With `preview` set,
+/// fzf's `--preview` option is added so the highlighted file is shown.
+pub fn picker_command(preview: bool) -> Vec<String> {
+    let mut command = std::env::var("YOINK_PICKER")
+        .ok()
+        .map(|value| value.split_whitespace().map(str::to_string).collect::<Vec<_>>())
+        .filter(|parts| !parts.is_empty())
+        .unwrap_or_else(|| vec!["fzf".to_string(), "--multi".to_string()]);
+    if preview {
+        command.extend(["--preview".to_string(), "head -n 200 -- {}".to_string()]);
+    }
+    command
+}
+
+/// Let the user choose which of `entries` to keep
+///
+/// Paths are offered relative to `root`, and the picker is run from `root`
+/// so a preview command can open them.
+pub fn pick(root: &Path, entries: Vec<FileEntry>, preview: bool) -> Result<Vec<FileEntry>, YoinkError> {
+    pick_with(&picker_command(preview), root, entries)
+}
+
+fn pick_with(command: &[String], root: &Path, mut entries: Vec<FileEntry>) -> Result<Vec<FileEntry>, YoinkError> {
+    let (program, args) = command.split_first().expect("picker command is never empty");
+    let base = crate::utils::relative_base(root);
+    let relative = |entry: &FileEntry| -> PathBuf {
+        entry.path().strip_prefix(base).unwrap_or(entry.path()).to_path_buf()
+    };
+
+    let mut child = Command::new(program)
+        .args(args)
+        .current_dir(base)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|source| YoinkError::Picker { command: command.join(" "), source })?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let candidates: String = entries
+            .iter()
+            .map(|entry| format!("{}\n", relative(entry).display()))
+            .collect();
+        // A picker that exits without reading everything isn't an error
+        match stdin.write_all(candidates.as_bytes()) {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                return Err(YoinkError::Output { source: e });
+            }
+            _ => {}
+        }
+    }
+
+    let output = child.wait_with_output().map_err(|source| YoinkError::Output { source })?;
+    if output.status.code() == Some(FZF_CANCELLED) {
+        return Err(YoinkError::Aborted { reason: "file selection cancelled".to_string() });
+    }
+
+    let selected: HashSet<PathBuf> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect();
+    if selected.is_empty() {
+        return Err(YoinkError::Aborted { reason: "no files were selected".to_string() });
+    }
+
+    let offered = entries.len();
+    entries.retain(|entry| selected.contains(&relative(entry)));
+    log::debug!("Picked {} of {} files", entries.len(), offered);
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(script: &str) -> Vec<String> {
+        ["sh", "-c", script].map(String::from).to_vec()
+    }
+
+    #[test]
+    fn test_only_picked_files_are_kept() {
+        let fixture = crate::fixture! {
+            "keep.txt" => "a",
+            "src/keep.rs" => "b",
+            "drop.txt" => "c",
+        };
+        let (dir, entries) = fixture.into_parts();
+
+        let picked = pick_with(&script("grep keep"), dir.path(), entries).unwrap();
+        let mut names: Vec<_> = picked
+            .iter()
+            .map(|e| e.path().strip_prefix(dir.path()).unwrap().to_path_buf())
+            .collect();
+        names.sort();
+        assert_eq!(names, [PathBuf::from("keep.txt"), PathBuf::from("src/keep.rs")]);
+    }
+
+    #[test]
+    fn test_cancelled_or_empty_pick_aborts() {
+        let fixture = crate::fixture! { "a.txt" => "a" };
+
+        let result = pick_with(&script("cat > /dev/null; exit 130"), fixture.path(), fixture.entries().to_vec());
+        assert!(matches!(result, Err(YoinkError::Aborted { .. })));
+
+        let result = pick_with(&script("cat > /dev/null"), fixture.path(), fixture.entries().to_vec());
+        assert!(matches!(result, Err(YoinkError::Aborted { .. })));
+    }
+
+    #[test]
+    fn test_missing_picker_is_reported() {
+        let fixture = crate::fixture! { "a.txt" => "a" };
+        let command = vec!["yoink-no-such-picker".to_string()];
+        let result = pick_with(&command, fixture.path(), fixture.entries().to_vec());
+        assert!(matches!(result, Err(YoinkError::Picker { .. })));
+    }
+}
@@ -0,0 +1,103 @@
+use colored::*;
+
+/// Lines kept from each file: its header plus the first three lines
+const LINES_PER_FILE: usize = 4;
+
+/// What part of the output a chunk belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Section {
+    /// Directory tree and the text files heading
+    Header,
+    File,
+    /// Image metadata table
+    Assets,
+    Summary,
+}
+
+/// Compact view of the output, built while it is written
+///
+/// The header and summary are kept whole and each file is cut to its first
+/// lines, so the digest stays small however large the output is.
+#[derive(Debug, Default)]
+pub struct Digest {
+    header: String,
+    files: Vec<(Vec<String>, usize)>,
+    assets: String,
+    summary: String,
+    bytes: u64,
+}
+
+impl Digest {
+    pub(crate) fn observe(&mut self, section: Section, chunk: &str) {
+        self.bytes += chunk.len() as u64;
+        match section {
+            Section::Header => self.header.push_str(chunk),
+            Section::Assets => self.assets.push_str(chunk),
+            Section::Summary => self.summary.push_str(chunk),
+            Section::File => {
+                let mut lines = chunk.lines();
+                let mut kept: Vec<String> = lines.by_ref().take(LINES_PER_FILE).map(str::to_string).collect();
+                while kept.last().is_some_and(|l| l.is_empty()) {
+                    kept.pop();
+                }
+                let rest = lines.filter(|l| !l.is_empty()).count();
+                self.files.push((kept, rest));
+            }
+        }
+    }
+
+    /// Rough token count, assuming about four bytes per token
+    pub fn estimated_tokens(&self) -> u64 {
+        self.bytes.div_ceil(4)
+    }
+
+    /// Colorized digest for printing to the terminal
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&self.header.trim_end().dimmed().to_string());
+        out.push_str("\n\n");
+
+        for (lines, rest) in &self.files {
+            let mut lines = lines.iter();
+            if let Some(header) = lines.next() {
+                out.push_str(&format!("{}\n", header.cyan().bold()));
+            }
+            for line in lines {
+                out.push_str(&format!("{}\n", line));
+            }
+            if *rest > 0 {
+                out.push_str(&format!("{}\n", format!("… {} more lines", rest).dimmed()));
+            }
+        }
+
+        if !self.assets.is_empty() {
+            out.push_str(&format!("\n{}\n", self.assets.trim().dimmed()));
+        }
+        out.push_str(&self.summary.trim().yellow().to_string());
+        out.push_str(&format!(
+            "\n{}\n",
+            format!("Size: {} bytes (~{} tokens)", self.bytes, self.estimated_tokens()).bold()
+        ));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_files_are_cut_to_first_lines() {
+        colored::control::set_override(false);
+        let mut digest = Digest::default();
+        digest.observe(Section::Header, "=== DIRECTORY STRUCTURE ===\n📁 root/\n\n=== TEXT FILES ===\n\n");
+        digest.observe(Section::File, "=== a.txt ===\n1\n2\n3\n4\n5\n\n\n");
+        digest.observe(Section::File, "=== b.txt ===\nonly\n\n\n");
+        digest.observe(Section::Summary, "\n=== SUMMARY ===\nText files processed: 2\nBinary files skipped: 0\n");
+
+        let rendered = digest.render();
+        assert!(rendered.contains("=== a.txt ===\n1\n2\n3\n… 2 more lines\n=== b.txt ===\nonly\n"));
+        assert!(rendered.contains("Text files processed: 2\nBinary files skipped: 0\n"));
+        assert!(rendered.ends_with(&format!("Size: {} bytes (~{} tokens)\n", digest.bytes, digest.estimated_tokens())));
+    }
+}
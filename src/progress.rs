@@ -0,0 +1,20 @@
+use indicatif::{MultiProgress, ProgressBar};
+use std::sync::OnceLock;
+
+/// Shared display that every progress bar and spinner is drawn through,
+/// so the logger can suspend them while it writes a line
+pub fn multi() -> &'static MultiProgress {
+    static MULTI: OnceLock<MultiProgress> = OnceLock::new();
+    MULTI.get_or_init(MultiProgress::new)
+}
+
+/// Register a bar with the shared display
+pub fn add(pb: ProgressBar) -> ProgressBar {
+    multi().add(pb)
+}
+
+/// Clear a bar and drop it from the shared display
+pub fn finish(pb: &ProgressBar) {
+    pb.finish_and_clear();
+    multi().remove(pb);
+}
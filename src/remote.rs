@@ -0,0 +1,172 @@
+use crate::error::YoinkError;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+/// A git repository to clone instead of a local path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteRepo {
+    pub url: String,
+    /// Repository name, used for the checkout directory and so the tree header
+    pub name: String,
+}
+
+impl RemoteRepo {
+    /// Recognize a git URL or `org/repo` GitHub shorthand
+    ///
+    /// Anything that exists on disk is always treated as a local path, so a
+    /// local directory that happens to look like `org/repo` never triggers a
+    /// network request. The same goes for a missing `dir/file` whose `dir`
+    /// exists, which is far more likely a typo than a repository.
+    pub fn parse(arg: &str) -> Option<Self> {
+        if Path::new(arg).exists() {
+            return None;
+        }
+
+        let is_url = ["https://", "http://", "ssh://", "git://", "git@"]
+            .iter()
+            .any(|scheme| arg.starts_with(scheme));
+        let url = if is_url {
+            arg.to_string()
+        } else if is_shorthand(arg) {
+            format!("https://github.com/{}.git", arg)
+        } else {
+            return None;
+        };
+
+        let name = url
+            .trim_end_matches('/')
+            .rsplit(['/', ':'])
+            .next()
+            .map(|last| last.trim_end_matches(".git"))
+            .filter(|name| !name.is_empty())
+            .unwrap_or("repo")
+            .to_string();
+
+        Some(Self { url, name })
+    }
+
+    /// Shallow-clone the repository into a temporary directory
+    pub fn clone_into_temp(&self, branch: Option<&str>) -> Result<Checkout, YoinkError> {
+        let dir = tempfile::Builder::new()
+            .prefix("yoink-clone-")
+            .tempdir()
+            .map_err(|source| YoinkError::Output { source })?;
+        let path = dir.path().join(&self.name);
+
+        let mut command = Command::new("git");
+        command.args(["clone", "--depth", "1", "--quiet"]);
+        if let Some(branch) = branch {
+            command.args(["--branch", branch]);
+        }
+        command.arg(&self.url).arg(&path);
+
+        log::debug!("Cloning {} into {}", self.url, path.display());
+        let output = command
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => YoinkError::GitNotFound,
+                _ => YoinkError::Clone { url: self.url.clone(), message: e.to_string() },
+            })?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(YoinkError::Clone {
+                url: self.url.clone(),
+                message: stderr.trim().to_string(),
+            });
+        }
+
+        Ok(Checkout { dir: Some(dir), path })
+    }
+}
+
+/// A cloned repository, deleted when dropped unless kept
+pub struct Checkout {
+    dir: Option<TempDir>,
+    path: PathBuf,
+}
+
+impl Checkout {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Leave the checkout on disk after yoink exits
+    pub fn keep(&mut self) {
+        if let Some(dir) = self.dir.take() {
+            let _ = dir.keep();
+        }
+    }
+}
+
+// `org/repo`, with no other slashes, only characters GitHub allows, and no
+// local `org` directory
+fn is_shorthand(arg: &str) -> bool {
+    let valid = |part: &str| {
+        !part.is_empty()
+            && !part.starts_with('.')
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+    matches!(arg.split_once('/'), Some((org, repo)) if valid(org) && valid(repo) && !Path::new(org).exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_recognizes_remotes_but_not_local_paths() {
+        let https = RemoteRepo::parse("https://github.com/user/repo").unwrap();
+        assert_eq!(https.url, "https://github.com/user/repo");
+        assert_eq!(https.name, "repo");
+
+        let ssh = RemoteRepo::parse("git@github.com:user/tool.git").unwrap();
+        assert_eq!(ssh.name, "tool");
+
+        let shorthand = RemoteRepo::parse("rust-lang/rust").unwrap();
+        assert_eq!(shorthand.url, "https://github.com/rust-lang/rust.git");
+
+        // Local paths never parse as remotes, even when they look like `org/repo`
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("org/repo")).unwrap();
+        let local = dir.path().join("org/repo");
+        assert!(RemoteRepo::parse(&local.to_string_lossy()).is_none());
+        assert!(RemoteRepo::parse(".").is_none());
+        assert!(RemoteRepo::parse("src/main.rs/extra").is_none());
+        assert!(RemoteRepo::parse("./org/repo").is_none());
+    }
+
+    #[test]
+    fn test_clone_into_temp_is_removed_on_drop() {
+        let origin = tempfile::tempdir().unwrap();
+        std::fs::write(origin.path().join("README.md"), "hello").unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(["-c", "user.name=yoink", "-c", "user.email=yoink@example.com"])
+                .args(args)
+                .current_dir(origin.path())
+                .output()
+        };
+        // Skip where git isn't installed
+        if git(&["init", "--quiet"]).is_err() {
+            return;
+        }
+        git(&["add", "."]).unwrap();
+        git(&["commit", "--quiet", "-m", "init"]).unwrap();
+
+        let repo = RemoteRepo {
+            url: format!("file://{}", origin.path().display()),
+            name: "origin".to_string(),
+        };
+        let checkout = repo.clone_into_temp(None).unwrap();
+        let cloned = checkout.path().to_path_buf();
+        assert!(cloned.ends_with("origin"));
+        assert_eq!(std::fs::read_to_string(cloned.join("README.md")).unwrap(), "hello");
+
+        drop(checkout);
+        assert!(!cloned.exists());
+    }
+}
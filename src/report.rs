@@ -0,0 +1,247 @@
+use crate::clipboard::ClipboardReceipt;
+use crate::dir_cap::DirCap;
+use crate::excluded::ExcludedDir;
+use crate::git::GitMeta;
+use crate::group::GroupSummary;
+use crate::manifest::ManifestDiff;
+use crate::media::Asset;
+use crate::output_dir::OutputDirSummary;
+use crate::roots::RootSummary;
+use crate::sample::SampleSummary;
+use crate::serve::ServeReceipt;
+use crate::target::Overflow;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Everything known about a finished run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProcessReport {
+    pub text_files: usize,
+    pub binary_files: usize,
+    pub skipped: SkipCounts,
+    /// One record per scanned file, in output order
+    pub files: Vec<FileRecord>,
+    /// Combined size of all scanned files
+    pub total_bytes: u64,
+    /// Size of the assembled output
+    pub output_bytes: u64,
+    /// Tokens the whole output comes to, at `fit::CHARS_PER_TOKEN` characters each
+    pub estimated_tokens: u64,
+    /// Files whose classification was served from the cache
+    pub cache_hits: usize,
+    /// Bytes `--condense` took out of the files written
+    pub condensed_bytes: u64,
+    /// Bytes of duplicate files replaced by stubs
+    pub deduped_bytes: u64,
+    pub durations: PhaseDurations,
+    /// Present once the output has been copied to the clipboard
+    pub clipboard: Option<ClipboardReceipt>,
+    /// Which run the output became when appended to the clipboard's yoink output
+    pub appended_run: Option<usize>,
+    /// Present once the output has been fetched over HTTP
+    pub served: Option<ServeReceipt>,
+    /// Images described by their headers, when media info was requested
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub assets: Vec<Asset>,
+    /// Present when files were compared against a saved manifest
+    pub manifest: Option<ManifestDiff>,
+    /// Where this run's manifest was written, if one was requested
+    pub manifest_saved: Option<PathBuf>,
+    /// Present when only changes against a revision were yoinked
+    pub diff: Option<DiffSummary>,
+    /// Present when the candidates were randomly sampled
+    pub sample: Option<SampleSummary>,
+    /// Files cut short to fit the token budget
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub truncated: Vec<Truncation>,
+    /// Separate git repositories below the root that weren't walked
+    pub skipped_repos: usize,
+    /// Files and bytes written under each group, in output order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<GroupSummary>,
+    /// What each root contributed, when files came from several
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub roots: Vec<RootSummary>,
+    /// `#[cfg(test)]` modules cut from Rust files by `--skip-tests`
+    pub test_modules_stripped: usize,
+    /// Directories that had files left out by the per-directory limits
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub capped_dirs: Vec<DirCap>,
+    /// Excluded directories and what's directly inside them, with `--excluded-overview`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub excluded_dirs: Vec<ExcludedDir>,
+    /// Entries of the `--files-from` list that don't exist, in list order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_listed: Vec<PathBuf>,
+    /// Paths shortened in headers, as the headers would have shown them,
+    /// numbered from 1 in this order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub abbreviated_paths: Vec<PathBuf>,
+    /// Present once the output has been written to `--output`
+    pub output_file: Option<WrittenFile>,
+    /// Present when each file was also written to `--output-dir`
+    pub output_dir: Option<OutputDirSummary>,
+    /// Present when the output looks too big for the `--target` window
+    pub over_target: Option<Overflow>,
+    /// Present when `--chunk-size` split the output
+    pub chunks: Option<Chunks>,
+}
+
+/// Where the output was written and how much of it there was
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WrittenFile {
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+/// How `--chunk-size` split the output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Chunks {
+    pub count: usize,
+    /// Most characters in any one chunk
+    pub size: usize,
+    /// The chunk copied, served, or printed, unless they all went to files
+    pub delivered: Option<usize>,
+    /// One numbered file per chunk, with `--output`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<WrittenFile>,
+}
+
+/// Files left out of the output for reasons other than being binary
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SkipCounts {
+    pub too_large: usize,
+    /// Files over the size limit included as a summary instead of skipped
+    pub summarized: usize,
+    pub no_match: usize,
+    pub failed: usize,
+    /// Files identical to the revision or manifest being diffed against
+    pub unchanged: usize,
+    /// Text files reduced to a header because the memory limit was reached
+    pub omitted: usize,
+    /// Text files dropped because they didn't fit the token budget
+    pub over_budget: usize,
+    /// Files left out because the output had reached `--max-total`
+    pub over_total: usize,
+    /// Text files reduced to a stub because an earlier file had the same content
+    pub duplicates: usize,
+    /// Files deleted between the scan and being read
+    pub vanished: usize,
+    /// Test files left out by `--skip-tests`
+    pub tests: usize,
+    /// Files left out as modified outside `--since` and `--until`
+    pub by_time: usize,
+    /// Files abandoned because reading them took longer than the file timeout
+    pub timed_out: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileRecord {
+    pub path: PathBuf,
+    pub size: u64,
+    pub outcome: FileOutcome,
+    /// Last commit to touch the file, when git metadata was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitMeta>,
+    /// Lines and characters the file took up in the output, 0 if nothing was written for it
+    pub lines: usize,
+    pub chars: usize,
+}
+
+/// What happened to a single file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileOutcome {
+    Included,
+    Binary,
+    TooLarge,
+    /// Text file over the size limit, included as a summary of its content
+    Summarized,
+    /// Text file that didn't contain the search text
+    NoMatch,
+    /// Identical to the revision or manifest being diffed against
+    Unchanged,
+    /// Text file whose body was left out because the memory limit was reached
+    Omitted,
+    /// Text file left out because it didn't fit the token budget
+    OverBudget,
+    /// Left out because the output had reached `--max-total`
+    OverTotal,
+    /// Text file reduced to a stub pointing at an earlier file with the same content
+    Duplicate,
+    /// Deleted between the scan and being read
+    Vanished,
+    /// Abandoned because reading it took longer than the file timeout
+    TimedOut,
+    Failed(String),
+}
+
+/// Lines kept and cut from a file truncated to fit the token budget
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Truncation {
+    pub path: PathBuf,
+    pub kept: usize,
+    pub omitted: usize,
+}
+
+/// Changes included when diffing against a revision
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DiffSummary {
+    pub rev: String,
+    /// Tracked files whose patch was included
+    pub files: usize,
+    /// Untracked files included in full
+    pub new_files: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// Wall time spent in each phase of a run
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PhaseDurations {
+    /// Loading the saved config, filled in by the caller
+    pub config: Duration,
+    pub tree: Duration,
+    pub scan: Duration,
+    pub process: Duration,
+    pub clipboard: Duration,
+    /// Time spent detecting file types, summed across worker threads
+    pub sniff: Duration,
+    /// Time spent reading file contents, summed across worker threads
+    pub read: Duration,
+}
+
+impl ProcessReport {
+    pub(crate) fn record(&mut self, record: FileRecord) {
+        match record.outcome {
+            FileOutcome::Included => self.text_files += 1,
+            FileOutcome::Binary => self.binary_files += 1,
+            FileOutcome::TooLarge => self.skipped.too_large += 1,
+            FileOutcome::Summarized => self.skipped.summarized += 1,
+            FileOutcome::NoMatch => self.skipped.no_match += 1,
+            FileOutcome::Unchanged => self.skipped.unchanged += 1,
+            FileOutcome::Omitted => self.skipped.omitted += 1,
+            FileOutcome::OverBudget => self.skipped.over_budget += 1,
+            FileOutcome::OverTotal => self.skipped.over_total += 1,
+            FileOutcome::Duplicate => self.skipped.duplicates += 1,
+            FileOutcome::Vanished => self.skipped.vanished += 1,
+            FileOutcome::TimedOut => self.skipped.timed_out += 1,
+            FileOutcome::Failed(_) => self.skipped.failed += 1,
+        }
+        self.files.push(record);
+    }
+
+    /// The biggest file left out for exceeding the size limit
+    pub fn largest_too_large(&self) -> Option<&FileRecord> {
+        self.files
+            .iter()
+            .filter(|f| f.outcome == FileOutcome::TooLarge)
+            .max_by_key(|f| f.size)
+    }
+
+    /// Number of files that reached the processing stage
+    pub fn processed(&self) -> usize {
+        self.files.len()
+    }
+}
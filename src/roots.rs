@@ -0,0 +1,251 @@
+//! Telling apart files from several roots, each under a label of its own
+//!
+//! With more than one root, `src/main.ts` could be in any of them, so headers
+//! show the file relative to its root after the root's label, as in
+//! `[frontend] src/main.ts`. A label is the root directory's name unless
+//! `--label` gives it another, with `-2`, `-3`, and so on added to keep two
+//! roots of the same name apart.
+
+use crate::config::Config;
+use crate::error::YoinkError;
+use crate::report::{FileOutcome, FileRecord};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// One root and the label its files are shown under
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Root {
+    pub label: String,
+    /// The root as given, which scanned paths start with
+    path: PathBuf,
+    /// Whether the root names a single file rather than a directory
+    file: bool,
+}
+
+impl Root {
+    // The directory this root's files are shown relative to
+    fn dir(&self) -> &Path {
+        match self.path.parent() {
+            Some(parent) if self.file && parent.as_os_str().is_empty() => Path::new("."),
+            Some(parent) if self.file => parent,
+            _ => &self.path,
+        }
+    }
+
+    fn holds(&self, path: &Path) -> bool {
+        if self.file {
+            path == self.path
+        } else {
+            path.starts_with(&self.path)
+        }
+    }
+}
+
+/// How many files one root contributed to the output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RootSummary {
+    pub label: String,
+    pub text_files: usize,
+    /// Files scanned under the root but left out, for whatever reason
+    pub skipped: usize,
+    /// Size of the files included from the root
+    pub bytes: u64,
+}
+
+/// The labeled roots of a run, in the order they were given
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootLabels {
+    roots: Vec<Root>,
+}
+
+impl RootLabels {
+    /// Labels for the config's roots, when there are several or `--label`
+    /// names one; `None` when headers show paths as they always have
+    pub fn new(config: &Config) -> Option<Self> {
+        if config.absolute_paths || (config.extra_paths.is_empty() && config.labels.is_empty()) {
+            return None;
+        }
+
+        let named: Vec<Option<&str>> = config
+            .roots()
+            .map(|root| config.labels.iter().find(|(_, path)| same_path(Path::new(path), Path::new(root))).map(|(label, _)| label.as_str()))
+            .collect();
+        // Names given with --label are kept as they are wherever they can be,
+        // so only a name given twice is suffixed
+        let mut taken: HashSet<String> = HashSet::new();
+        let claimed: Vec<bool> = named.iter().map(|name| name.is_some_and(|name| taken.insert(name.to_string()))).collect();
+
+        let roots = config
+            .roots()
+            .zip(named.iter().zip(claimed))
+            .map(|(root, (name, claimed))| {
+                let path = PathBuf::from(root);
+                let file = path.is_file();
+                let mut root = Root { label: String::new(), path, file };
+                root.label = match name {
+                    Some(name) if claimed => name.to_string(),
+                    Some(name) => unique(name, &mut taken),
+                    None => unique(&dir_name(root.dir()), &mut taken),
+                };
+                root
+            })
+            .collect();
+        Some(Self { roots })
+    }
+
+    /// The root `path` was scanned from: the first given that holds it, as
+    /// a file reachable from two roots is kept from the first
+    pub fn root_of(&self, path: &Path) -> Option<&Root> {
+        self.roots.iter().find(|root| root.holds(path))
+    }
+
+    /// `path` as headers show it, `[label] path/in/root`
+    pub fn shown(&self, path: &Path) -> Option<PathBuf> {
+        let root = self.root_of(path)?;
+        let relative = path.strip_prefix(root.dir()).unwrap_or(path);
+        Some(PathBuf::from(format!("[{}] {}", root.label, relative.display())))
+    }
+
+    /// The tree's top-level entry for the root given as `root`
+    pub fn node_name(&self, root: &Path) -> Option<String> {
+        let root = self.roots.iter().find(|r| r.path == root)?;
+        Some(match root.file {
+            true => format!("[{}] {}", root.label, root.path.file_name().unwrap_or_default().to_string_lossy()),
+            false => format!("[{}]", root.label),
+        })
+    }
+
+    /// What each root contributed, in the order the roots were given
+    pub fn summarize(&self, files: &[FileRecord]) -> Vec<RootSummary> {
+        let mut summaries: Vec<RootSummary> = self
+            .roots
+            .iter()
+            .map(|root| RootSummary { label: root.label.clone(), text_files: 0, skipped: 0, bytes: 0 })
+            .collect();
+        for file in files {
+            let Some(index) = self.roots.iter().position(|root| root.holds(&file.path)) else { continue };
+            let summary = &mut summaries[index];
+            if file.outcome == FileOutcome::Included {
+                summary.text_files += 1;
+                summary.bytes += file.size;
+            } else {
+                summary.skipped += 1;
+            }
+        }
+        summaries
+    }
+}
+
+/// `path` as its header shows it: after its root's label when there are
+/// `labels`, otherwise relative to `base` as [`crate::utils::header_path`] has it
+pub fn shown<'a>(labels: Option<&RootLabels>, path: &'a Path, base: Option<&Path>) -> Cow<'a, Path> {
+    match labels.and_then(|labels| labels.shown(path)) {
+        Some(labeled) => Cow::Owned(labeled),
+        None => Cow::Borrowed(crate::utils::header_path(path, base)),
+    }
+}
+
+/// Check that each `--label` names one of the paths given
+pub fn check_labels(config: &Config) -> Result<(), YoinkError> {
+    for (label, path) in &config.labels {
+        if !config.roots().any(|root| same_path(Path::new(path), Path::new(root))) {
+            return Err(YoinkError::Label { label: label.clone(), path: path.clone() });
+        }
+    }
+    Ok(())
+}
+
+/// Parse `name=path` for `--label`
+pub fn parse_label(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((name, path)) if !name.trim().is_empty() && !path.is_empty() => Ok((name.trim().to_string(), path.to_string())),
+        _ => Err(format!("expected name=path, got '{}'", value)),
+    }
+}
+
+// `name`, or the first of `name-2`, `name-3`, ... no other root has
+fn unique(name: &str, taken: &mut HashSet<String>) -> String {
+    let label = (1..)
+        .map(|n| if n == 1 { name.to_string() } else { format!("{}-{}", name, n) })
+        .find(|label| !taken.contains(label))
+        .expect("some suffix is always free");
+    taken.insert(label.clone());
+    label
+}
+
+// The directory's own name, looked up for `.`, `..`, and the like
+fn dir_name(dir: &Path) -> String {
+    let resolved = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    resolved
+        .file_name()
+        .or_else(|| dir.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| resolved.display().to_string())
+}
+
+// Whether two paths name the same file, however each was written
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a.components().eq(b.components()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn labels(dir: &Path, roots: &[&str], named: &[(&str, &str)]) -> RootLabels {
+        let mut config = Config::builder().path(dir.join(roots[0]).to_string_lossy());
+        for root in &roots[1..] {
+            config = config.extra_path(dir.join(root).to_string_lossy());
+        }
+        for (label, root) in named {
+            config = config.label(*label, dir.join(root).to_string_lossy());
+        }
+        RootLabels::new(&config.build()).unwrap()
+    }
+
+    #[test]
+    fn test_labels_default_to_directory_names_and_stay_unique() {
+        let dir = tempfile::tempdir().unwrap();
+        for root in ["work/api", "work/frontend", "old/api", "other/api"] {
+            fs::create_dir_all(dir.path().join(root)).unwrap();
+        }
+
+        let labels = labels(dir.path(), &["work/api", "work/frontend", "old/api", "other/api"], &[]);
+        let names: Vec<&str> = labels.roots.iter().map(|root| root.label.as_str()).collect();
+        assert_eq!(names, ["api", "frontend", "api-2", "api-3"]);
+        assert_eq!(
+            labels.shown(&dir.path().join("old/api/src/main.ts")).unwrap(),
+            Path::new("[api-2] src/main.ts")
+        );
+        assert_eq!(labels.root_of(Path::new("/elsewhere/main.ts")), None);
+    }
+
+    #[test]
+    fn test_label_overrides_win_over_directory_names() {
+        let dir = tempfile::tempdir().unwrap();
+        for root in ["web", "api", "v2/web"] {
+            fs::create_dir_all(dir.path().join(root)).unwrap();
+        }
+
+        // The second `web` gets the name it was given, so the first one moves aside
+        let labels = labels(dir.path(), &["web", "api", "v2/web"], &[("server", "api"), ("web", "v2/web")]);
+        let names: Vec<&str> = labels.roots.iter().map(|root| root.label.as_str()).collect();
+        assert_eq!(names, ["web-2", "server", "web"]);
+        assert_eq!(labels.node_name(&dir.path().join("api")).unwrap(), "[server]");
+    }
+
+    #[test]
+    fn test_parse_label() {
+        assert_eq!(parse_label("web=../frontend"), Ok(("web".to_string(), "../frontend".to_string())));
+        assert_eq!(parse_label("a=b=c"), Ok(("a".to_string(), "b=c".to_string())));
+        assert!(parse_label("web").is_err());
+        assert!(parse_label("=path").is_err());
+        assert!(parse_label("web=").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+//! Reproducible random sampling of candidate files
+
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::hash::BuildHasher;
+use std::path::Path;
+
+/// How a run's files were sampled, so the same sample can be drawn again
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SampleSummary {
+    pub requested: usize,
+    pub candidates: usize,
+    pub seed: u64,
+}
+
+/// SplitMix64, which is small, fast, and stable across releases
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n`, rejecting the biased tail
+    fn below(&mut self, n: usize) -> usize {
+        let n = n as u64;
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return (x % n) as usize;
+            }
+        }
+    }
+}
+
+/// A seed for runs that didn't ask for one
+pub fn random_seed() -> u64 {
+    RandomState::new().hash_one(std::time::SystemTime::now())
+}
+
+/// Pick `n` of `items` at random, spread evenly over top-level directories
+///
+/// `relative` gives each item's path under the root. Each directory gets an
+/// equal share, with any share a small directory can't fill passed on to the
+/// others. Chosen items keep their original order.
+pub fn sample<T>(items: Vec<T>, n: usize, seed: u64, relative: impl Fn(&T) -> &Path) -> Vec<T> {
+    if n >= items.len() {
+        return items;
+    }
+
+    // Files directly under the root form their own group
+    let mut groups: BTreeMap<OsString, Vec<usize>> = BTreeMap::new();
+    for (i, item) in items.iter().enumerate() {
+        let path = relative(item);
+        let group = match path.parent() {
+            Some(parent) if parent != Path::new("") => path.iter().next().unwrap_or_default().to_os_string(),
+            _ => OsString::new(),
+        };
+        groups.entry(group).or_default().push(i);
+    }
+
+    let sizes: Vec<usize> = groups.values().map(Vec::len).collect();
+    let mut rng = Rng(seed);
+    let mut chosen = vec![false; items.len()];
+    for (mut members, quota) in groups.into_values().zip(quotas(&sizes, n)) {
+        // Walk order varies between filesystems, so shuffle from a fixed order
+        members.sort_by(|&a, &b| relative(&items[a]).cmp(relative(&items[b])));
+        for picked in 0..quota {
+            let swap = picked + rng.below(members.len() - picked);
+            members.swap(picked, swap);
+            chosen[members[picked]] = true;
+        }
+    }
+
+    items.into_iter().zip(chosen).filter_map(|(item, chosen)| chosen.then_some(item)).collect()
+}
+
+// Share `n` picks out equally, topping up from groups with room to spare
+fn quotas(sizes: &[usize], n: usize) -> Vec<usize> {
+    let mut quotas = vec![0; sizes.len()];
+    let mut remaining = n.min(sizes.iter().sum());
+    while remaining > 0 {
+        let open: Vec<usize> = (0..sizes.len()).filter(|&i| quotas[i] < sizes[i]).collect();
+        let share = (remaining / open.len()).max(1);
+        for i in open {
+            let take = share.min(sizes[i] - quotas[i]).min(remaining);
+            quotas[i] += take;
+            remaining -= take;
+        }
+    }
+    quotas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn tree() -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = (0..90).map(|i| PathBuf::from(format!("big/{}.rs", i))).collect();
+        paths.extend((0..5).map(|i| PathBuf::from(format!("small/{}.rs", i))));
+        paths.extend((0..5).map(|i| PathBuf::from(format!("{}.md", i))));
+        paths
+    }
+
+    #[test]
+    fn test_same_seed_gives_same_sample() {
+        let first = sample(tree(), 12, 7, |p| p.as_path());
+        let mut reversed = tree();
+        reversed.reverse();
+        let mut second = sample(reversed, 12, 7, |p| p.as_path());
+        second.reverse();
+
+        assert_eq!(first, second);
+        assert_ne!(first, sample(tree(), 12, 8, |p| p.as_path()));
+    }
+
+    #[test]
+    fn test_large_directory_does_not_dominate() {
+        let picked = sample(tree(), 12, 1, |p| p.as_path());
+        let in_big = picked.iter().filter(|p| p.starts_with("big")).count();
+
+        assert_eq!(picked.len(), 12);
+        assert_eq!(in_big, 4);
+    }
+
+    #[test]
+    fn test_small_groups_pass_on_unused_share() {
+        assert_eq!(quotas(&[90, 5, 5], 30), vec![20, 5, 5]);
+        assert_eq!(quotas(&[1, 2, 10], 10), vec![1, 2, 7]);
+        assert_eq!(quotas(&[3, 3], 5), vec![3, 2]);
+    }
+
+    #[test]
+    fn test_keeps_original_order() {
+        let picked = sample(tree(), 50, 3, |p| p.as_path());
+        let positions: Vec<usize> = picked.iter().map(|p| tree().iter().position(|t| t == p).unwrap()).collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    }
+}
@@ -0,0 +1,222 @@
+use crate::config::Config;
+use crate::error::YoinkError;
+use crate::file_scanner::FileEntry;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Numbered candidates from a `--list`, in the order `--select` refers to
+pub struct Listing {
+    /// Fingerprint of the filters that produced the listing
+    pub filters: String,
+    /// Paths relative to the scan root, numbered from 1 in this order
+    pub paths: Vec<PathBuf>,
+}
+
+impl Listing {
+    /// Number the scanned entries and remember the filters for a later `--select`
+    pub fn new(config: &Config, entries: &[FileEntry]) -> Self {
+        let root = Path::new(&config.path);
+        let listing = Self { filters: filter_fingerprint(config), paths: relative_paths(&config.base(), entries) };
+
+        let marker = marker_path(root);
+        let saved = marker
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&marker, format!("{}\n{}\n", listing.filters, listing.fingerprint())));
+        if let Err(e) = saved {
+            log::warn!("Failed to remember listing in {}: {}", marker.display(), e);
+        }
+        listing
+    }
+
+    // Changes whenever a path is added, removed, or renamed
+    fn fingerprint(&self) -> String {
+        let joined: String = self.paths.iter().map(|p| format!("{}\n", p.display())).collect();
+        format!("{:016x}", crate::cache::content_hash(&joined))
+    }
+}
+
+/// Version of the `--list --json` line format, bumped on any breaking change
+pub const LISTED_FILE_VERSION: u32 = 1;
+
+/// One line of `--list --json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListedFile {
+    pub version: u32,
+    /// Position in the numbered listing, for `--select`; absent for files the filters reject
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    /// Relative to the scan root, with `/` separators
+    pub path: String,
+    pub size: u64,
+    pub kind: FileKind,
+    /// Why the file would be left out of the output, if it would be
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileKind {
+    Text,
+    Binary,
+}
+
+/// Order that listings and selections share, independent of `--sort`
+pub fn sort_for_listing(entries: &mut [FileEntry]) {
+    entries.sort_by(|a, b| a.path().cmp(b.path()));
+}
+
+/// Keep only the entries at the given 1-based indices
+///
+/// Fails if the filters or the set of files changed since the last `--list`
+/// for this root, since the indices would then point at different files.
+pub fn select(config: &Config, mut entries: Vec<FileEntry>, indices: &[usize]) -> Result<Vec<FileEntry>, YoinkError> {
+    let root = Path::new(&config.path);
+    let error = |message: String| YoinkError::Selection { message };
+
+    let marker = fs::read_to_string(marker_path(root))
+        .map_err(|_| error("no listing found for this path; run with --list first".to_string()))?;
+    let mut lines = marker.lines();
+    let (listed_filters, listed_files) = (lines.next().unwrap_or_default(), lines.next().unwrap_or_default());
+
+    let filters = filter_fingerprint(config);
+    if listed_filters != filters {
+        return Err(error(format!(
+            "the listing used different filters ({} vs {}); rerun --list with the same options",
+            listed_filters, filters
+        )));
+    }
+
+    sort_for_listing(&mut entries);
+    let current = Listing { filters, paths: relative_paths(&config.base(), &entries) };
+    if current.fingerprint() != listed_files {
+        return Err(error("files were added, removed, or renamed since the listing; rerun --list".to_string()));
+    }
+
+    if let Some(&bad) = indices.iter().find(|&&i| i == 0 || i > entries.len()) {
+        return Err(error(format!("index {} is out of range, the listing has {} files", bad, entries.len())));
+    }
+
+    let mut index = 0;
+    entries.retain(|_| {
+        index += 1;
+        indices.contains(&index)
+    });
+    Ok(entries)
+}
+
+fn relative_paths(base: &Path, entries: &[FileEntry]) -> Vec<PathBuf> {
+    entries.iter().map(|e| e.path().strip_prefix(base).unwrap_or(e.path()).to_path_buf()).collect()
+}
+
+/// Parse a selection such as `1,4-7,12` into sorted, de-duplicated indices
+pub fn parse_indices(spec: &str) -> Result<Vec<usize>, String> {
+    let mut indices = Vec::new();
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let number = |s: &str| s.trim().parse::<usize>().map_err(|_| format!("'{}' is not an index", s.trim()));
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (number(start)?, number(end)?);
+                if start > end {
+                    return Err(format!("range {} runs backwards", part));
+                }
+                indices.extend(start..=end);
+            }
+            None => indices.push(number(part)?),
+        }
+    }
+    if indices.is_empty() {
+        return Err("no indices given".to_string());
+    }
+    indices.sort_unstable();
+    indices.dedup();
+    Ok(indices)
+}
+
+// Only options that change which files are listed, or their order, count
+fn filter_fingerprint(config: &Config) -> String {
+    let filters = format!(
+        "{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{}|{}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{}|{:?}|{}|{:?}|{:?}|{:?}|{}|{}",
+        config.extra_paths,
+        config.include_extensions,
+        config.exclude_extensions,
+        config.exclude_paths,
+        config.exclude_globs.iter().map(glob::Pattern::as_str).collect::<Vec<_>>(),
+        config.pattern.as_ref().map(|p| p.as_str()),
+        config.exclude_patterns.iter().map(glob::Pattern::as_str).collect::<Vec<_>>(),
+        config.regex.as_ref().map(|r| r.as_str()),
+        config.skip_hidden,
+        config.max_depth,
+        config.submodules,
+        config.nested_repos,
+        config.rules,
+        config.packages,
+        config.max_files_per_dir,
+        config.max_bytes_per_dir,
+        config.skip_tests,
+        config.test_patterns,
+        config.skip_generated,
+        config.generated_patterns,
+        config.since.as_ref().map(ToString::to_string),
+        config.until.as_ref().map(ToString::to_string),
+        config.git_tracked,
+        config.git_others,
+    );
+    format!("{:016x}", crate::cache::content_hash(&filters))
+}
+
+// The last listing for a root is remembered next to the classification cache
+fn marker_path(root: &Path) -> PathBuf {
+    let canonical = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let mut hasher = DefaultHasher::new();
+    canonical.hash(&mut hasher);
+
+    let mut path = dirs::cache_dir().unwrap_or_else(std::env::temp_dir);
+    path.push("yoink");
+    path.push("listings");
+    path.push(format!("{:016x}", hasher.finish()));
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_indices() {
+        assert_eq!(parse_indices("1,4-7,12").unwrap(), [1, 4, 5, 6, 7, 12]);
+        assert_eq!(parse_indices("3, 1-2,2").unwrap(), [1, 2, 3]);
+        assert!(parse_indices("5-2").is_err());
+        assert!(parse_indices("a").is_err());
+        assert!(parse_indices("").is_err());
+    }
+
+    #[test]
+    fn test_select_checks_listing() {
+        let fixture = crate::fixture! {
+            "a.txt" => "a",
+            "b.rs" => "b",
+            "c.txt" => "c",
+        };
+        let config = fixture.config().build();
+        let mut entries = fixture.entries().to_vec();
+        sort_for_listing(&mut entries);
+        let listing = Listing::new(&config, &entries);
+        assert_eq!(listing.paths, [PathBuf::from("a.txt"), PathBuf::from("b.rs"), PathBuf::from("c.txt")]);
+
+        let picked = select(&config, entries.clone(), &[1, 3]).unwrap();
+        let names: Vec<_> = picked.iter().map(|e| e.file_name().to_owned()).collect();
+        assert_eq!(names, ["a.txt", "c.txt"]);
+
+        assert!(matches!(select(&config, entries.clone(), &[4]), Err(YoinkError::Selection { .. })));
+
+        let narrowed = fixture.config().include_extensions(["txt"]).build();
+        assert!(matches!(select(&narrowed, entries.clone(), &[1]), Err(YoinkError::Selection { .. })));
+
+        assert!(matches!(select(&config, entries[..2].to_vec(), &[1]), Err(YoinkError::Selection { .. })));
+        fs::remove_file(marker_path(fixture.path())).unwrap();
+    }
+}
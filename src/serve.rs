@@ -0,0 +1,214 @@
+use crate::cancel::CancelToken;
+use crate::error::YoinkError;
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+/// Largest request head read before giving up on a client
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+/// How often the idle listener checks for cancellation and the deadline
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Record of the output being fetched over HTTP
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ServeReceipt {
+    pub url: String,
+    /// Address of the client that fetched the output
+    pub peer: String,
+    pub bytes: u64,
+}
+
+/// Where and for how long to wait for the output to be fetched
+#[derive(Debug, Clone, Copy)]
+pub struct ServeOptions {
+    pub bind: IpAddr,
+    pub timeout: Duration,
+}
+
+/// Serve `body` once over HTTP on a random port, then stop
+///
+/// The body is only served at a random one-time path, and requests for any
+/// other path get a 404 without using up the one fetch. `on_ready` receives
+/// the URL once the listener is up.
+pub fn serve_once(
+    body: &mut dyn Read,
+    len: u64,
+    options: ServeOptions,
+    cancel: &CancelToken,
+    on_ready: &dyn Fn(&str),
+) -> Result<ServeReceipt, YoinkError> {
+    let listener = TcpListener::bind((options.bind, 0)).map_err(|source| YoinkError::Serve { source })?;
+    let addr = listener.local_addr().map_err(|source| YoinkError::Serve { source })?;
+    listener.set_nonblocking(true).map_err(|source| YoinkError::Serve { source })?;
+
+    let path = format!("/{}", one_time_token());
+    let url = format!("http://{}{}", addr, path);
+    on_ready(&url);
+
+    let deadline = Instant::now() + options.timeout;
+    loop {
+        if cancel.is_cancelled() {
+            return Err(YoinkError::Cancelled { processed: 0 });
+        }
+        if Instant::now() >= deadline {
+            return Err(YoinkError::ServeTimeout { seconds: options.timeout.as_secs() });
+        }
+
+        let (mut stream, peer) = match listener.accept() {
+            Ok(connection) => connection,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                std::thread::sleep(POLL_INTERVAL);
+                continue;
+            }
+            Err(source) => return Err(YoinkError::Serve { source }),
+        };
+
+        // A misbehaving client shouldn't end the wait for the real one
+        match respond(&mut stream, &path, len) {
+            Ok(true) => {}
+            Ok(false) => {
+                log::debug!("Turned away request from {}", peer);
+                continue;
+            }
+            Err(e) => {
+                log::debug!("Connection from {} failed: {}", peer, e);
+                continue;
+            }
+        }
+        
+        // Once the body has started going out it can't be sent again, so a
+        // transfer cut short ends the run rather than serving what's left
+        io::copy(body, &mut stream)
+            .and_then(|_| stream.flush())
+            .map_err(|source| YoinkError::ServeInterrupted { peer: peer.to_string(), source })?;
+        return Ok(ServeReceipt { url, peer: peer.to_string(), bytes: len });
+    }
+}
+
+// Answers one request, returning whether it was for the body, in which case
+// the headers for it have been sent
+fn respond(stream: &mut TcpStream, path: &str, len: u64) -> io::Result<bool> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let head = read_request_head(stream)?;
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let (method, target) = (request_line.next(), request_line.next());
+
+    if method != Some("GET") {
+        write_status(stream, "405 Method Not Allowed")?;
+        return Ok(false);
+    }
+    if target != Some(path) {
+        write_status(stream, "404 Not Found")?;
+        return Ok(false);
+    }
+
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        len
+    )?;
+    Ok(true)
+}
+
+fn read_request_head(stream: &mut TcpStream) -> io::Result<String> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_REQUEST_BYTES {
+        let n = stream.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+fn write_status(stream: &mut TcpStream, status: &str) -> io::Result<()> {
+    write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status)
+}
+
+// 128 bits from the standard library's randomly keyed hasher
+fn one_time_token() -> String {
+    let half = || RandomState::new().build_hasher().finish();
+    format!("{:016x}{:016x}", half(), half())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn get(url: &str, path_override: Option<&str>) -> String {
+        let rest = url.trim_start_matches("http://");
+        let (host, path) = rest.split_once('/').unwrap();
+        let path = path_override.map(str::to_string).unwrap_or(format!("/{}", path));
+        let mut stream = TcpStream::connect(host).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", path, host).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    fn options(timeout: Duration) -> ServeOptions {
+        ServeOptions { bind: IpAddr::from([127, 0, 0, 1]), timeout }
+    }
+
+    #[test]
+    fn test_serves_once_at_token_path() {
+        let (tx, rx) = mpsc::channel();
+        let server = std::thread::spawn(move || {
+            let body = "hello ✨";
+            serve_once(&mut body.as_bytes(), body.len() as u64, options(Duration::from_secs(10)), &CancelToken::new(), &|url| {
+                tx.send(url.to_string()).unwrap()
+            })
+        });
+        let url = rx.recv().unwrap();
+
+        assert!(get(&url, Some("/guess")).starts_with("HTTP/1.1 404"));
+        let response = get(&url, None);
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("Content-Type: text/plain; charset=utf-8"));
+        assert!(response.ends_with("\r\n\r\nhello ✨"));
+
+        let receipt = server.join().unwrap().unwrap();
+        assert_eq!(receipt.url, url);
+        assert_eq!(receipt.bytes, "hello ✨".len() as u64);
+    }
+
+    #[test]
+    fn test_a_transfer_cut_short_is_not_retried() {
+        // Gives out part of the body, then fails the way a dropped client would
+        struct CutShort(bool);
+        impl Read for CutShort {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if std::mem::replace(&mut self.0, true) {
+                    return Err(io::ErrorKind::BrokenPipe.into());
+                }
+                buf[..4].copy_from_slice(b"half");
+                Ok(4)
+            }
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let server = std::thread::spawn(move || {
+            serve_once(&mut CutShort(false), 8, options(Duration::from_secs(10)), &CancelToken::new(), &|url| {
+                tx.send(url.to_string()).unwrap()
+            })
+        });
+        let url = rx.recv().unwrap();
+
+        assert!(get(&url, None).ends_with("\r\n\r\nhalf"));
+        assert!(matches!(server.join().unwrap(), Err(YoinkError::ServeInterrupted { .. })));
+    }
+
+    #[test]
+    fn test_gives_up_after_timeout() {
+        let result = serve_once(&mut "x".as_bytes(), 1, options(Duration::from_millis(100)), &CancelToken::new(), &|_| {});
+        assert!(matches!(result, Err(YoinkError::ServeTimeout { .. })));
+    }
+}
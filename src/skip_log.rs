@@ -0,0 +1,128 @@
+//! Verbose skip messages, coalesced when many files share a reason
+
+use crate::explain::SkipReason;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+/// Files logged one by one for each reason and directory before the rest
+/// are only counted
+pub const SHOWN_PER_GROUP: usize = 3;
+
+/// Where a run's skip messages go when verbose logging is on
+///
+/// The first few files skipped for the same reason in the same directory are
+/// logged as they happen; [`SkipLog::finish`] then logs one line counting
+/// each group that went past that.
+#[derive(Debug)]
+pub struct SkipLog {
+    /// Log target, so `YOINK_LOG` filters still see the component that skipped
+    target: &'static str,
+    base: PathBuf,
+    enabled: bool,
+    groups: Mutex<HashMap<(PathBuf, String), usize>>,
+}
+
+impl SkipLog {
+    /// A log for files under `base`, logging as `target` and doing nothing
+    /// unless debug logging is on for it
+    pub fn new(target: &'static str, base: &Path) -> Self {
+        Self {
+            target,
+            base: base.to_path_buf(),
+            enabled: log::log_enabled!(target: target, log::Level::Debug),
+            groups: Mutex::default(),
+        }
+    }
+
+    /// Note that `path` was left out, logging it if its group is still small
+    pub fn record(&self, path: &Path, reason: &SkipReason) {
+        if !self.enabled {
+            return;
+        }
+        let relative = path.strip_prefix(&self.base).unwrap_or(path);
+        let key = (group_dir(relative, reason), reason.to_string());
+        let mut groups = self.groups.lock().unwrap();
+        let count = groups.entry(key).or_default();
+        *count += 1;
+        if *count <= SHOWN_PER_GROUP {
+            log::debug!(target: self.target, "Skipping {}: {}", path.display(), reason);
+        }
+    }
+
+    /// Log a count for every group with more files than were shown
+    pub fn finish(&self) {
+        let groups = std::mem::take(&mut *self.groups.lock().unwrap());
+        let mut summaries: Vec<_> = groups.into_iter().filter(|(_, count)| *count > SHOWN_PER_GROUP).collect();
+        summaries.sort();
+        for ((dir, reason), count) in summaries {
+            log::debug!(
+                target: self.target,
+                "Skipped {} files under {}: {} ({} shown above)",
+                count,
+                display_dir(&dir),
+                reason,
+                SHOWN_PER_GROUP
+            );
+        }
+    }
+}
+
+// The directory a skip is grouped under: the excluded or ignored directory or
+// rule itself when one decided it, otherwise the file's own directory
+fn group_dir(relative: &Path, reason: &SkipReason) -> PathBuf {
+    let parent = || relative.parent().unwrap_or(Path::new("")).to_path_buf();
+    match reason {
+        SkipReason::ExcludedPath { component } => {
+            let mut dir = PathBuf::new();
+            for part in relative.components() {
+                dir.push(part);
+                if matches!(part, Component::Normal(name) if name == component.as_str()) {
+                    return dir;
+                }
+            }
+            parent()
+        }
+        SkipReason::SkippedByRule { rule } => PathBuf::from(rule.trim_start_matches("./").trim_end_matches('/')),
+        SkipReason::Ignored { dir: Some(dir), .. } => dir.clone(),
+        _ => parent(),
+    }
+}
+
+fn display_dir(dir: &Path) -> String {
+    if dir.as_os_str().is_empty() {
+        "./".to_string()
+    } else {
+        format!("{}/", dir.to_string_lossy().replace('\\', "/"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups_by_reason_and_deciding_directory() {
+        let excluded = SkipReason::ExcludedPath { component: "node_modules".to_string() };
+        let png = SkipReason::Excluded { extension: "png".to_string(), rule: None };
+
+        assert_eq!(group_dir(Path::new("web/node_modules/react/index.js"), &excluded), PathBuf::from("web/node_modules"));
+        assert_eq!(group_dir(Path::new("assets/icons/a.png"), &png), PathBuf::from("assets/icons"));
+        assert_eq!(group_dir(Path::new("logo.png"), &png), PathBuf::new());
+        assert_eq!(display_dir(Path::new("")), "./");
+    }
+
+    #[test]
+    fn test_counts_every_skip_in_a_group() {
+        let log = SkipLog { target: "test", base: PathBuf::from("/p"), enabled: true, groups: Mutex::default() };
+        let png = SkipReason::Excluded { extension: "png".to_string(), rule: None };
+        for i in 0..10 {
+            log.record(Path::new(&format!("/p/assets/{}.png", i)), &png);
+        }
+        log.record(Path::new("/p/logo.png"), &png);
+
+        let groups = log.groups.lock().unwrap();
+        assert_eq!(groups[&(PathBuf::from("assets"), png.to_string())], 10);
+        assert_eq!(groups[&(PathBuf::new(), png.to_string())], 1);
+    }
+}
@@ -0,0 +1,70 @@
+//! Size and count of the files a run would include, by extension, for `--stats`
+
+use crate::report::{FileOutcome, FileRecord};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Bucket for files without an extension
+pub const NO_EXTENSION: &str = "(none)";
+
+/// The files with one extension that would land in the output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExtensionStats {
+    /// Lowercased, without the dot, or `NO_EXTENSION`
+    pub extension: String,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Included files grouped by extension, largest total first
+///
+/// Only files whose content would be written count, so binary files and
+/// files skipped for size are left out.
+pub fn by_extension(files: &[FileRecord]) -> Vec<ExtensionStats> {
+    let mut buckets: HashMap<String, ExtensionStats> = HashMap::new();
+    for file in files.iter().filter(|f| matches!(f.outcome, FileOutcome::Included | FileOutcome::Summarized)) {
+        let extension = file
+            .path
+            .extension()
+            .map_or_else(|| NO_EXTENSION.to_string(), |ext| ext.to_string_lossy().to_lowercase());
+        let bucket = buckets
+            .entry(extension.clone())
+            .or_insert(ExtensionStats { extension, files: 0, bytes: 0 });
+        bucket.files += 1;
+        bucket.bytes += file.size;
+    }
+    let mut stats: Vec<ExtensionStats> = buckets.into_values().collect();
+    stats.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.extension.cmp(&b.extension)));
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn record(path: &str, size: u64, outcome: FileOutcome) -> FileRecord {
+        FileRecord { path: PathBuf::from(path), size, outcome, git: None, lines: 0, chars: 0 }
+    }
+
+    #[test]
+    fn test_extensions_are_bucketed_by_size() {
+        let files = [
+            record("src/main.rs", 100, FileOutcome::Included),
+            record("src/lib.RS", 50, FileOutcome::Included),
+            record("Makefile", 200, FileOutcome::Included),
+            record(".env", 10, FileOutcome::Included),
+            record("logo.png", 5000, FileOutcome::Binary),
+            record("big.log", 9000, FileOutcome::TooLarge),
+        ];
+        let stats = by_extension(&files);
+
+        assert_eq!(
+            stats,
+            [
+                ExtensionStats { extension: NO_EXTENSION.to_string(), files: 2, bytes: 210 },
+                ExtensionStats { extension: "rs".to_string(), files: 2, bytes: 150 },
+            ]
+        );
+    }
+}
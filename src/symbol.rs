@@ -0,0 +1,362 @@
+//! Picking single items out of source files, for `--symbol`
+//!
+//! Items are found line by line from how they start, so this is a quick
+//! lookup rather than a parser: Rust `fn`, `struct`, `enum`, `trait`, and
+//! `impl`, Python `def` and `class`, and JavaScript or TypeScript
+//! `function`, `class`, and `const` arrow functions.
+
+use crate::markdown;
+use crate::test_code;
+use crate::utils;
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+
+/// A language items can be found in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    Python,
+    /// JavaScript and TypeScript, with or without JSX
+    JavaScript,
+}
+
+impl Language {
+    /// The language of the file at `path`, if it's one items can be found in
+    pub fn of(path: &Path) -> Option<Self> {
+        match markdown::language(path)? {
+            "rust" => Some(Self::Rust),
+            "python" => Some(Self::Python),
+            "javascript" | "jsx" | "typescript" | "tsx" => Some(Self::JavaScript),
+            _ => None,
+        }
+    }
+}
+
+/// An item found in a source file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub name: String,
+    /// The keyword that introduced it, such as `fn` or `class`
+    pub kind: &'static str,
+    /// 1-based and inclusive, counting attributes, decorators, and doc comments above it
+    pub first_line: usize,
+    pub last_line: usize,
+}
+
+/// The items picked out of one file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Selection {
+    /// The items' lines, with `...` between items that aren't adjacent
+    pub text: String,
+    /// The items, in file order; an item inside another picked item isn't repeated
+    pub items: Vec<Item>,
+}
+
+impl Selection {
+    /// The line ranges taken, as `lines 10-42, 50-51`
+    pub fn note(&self) -> String {
+        let ranges: Vec<String> = self.items.iter().map(|item| format!("{}-{}", item.first_line, item.last_line)).collect();
+        format!("lines {}", ranges.join(", "))
+    }
+}
+
+/// Every item in `source`, in file order
+pub fn items(language: Language, source: &str) -> Vec<Item> {
+    let lines: Vec<&str> = source.split_inclusive('\n').collect();
+    let mut offsets = Vec::with_capacity(lines.len());
+    let mut offset = 0;
+    for line in &lines {
+        offsets.push(offset);
+        offset += line.len();
+    }
+
+    let mut items = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let found = match language {
+            Language::Rust => rust_item(line),
+            Language::Python => python_item(line),
+            Language::JavaScript => javascript_item(line),
+        };
+        let Some((kind, name)) = found else { continue };
+        let last = match language {
+            Language::Python => indented_end(&lines, index),
+            Language::Rust | Language::JavaScript => braced_end(language, source, &lines, &offsets, index),
+        };
+        let first = lines[..index]
+            .iter()
+            .rposition(|above| !is_preamble(language, above))
+            .map_or(0, |i| i + 1);
+        items.push(Item { name: name.to_string(), kind, first_line: first + 1, last_line: last + 1 });
+    }
+    items
+}
+
+/// The `items` of `source` named any of `names`
+pub fn select(source: &str, items: &[Item], names: &[String]) -> Selection {
+    let lines: Vec<&str> = source.split_inclusive('\n').collect();
+    let mut selection = Selection::default();
+    for item in items.iter().filter(|item| names.contains(&item.name)).cloned() {
+        if selection.items.last().is_some_and(|last| item.last_line <= last.last_line) {
+            continue;
+        }
+        if let Some(last) = selection.items.last() {
+            if item.first_line > last.last_line + 1 {
+                selection.text.push_str("...\n");
+            }
+        }
+        for line in &lines[item.first_line - 1..item.last_line] {
+            selection.text.push_str(line);
+        }
+        if !selection.text.ends_with('\n') {
+            selection.text.push('\n');
+        }
+        selection.items.push(item);
+    }
+    selection
+}
+
+/// The names of the items seen across a run, for reporting the ones asked
+/// for that no file has
+#[derive(Debug, Default)]
+pub struct Seen {
+    found: HashSet<String>,
+    known: BTreeSet<String>,
+}
+
+impl Seen {
+    /// Note the items of one file
+    pub fn add(&mut self, items: &[Item], names: &[String]) {
+        for item in items {
+            if names.contains(&item.name) {
+                self.found.insert(item.name.clone());
+            } else {
+                self.known.insert(item.name.clone());
+            }
+        }
+    }
+
+    /// Fail with every name in `names` no file had, and the close ones that were there
+    pub fn check(&self, names: &[String]) -> Result<(), String> {
+        let missing: Vec<String> = names
+            .iter()
+            .filter(|name| !self.found.contains(*name))
+            .map(|name| match suggestions(name, &self.known)[..] {
+                [] => format!("no item named `{}` found", name),
+                ref close => format!("no item named `{}` found (did you mean `{}`?)", name, close.join("`, `")),
+            })
+            .collect();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(missing.join("; "))
+        }
+    }
+}
+
+/// Up to three of `known` closest to the missing `name`
+pub fn suggestions<'a>(name: &str, known: impl IntoIterator<Item = &'a String>) -> Vec<&'a str> {
+    let mut close = utils::close_matches(name, known.into_iter().map(String::as_str));
+    close.truncate(3);
+    close
+}
+
+// Attributes and doc comments go with the Rust item below them, decorators
+// with the Python one
+fn is_preamble(language: Language, line: &str) -> bool {
+    let line = line.trim_start();
+    match language {
+        Language::Rust => line.starts_with("#[") || line.starts_with("///"),
+        Language::Python => line.starts_with('@'),
+        Language::JavaScript => false,
+    }
+}
+
+// The kind and name of a Rust item starting on `line`
+fn rust_item(line: &str) -> Option<(&'static str, &str)> {
+    let mut rest = line.trim_start();
+    if let Some(after) = rest.strip_prefix("pub") {
+        rest = match after.strip_prefix('(') {
+            Some(scope) => scope.split_once(')')?.1,
+            None => after,
+        };
+        rest = rest.strip_prefix(' ')?;
+    }
+    for qualifier in ["default ", "const ", "async ", "unsafe ", "extern \"C\" "] {
+        rest = rest.strip_prefix(qualifier).unwrap_or(rest);
+    }
+    for kind in ["fn", "struct", "enum", "trait"] {
+        if let Some(after) = rest.strip_prefix(kind).and_then(|after| after.strip_prefix(' ')) {
+            return identifier(after).map(|name| (kind, name));
+        }
+    }
+
+    // `impl<T> Trait for Type<T>` is found under `Type`
+    let after = rest.strip_prefix("impl")?;
+    let after = match after.strip_prefix('<') {
+        Some(generics) => &generics[generic_end(generics)?..],
+        None => after,
+    };
+    let target = after.trim_start();
+    if target.len() == after.len() {
+        return None;
+    }
+    let target = target.split([' ', '{']).collect::<Vec<_>>();
+    let target = match target.iter().position(|word| *word == "for") {
+        Some(at) => *target.get(at + 1)?,
+        None => target[0],
+    };
+    let last = target.split('<').next()?.rsplit("::").next()?;
+    identifier(last).map(|name| ("impl", name))
+}
+
+// The kind and name of a Python function or class starting on `line`
+fn python_item(line: &str) -> Option<(&'static str, &str)> {
+    let rest = line.trim_start();
+    let rest = rest.strip_prefix("async ").unwrap_or(rest);
+    for kind in ["def", "class"] {
+        if let Some(after) = rest.strip_prefix(kind).and_then(|after| after.strip_prefix(' ')) {
+            return identifier(after.trim_start()).map(|name| (kind, name));
+        }
+    }
+    None
+}
+
+// The kind and name of a JavaScript function, class, or arrow function
+// starting on `line`
+fn javascript_item(line: &str) -> Option<(&'static str, &str)> {
+    let mut rest = line.trim_start();
+    for qualifier in ["export ", "default ", "declare ", "abstract ", "async "] {
+        rest = rest.strip_prefix(qualifier).unwrap_or(rest);
+    }
+    if let Some(after) = rest.strip_prefix("function") {
+        let after = after.strip_prefix('*').unwrap_or(after);
+        return identifier(after.trim_start()).map(|name| ("function", name));
+    }
+    if let Some(after) = rest.strip_prefix("class ") {
+        return identifier(after).map(|name| ("class", name));
+    }
+    let after = rest.strip_prefix("const ")?;
+    let name = identifier(after)?;
+    let value = after[name.len()..].split_once('=')?.1.trim_start();
+    let value = value.strip_prefix("async").unwrap_or(value).trim_start();
+    let arrow = value.contains("=>") || value.starts_with("function") || (value.starts_with('(') && !value.contains(';'));
+    arrow.then_some(("const", name))
+}
+
+// The identifier `text` starts with
+fn identifier(text: &str) -> Option<&str> {
+    let end = text.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$')).unwrap_or(text.len());
+    (end > 0).then(|| &text[..end])
+}
+
+// Offset just past the `>` closing generics whose body starts `text`
+fn generic_end(text: &str) -> Option<usize> {
+    let mut depth = 1;
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// Index of the last line of the item on line `start` whose body is braced,
+// or that ends with `;` before any brace opens
+fn braced_end(language: Language, source: &str, lines: &[&str], offsets: &[usize], start: usize) -> usize {
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        // An arrow function's body starts after its arrow, past any braces in the parameters
+        let arrow = line.find("=>").filter(|_| language == Language::JavaScript);
+        let body = arrow.map_or(*line, |arrow| &line[arrow..]);
+        if body.contains('{') {
+            let from = offsets[index] + (line.len() - body.len());
+            return match test_code::block_end(source, from) {
+                Some(end) => offsets.partition_point(|&offset| offset < end) - 1,
+                None => lines.len() - 1,
+            };
+        }
+        if line.trim_end().ends_with(';') {
+            return index;
+        }
+    }
+    lines.len() - 1
+}
+
+// Index of the last line indented deeper than line `start`, ignoring blank lines after it
+fn indented_end(lines: &[&str], start: usize) -> usize {
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let own = indent(lines[start]);
+    let mut last = start;
+    for (index, line) in lines.iter().enumerate().skip(start + 1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if indent(line) <= own {
+            break;
+        }
+        last = index;
+    }
+    last
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(items: &[Item]) -> Vec<(&str, &str, usize, usize)> {
+        items.iter().map(|i| (i.kind, i.name.as_str(), i.first_line, i.last_line)).collect()
+    }
+
+    #[test]
+    fn test_rust_items_span_their_braces() {
+        let source = "/// A point\n#[derive(Debug)]\npub struct Point { x: i32 }\n\npub(crate) struct Unit;\n\nimpl<T: Clone> From<T> for Point {\n    pub async fn new(s: &str) -> Self {\n        let brace = \"}\";\n        todo!()\n    }\n}\n\nfn sig(a: [u8; 4]) {}\n";
+        assert_eq!(
+            names(&items(Language::Rust, source)),
+            [("struct", "Point", 1, 3), ("struct", "Unit", 5, 5), ("impl", "Point", 7, 12), ("fn", "new", 8, 11), ("fn", "sig", 14, 14)]
+        );
+
+        let found = items(Language::Rust, source);
+        let selection = select(source, &found, &["new".to_string(), "sig".to_string()]);
+        assert_eq!(selection.note(), "lines 8-11, 14-14");
+        assert!(selection.text.starts_with("    pub async fn new"));
+        assert!(selection.text.ends_with("    }\n...\nfn sig(a: [u8; 4]) {}\n"));
+
+        // A method inside an impl that was also asked for isn't repeated
+        assert_eq!(select(source, &found, &["Point".to_string(), "new".to_string()]).items.len(), 2);
+    }
+
+    #[test]
+    fn test_python_and_javascript_items() {
+        let python = "import os\n\n@cache\ndef load(path):\n    with open(path) as f:\n\n        return f.read()\n\nclass Repo:\n    def save(self):\n        pass\n";
+        assert_eq!(names(&items(Language::Python, python)), [("def", "load", 3, 7), ("class", "Repo", 9, 11), ("def", "save", 10, 11)]);
+
+        let js = "export default async function fetchAll(urls) {\n  return urls.map(u => `${u}`);\n}\nexport const add = (a, b) => a + b;\nconst handler = async ({ id }) => {\n  return id;\n};\nconst LIMIT = 10;\nclass Cache {}\n";
+        assert_eq!(
+            names(&items(Language::JavaScript, js)),
+            [("function", "fetchAll", 1, 3), ("const", "add", 4, 4), ("const", "handler", 5, 7), ("class", "Cache", 9, 9)]
+        );
+    }
+
+    #[test]
+    fn test_close_names_are_suggested() {
+        let known = ["process_file".to_string(), "process_files".to_string(), "parse".to_string()];
+        assert_eq!(suggestions("proces_file", &known), ["process_file", "process_files"]);
+        assert!(suggestions("zzz", &known).is_empty());
+
+        let mut seen = Seen::default();
+        let found = [Item { name: "process_file".to_string(), kind: "fn", first_line: 1, last_line: 2 }];
+        seen.add(&found, &["parse".to_string()]);
+        assert_eq!(
+            seen.check(&["proces_file".to_string(), "zzz".to_string()]).unwrap_err(),
+            "no item named `proces_file` found (did you mean `process_file`?); no item named `zzz` found"
+        );
+        assert_eq!(Language::of(Path::new("a.tsx")), Some(Language::JavaScript));
+        assert_eq!(Language::of(Path::new("a.go")), None);
+    }
+}
@@ -0,0 +1,179 @@
+//! Context windows of the models output is meant for, checked by `--target`
+
+use crate::fit;
+use crate::report::{FileOutcome, FileRecord};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Known models and their context windows in tokens, matched by name
+/// ignoring case
+pub const MODELS: &[(&str, u64)] = &[
+    ("gpt-4o", 128_000),
+    ("gpt-4o-mini", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4", 8_192),
+    ("gpt-4.1", 1_047_576),
+    ("gpt-3.5-turbo", 16_385),
+    ("o1", 200_000),
+    ("o3", 200_000),
+    ("o4-mini", 200_000),
+    ("claude", 200_000),
+    ("gemini-1.5-pro", 2_000_000),
+    ("gemini-1.5-flash", 1_000_000),
+    ("gemini-2.0-flash", 1_000_000),
+    ("llama-3", 8_192),
+    ("llama-3.1", 128_000),
+    ("mistral-large", 128_000),
+];
+
+/// The context window output should fit: a known model, or a token count
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Target {
+    /// The model named, if one was
+    pub model: Option<String>,
+    /// Tokens in the window
+    pub tokens: u64,
+}
+
+impl Target {
+    /// Parse a model name from `MODELS` or a token count such as "128k",
+    /// "1m", or "32000"
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        let lower = input.to_ascii_lowercase();
+        if let Some(&(model, tokens)) = MODELS.iter().find(|(model, _)| *model == lower) {
+            return Ok(Self { model: Some(model.to_string()), tokens });
+        }
+        let (digits, multiplier) = match lower.strip_suffix('k') {
+            Some(digits) => (digits, 1_000),
+            None => match lower.strip_suffix('m') {
+                Some(digits) => (digits, 1_000_000),
+                None => (lower.as_str(), 1),
+            },
+        };
+        match digits.trim().parse::<u64>() {
+            Ok(count) if count > 0 => count
+                .checked_mul(multiplier)
+                .map(|tokens| Self { model: None, tokens })
+                .ok_or_else(|| format!("window '{}' is too large", input)),
+            _ => Err(format!(
+                "unknown model or size '{}' (use a token count such as 128k, or one of: {})",
+                input,
+                MODELS.iter().map(|(model, _)| *model).collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.model {
+            Some(model) => write!(f, "{} ({} tokens)", model, self.tokens),
+            None => write!(f, "{} tokens", self.tokens),
+        }
+    }
+}
+
+impl TryFrom<String> for Target {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        Self::parse(&text)
+    }
+}
+
+impl From<Target> for String {
+    fn from(target: Target) -> Self {
+        match target.model {
+            Some(model) => model,
+            None if target.tokens.is_multiple_of(1_000) => format!("{}k", target.tokens / 1_000),
+            None => target.tokens.to_string(),
+        }
+    }
+}
+
+/// How far a run's output went past its target's window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Overflow {
+    /// The target as given, such as "gpt-4o (128000 tokens)"
+    pub target: String,
+    pub window: u64,
+    /// Tokens over the window
+    pub tokens: u64,
+    /// The fewest files whose removal would fit the window, largest first
+    pub largest: Vec<TokenShare>,
+}
+
+/// One file's share of the output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TokenShare {
+    pub path: PathBuf,
+    pub tokens: u64,
+}
+
+/// How far `estimated` tokens go past `target`, if they do, with the largest
+/// of the written `files` that would have to go, shown relative to `base`
+/// where there is one
+///
+/// Each file's share is counted the same way as the estimate, from the
+/// characters it took up in the output.
+pub fn overflow(target: &Target, estimated: u64, files: &[FileRecord], base: Option<&Path>) -> Option<Overflow> {
+    let over = estimated.checked_sub(target.tokens).filter(|&over| over > 0)?;
+    let mut written: Vec<&FileRecord> = files
+        .iter()
+        .filter(|f| f.chars > 0 && matches!(f.outcome, FileOutcome::Included | FileOutcome::Summarized))
+        .collect();
+    written.sort_by(|a, b| b.chars.cmp(&a.chars).then_with(|| a.path.cmp(&b.path)));
+
+    let mut largest = Vec::new();
+    let mut freed = 0;
+    for file in written {
+        if freed >= over {
+            break;
+        }
+        let tokens = fit::estimate_tokens(file.chars as u64);
+        freed += tokens;
+        largest.push(TokenShare { path: crate::utils::header_path(&file.path, base).to_path_buf(), tokens });
+    }
+    Some(Overflow { target: target.to_string(), window: target.tokens, tokens: over, largest })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_targets_parse_models_and_sizes() {
+        assert_eq!(Target::parse("GPT-4o").unwrap(), Target { model: Some("gpt-4o".to_string()), tokens: 128_000 });
+        assert_eq!(Target::parse("128k").unwrap().tokens, 128_000);
+        assert_eq!(Target::parse("1M").unwrap().tokens, 1_000_000);
+        assert_eq!(Target::parse("32000").unwrap().tokens, 32_000);
+        assert!(Target::parse("gpt-9").unwrap_err().contains("gpt-4o"));
+        assert!(Target::parse("0").is_err());
+        assert_eq!(String::from(Target::parse("200k").unwrap()), "200k");
+    }
+
+    #[test]
+    fn test_overflow_names_the_fewest_largest_files() {
+        let record = |path: &str, chars: usize| FileRecord {
+            path: PathBuf::from(path),
+            size: chars as u64,
+            outcome: FileOutcome::Included,
+            git: None,
+            lines: 1,
+            chars,
+        };
+        let files = [record("/app/small.rs", 400), record("/app/big.rs", 4_000), record("/app/mid.rs", 2_000)];
+        let target = Target::parse("1k").unwrap();
+
+        assert_eq!(overflow(&target, 1_000, &files, None), None);
+        let over = overflow(&target, 1_700, &files, Some(Path::new("/app"))).unwrap();
+        assert_eq!(over.tokens, 700);
+        assert_eq!(over.largest, [TokenShare { path: PathBuf::from("big.rs"), tokens: 1_000 }]);
+        let over = overflow(&target, 2_200, &files, None).unwrap();
+        assert_eq!(over.largest.len(), 2);
+        assert_eq!(over.largest[1].path, Path::new("/app/mid.rs"));
+    }
+}
@@ -0,0 +1,199 @@
+//! Recognizing test files, and cutting test modules out of Rust sources
+
+use std::path::{Component, Path};
+
+/// Test layouts by ecosystem; a trailing `/` names a directory, anything
+/// else is a glob matched against the file name
+pub const TEST_PATTERNS: [(&str, &[&str]); 7] = [
+    ("Rust", &["tests/", "benches/"]),
+    ("Go", &["*_test.go", "testdata/"]),
+    ("JavaScript", &["__tests__/", "test/", "*.test.js", "*.spec.js", "*.test.jsx", "*.spec.jsx", "*.test.mjs", "*.spec.mjs"]),
+    ("TypeScript", &["*.test.ts", "*.spec.ts", "*.test.tsx", "*.spec.tsx"]),
+    ("Python", &["test_*.py", "*_test.py", "conftest.py"]),
+    ("Ruby", &["spec/", "*_spec.rb", "*_test.rb"]),
+    ("Java and Kotlin", &["*Test.java", "*Tests.java", "*Test.kt", "*Tests.kt"]),
+];
+
+/// The built-in test patterns plus any from the config
+#[derive(Debug, Clone)]
+pub struct TestPatterns {
+    dirs: Vec<glob::Pattern>,
+    files: Vec<glob::Pattern>,
+}
+
+impl TestPatterns {
+    /// Add `extra` patterns, in the same form as [`TEST_PATTERNS`], to the built-in ones
+    pub fn new(extra: &[String]) -> Self {
+        let builtin = TEST_PATTERNS.iter().flat_map(|(_, patterns)| patterns.iter().copied());
+        let mut dirs = Vec::new();
+        let mut files = Vec::new();
+        for pattern in builtin.chain(extra.iter().map(String::as_str)) {
+            let (list, glob) = match pattern.strip_suffix('/') {
+                Some(dir) => (&mut dirs, dir),
+                None => (&mut files, pattern),
+            };
+            match glob::Pattern::new(glob) {
+                Ok(glob) => list.push(glob),
+                Err(e) => log::warn!("Ignoring invalid test pattern '{}': {}", pattern, e),
+            }
+        }
+        Self { dirs, files }
+    }
+
+    /// The pattern that marks `relative` as test code, if any does
+    pub fn find(&self, relative: &Path) -> Option<String> {
+        let mut components: Vec<&str> = relative
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => name.to_str(),
+                _ => None,
+            })
+            .collect();
+        let name = components.pop()?;
+        if let Some(dir) = self.dirs.iter().find(|p| components.iter().any(|c| p.matches(c))) {
+            return Some(format!("{}/", dir.as_str()));
+        }
+        self.files.iter().find(|p| p.matches(name)).map(|p| p.as_str().to_string())
+    }
+}
+
+/// Remove `#[cfg(test)] mod ... { ... }` blocks, with how many were removed
+///
+/// Only an attribute alone on its line, directly followed by an inline `mod`,
+/// starts a block. A block whose braces don't balance is kept, along with
+/// everything after it.
+pub fn strip_rust_test_modules(source: &str) -> (String, usize) {
+    let mut out = String::with_capacity(source.len());
+    let mut stripped = 0;
+    let mut rest = source;
+    while let Some(start) = find_test_module(rest) {
+        let Some(end) = block_end(rest, start) else { break };
+        out.push_str(&rest[..start]);
+        rest = &rest[end..];
+        stripped += 1;
+    }
+    out.push_str(rest);
+    (out, stripped)
+}
+
+// Offset of the first line holding a lone `#[cfg(test)]` followed by a `mod` line
+fn find_test_module(text: &str) -> Option<usize> {
+    let mut offset = 0;
+    let mut lines = text.split_inclusive('\n').peekable();
+    while let Some(line) = lines.next() {
+        if line.trim() == "#[cfg(test)]" {
+            let next = lines.peek().map_or("", |next| next.trim_start());
+            let item = next.strip_prefix("pub ").or_else(|| next.strip_prefix("pub(crate) ")).unwrap_or(next);
+            if item.starts_with("mod ") && item.contains('{') {
+                return Some(offset);
+            }
+        }
+        offset += line.len();
+    }
+    None
+}
+
+// Offset just past the line closing the first brace block at or after
+// `start`, skipping braces inside strings, chars, and comments
+pub(crate) fn block_end(text: &str, start: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut i = start + text[start..].find('{')?;
+    let mut depth = 0usize;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    let end = text[i..].find('\n').map_or(text.len(), |n| i + n + 1);
+                    return Some(end);
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => i += text[i..].find('\n')?,
+            b'/' if bytes.get(i + 1) == Some(&b'*') => i += text[i..].find("*/")? + 1,
+            b'"' => i = string_end(bytes, i + 1)?,
+            b'r' if !is_ident(bytes, i) && matches!(bytes.get(i + 1), Some(b'"' | b'#')) => {
+                let hashes = bytes[i + 1..].iter().take_while(|&&b| b == b'#').count();
+                if bytes.get(i + 1 + hashes) == Some(&b'"') {
+                    let close = format!("\"{}", "#".repeat(hashes));
+                    let body = i + 2 + hashes;
+                    i = body + text[body..].find(&close)? + close.len() - 1;
+                }
+            }
+            // A char literal, as opposed to a lifetime like 'a
+            b'\'' if bytes.get(i + 1) == Some(&b'\\') => i += 3 + text.get(i + 3..)?.find('\'')?,
+            b'\'' if bytes.get(i + 2) == Some(&b'\'') => i += 2,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+// Index of the quote closing a string whose body starts at `i`
+fn string_end(bytes: &[u8], mut i: usize) -> Option<usize> {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'"' => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+// Whether the byte at `i` continues an identifier, as the `r` in `for`
+fn is_ident(bytes: &[u8], i: usize) -> bool {
+    i > 0 && (bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patterns_match_dirs_and_names() {
+        let patterns = TestPatterns::new(&["fixtures/".to_string(), "*.e2e.ts".to_string()]);
+        let find = |path: &str| patterns.find(Path::new(path));
+
+        assert_eq!(find("tests/cli.rs").as_deref(), Some("tests/"));
+        assert_eq!(find("web/src/__tests__/app.js").as_deref(), Some("__tests__/"));
+        assert_eq!(find("pkg/server_test.go").as_deref(), Some("*_test.go"));
+        assert_eq!(find("src/app.spec.ts").as_deref(), Some("*.spec.ts"));
+        assert_eq!(find("lib/test_parser.py").as_deref(), Some("test_*.py"));
+        assert_eq!(find("data/fixtures/a.json").as_deref(), Some("fixtures/"));
+        assert_eq!(find("login.e2e.ts").as_deref(), Some("*.e2e.ts"));
+        assert_eq!(find("src/tests.rs"), None);
+        assert_eq!(find("src/latest/main.go"), None);
+    }
+
+    #[test]
+    fn test_strips_only_well_formed_test_modules() {
+        let source = concat!(
+            "fn main() {}\n",
+            "\n",
+            "#[cfg(test)]\n",
+            "mod tests {\n",
+            "    fn f<'a>(s: &'a str) -> char { let _ = \"}\"; let _ = r#\"{\"#; '{' }\n",
+            "    // }\n",
+            "    #[test]\n",
+            "    fn t() { assert!(true) }\n",
+            "}\n",
+            "\n",
+            "#[cfg(test)]\n",
+            "mod helpers;\n",
+            "#[cfg(test)] mod inline {}\n",
+        );
+        let (stripped, count) = strip_rust_test_modules(source);
+
+        assert_eq!(count, 1);
+        assert_eq!(stripped, "fn main() {}\n\n\n#[cfg(test)]\nmod helpers;\n#[cfg(test)] mod inline {}\n");
+    }
+
+    #[test]
+    fn test_unbalanced_module_is_kept() {
+        let source = "fn a() {}\n#[cfg(test)]\nmod tests {\n    fn t() {\n}\n";
+        assert_eq!(strip_rust_test_modules(source), (source.to_string(), 0));
+    }
+}
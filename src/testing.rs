@@ -0,0 +1,137 @@
+//! Helpers for testing against real files
+//!
+//! `walkdir::DirEntry` can't be constructed directly, so these materialize a
+//! described tree in a temporary directory and scan it with the real
+//! `FileScanner` to get genuine entries. Available to this crate's tests and,
+//! with the `test-util` feature, to downstream crates.
+
+use crate::config::{Config, ConfigBuilder};
+use crate::file_scanner::{FileEntry, FileScanner, FileScanning};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+/// A temporary tree of files along with the entries found by scanning it
+///
+/// The directory is removed when the fixture is dropped, so keep it alive
+/// for as long as the entries are in use.
+pub struct Fixture {
+    dir: TempDir,
+    entries: Vec<FileEntry>,
+}
+
+impl Fixture {
+    /// Write each `(relative path, content)` pair, creating parent directories
+    /// as needed, then scan the tree. Entries are sorted by path.
+    pub fn new<I, P>(files: I) -> Self
+    where
+        I: IntoIterator<Item = (P, Vec<u8>)>,
+        P: AsRef<Path>,
+    {
+        let dir = tempfile::tempdir().expect("create fixture directory");
+        for (relative, content) in files {
+            let path = dir.path().join(relative);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).expect("create fixture subdirectory");
+            }
+            fs::write(&path, content).expect("write fixture file");
+        }
+
+        let config = Config::builder().path(dir.path().to_string_lossy()).build();
+        let mut entries = FileScanner::new(&config).collect_files().expect("scan fixture");
+        entries.sort_by(|a, b| a.path().cmp(b.path()));
+
+        Self { dir, entries }
+    }
+
+    /// Root of the fixture tree
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Absolute path of a file in the fixture
+    pub fn join(&self, relative: impl AsRef<Path>) -> PathBuf {
+        self.dir.path().join(relative)
+    }
+
+    /// Entries found by scanning the fixture with the default filters
+    pub fn entries(&self) -> &[FileEntry] {
+        &self.entries
+    }
+
+    /// A config builder already pointed at the fixture root
+    pub fn config(&self) -> ConfigBuilder {
+        Config::builder().path(self.dir.path().to_string_lossy())
+    }
+
+    /// Write or overwrite a file in the fixture after it was created
+    pub fn write(&self, relative: impl AsRef<Path>, content: impl AsRef<[u8]>) {
+        fs::write(self.join(relative), content).expect("write fixture file");
+    }
+
+    /// Turn the fixture into a git repository with everything committed
+    ///
+    /// Returns `false` when git isn't installed, so callers can skip.
+    pub fn init_git_repo(&self) -> bool {
+        if self.git(&["init", "--quiet"]).is_none() {
+            return false;
+        }
+        self.git(&["add", "."]);
+        self.git(&["commit", "--quiet", "-m", "fixture"]);
+        true
+    }
+
+    /// Run git in the fixture with a throwaway identity, returning stdout
+    pub fn git(&self, args: &[&str]) -> Option<String> {
+        let output = Command::new("git")
+            .args(["-c", "user.name=yoink", "-c", "user.email=yoink@example.com"])
+            .args(args)
+            .current_dir(self.path())
+            .output()
+            .ok()?;
+        assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+        Some(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Split into the directory guard and the scanned entries
+    pub fn into_parts(self) -> (TempDir, Vec<FileEntry>) {
+        (self.dir, self.entries)
+    }
+}
+
+/// Create a FIFO at `path` that nothing writes to, so reading it would block forever
+#[cfg(all(test, unix))]
+pub(crate) fn make_fifo(path: &Path) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+
+    let name = std::ffi::CString::new(path.as_os_str().as_bytes()).expect("path without NUL bytes");
+    // SAFETY: `name` is a valid NUL-terminated string for the duration of the call
+    let result = unsafe { libc::mkfifo(name.as_ptr(), 0o644) };
+    assert_eq!(result, 0, "mkfifo failed: {}", std::io::Error::last_os_error());
+    path.to_path_buf()
+}
+
+/// Convert fixture content into bytes; used by [`fixture!`](crate::fixture)
+pub fn bytes(content: impl AsRef<[u8]>) -> Vec<u8> {
+    content.as_ref().to_vec()
+}
+
+/// Build a [`Fixture`] from `"relative/path" => content` pairs, where content
+/// is anything that converts to bytes
+///
+/// ```
+/// # #[cfg(feature = "test-util")] {
+/// let fixture = yoink::fixture! {
+///     "src/main.rs" => "fn main() {}",
+///     "img.png" => [0x89, b'P', b'N', b'G'],
+/// };
+/// assert_eq!(fixture.entries().len(), 2);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! fixture {
+    ($($path:expr => $content:expr),* $(,)?) => {
+        $crate::testing::Fixture::new([$(($path, $crate::testing::bytes($content))),*])
+    };
+}
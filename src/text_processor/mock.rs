@@ -1,8 +1,10 @@
 use std::path::Path;
 use std::collections::HashMap;
 use super::TextProcessing;
+use crate::error::YoinkError;
 
 /// Mock implementation of TextProcessing for testing
+#[derive(Default)]
 pub struct MockTextProcessor {
     text_files: HashMap<String, String>,
     binary_files: Vec<String>,
@@ -28,7 +30,7 @@ impl MockTextProcessor {
 }
 
 impl TextProcessing for MockTextProcessor {
-    fn process_file(&self, path: &Path) -> Result<Option<String>, String> {
+    fn process_file(&self, path: &Path) -> Result<Option<String>, YoinkError> {
         let path_str = path.to_string_lossy().to_string();
         
         if self.binary_files.contains(&path_str) {
@@ -43,7 +45,7 @@ impl TextProcessing for MockTextProcessor {
         }
     }
 
-    fn format_text_content(&self, path: &Path, content: &str, buffer: &mut String) -> Result<bool, String> {
+    fn format_text_content(&self, path: &Path, content: &str, buffer: &mut String) -> Result<bool, YoinkError> {
         // Simple implementation for testing
         buffer.push_str(&format!("=== {} ===\n", path.display()));
         buffer.push_str(content);
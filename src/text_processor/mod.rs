@@ -1,19 +1,93 @@
 pub mod processor;
+pub mod search;
 #[cfg(test)]
 pub mod mock;
 
 // Re-export the implementation
 pub use processor::TextProcessor;
+pub use search::LineSearch;
+pub(crate) use processor::{add_io_timings, take_io_timings};
 #[cfg(test)]
 pub use mock::MockTextProcessor;
 
+use std::any::Any;
 use std::path::Path;
+use crate::error::YoinkError;
+use crate::utils::format_size;
+
+/// Lines kept from each end of an oversized file
+pub const EXCERPT_LINES: usize = 20;
+
+/// What's known about a file too large to include, gathered in one streaming pass
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Excerpt {
+    pub size: u64,
+    pub lines: u64,
+    pub head: Vec<String>,
+    pub tail: Vec<String>,
+    /// Lines containing the search text, when searching
+    pub matches: Option<u64>,
+}
+
+impl Excerpt {
+    /// A stub standing in for the file's content
+    pub fn render(&self, path: &Path) -> String {
+        let mut stub = format!(
+            "=== {} === (summarized: {}, {} lines)\n",
+            path.display(),
+            format_size(self.size),
+            self.lines
+        );
+        for line in &self.head {
+            stub.push_str(line);
+            stub.push('\n');
+        }
+        let omitted = self.lines.saturating_sub((self.head.len() + self.tail.len()) as u64);
+        if omitted > 0 {
+            stub.push_str(&format!("[... {} lines omitted ...]\n", omitted));
+        }
+        for line in &self.tail {
+            stub.push_str(line);
+            stub.push('\n');
+        }
+        if let Some(matches) = self.matches {
+            stub.push_str(&format!("[{} lines match the search]\n", matches));
+        }
+        stub.push('\n');
+        stub
+    }
+}
+
+/// Where the search text appears in one file, for the match index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchMatches {
+    /// Lines containing the search text
+    pub lines: usize,
+    /// 1-based number of the first matching line
+    pub first_line: usize,
+    pub first: String,
+}
 
 /// Trait defining the text processing operations interface
-pub trait TextProcessing {
+pub trait TextProcessing: Any + Send + Sync {
     /// Process a file and determine if it's a text file, returning its content if so
-    fn process_file(&self, path: &Path) -> Result<Option<String>, String>;
+    fn process_file(&self, path: &Path) -> Result<Option<String>, YoinkError>;
     
     /// Format text content for display/clipboard and return whether it was included
-    fn format_text_content(&self, path: &Path, content: &str, buffer: &mut String) -> Result<bool, String>;
+    fn format_text_content(&self, path: &Path, content: &str, buffer: &mut String) -> Result<bool, YoinkError>;
+    
+    /// Stream through a text file too large to read whole, returning `None` if it isn't text
+    fn summarize_file(&self, _path: &Path) -> Result<Option<Excerpt>, YoinkError> {
+        Ok(None)
+    }
+    
+    /// Where the search text appears in `content`, or `None` when not searching
+    fn search_matches(&self, _content: &str) -> Option<SearchMatches> {
+        None
+    }
+    
+    /// Bytes taken out of formatted files by condensing whitespace so far
+    fn condensed_bytes(&self) -> u64 {
+        0
+    }
 } 
\ No newline at end of file
@@ -1,51 +1,155 @@
-use crate::cli::Config;
-use crate::utils::{is_text, is_text_file};
-use super::TextProcessing;
-use std::fs;
-use std::path::Path;
+use crate::cache::{self, Cached, FileCache};
+use crate::roots::{self, RootLabels};
+use crate::config::{Config, OutputFormat};
+use crate::{comments, markdown, xml};
+use crate::utils::{detect_text_file, is_text, special_file_kind};
+use super::{Excerpt, LineSearch, SearchMatches, TextProcessing, EXCERPT_LINES};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use crate::error::YoinkError;
+
+thread_local! {
+    // Time this thread has spent sniffing file types and reading file
+    // contents, kept per thread so workers never contend on a shared counter
+    static IO_TIMINGS: Cell<(Duration, Duration)> = const { Cell::new((Duration::ZERO, Duration::ZERO)) };
+}
+
+/// Take and reset the calling thread's `(sniffing, reading)` totals
+pub(crate) fn take_io_timings() -> (Duration, Duration) {
+    IO_TIMINGS.with(|timings| timings.take())
+}
+
+/// Add totals gathered on another thread to the calling thread's
+pub(crate) fn add_io_timings((sniff, read): (Duration, Duration)) {
+    IO_TIMINGS.with(|timings| {
+        let (total_sniff, total_read) = timings.get();
+        timings.set((total_sniff + sniff, total_read + read));
+    });
+}
+
+fn timed<T>(add: impl FnOnce(&mut (Duration, Duration), Duration), work: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = work();
+    let elapsed = started.elapsed();
+    IO_TIMINGS.with(|timings| {
+        let mut totals = timings.get();
+        add(&mut totals, elapsed);
+        timings.set(totals);
+    });
+    result
+}
 
 pub struct TextProcessor {
     config: Config,
+    cache: Option<Arc<FileCache>>,
+    // Type overrides are matched against, and headers show, paths relative to this
+    base: PathBuf,
+    // With several roots, headers show paths relative to their root instead
+    labels: Option<RootLabels>,
+    // Bytes --condense has taken out of the files formatted so far
+    condensed: AtomicU64,
+    // The search term, compiled once for every thread; an invalid regex is
+    // reported before any file is read, so it never gets this far
+    search: Option<LineSearch>,
 }
 
 impl TextProcessor {
     pub fn new(config: &Config) -> Self {
         Self {
             config: config.clone(),
+            cache: None,
+            base: config.base(),
+            labels: RootLabels::new(config),
+            condensed: AtomicU64::new(0),
+            search: LineSearch::from_config(config).ok().flatten(),
         }
     }
-}
 
-impl TextProcessing for TextProcessor {
-    fn process_file(&self, path: &Path) -> Result<Option<String>, String> {
+    /// Reuse and record text/binary classifications in `cache`
+    pub fn with_cache(mut self, cache: Arc<FileCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    // `path` as its header shows it
+    fn shown<'a>(&self, path: &'a Path) -> Cow<'a, Path> {
+        roots::shown(self.labels.as_ref(), path, (!self.config.absolute_paths).then_some(self.base.as_path()))
+    }
+
+    // What --force-text or --force-binary says about `path`, if either matches
+    fn forced_kind(&self, path: &Path) -> Option<bool> {
+        let relative = path.strip_prefix(&self.base).unwrap_or(path);
+        let (text, pattern) = self.config.forced_kind(relative)?;
+        if text {
+            log::debug!("Treating {} as text (--force-text {})", path.display(), pattern);
+        } else {
+            log::debug!("Treating {} as binary (--force-binary {})", path.display(), pattern);
+        }
+        Some(text)
+    }
+
+    // Lines to keep of each file with --max-lines; search excerpts and symbols
+    // need the whole file to find what they keep, and are short already
+    fn max_lines(&self) -> Option<usize> {
+        self.config.max_lines.filter(|_| self.config.search_text.is_none() && self.config.symbols.is_empty())
+    }
+
+    // Forced text may not be valid UTF-8, so read it whatever it contains
+    fn read_forced_text(&self, path: &Path) -> Result<Option<String>, YoinkError> {
+        if let Some(max) = self.max_lines() {
+            return match timed(|t, d| t.1 += d, || read_head(path, max)) {
+                Ok((head, more)) => Ok(Some(truncated(String::from_utf8_lossy(&head).into_owned(), max, more))),
+                Err(e) => unreadable(path, e),
+            };
+        }
+        match timed(|t, d| t.1 += d, || fs::read(path)) {
+            Ok(bytes) => Ok(Some(String::from_utf8_lossy(&bytes).into_owned())),
+            Err(e) => unreadable(path, e),
+        }
+    }
+
+    // A file that vanished is an error for the caller to count; anything else
+    // unreadable is treated like a binary file
+    fn read_text(&self, path: &Path) -> Result<Option<String>, YoinkError> {
+        if let Some(max) = self.max_lines() {
+            return match timed(|t, d| t.1 += d, || read_head(path, max)) {
+                Ok((head, more)) => Ok(decode(path, head).map(|head| truncated(head, max, more))),
+                Err(e) => unreadable(path, e),
+            };
+        }
+        match timed(|t, d| t.1 += d, || fs::read(path)) {
+            Ok(bytes) => Ok(decode(path, bytes)),
+            Err(e) => unreadable(path, e),
+        }
+    }
+
+    fn classify_and_read(&self, path: &Path) -> Result<Option<String>, YoinkError> {
         // First try to determine if it's a text file by extension and content type
-        let is_text_result = is_text_file(path);
+        let is_text_result = timed(|t, d| t.0 += d, || detect_text_file(path, self.config.sniff_all));
          
         match is_text_result {
             Ok(true) => {
                 // It's a text file, read and process its content
-                match fs::read_to_string(path) {
-                    Ok(content) => Ok(Some(content)),
-                    Err(e) => {
-                        if self.config.verbose {
-                            println!("Error reading text file {}: {}", path.display(), e);
-                        }
-                        Ok(None)
-                    }
-                }
+                self.read_text(path)
             },
             Ok(false) => {
                 // It's a binary file
                 Ok(None)
             },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => unreadable(path, e),
             Err(e) => {
                 // Error determining file type, use legacy method as fallback
-                if self.config.verbose {
-                    println!("Warning: Could not determine file type, falling back to content analysis: {}", e);
-                }
+                log::warn!("Could not determine file type, falling back to content analysis: {}", e);
                 
                 // Read file content
-                match fs::read(path) {
+                match timed(|t, d| t.1 += d, || fs::read(path)) {
                     Ok(content) => {
                         // Check if it's a text file using the legacy method
                         if is_text(&content) {
@@ -53,9 +157,7 @@ impl TextProcessing for TextProcessor {
                             match String::from_utf8(content) {
                                 Ok(text) => Ok(Some(text)),
                                 Err(_) => {
-                                    if self.config.verbose {
-                                        println!("Error converting file to UTF-8: {}", path.display());
-                                    }
+                                    log::warn!("Error converting file to UTF-8: {}", path.display());
                                     Ok(None)
                                 }
                             }
@@ -63,92 +165,392 @@ impl TextProcessing for TextProcessor {
                             Ok(None)
                         }
                     },
-                    Err(e) => {
-                        if self.config.verbose {
-                            println!("Error reading file {}: {}", path.display(), e);
-                        }
-                        Ok(None)
-                    }
+                    Err(e) => unreadable(path, e),
                 }
             }
         }
     }
+}
 
-    fn format_text_content(&self, path: &Path, content: &str, buffer: &mut String) -> Result<bool, String> {
-        // Check if we need to search for text
-        if let Some(search_text) = &self.config.search_text {
-            let found = if self.config.case_sensitive {
-                content.contains(search_text)
+// Text that isn't quite UTF-8, such as a script with a Latin-1 string in it,
+// is still text and the odd bytes become replacement characters; a binary
+// file that only passed for text by its name is left out
+fn decode(path: &Path, bytes: Vec<u8>) -> Option<String> {
+    match String::from_utf8(bytes) {
+        Ok(text) => Some(text),
+        Err(e) if e.as_bytes().starts_with(b"#!") || is_text(e.as_bytes()) => {
+            log::debug!("{} is not valid UTF-8, replacing what isn't: {}", path.display(), e.utf8_error());
+            Some(String::from_utf8_lossy(e.as_bytes()).into_owned())
+        }
+        Err(_) => None,
+    }
+}
+
+// The first `max` lines of `path` exactly as they are, and how many lines
+// follow them, counted without holding the rest in memory
+fn read_head(path: &Path, max: usize) -> io::Result<(Vec<u8>, usize)> {
+    let mut reader = BufReader::with_capacity(64 * 1024, File::open(path)?);
+    let mut head = Vec::new();
+    let mut kept = 0;
+    while kept < max && reader.read_until(b'\n', &mut head)? > 0 {
+        kept += 1;
+    }
+    
+    let (mut more, mut unterminated) = (0, false);
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            break;
+        }
+        more += buffer.iter().filter(|&&b| b == b'\n').count();
+        unterminated = buffer.last() != Some(&b'\n');
+        let consumed = buffer.len();
+        reader.consume(consumed);
+    }
+    Ok((head, more + unterminated as usize))
+}
+
+// `head` followed by a marker for the `more` lines cut after the first `max`,
+// or `head` untouched when nothing was cut
+fn truncated(mut head: String, max: usize, more: usize) -> String {
+    if more == 0 {
+        return head;
+    }
+    if !head.ends_with('\n') {
+        head.push('\n');
+    }
+    head.push_str(&format!("{}{} more lines, {} in all]\n", TRUNCATED_PREFIX, more, max + more));
+    head
+}
+
+// `content` without the marker `truncated` ends it with, and the marker
+fn split_marker(content: &str) -> (&str, &str) {
+    match content.rfind(TRUNCATED_PREFIX) {
+        Some(at) if content[..at].ends_with('\n') && !content[at..].trim_end_matches('\n').contains('\n') => content.split_at(at),
+        _ => (content, ""),
+    }
+}
+
+fn unreadable<T>(path: &Path, e: io::Error) -> Result<Option<T>, YoinkError> {
+    if e.kind() == io::ErrorKind::NotFound {
+        return Err(YoinkError::Io { path: path.to_path_buf(), source: e });
+    }
+    log::warn!("Error reading file {}: {}", path.display(), e);
+    Ok(None)
+}
+
+// Longest prefix of a line that is kept or searched, so one enormous line
+// can't undo the point of streaming
+const MAX_LINE_BYTES: usize = 64 * 1024;
+
+// Start of the line that ends a file cut short by --max-lines
+const TRUNCATED_PREFIX: &str = "... [truncated ";
+
+// Longest first matching line quoted in the match index
+const MAX_INDEX_LINE_CHARS: usize = 120;
+
+impl TextProcessor {
+    fn stream_excerpt(&self, path: &Path) -> io::Result<Excerpt> {
+        let file = File::open(path)?;
+        let mut excerpt = Excerpt { size: file.metadata()?.len(), ..Excerpt::default() };
+        // Lines are matched one at a time here, even by a regex that could span them
+        let search = self.search.as_ref();
+        if search.is_some() {
+            excerpt.matches = Some(0);
+        }
+        
+        let mut tail = VecDeque::with_capacity(EXCERPT_LINES + 1);
+        let mut line = Vec::new();
+        let mut finish_line = |line: &mut Vec<u8>, excerpt: &mut Excerpt| {
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            let text = String::from_utf8_lossy(line);
+            if let (Some(search), Some(matches)) = (search, excerpt.matches.as_mut()) {
+                *matches += search.is_match(&text) as u64;
+            }
+            excerpt.lines += 1;
+            if excerpt.head.len() < EXCERPT_LINES {
+                excerpt.head.push(text.into_owned());
             } else {
-                content.to_lowercase().contains(&search_text.to_lowercase())
-            };
-            
-            if !found {
+                tail.push_back(text.into_owned());
+                if tail.len() > EXCERPT_LINES {
+                    tail.pop_front();
+                }
+            }
+            line.clear();
+        };
+        
+        let mut reader = BufReader::with_capacity(64 * 1024, file);
+        loop {
+            let buffer = timed(|t, d| t.1 += d, || reader.fill_buf())?;
+            if buffer.is_empty() {
+                break;
+            }
+            let consumed = buffer.len();
+            for piece in buffer.split_inclusive(|&b| b == b'\n') {
+                let (body, ends_line) = match piece.split_last() {
+                    Some((b'\n', body)) => (body, true),
+                    _ => (piece, false),
+                };
+                let room = MAX_LINE_BYTES.saturating_sub(line.len());
+                line.extend_from_slice(&body[..body.len().min(room)]);
+                if ends_line {
+                    finish_line(&mut line, &mut excerpt);
+                }
+            }
+            reader.consume(consumed);
+        }
+        if !line.is_empty() {
+            finish_line(&mut line, &mut excerpt);
+        }
+        
+        excerpt.tail = tail.into();
+        Ok(excerpt)
+    }
+
+    fn read(&self, path: &Path) -> Result<Option<String>, YoinkError> {
+        if let Some(kind) = special_file_kind(path) {
+            log::debug!("Not opening {} ({})", path.display(), kind);
+            return Ok(None);
+        }
+        
+        // Overrides are consulted before the cache or any sniffing
+        match self.forced_kind(path) {
+            Some(true) => return self.read_forced_text(path),
+            Some(false) => return Ok(None),
+            None => {}
+        }
+        
+        let cached = self.cache.as_ref().and_then(|cache| {
+            let metadata = fs::metadata(path).ok()?;
+            Some((cache, metadata))
+        });
+        
+        let Some((cache, metadata)) = cached else {
+            return self.classify_and_read(path);
+        };
+        
+        // Unchanged files skip type sniffing entirely, though a text file is
+        // only taken as unchanged once its content hashes the same
+        match cache.lookup(path, &metadata) {
+            Some(Cached::Binary) => {
+                cache.keep(path);
+                return Ok(None);
+            }
+            Some(Cached::Text { content_hash }) => {
+                let content = self.read_text(path)?;
+                if content.as_deref().map(cache::content_hash) == Some(content_hash) {
+                    cache.keep(path);
+                    return Ok(content);
+                }
+                log::debug!("{} changed without its size or modification time changing", path.display());
+            }
+            None => {}
+        }
+        
+        let content = self.classify_and_read(path)?;
+        cache.record(path, &metadata, content.as_deref());
+        Ok(content)
+    }
+
+    // `content` without its comments with --strip-comments, for a file whose
+    // language is known; a --max-lines marker is kept as it is
+    fn strip_comments(&self, path: &Path, content: String) -> String {
+        let Some(syntax) = comments::Syntax::of(path).filter(|_| self.config.strip_comments) else {
+            return content;
+        };
+        let (body, marker) = if self.max_lines().is_some() { split_marker(&content) } else { (content.as_str(), "") };
+        let mut stripped = comments::strip(syntax, body);
+        if !marker.is_empty() && !stripped.is_empty() && !stripped.ends_with('\n') {
+            stripped.push('\n');
+        }
+        stripped.push_str(marker);
+        stripped
+    }
+}
+
+impl TextProcessing for TextProcessor {
+    fn process_file(&self, path: &Path) -> Result<Option<String>, YoinkError> {
+        let content = self.read(path)?;
+        Ok(content.map(|content| self.strip_comments(path, content)))
+    }
+
+    fn search_matches(&self, content: &str) -> Option<SearchMatches> {
+        let matching = self.search.as_ref()?.matching_lines(content);
+        let &index = matching.first()?;
+        let first = content.lines().nth(index).unwrap_or_default();
+        Some(SearchMatches {
+            lines: matching.len(),
+            first_line: index + 1,
+            first: first.trim().chars().take(MAX_INDEX_LINE_CHARS).collect(),
+        })
+    }
+
+    fn condensed_bytes(&self) -> u64 {
+        self.condensed.load(Ordering::Relaxed)
+    }
+
+    fn summarize_file(&self, path: &Path) -> Result<Option<Excerpt>, YoinkError> {
+        if special_file_kind(path).is_some() {
+            return Ok(None);
+        }
+        let is_text = match self.forced_kind(path) {
+            Some(forced) => forced,
+            None => timed(|t, d| t.0 += d, || detect_text_file(path, self.config.sniff_all))
+                .map_err(|source| YoinkError::Io { path: path.to_path_buf(), source })?,
+        };
+        if !is_text {
+            return Ok(None);
+        }
+        
+        self.stream_excerpt(path)
+            .map(Some)
+            .map_err(|source| YoinkError::Io { path: path.to_path_buf(), source })
+    }
+
+    fn format_text_content(&self, path: &Path, content: &str, buffer: &mut String) -> Result<bool, YoinkError> {
+        // Check if we need to search for text
+        if let Some(search) = &self.search {
+            let matching = search.matching_lines(content);
+            if matching.is_empty() {
                 return Ok(false);
             }
             
-            // Add search match information
-            buffer.push_str(&format!("=== MATCH IN: {} ===\n", path.display()));
-            
             // Add context around matches
             let lines: Vec<&str> = content.lines().collect();
             let mut found_lines = Vec::new();
             
-            for (i, line) in lines.iter().enumerate() {
-                let line_matches = if self.config.case_sensitive {
-                    line.contains(search_text)
-                } else {
-                    line.to_lowercase().contains(&search_text.to_lowercase())
-                };
+            for i in matching {
+                // Add context (3 lines before and after)
+                let start = i.saturating_sub(3);
+                let end = (i + 3).min(lines.len() - 1);
                 
-                if line_matches {
-                    // Add context (3 lines before and after)
-                    let start = i.saturating_sub(3);
-                    let end = (i + 3).min(lines.len() - 1);
-                    
-                    for j in start..=end {
-                        found_lines.push((j, lines[j]));
-                    }
-                    
-                    // Add a separator between different match contexts
-                    found_lines.push((usize::MAX, "..."));
-                }
+                found_lines.extend((start..=end).map(|j| (j, lines[j])));
+                
+                // Add a separator between different match contexts
+                found_lines.push((usize::MAX, "..."));
             }
             
             // Remove duplicates and sort
             found_lines.sort_by_key(|&(idx, _)| idx);
             found_lines.dedup_by_key(|&mut (idx, _)| idx);
             
-            // Add to buffer
+            // Number the kept lines, marking gaps between them
+            let mut excerpt = String::new();
             let mut prev_idx = 0;
             let mut first = true;
             
             for (idx, line) in found_lines {
                 if idx == usize::MAX {
-                    buffer.push_str("...\n");
+                    excerpt.push_str("...\n");
                     first = true;
                     continue;
                 }
                 
                 if !first && idx > prev_idx + 1 {
-                    buffer.push_str("...\n");
+                    excerpt.push_str("...\n");
                 }
                 
                 // Add the line with line number
-                buffer.push_str(&format!("{}: {}\n", idx + 1, line));
+                excerpt.push_str(&format!("{}: {}\n", idx + 1, line));
                 
                 prev_idx = idx;
                 first = false;
             }
             
-            buffer.push_str("\n");
+            let shown = self.shown(path);
+            let shown = shown.display();
+            match self.config.format {
+                // HTML is laid out from the plain chunks by its formatter
+                OutputFormat::Plain | OutputFormat::Html => buffer.push_str(&format!("=== MATCH IN: {} ===\n{}\n", shown, excerpt)),
+                OutputFormat::Markdown => buffer.push_str(&markdown::file_chunk(
+                    &format!("{} (matches)", shown),
+                    markdown::language(path).unwrap_or_default(),
+                    &excerpt,
+                )),
+                OutputFormat::Xml => buffer.push_str(&xml::document(&shown.to_string(), &[("view", "matches")], &excerpt)),
+            }
         } else {
-            // Add file header
-            buffer.push_str(&format!("=== {} ===\n", path.display()));
-            buffer.push_str(content);
-            buffer.push_str("\n\n");
+            // Searching above matched the content as it was read
+            let condensed;
+            let content = if self.config.condense {
+                condensed = condense(content);
+                self.condensed.fetch_add((content.len() - condensed.len()) as u64, Ordering::Relaxed);
+                condensed.as_str()
+            } else {
+                content
+            };
+            
+            // Search excerpts above carry their own numbers, and symbol
+            // excerpts their line range in the header
+            let numbered;
+            let content = if self.config.line_numbers && self.config.symbols.is_empty() {
+                // The marker for lines cut by --max-lines isn't one of the file's
+                let (body, marker) = if self.max_lines().is_some() { split_marker(content) } else { (content, "") };
+                numbered = number_lines(body) + marker;
+                numbered.as_str()
+            } else {
+                content
+            };
+            let shown = self.shown(path);
+            match self.config.format {
+                OutputFormat::Plain | OutputFormat::Html => {
+                    // Add file header
+                    buffer.push_str(&self.config.header_format.render(path, &shown, content));
+                    buffer.push('\n');
+                    buffer.push_str(content);
+                    if let Some(footer) = &self.config.footer_format {
+                        if !content.is_empty() && !content.ends_with('\n') {
+                            buffer.push('\n');
+                        }
+                        buffer.push_str(&footer.render(path, &shown, content));
+                    }
+                    buffer.push_str("\n\n");
+                }
+                OutputFormat::Markdown => buffer.push_str(&markdown::file_chunk(
+                    &shown.display().to_string(),
+                    markdown::language(path).unwrap_or_default(),
+                    content,
+                )),
+                OutputFormat::Xml => buffer.push_str(&xml::document(&shown.display().to_string(), &[], content)),
+            }
         }
         
         Ok(true)
     }
-} 
\ No newline at end of file
+}
+
+// `content` with trailing whitespace stripped from each line and each run of
+// blank lines collapsed into one, keeping line endings as they were
+fn condense(content: &str) -> String {
+    let mut condensed = String::with_capacity(content.len());
+    let mut blank = false;
+    for line in content.split_inclusive('\n') {
+        let body = line.trim_end_matches(['\n', '\r']);
+        let kept = body.trim_end();
+        if kept.is_empty() && blank {
+            continue;
+        }
+        blank = kept.is_empty();
+        condensed.push_str(kept);
+        condensed.push_str(&line[body.len()..]);
+    }
+    condensed
+}
+
+// Each line of `content` after its line number, right-aligned to the widest one
+fn number_lines(content: &str) -> String {
+    let width = content.lines().count().to_string().len();
+    let mut numbered = String::with_capacity(content.len() + content.lines().count() * (width + 3));
+    for (index, line) in content.lines().enumerate() {
+        if index > 0 {
+            numbered.push('\n');
+        }
+        numbered.push_str(&format!("{:>width$} | {}", index + 1, line, width = width));
+    }
+    if content.ends_with('\n') {
+        numbered.push('\n');
+    }
+    numbered
+}
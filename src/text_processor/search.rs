@@ -0,0 +1,130 @@
+//! Which lines `--search` and `--search-regex` pick out of a file
+
+use crate::config::Config;
+use crate::error::YoinkError;
+use regex::{Regex, RegexBuilder};
+
+/// The search term, ready to match line by line
+#[derive(Debug, Clone)]
+pub enum LineSearch {
+    /// Literal text, lowercased for a case-insensitive search
+    Literal { text: String, case_sensitive: bool },
+    /// A compiled expression; one written with the `s` flag is run over the
+    /// whole file so its matches can span lines. A `whole_word` search only
+    /// counts matches with no word character on either side.
+    Regex { regex: Regex, spans_lines: bool, whole_word: bool },
+}
+
+impl LineSearch {
+    /// The search the config asks for, if any, with `--case-sensitive`
+    /// deciding the regex's case-insensitivity flag
+    ///
+    /// Literal text searched for as a whole word is escaped into an
+    /// expression, so the boundaries are checked the same way for both.
+    pub fn from_config(config: &Config) -> Result<Option<Self>, YoinkError> {
+        let Some(text) = config.search_text.as_deref() else {
+            return Ok(None);
+        };
+        if !config.search_regex && !config.whole_word {
+            let text = if config.case_sensitive { text.to_string() } else { text.to_lowercase() };
+            return Ok(Some(Self::Literal { text, case_sensitive: config.case_sensitive }));
+        }
+        let pattern = if config.search_regex { text.to_string() } else { regex::escape(text) };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!config.case_sensitive)
+            .build()
+            .map_err(|e| YoinkError::SearchRegex { message: e.to_string() })?;
+        let spans_lines = config.search_regex && sets_dot_all(text);
+        Ok(Some(Self::Regex { regex, spans_lines, whole_word: config.whole_word }))
+    }
+
+    /// Whether a single line matches
+    pub fn is_match(&self, line: &str) -> bool {
+        match self {
+            Self::Literal { text, case_sensitive: true } => line.contains(text.as_str()),
+            Self::Literal { text, case_sensitive: false } => line.to_lowercase().contains(text.as_str()),
+            Self::Regex { regex, whole_word: false, .. } => regex.is_match(line),
+            Self::Regex { regex, whole_word: true, .. } => regex.find_iter(line).any(|m| is_whole_word(line, m.range())),
+        }
+    }
+
+    /// Zero-based indices, in order, of the lines of `content` with a match
+    /// in them, counting every line a multi-line match touches
+    pub fn matching_lines(&self, content: &str) -> Vec<usize> {
+        match self {
+            Self::Regex { regex, spans_lines: true, whole_word } => {
+                let starts: Vec<usize> = std::iter::once(0)
+                    .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+                    .filter(|&start| start < content.len())
+                    .collect();
+                let line_of = |offset: usize| starts.partition_point(|&start| start <= offset).saturating_sub(1);
+                let mut lines: Vec<usize> = regex
+                    .find_iter(content)
+                    .filter(|m| !whole_word || is_whole_word(content, m.range()))
+                    .flat_map(|m| line_of(m.start())..=line_of(m.end().saturating_sub(1).max(m.start())))
+                    .filter(|&line| line < starts.len())
+                    .collect();
+                lines.dedup();
+                lines
+            }
+            _ => content.lines().enumerate().filter(|(_, line)| self.is_match(line)).map(|(i, _)| i).collect(),
+        }
+    }
+}
+
+// Whether the match at `range` in `text` has no letter, digit, or `_` right
+// before or after it
+fn is_whole_word(text: &str, range: std::ops::Range<usize>) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    !text[..range.start].chars().next_back().is_some_and(is_word) && !text[range.end..].chars().next().is_some_and(is_word)
+}
+
+// Whether a `(?flags)` or `(?flags:...)` group in `pattern` turns on `s`,
+// letting `.` match a newline
+fn sets_dot_all(pattern: &str) -> bool {
+    pattern.match_indices("(?").any(|(i, _)| {
+        let flags = &pattern[i + 2..];
+        let flags = &flags[..flags.find([')', ':']).unwrap_or(flags.len())];
+        flags.split('-').next().unwrap_or_default().contains('s')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(pattern: &str, case_sensitive: bool) -> LineSearch {
+        let config = Config::builder().search_regex(pattern).case_sensitive(case_sensitive).build();
+        LineSearch::from_config(&config).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_regex_matches_stay_within_lines_unless_asked() {
+        let content = "fn load() {\n    todo!()\n}\nfn Save() {}\n";
+
+        assert_eq!(search(r"fn [a-z]+\(", true).matching_lines(content), [0]);
+        assert_eq!(search(r"fn [a-z]+\(", false).matching_lines(content), [0, 3]);
+        assert_eq!(search(r"\{\s+todo", false).matching_lines(content), Vec::<usize>::new());
+        assert_eq!(search(r"(?s)\{.+?todo", false).matching_lines(content), [0, 1]);
+        assert_eq!(search(r"(?-s:\{.+?todo)", false).matching_lines(content), Vec::<usize>::new());
+
+        let config = Config::builder().search_regex("fn (").build();
+        let error = LineSearch::from_config(&config).unwrap_err().to_string();
+        assert!(error.contains("unclosed group"), "{}", error);
+    }
+
+    #[test]
+    fn test_whole_word_needs_non_word_characters_around_the_match() {
+        let content = "let id = 1;\nlet identifier = valid_id;\nuser.ID\nstrčid\ncall(id)\n";
+        let word = |config: crate::config::ConfigBuilder| {
+            LineSearch::from_config(&config.whole_word(true).build()).unwrap().unwrap().matching_lines(content)
+        };
+
+        assert_eq!(word(Config::builder().search_text("id")), [0, 2, 4]);
+        assert_eq!(word(Config::builder().search_text("id").case_sensitive(true)), [0, 4]);
+        assert_eq!(word(Config::builder().search_regex("i[dD]").case_sensitive(true)), [0, 4]);
+        assert_eq!(word(Config::builder().search_text("1;")), [0]);
+        assert_eq!(word(Config::builder().search_text("valid")), Vec::<usize>::new());
+        assert!(search("id", false).is_match("valid"));
+    }
+}
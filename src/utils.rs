@@ -1,6 +1,6 @@
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{self, Read};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 // General-purpose text file detection based on content analysis
 pub fn is_text(data: &[u8]) -> bool {
@@ -37,28 +37,264 @@ pub fn is_text(data: &[u8]) -> bool {
     (text_chars as f32 / sample_size as f32) >= threshold
 }
 
+// Parse a size like "512K", "64M" or "2G"; a bare number is taken as MB
+// to match --max-size
+pub fn parse_size(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    let split = input.find(|c: char| !c.is_ascii_digit()).unwrap_or(input.len());
+    let (digits, unit) = input.split_at(split);
+    
+    let value: u64 = digits.parse().map_err(|_| format!("invalid size '{}'", input))?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "B" => 1,
+        "K" | "KB" => 1024,
+        "" | "M" | "MB" => 1024 * 1024,
+        "G" | "GB" => 1024 * 1024 * 1024,
+        _ => return Err(format!("unknown size unit in '{}' (use K, M or G)", input)),
+    };
+    
+    value.checked_mul(multiplier).ok_or_else(|| format!("size '{}' is too large", input))
+}
+
+/// Human-readable size such as "12.3 KB", using powers of 1024
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Levenshtein distance between two names, counting characters rather than bytes
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The entry beside a missing `path` whose name is closest to its own, if
+/// any is within a typo or two
+///
+/// Only the parent directory is listed, so this is cheap even for a big tree.
+pub fn closest_sibling(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let names: Vec<String> = fs::read_dir(parent)
+        .ok()?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .collect();
+    close_matches(name, names.iter().map(String::as_str)).first().map(|candidate| parent.join(candidate))
+}
+
+/// Those of `candidates` within a typo or two of `name`, closest first and
+/// then alphabetically, leaving out `name` itself
+pub fn close_matches<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    // Short names allow a single edit, so `a` doesn't suggest every other one-letter name
+    let allowed = (name.chars().count() / 3).clamp(1, 2);
+    let mut close: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= allowed)
+        .collect();
+    close.sort();
+    close.dedup();
+    close.into_iter().map(|(_, candidate)| candidate).collect()
+}
+
+/// Whether a file or directory should be treated as hidden
+///
+/// A leading dot hides an entry everywhere. On Windows, entries carrying the
+/// hidden attribute are hidden too.
+pub fn is_hidden(path: &Path) -> bool {
+    let dotted = path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+    dotted || has_hidden_attribute(path)
+}
+
+#[cfg(windows)]
+fn has_hidden_attribute(path: &Path) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    use windows_sys::Win32::Storage::FileSystem::FILE_ATTRIBUTE_HIDDEN;
+
+    std::fs::symlink_metadata(path).is_ok_and(|m| m.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+}
+
+#[cfg(not(windows))]
+fn has_hidden_attribute(_path: &Path) -> bool {
+    false
+}
+
+/// What kind of special file `path` is, if it's a FIFO, socket, or device
+///
+/// Reading one of these can block forever or never reach end of file.
+/// Symlinks are followed, so a link to `/dev/zero` counts too.
+pub fn special_file_kind(path: &Path) -> Option<&'static str> {
+    let file_type = std::fs::metadata(path).ok()?.file_type();
+    special_kind(&file_type)
+}
+
+#[cfg(unix)]
+fn special_kind(file_type: &std::fs::FileType) -> Option<&'static str> {
+    use std::os::unix::fs::FileTypeExt;
+
+    if file_type.is_fifo() {
+        Some("FIFO")
+    } else if file_type.is_socket() {
+        Some("socket")
+    } else if file_type.is_block_device() {
+        Some("block device")
+    } else if file_type.is_char_device() {
+        Some("character device")
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn special_kind(_file_type: &std::fs::FileType) -> Option<&'static str> {
+    None
+}
+
+/// This machine's hostname, if it can be found without extra dependencies
+pub fn hostname() -> Option<String> {
+    let from_env = ["HOSTNAME", "COMPUTERNAME"].iter().find_map(|var| std::env::var(var).ok());
+    let from_file = || std::fs::read_to_string("/etc/hostname").ok();
+    let from_command = || {
+        std::process::Command::new("hostname")
+            .output()
+            .ok()
+            .filter(|out| out.status.success())
+            .and_then(|out| String::from_utf8(out.stdout).ok())
+    };
+
+    from_env
+        .or_else(from_file)
+        .or_else(from_command)
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// Directory that scanned paths are shown relative to: the root itself, or
+/// its parent when the root is a single file
+pub fn relative_base(root: &Path) -> &Path {
+    if root.is_file() {
+        root.parent().unwrap_or(root)
+    } else {
+        root
+    }
+}
+
+/// `path` as a file's header shows it: relative to `base`, or as walked when
+/// there is no base or the path lies outside it, as a symlinked entry may
+pub fn header_path<'a>(path: &'a Path, base: Option<&Path>) -> &'a Path {
+    match base.map(|base| path.strip_prefix(base)) {
+        Some(Ok(relative)) if !relative.as_os_str().is_empty() => relative,
+        _ => path,
+    }
+}
+
 // Load a file and determine if it's a text file
 pub fn is_text_file(path: &Path) -> io::Result<bool> {
-    // First check file extension for common text formats
-    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-        let ext = ext.to_lowercase();
-        if is_common_text_extension(&ext) {
-            return Ok(true);
-        }
-        if is_common_binary_extension(&ext) {
-            return Ok(false);
-        }
+    // Well-known extensionless files are text by name alone
+    if path.file_name().and_then(|n| n.to_str()).is_some_and(is_common_text_filename) {
+        return Ok(true);
+    }
+
+    // A text extension needs no reading; a shebang outranks a binary one
+    let ext = path.extension().and_then(|e| e.to_str()).map(str::to_lowercase);
+    if ext.as_deref().is_some_and(is_common_text_extension) {
+        return Ok(true);
     }
 
-    // For other files, examine the content
     let mut file = File::open(path)?;
+    if ext.as_deref().is_some_and(is_common_binary_extension) {
+        let mut start = [0; 2];
+        let bytes_read = file.read(&mut start)?;
+        return Ok(start[..bytes_read] == *b"#!");
+    }
     
     // Read up to 8KB for analysis (sufficient for file type detection)
     let mut buffer = vec![0; 8192];
     let bytes_read = file.read(&mut buffer)?;
     buffer.truncate(bytes_read);
     
-    Ok(is_text(&buffer))
+    // Scripts say what they are on their first line, whatever bytes follow
+    Ok(buffer.starts_with(b"#!") || is_text(&buffer))
+}
+
+// How much of a file `--sniff-all` looks at
+const SNIFF_BYTES: u64 = 64 * 1024;
+
+/// Whether a file is text, with `--sniff-all` when `sniff_all` is set and
+/// the extension fast path of `is_text_file` otherwise
+pub fn detect_text_file(path: &Path, sniff_all: bool) -> io::Result<bool> {
+    if sniff_all {
+        sniff_text_file(path)
+    } else {
+        is_text_file(path)
+    }
+}
+
+// Content always decides, with the extension lists only saying what to expect:
+// a binary extension is overruled by content that is text beyond doubt, and a
+// text extension by content that isn't text at all
+pub fn sniff_text_file(path: &Path) -> io::Result<bool> {
+    let mut buffer = Vec::new();
+    File::open(path)?.take(SNIFF_BYTES).read_to_end(&mut buffer)?;
+    
+    let ext = path.extension().and_then(|e| e.to_str()).map(str::to_lowercase);
+    let expected = match ext.as_deref() {
+        Some(ext) if is_common_text_extension(ext) => Some(true),
+        Some(ext) if is_common_binary_extension(ext) => Some(false),
+        _ => None,
+    };
+    // An empty file has nothing to sniff
+    if buffer.is_empty() {
+        return Ok(expected.unwrap_or(false));
+    }
+    if buffer.starts_with(b"#!") {
+        return Ok(true);
+    }
+    match expected {
+        Some(false) if is_overwhelmingly_text(&buffer) => {
+            log::info!("Included despite binary extension: {} (its content is text)", path.display());
+            Ok(true)
+        }
+        Some(false) => Ok(false),
+        Some(true) if !is_text(&buffer) => {
+            log::info!("Skipped despite text extension: {} (its content is binary)", path.display());
+            Ok(false)
+        }
+        _ => Ok(is_text(&buffer)),
+    }
+}
+
+// No null bytes, no known binary signature, and over 99% printable
+fn is_overwhelmingly_text(data: &[u8]) -> bool {
+    if data.contains(&0) || infer::get(data).is_some_and(|kind| is_binary_mime_type(kind.mime_type())) {
+        return false;
+    }
+    let printable = data.iter().filter(|&&b| (b >= 32 && b != 127) || matches!(b, b'\n' | b'\r' | b'\t')).count();
+    printable as f64 / data.len() as f64 > 0.99
 }
 
 // List of common text file extensions
@@ -75,6 +311,19 @@ fn is_common_text_extension(ext: &str) -> bool {
     )
 }
 
+// Files that are text whatever their content looks like, and usually have
+// no extension to say so
+fn is_common_text_filename(name: &str) -> bool {
+    matches!(name,
+        "Makefile" | "GNUmakefile" | "makefile" | "Dockerfile" | "Containerfile" |
+        "Jenkinsfile" | "Vagrantfile" | "Procfile" | "Gemfile" | "Rakefile" | "Brewfile" |
+        "LICENSE" | "LICENCE" | "COPYING" | "NOTICE" | "AUTHORS" | "README" | "CHANGELOG" |
+        "CMakeLists.txt" | "Cargo.lock" |
+        ".gitignore" | ".gitattributes" | ".gitmodules" | ".dockerignore" | ".editorconfig" |
+        ".yoinkignore" | ".env.example"
+    )
+}
+
 // List of common binary file extensions
 fn is_common_binary_extension(ext: &str) -> bool {
     matches!(ext,
@@ -96,4 +345,104 @@ fn is_binary_mime_type(mime_type: &str) -> bool {
     mime_type.starts_with("video/") || 
     mime_type.starts_with("audio/") || 
     mime_type.starts_with("application/") && !mime_type.contains("json") && !mime_type.contains("xml") && !mime_type.contains("text")
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_all_lets_content_overrule_the_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = dir.path().join("data.bin");
+        fs::write(&data, "id,name\n1,alpha\n2,beta\n").unwrap();
+        let image = dir.path().join("image.txt");
+        fs::write(&image, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01\0\0\0\x01\x08\x06\0\0\0\x1f\x15\xc4\x89").unwrap();
+        let blob = dir.path().join("blob.dat");
+        fs::write(&blob, b"header\x01\x02\x03 mostly text but not quite").unwrap();
+
+        assert!(!is_text_file(&data).unwrap());
+        assert!(sniff_text_file(&data).unwrap());
+        assert!(is_text_file(&image).unwrap());
+        assert!(!sniff_text_file(&image).unwrap());
+        assert!(!sniff_text_file(&blob).unwrap());
+    }
+
+    #[test]
+    fn test_shebangs_and_known_names_are_text() {
+        let dir = tempfile::tempdir().unwrap();
+        // Escape sequences and Latin-1 bytes sink a short file under the content heuristic
+        let script: &[u8] = b"#!/bin/sh\necho \x1b[1m\xe9t\xe9\x1b[0m\x07\n";
+        assert!(!is_text(script));
+        for name in ["deploy", "run.bin"] {
+            fs::write(dir.path().join(name), script).unwrap();
+            assert!(is_text_file(&dir.path().join(name)).unwrap(), "{}", name);
+            assert!(sniff_text_file(&dir.path().join(name)).unwrap(), "{}", name);
+        }
+
+        fs::write(dir.path().join("LICENSE"), b"\x1b\x1b\x1b").unwrap();
+        assert!(is_text_file(&dir.path().join("LICENSE")).unwrap());
+        fs::write(dir.path().join("configure"), b"\x7fELF\x02\x01\x01\0\0\0").unwrap();
+        assert!(!is_text_file(&dir.path().join("configure")).unwrap());
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(48 * 1024 * 1024), "48.0 MB");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("sr", "src"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("é", "e"), 1);
+    }
+
+    #[test]
+    fn test_header_paths_are_relative_inside_the_base_only() {
+        let base = Path::new("/home/me/app");
+        assert_eq!(header_path(Path::new("/home/me/app/src/main.rs"), Some(base)), Path::new("src/main.rs"));
+        assert_eq!(header_path(Path::new("/opt/shared/lib.rs"), Some(base)), Path::new("/opt/shared/lib.rs"));
+        assert_eq!(header_path(base, Some(base)), base);
+        assert_eq!(header_path(Path::new("/home/me/app/src/main.rs"), None), Path::new("/home/me/app/src/main.rs"));
+        assert_eq!(header_path(Path::new("main.rs"), Some(Path::new(""))), Path::new("main.rs"));
+    }
+
+    #[test]
+    fn test_closest_sibling_suggests_near_names_only() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["src", "docs", "Cargo.toml"] {
+            std::fs::create_dir(dir.path().join(name)).unwrap();
+        }
+
+        assert_eq!(closest_sibling(&dir.path().join("sr")), Some(dir.path().join("src")));
+        assert_eq!(closest_sibling(&dir.path().join("cargo.tom")), Some(dir.path().join("Cargo.toml")));
+        assert_eq!(closest_sibling(&dir.path().join("lib")), None);
+        assert_eq!(closest_sibling(&dir.path().join("missing/src")), None);
+    }
+
+    #[test]
+    fn test_dot_files_are_hidden() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".env"), "x").unwrap();
+        std::fs::write(dir.path().join("visible.txt"), "x").unwrap();
+
+        assert!(is_hidden(&dir.path().join(".env")));
+        assert!(!is_hidden(&dir.path().join("visible.txt")));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_hidden_attribute_hides_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("desktop.ini");
+        std::fs::write(&file, "x").unwrap();
+        assert!(!is_hidden(&file));
+
+        let status = std::process::Command::new("attrib").arg("+h").arg(&file).status().unwrap();
+        assert!(status.success());
+        assert!(is_hidden(&file));
+    }
+}
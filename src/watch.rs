@@ -0,0 +1,115 @@
+use crate::error::YoinkError;
+use crate::file_processor::FileProcessor;
+use crate::report::ProcessReport;
+use notify::{RecursiveMode, Watcher};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// How long the tree must be quiet before re-running
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// How often to check for cancellation while waiting for changes
+const POLL: Duration = Duration::from_millis(100);
+
+/// Re-run `processor` whenever files under its root change
+///
+/// Changes to paths the scanner would filter out (excluded directories,
+/// hidden files, other extensions) are ignored. Bursts of events are
+/// debounced so a save or `git checkout` triggers a single run. Returns once
+/// the processor's cancel token is triggered.
+pub fn watch<F>(processor: &mut FileProcessor, mut on_run: F) -> Result<(), YoinkError>
+where
+    F: FnMut(Result<ProcessReport, YoinkError>),
+{
+    let root = PathBuf::from(&processor.config().path);
+    let cancel = processor.cancel_token();
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })
+    .map_err(|source| YoinkError::Watch { path: root.clone(), source })?;
+    watcher
+        .watch(&root, RecursiveMode::Recursive)
+        .map_err(|source| YoinkError::Watch { path: root.clone(), source })?;
+
+    // Rewriting an --output file inside the tree mustn't set off another run
+    let output = processor.config().output.as_ref().and_then(|path| path.canonicalize().ok());
+    let is_output = |path: &PathBuf| output.is_some() && path.canonicalize().ok() == output;
+
+    let mut pending_since: Option<Instant> = None;
+
+    while !cancel.is_cancelled() {
+        match rx.recv_timeout(POLL) {
+            Ok(Ok(event)) => {
+                let relevant = event.paths.iter().any(|path| !is_output(path) && processor.matches_filters(path));
+                if relevant {
+                    log::debug!("Change detected: {:?}", event.paths);
+                    pending_since = Some(Instant::now());
+                }
+            }
+            Ok(Err(e)) => log::warn!("Watch error: {}", e),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        if pending_since.is_some_and(|since| since.elapsed() >= DEBOUNCE) {
+            pending_since = None;
+            let result = processor.process();
+            if matches!(result, Err(YoinkError::Cancelled { .. })) {
+                break;
+            }
+            on_run(result);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::MockClipboardManager;
+    use crate::config::Config;
+    use std::fs;
+    use std::thread;
+
+    #[test]
+    fn test_reruns_on_relevant_changes_only() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("target")).unwrap();
+        fs::write(dir.path().join("a.txt"), "alpha").unwrap();
+
+        let config = Config::builder()
+            .path(dir.path().to_string_lossy())
+            .exclude_paths(["target"])
+            .build();
+        let mut processor = FileProcessor::builder(config)
+            .clipboard(MockClipboardManager::new())
+            .build();
+
+        let cancel = processor.cancel_token();
+        let root = dir.path().to_path_buf();
+        let timeout = cancel.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            // Churn in an excluded directory must not trigger a run
+            fs::write(root.join("target").join("build.txt"), "ignored").unwrap();
+            thread::sleep(Duration::from_millis(1000));
+            fs::write(root.join("b.txt"), "beta").unwrap();
+            // Safety net so a missed event can't hang the test
+            thread::sleep(Duration::from_secs(10));
+            timeout.cancel();
+        });
+
+        let mut runs = Vec::new();
+        watch(&mut processor, |result| {
+            runs.push(result.unwrap().text_files);
+            cancel.cancel();
+        })
+        .unwrap();
+
+        assert_eq!(runs, vec![2]);
+    }
+}
@@ -0,0 +1,292 @@
+//! Finding the members of Cargo and JS workspaces
+//!
+//! Only the few keys that name members are read, so the manifests are parsed
+//! by hand rather than with a full TOML or YAML parser.
+
+use crate::error::YoinkError;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Files at the workspace root that are kept alongside any selected members
+pub const ROOT_MANIFESTS: [&str; 7] = [
+    "Cargo.toml",
+    "Cargo.lock",
+    "package.json",
+    "package-lock.json",
+    "pnpm-workspace.yaml",
+    "pnpm-lock.yaml",
+    "yarn.lock",
+];
+
+/// One package in a workspace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    pub name: String,
+    /// Directory relative to the workspace root
+    pub path: PathBuf,
+}
+
+/// Every member found under a root, from all the workspace definitions there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workspace {
+    root: PathBuf,
+    pub members: Vec<Member>,
+}
+
+/// What a `--package` selection keeps, by path relative to the root
+#[derive(Debug, Clone)]
+pub struct Scope {
+    dirs: Vec<PathBuf>,
+}
+
+impl Scope {
+    pub fn contains(&self, relative: &Path) -> bool {
+        let root_manifest = relative.parent() == Some(Path::new(""))
+            && relative.to_str().is_some_and(|name| ROOT_MANIFESTS.contains(&name));
+        root_manifest || self.dirs.iter().any(|dir| relative.starts_with(dir))
+    }
+}
+
+impl Workspace {
+    /// Read the Cargo, pnpm, and package.json workspace definitions at `root`
+    pub fn discover(root: &Path) -> Result<Self, YoinkError> {
+        let error = |message: String| YoinkError::Workspace { path: root.to_path_buf(), message };
+
+        let mut definitions = Vec::new();
+        if let Some(text) = read(&root.join("Cargo.toml")) {
+            if let Some(table) = toml_table(&text, "workspace") {
+                let members = toml_array(&table, "members").unwrap_or_default();
+                let exclude = toml_array(&table, "exclude").unwrap_or_default();
+                definitions.push((members, exclude, "Cargo.toml"));
+            }
+        }
+        let pnpm = read(&root.join("pnpm-workspace.yaml")).map(|text| pnpm_packages(&text));
+        let npm = read(&root.join("package.json")).map(|text| package_json_workspaces(&text)).transpose().map_err(error)?.flatten();
+        if let Some(patterns) = pnpm.or(npm) {
+            let (exclude, members): (Vec<String>, Vec<String>) = patterns.into_iter().partition(|p| p.starts_with('!'));
+            let exclude = exclude.into_iter().map(|p| p[1..].to_string()).collect();
+            definitions.push((members, exclude, "package.json"));
+        }
+
+        if definitions.is_empty() {
+            return Err(error(
+                "no workspace found (looked for [workspace] in Cargo.toml, pnpm-workspace.yaml, and workspaces in package.json)".to_string(),
+            ));
+        }
+
+        let mut members = Vec::new();
+        for (patterns, exclude, manifest) in definitions {
+            for path in expand(root, &patterns, &exclude, manifest) {
+                let name = member_name(&root.join(&path), manifest)
+                    .unwrap_or_else(|| path.file_name().unwrap_or_default().to_string_lossy().into_owned());
+                members.push(Member { name, path });
+            }
+        }
+        members.sort_by(|a, b| a.path.cmp(&b.path));
+        members.dedup_by(|a, b| a.path == b.path);
+        Ok(Self { root: root.to_path_buf(), members })
+    }
+
+    /// The directories of the named members, matched by package name or path
+    pub fn scope(&self, names: &[String]) -> Result<Scope, YoinkError> {
+        let mut dirs = Vec::new();
+        for name in names {
+            let member = self
+                .members
+                .iter()
+                .find(|m| m.name == *name || m.path == Path::new(name))
+                .ok_or_else(|| YoinkError::Workspace {
+                    path: self.root.clone(),
+                    message: format!("no package named '{}'", name),
+                })?;
+            dirs.push(member.path.clone());
+        }
+        Ok(Scope { dirs })
+    }
+}
+
+fn read(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok()
+}
+
+// Member directories matching `patterns` but not `exclude`, relative to `root`
+fn expand(root: &Path, patterns: &[String], exclude: &[String], manifest: &str) -> Vec<PathBuf> {
+    let exclude: Vec<glob::Pattern> = exclude.iter().filter_map(|p| glob::Pattern::new(p.trim_end_matches('/')).ok()).collect();
+    let escaped_root = glob::Pattern::escape(&root.to_string_lossy());
+
+    let mut dirs = Vec::new();
+    for pattern in patterns {
+        let full = format!("{}/{}", escaped_root, pattern.trim_end_matches('/'));
+        let Ok(matches) = glob::glob(&full) else {
+            log::warn!("Ignoring invalid workspace member pattern '{}'", pattern);
+            continue;
+        };
+        for path in matches.filter_map(Result::ok) {
+            let Ok(relative) = path.strip_prefix(root) else { continue };
+            if path.join(manifest).is_file() && !exclude.iter().any(|p| p.matches_path(relative)) {
+                dirs.push(relative.to_path_buf());
+            }
+        }
+    }
+    dirs
+}
+
+fn member_name(dir: &Path, manifest: &str) -> Option<String> {
+    let text = read(&dir.join(manifest))?;
+    if manifest == "Cargo.toml" {
+        toml_string(&toml_table(&text, "package")?, "name")
+    } else {
+        let json: serde_json::Value = serde_json::from_str(&text).ok()?;
+        json.get("name")?.as_str().map(str::to_string)
+    }
+}
+
+// The body of `[name]`, with comments removed
+fn toml_table(text: &str, name: &str) -> Option<String> {
+    let header = format!("[{}]", name);
+    let mut lines = text.lines().map(strip_comment);
+    lines.find(|line| line.trim() == header)?;
+    let body: Vec<&str> = lines.take_while(|line| !line.trim_start().starts_with('[')).collect();
+    Some(body.join("\n"))
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    for (i, c) in line.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            ('#', None) => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+// The value text after `key =`, which must start a line of the table
+fn toml_value<'a>(table: &'a str, key: &str) -> Option<&'a str> {
+    let mut offset = 0;
+    for line in table.split_inclusive('\n') {
+        if let Some((name, _)) = line.split_once('=') {
+            if name.trim() == key {
+                return Some(table[offset + name.len() + 1..].trim_start());
+            }
+        }
+        offset += line.len();
+    }
+    None
+}
+
+fn toml_string(table: &str, key: &str) -> Option<String> {
+    quoted_strings(toml_value(table, key)?.lines().next()?).into_iter().next()
+}
+
+fn toml_array(table: &str, key: &str) -> Option<Vec<String>> {
+    let value = toml_value(table, key)?.strip_prefix('[')?;
+    Some(quoted_strings(&value[..value.find(']')?]))
+}
+
+fn quoted_strings(text: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(['"', '\'']) {
+        let quote = rest[start..].chars().next().unwrap();
+        let Some(len) = rest[start + 1..].find(quote) else { break };
+        strings.push(rest[start + 1..start + 1 + len].to_string());
+        rest = &rest[start + len + 2..];
+    }
+    strings
+}
+
+// Entries of the top-level `packages:` list
+fn pnpm_packages(text: &str) -> Vec<String> {
+    let mut lines = text.lines().map(strip_comment);
+    if lines.by_ref().find(|line| line.trim_end() == "packages:").is_none() {
+        return Vec::new();
+    }
+    lines
+        .take_while(|line| line.is_empty() || line.starts_with([' ', '\t', '-']))
+        .filter_map(|line| line.trim().strip_prefix('-'))
+        .map(|entry| entry.trim().trim_matches(['"', '\'']).to_string())
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+// `workspaces` as either a list of patterns or an object with a `packages` list
+fn package_json_workspaces(text: &str) -> Result<Option<Vec<String>>, String> {
+    let json: serde_json::Value = serde_json::from_str(text).map_err(|e| format!("package.json is not valid JSON: {}", e))?;
+    let Some(workspaces) = json.get("workspaces") else {
+        return Ok(None);
+    };
+    let list = workspaces.get("packages").unwrap_or(workspaces);
+    Ok(list.as_array().map(|patterns| patterns.iter().filter_map(|p| p.as_str().map(str::to_string)).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, files: &[(&str, &str)]) {
+        for (path, content) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+    }
+
+    fn members(workspace: &Workspace) -> Vec<(&str, &str)> {
+        workspace.members.iter().map(|m| (m.name.as_str(), m.path.to_str().unwrap())).collect()
+    }
+
+    #[test]
+    fn test_cargo_members_and_excludes() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), &[
+            ("Cargo.toml", "[workspace]\nmembers = [\n    \"crates/*\", # all crates\n    'cli',\n]\nexclude = [\"crates/scratch\"]\n\n[workspace.package]\nversion = \"1.0.0\"\n"),
+            ("crates/parser/Cargo.toml", "[package]\nname = \"acme-parser\"\nversion = \"0.1.0\"\n"),
+            ("crates/lexer/Cargo.toml", "[package]\nname = \"acme-lexer\" # the lexer\n"),
+            ("crates/scratch/Cargo.toml", "[package]\nname = \"scratch\"\n"),
+            ("crates/notes/README.md", "not a crate"),
+            ("cli/Cargo.toml", "[package]\nname = \"acme\"\n"),
+        ]);
+
+        let workspace = Workspace::discover(dir.path()).unwrap();
+        assert_eq!(members(&workspace), vec![
+            ("acme", "cli"),
+            ("acme-lexer", "crates/lexer"),
+            ("acme-parser", "crates/parser"),
+        ]);
+
+        let scope = workspace.scope(&["acme-parser".to_string()]).unwrap();
+        assert!(scope.contains(Path::new("crates/parser/src/lib.rs")));
+        assert!(scope.contains(Path::new("Cargo.lock")));
+        assert!(!scope.contains(Path::new("crates/lexer/src/lib.rs")));
+        assert!(!scope.contains(Path::new("crates/parser-extras/Cargo.toml")));
+        assert!(workspace.scope(&["nope".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_js_workspaces() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), &[
+            ("package.json", r#"{ "name": "root", "workspaces": { "packages": ["packages/*"] } }"#),
+            ("packages/ui/package.json", r#"{ "name": "@acme/ui" }"#),
+            ("packages/api/package.json", r#"{ "name": "@acme/api" }"#),
+        ]);
+        let workspace = Workspace::discover(dir.path()).unwrap();
+        assert_eq!(members(&workspace), vec![("@acme/api", "packages/api"), ("@acme/ui", "packages/ui")]);
+
+        // pnpm's file takes over from package.json
+        write(dir.path(), &[("pnpm-workspace.yaml", "packages:\n  - 'packages/*'\n  - \"!packages/api\" # private\nonlyBuiltDependencies:\n  - esbuild\n")]);
+        let workspace = Workspace::discover(dir.path()).unwrap();
+        assert_eq!(members(&workspace), vec![("@acme/ui", "packages/ui")]);
+    }
+
+    #[test]
+    fn test_missing_workspace_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), &[("Cargo.toml", "[package]\nname = \"solo\"\n"), ("package.json", "{}")]);
+        let error = Workspace::discover(dir.path()).unwrap_err();
+        assert!(error.to_string().contains("no workspace found"));
+    }
+}
@@ -0,0 +1,130 @@
+//! XML output: one `<document>` element per file inside `<documents>`
+
+/// Escape `text` for use in an attribute value or element content
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `content` as CDATA, split wherever it contains the `]]>` that would end it
+pub fn cdata(content: &str) -> String {
+    format!("<![CDATA[{}]]>", content.replace("]]>", "]]]]><![CDATA[>"))
+}
+
+/// A file's element, with `attributes` after its path and the content on
+/// the lines between the opening and closing tags
+pub fn document(path: &str, attributes: &[(&str, &str)], content: &str) -> String {
+    let mut element = format!("<document path=\"{}\"", escape(path));
+    for (name, value) in attributes {
+        element.push_str(&format!(" {}=\"{}\"", name, escape(value)));
+    }
+    let mut body = content.to_string();
+    if !body.ends_with('\n') {
+        body.push('\n');
+    }
+    // The CDATA opens and closes on the tag lines so the content keeps its own lines
+    let cdata = cdata(&format!("\n{}", body));
+    let (open, rest) = cdata.split_at("<![CDATA[\n".len());
+    element.push_str(&format!(">{}{}</document>\n\n", open, rest));
+    element
+}
+
+/// `text` escaped inside an element of its own
+pub fn element(name: &str, text: &str) -> String {
+    let mut element = format!("<{}>\n{}", name, escape(text));
+    if !element.ends_with('\n') {
+        element.push('\n');
+    }
+    element.push_str(&format!("</{}>\n", name));
+    element
+}
+
+/// Restyle a plain `=== path === (note)` chunk, such as a summary or
+/// duplicate stub, as a `<document>`
+pub fn from_plain(chunk: &str) -> String {
+    let (first, body) = chunk.split_once('\n').unwrap_or((chunk, ""));
+    let heading = first.strip_prefix("=== ").unwrap_or(first);
+    let (path, note) = match heading.split_once(" ===") {
+        Some((path, note)) => (path, note.trim().trim_start_matches('(').trim_end_matches(')')),
+        None => (heading, ""),
+    };
+    let attributes: &[(&str, &str)] = if note.is_empty() { &[] } else { &[("note", note)] };
+    let body = body.trim_end_matches('\n');
+    if body.is_empty() {
+        let mut element = document(path, attributes, "");
+        let open = element.find('>').expect("a document has an opening tag");
+        element.replace_range(open.., "/>\n\n");
+        element
+    } else {
+        document(path, attributes, body)
+    }
+}
+
+/// Add `note` to the opening tag of a `<document>` chunk, joining it to any
+/// note already there
+pub fn annotate(chunk: &mut String, note: &str) {
+    let tag_end = match chunk.find('>') {
+        Some(end) if chunk.starts_with("<document ") => end,
+        _ => return,
+    };
+    let tag_end = if chunk[..tag_end].ends_with('/') { tag_end - 1 } else { tag_end };
+    match chunk[..tag_end].find(" note=\"") {
+        Some(at) => {
+            let close = at + " note=\"".len() + chunk[at + " note=\"".len()..tag_end].find('"').expect("attributes are quoted");
+            chunk.insert_str(close, &format!("; {}", escape(note)));
+        }
+        None => chunk.insert_str(tag_end, &format!(" note=\"{}\"", escape(note))),
+    }
+}
+
+/// A `<document>` chunk split into its opening line, the content lines, and
+/// the closing line, or `None` for any other chunk
+pub fn split(chunk: &str) -> Option<(&str, &str, &str)> {
+    if !chunk.starts_with("<document ") {
+        return None;
+    }
+    let body_start = chunk.find('\n')? + 1;
+    let trimmed = chunk.trim_end_matches('\n');
+    let close = trimmed.rfind('\n')? + 1;
+    if close < body_start {
+        return None;
+    }
+    Some((&chunk[..body_start], &chunk[body_start..close], &chunk[close..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths_and_content_are_escaped() {
+        assert_eq!(escape(r#"a&b <"c">"#), "a&amp;b &lt;&quot;c&quot;&gt;");
+        assert_eq!(
+            document("R&D/\"x\".md", &[], "if a < b && c]]>d\n"),
+            "<document path=\"R&amp;D/&quot;x&quot;.md\"><![CDATA[\nif a < b && c]]]]><![CDATA[>d\n]]></document>\n\n"
+        );
+        assert_eq!(element("directory_tree", "📁 a&b/\n"), "<directory_tree>\n📁 a&amp;b/\n</directory_tree>\n");
+    }
+
+    #[test]
+    fn test_stubs_notes_and_splitting() {
+        assert_eq!(from_plain("=== b.rs === (identical to a.rs)\n\n"), "<document path=\"b.rs\" note=\"identical to a.rs\"/>\n\n");
+        assert_eq!(from_plain("=== big.log ===\n[omitted]\n\n"), "<document path=\"big.log\"><![CDATA[\n[omitted]\n]]></document>\n\n");
+
+        let mut chunk = document("a.rs", &[], "x\n");
+        annotate(&mut chunk, "abc123, 2024-01-01");
+        annotate(&mut chunk, "file changed while reading");
+        assert!(chunk.starts_with("<document path=\"a.rs\" note=\"abc123, 2024-01-01; file changed while reading\"><![CDATA[\n"));
+        assert_eq!(split(&chunk).map(|(_, body, footer)| (body, footer)), Some(("x\n", "]]></document>\n\n")));
+    }
+}
@@ -0,0 +1,170 @@
+//! `.yoinkignore` files: gitignore-style patterns that leave paths out of
+//! yoink's output without touching `.gitignore`
+//!
+//! Rules come from the global file in yoink's config directory and then from
+//! `.yoinkignore` in the scanned root, all matched against paths relative to
+//! the root. As in git, the last matching rule wins, and a file can't be
+//! brought back with `!` once a directory above it is ignored.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Name of the ignore file looked for in the scanned root
+pub const FILE_NAME: &str = ".yoinkignore";
+
+const MATCH: glob::MatchOptions = glob::MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// One pattern line of an ignore file
+#[derive(Debug, Clone)]
+pub struct IgnoreRule {
+    /// The line as written, without surrounding whitespace
+    pub text: String,
+    /// The file the line came from, and its 1-based line number
+    pub source: PathBuf,
+    pub line: usize,
+    pattern: glob::Pattern,
+    negated: bool,
+    dir_only: bool,
+    // Matched against the whole relative path rather than each name in it
+    anchored: bool,
+}
+
+impl IgnoreRule {
+    fn matches(&self, relative: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if self.anchored {
+            self.pattern.matches_with(relative, MATCH)
+        } else {
+            let name = relative.rsplit('/').next().unwrap_or(relative);
+            self.pattern.matches_with(name, MATCH)
+        }
+    }
+}
+
+/// Every rule that applies to a scan, in the order they're weighed
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+    /// The global ignore file's rules followed by those of `root`'s
+    /// `.yoinkignore`; either file may be missing
+    pub fn load(root: &Path) -> Self {
+        let mut rules = Self::default();
+        for path in global_path().into_iter().chain([root.join(FILE_NAME)]) {
+            match fs::read_to_string(&path) {
+                Ok(text) => rules.rules.extend(Self::parse(&path, &text).rules),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => log::warn!("Couldn't read {}: {}", path.display(), e),
+            }
+        }
+        rules
+    }
+
+    /// Rules from the ignore file `text`, read from `source`
+    pub fn parse(source: &Path, text: &str) -> Self {
+        let mut rules = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negated, body) = match line.strip_prefix('!') {
+                Some(body) => (true, body),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (dir_only, body) = match body.strip_suffix('/') {
+                Some(body) => (true, body),
+                None => (false, body),
+            };
+            let anchored = body.contains('/');
+            let body = body.strip_prefix('/').unwrap_or(body);
+            match glob::Pattern::new(body) {
+                Ok(pattern) if !body.is_empty() => rules.push(IgnoreRule {
+                    text: line.to_string(),
+                    source: source.to_path_buf(),
+                    line: index + 1,
+                    pattern,
+                    negated,
+                    dir_only,
+                    anchored,
+                }),
+                Ok(_) => {}
+                Err(e) => log::warn!("Ignoring invalid pattern '{}' at {}:{}: {}", line, source.display(), index + 1, e),
+            }
+        }
+        Self { rules }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The rule that leaves out `relative`, or a directory above it, along
+    /// with that directory
+    pub fn ignored(&self, relative: &Path, is_dir: bool) -> Option<(&IgnoreRule, Option<PathBuf>)> {
+        if self.rules.is_empty() {
+            return None;
+        }
+        let names: Vec<String> = relative
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect();
+        for end in 1..names.len() {
+            let dir = names[..end].join("/");
+            if let Some(rule) = self.decide(&dir, true) {
+                return Some((rule, Some(PathBuf::from(dir))));
+            }
+        }
+        self.decide(&names.join("/"), is_dir).map(|rule| (rule, None))
+    }
+
+    // The last rule matching `relative`, unless it lets the path back in
+    fn decide(&self, relative: &str, is_dir: bool) -> Option<&IgnoreRule> {
+        self.rules.iter().rev().find(|rule| rule.matches(relative, is_dir)).filter(|rule| !rule.negated)
+    }
+}
+
+/// Where the global ignore file lives, next to the saved config
+pub fn global_path() -> Option<PathBuf> {
+    let mut path = dirs::config_dir()?;
+    path.push("yoink");
+    path.push("ignore");
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directories_globs_and_negation() {
+        let text = "# fixtures and snapshots\n\nfixtures/\n*.snap\n!keep.snap\n/docs/vendor\nsrc/**/gen_*.rs\n\\!bang\n";
+        let rules = IgnoreRules::parse(Path::new(".yoinkignore"), text);
+        let ignored = |path: &str, is_dir: bool| rules.ignored(Path::new(path), is_dir).map(|(rule, dir)| (rule.text.clone(), rule.line, dir));
+
+        assert_eq!(ignored("tests/fixtures/a.json", false), Some(("fixtures/".to_string(), 3, Some(PathBuf::from("tests/fixtures")))));
+        assert_eq!(ignored("fixtures", false), None);
+        assert_eq!(ignored("ui/__snapshots__/app.snap", false), Some(("*.snap".to_string(), 4, None)));
+        assert_eq!(ignored("ui/keep.snap", false), None);
+        assert_eq!(ignored("docs/vendor/guide.md", false).map(|(_, line, _)| line), Some(6));
+        assert_eq!(ignored("site/docs/vendor/guide.md", false), None);
+        assert_eq!(ignored("src/parser/gen_tables.rs", false).map(|(_, line, _)| line), Some(7));
+        assert_eq!(ignored("gen_tables.rs", false), None);
+        assert_eq!(ignored("!bang", false).map(|(_, line, _)| line), Some(8));
+
+        // A file under an ignored directory stays out whatever comes after
+        let rules = IgnoreRules::parse(Path::new(".yoinkignore"), "build/\n!build/keep.txt\n");
+        assert!(rules.ignored(Path::new("build/keep.txt"), false).is_some());
+    }
+}
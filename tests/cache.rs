@@ -0,0 +1,43 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+// Yoink `project` under `dir` into out.txt, returning the output and what was
+// printed about the run
+fn yoink(dir: &Path, flags: &[&str]) -> (String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_yoink"))
+        .args(["project", "--no-config", "--force", "-o", "out.txt"])
+        .args(flags)
+        .current_dir(dir)
+        .env("XDG_CACHE_HOME", dir.join("cache"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    (fs::read_to_string(dir.join("out.txt")).unwrap(), String::from_utf8(output.stdout).unwrap())
+}
+
+#[test]
+fn cache_serves_unchanged_files_on_the_next_run() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("project");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("a.txt"), "alpha\n").unwrap();
+    fs::write(root.join("b.txt"), "beta\n").unwrap();
+    fs::write(root.join("logo.bin"), [0u8, 159, 146, 150, 0, 1]).unwrap();
+
+    let (first, status) = yoink(dir.path(), &["--cache"]);
+    assert!(!status.contains("served from cache"), "{}", status);
+
+    let (second, status) = yoink(dir.path(), &["--cache"]);
+    assert!(status.contains("3 served from cache"), "{}", status);
+    assert_eq!(first, second);
+
+    // A changed file is classified and read again, the rest still come from the cache
+    fs::write(root.join("a.txt"), "alpha, again\n").unwrap();
+    let (third, status) = yoink(dir.path(), &["--cache"]);
+    assert!(status.contains("2 served from cache"), "{}", status);
+    assert!(third.contains("alpha, again\n"), "{}", third);
+
+    let (_, status) = yoink(dir.path(), &["--cache", "--clear-cache"]);
+    assert!(!status.contains("served from cache"), "{}", status);
+}
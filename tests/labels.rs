@@ -0,0 +1,57 @@
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn label_renames_a_root_and_must_name_one() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("client/src")).unwrap();
+    fs::create_dir_all(dir.path().join("server")).unwrap();
+    fs::write(dir.path().join("client/src/main.ts"), "render()\n").unwrap();
+    fs::write(dir.path().join("server/main.go"), "package main\n").unwrap();
+
+    let yoink = |label: &str| {
+        Command::new(env!("CARGO_BIN_EXE_yoink"))
+            .args(["client", "server", "--label", label, "--no-config", "--stdout"])
+            .current_dir(dir.path())
+            .env("XDG_CACHE_HOME", dir.path().join("cache"))
+            .env("PATH", "")
+            .output()
+            .unwrap()
+    };
+
+    let output = yoink("frontend=client");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("=== [frontend] src/main.ts ===\nrender()"), "{}", stdout);
+    assert!(stdout.contains("=== [server] main.go ==="), "{}", stdout);
+    assert!(stdout.contains("  [frontend]: 1 files"), "{}", stdout);
+
+    // A label for a path that wasn't given is a usage error
+    let output = yoink("frontend=web");
+    assert_eq!(output.status.code(), Some(64));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--label frontend=web doesn't name one of the paths given"), "{}", stderr);
+}
+
+#[test]
+fn a_label_given_twice_names_both_roots() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("a")).unwrap();
+    fs::create_dir_all(dir.path().join("b")).unwrap();
+    fs::write(dir.path().join("a/one.txt"), "one\n").unwrap();
+    fs::write(dir.path().join("b/two.txt"), "two\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_yoink"))
+        .args(["a", "b", "--label", "x=a", "--label", "x=b", "--no-config", "--stdout"])
+        .current_dir(dir.path())
+        .env("XDG_CACHE_HOME", dir.path().join("cache"))
+        .env("PATH", "")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // Neither is dropped for the other: the second one given moves aside
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("=== [x] one.txt ==="), "{}", stdout);
+    assert!(stdout.contains("=== [x-2] two.txt ==="), "{}", stdout);
+}
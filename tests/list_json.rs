@@ -0,0 +1,41 @@
+use std::fs;
+use std::process::Command;
+use yoink::selection::{FileKind, ListedFile, LISTED_FILE_VERSION};
+
+#[test]
+fn json_listing_round_trips_against_the_fixture() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("project");
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(root.join("README.md"), "# Project\n").unwrap();
+    fs::write(root.join("logo.bin"), [0u8, 1, 2, 0, 255]).unwrap();
+    fs::write(root.join(".env"), "SECRET=1\n").unwrap();
+
+    let cache = dir.path().join("cache");
+    let output = Command::new(env!("CARGO_BIN_EXE_yoink"))
+        .arg(&root)
+        .args(["--no-config", "--no-hidden", "--list", "--json", "--all"])
+        .env("XDG_CACHE_HOME", &cache)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let listed: Vec<ListedFile> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let summary: Vec<_> = listed
+        .iter()
+        .map(|f| (f.path.as_str(), f.index, f.size, f.kind, f.skipped.as_deref()))
+        .collect();
+
+    assert!(listed.iter().all(|f| f.version == LISTED_FILE_VERSION));
+    assert_eq!(summary, vec![
+        (".env", None, 9, FileKind::Text, Some("hidden file")),
+        ("README.md", Some(1), 10, FileKind::Text, None),
+        ("logo.bin", Some(2), 5, FileKind::Binary, Some("binary content")),
+        ("src/main.rs", Some(3), 13, FileKind::Text, None),
+    ]);
+}
@@ -0,0 +1,43 @@
+use std::fs;
+use std::io::{Read, Seek};
+use yoink::{Config, FileProcessor};
+
+fn write_synthetic_tree(root: &std::path::Path) {
+    for dir in 0..5 {
+        let sub = root.join(format!("dir_{}", dir));
+        fs::create_dir_all(&sub).unwrap();
+        for file in 0..40 {
+            let body: String = (0..file * 10)
+                .map(|line| format!("dir {} file {} line {}\n", dir, file, line))
+                .collect();
+            fs::write(sub.join(format!("file_{:02}.txt", file)), body).unwrap();
+        }
+        fs::write(sub.join("blob.bin"), [0u8, 1, 2, 3, 0, 255]).unwrap();
+    }
+}
+
+#[test]
+fn low_memory_output_matches_in_memory_output() {
+    let dir = tempfile::tempdir().unwrap();
+    write_synthetic_tree(dir.path());
+
+    let config = Config::builder()
+        .path(dir.path().to_string_lossy())
+        .sort(true)
+        .build();
+    let in_memory = FileProcessor::with_defaults(config.clone()).assemble().unwrap();
+
+    let low_memory = FileProcessor::with_defaults(Config { low_memory: true, ..config });
+    let mut spill = tempfile::tempfile().unwrap();
+    let report = low_memory.write_output(&mut spill).unwrap();
+
+    let mut spilled = Vec::new();
+    spill.rewind().unwrap();
+    spill.read_to_end(&mut spilled).unwrap();
+
+    assert_eq!(report.files, in_memory.report.files);
+    assert_eq!(report.text_files, 200);
+    assert_eq!(report.binary_files, 5);
+    assert_eq!(report.output_bytes, spilled.len() as u64);
+    assert_eq!(spilled, in_memory.content.as_bytes());
+}
@@ -0,0 +1,25 @@
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn missing_file_in_existing_dir_is_not_a_github_repo() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/a.rs"), "fn a() {}\n").unwrap();
+
+    // `src/aa.rs` looks like `org/repo`, but `src` is right here
+    let output = Command::new(env!("CARGO_BIN_EXE_yoink"))
+        .args(["src/aa.rs", "--no-config", "--stdout"])
+        .current_dir(dir.path())
+        .env("XDG_CACHE_HOME", dir.path().join("cache"))
+        // No git to be found, so an attempted clone would fail differently
+        .env("PATH", "")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("does not exist"), "{}", stderr);
+    assert!(stderr.contains("Did you mean src/a.rs?"), "{}", stderr);
+    assert!(!stderr.contains("github.com"), "{}", stderr);
+}
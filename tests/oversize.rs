@@ -0,0 +1,66 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use yoink::text_processor::{TextProcessing, TextProcessor};
+use yoink::Config;
+
+// Tracks live heap bytes and their high-water mark for this test binary
+struct PeakAlloc;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let live = LIVE.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(live, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAlloc = PeakAlloc;
+
+#[test]
+fn summarizing_a_large_file_keeps_memory_flat() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("server.log");
+    let mut writer = BufWriter::new(File::create(&path).unwrap());
+    let mut lines = 0u64;
+    let mut written = 0;
+    while written < 50 * 1024 * 1024 {
+        let level = if lines.is_multiple_of(1000) { "ERROR" } else { "INFO" };
+        let line = format!("2024-05-01T12:00:00Z {} request {} handled in 12ms\n", level, lines);
+        writer.write_all(line.as_bytes()).unwrap();
+        written += line.len();
+        lines += 1;
+    }
+    writer.flush().unwrap();
+    drop(writer);
+
+    let config = Config::builder().search_text("ERROR").case_sensitive(true).build();
+    let processor = TextProcessor::new(&config);
+
+    let baseline = LIVE.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+    let excerpt = processor.summarize_file(&path).unwrap().unwrap();
+    let peak = PEAK.load(Ordering::Relaxed) - baseline;
+
+    assert!(peak < 1024 * 1024, "peak heap use was {} bytes", peak);
+    assert_eq!(excerpt.size, written as u64);
+    assert_eq!(excerpt.lines, lines);
+    assert_eq!(excerpt.matches, Some(lines.div_ceil(1000)));
+    assert_eq!(excerpt.head.len(), 20);
+    assert_eq!(excerpt.tail.len(), 20);
+    assert!(excerpt.head[0].ends_with("ERROR request 0 handled in 12ms"));
+    assert!(excerpt.tail[19].contains(&format!("request {} ", lines - 1)));
+}
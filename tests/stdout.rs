@@ -0,0 +1,64 @@
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn stdout_gets_only_the_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("project");
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+
+    let cache = dir.path().join("cache");
+    for flags in [&["--stdout"][..], &["-o", "-"], &["--stdout", "--low-memory"]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_yoink"))
+            .arg(&root)
+            .arg("--no-config")
+            .args(flags)
+            .env("XDG_CACHE_HOME", &cache)
+            // No clipboard tool can be found, so any attempt to copy would fail
+            .env("PATH", "")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.starts_with("=== DIRECTORY STRUCTURE ===\n"), "{}", stdout);
+        assert!(stdout.contains("main.rs ===\nfn main() {}\n"));
+        assert!(stdout.contains("=== SUMMARY ===\nText files processed: 1\n"));
+        assert!(!stdout.contains("Yoinked"));
+    }
+}
+
+#[test]
+fn limits_are_still_reported_with_stdout() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("project");
+    fs::create_dir_all(&root).unwrap();
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        fs::write(root.join(name), "word ".repeat(200)).unwrap();
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_yoink"))
+        .arg(&root)
+        .args(["--no-config", "--stdout", "--target", "100"])
+        .env("XDG_CACHE_HOME", dir.path().join("cache"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("tokens over the 100 tokens window"), "{}", stderr);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("window"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_yoink"))
+        .arg(&root)
+        .args(["--no-config", "--stdout", "--max-total", "1500B"])
+        .env("XDG_CACHE_HOME", dir.path().join("cache"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Output size limit reached: 2 files were left out"), "{}", stderr);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("limit reached"));
+}
@@ -0,0 +1,88 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Lines from `stream` as they come, so a watching yoink can be followed
+// without blocking on it
+fn lines(stream: impl Read + Send + 'static) -> mpsc::Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stream).lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+// Run `yoink --watch` on `root` with `flags`, change a file once the first run
+// is done, and return the stderr of both runs once `expected` has shown up
+// twice, or of however far it got in 20 seconds. The watcher starts just after
+// "Watching" is printed, so the change is made again until a rebuild shows up
+fn watch_rebuild(dir: &Path, root: &Path, flags: &[&str], expected: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_yoink"))
+        .arg(root)
+        .args(["--no-config", "--watch", "-o"])
+        .arg(dir.join("out.txt"))
+        .args(flags)
+        .env("XDG_CACHE_HOME", dir.join("cache"))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stdout = lines(child.stdout.take().unwrap());
+    let stderr = lines(child.stderr.take().unwrap());
+
+    let deadline = Instant::now() + Duration::from_secs(20);
+    while let Ok(line) = stdout.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+        if line.contains("Watching for changes") {
+            break;
+        }
+    }
+
+    let mut seen = String::new();
+    while seen.matches(expected).count() < 2 && Instant::now() < deadline {
+        match stderr.recv_timeout(Duration::from_secs(1)) {
+            Ok(line) => seen.push_str(&format!("{}\n", line)),
+            Err(mpsc::RecvTimeoutError::Timeout) => fs::write(root.join("d.txt"), "word ".repeat(200)).unwrap(),
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    child.kill().unwrap();
+    child.wait().unwrap();
+    seen
+}
+
+fn project(dir: &Path) -> std::path::PathBuf {
+    let root = dir.join("project");
+    fs::create_dir_all(&root).unwrap();
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        fs::write(root.join(name), "word ".repeat(200)).unwrap();
+    }
+    root
+}
+
+#[test]
+fn rebuilds_report_the_size_limit() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = project(dir.path());
+
+    let expected = "Output size limit reached";
+    let stderr = watch_rebuild(dir.path(), &root, &["--max-total", "1500B"], expected);
+    assert_eq!(stderr.matches(expected).count(), 2, "{}", stderr);
+}
+
+#[test]
+fn rebuilds_warn_about_going_over_target() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = project(dir.path());
+
+    let expected = "tokens over the 100 tokens window";
+    let stderr = watch_rebuild(dir.path(), &root, &["--target", "100"], expected);
+    assert_eq!(stderr.matches(expected).count(), 2, "{}", stderr);
+}